@@ -23,7 +23,7 @@
 //! ```
 
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 // Set up UniFFI scaffolding
 uniffi::setup_scaffolding!();
@@ -51,6 +51,11 @@ pub enum FfiError {
     #[error("Already exists: {path}")]
     AlreadyExists { path: String },
 
+    /// The name differs from an existing sibling's only in case or
+    /// normalization.
+    #[error("Name collision: {}", collision.paths.join(" and "))]
+    NameCollision { collision: FfiNameCollision },
+
     /// IO error.
     #[error("IO error: {message}")]
     Io { message: String },
@@ -59,6 +64,23 @@ pub enum FfiError {
     #[error("Index error: {message}")]
     Index { message: String },
 
+    /// The file changed since it was read (see `write_file_if`).
+    #[error("Conflict: {path} changed since it was read")]
+    Conflict {
+        path: String,
+        expected: FfiFileVersion,
+        /// `None` if the file was deleted.
+        actual: Option<FfiFileVersion>,
+    },
+
+    /// The Location was written by a newer version of Fracta.
+    #[error("Unsupported layout: {path} is version {found}, this build supports {supported}")]
+    UnsupportedLayout {
+        path: String,
+        found: u32,
+        supported: u32,
+    },
+
     /// Invalid argument.
     #[error("Invalid argument: {message}")]
     InvalidArgument { message: String },
@@ -83,6 +105,45 @@ impl From<fracta_vfs::VfsError> for FfiError {
             fracta_vfs::VfsError::AlreadyExists(p) => FfiError::AlreadyExists {
                 path: p.display().to_string(),
             },
+            fracta_vfs::VfsError::UnsupportedLayout {
+                path,
+                found,
+                supported,
+            } => FfiError::UnsupportedLayout {
+                path: path.display().to_string(),
+                found,
+                supported,
+            },
+            fracta_vfs::VfsError::NameCollision {
+                path,
+                existing,
+                kind,
+            } => {
+                let mut paths = vec![path, existing];
+                paths.sort();
+                FfiError::NameCollision {
+                    collision: fracta_vfs::NameCollision { paths, kind }.into(),
+                }
+            }
+            fracta_vfs::VfsError::UnknownLocation(id) => FfiError::NotFound {
+                path: id.to_string(),
+            },
+            fracta_vfs::VfsError::Conflict {
+                path,
+                expected,
+                actual,
+            } => FfiError::Conflict {
+                path: path.display().to_string(),
+                expected: expected.into(),
+                actual: actual.map(Into::into),
+            },
+            fracta_vfs::VfsError::OverlappingLocation { .. }
+            | fracta_vfs::VfsError::NotManaged(_)
+            | fracta_vfs::VfsError::LocationMismatch { .. }
+            | fracta_vfs::VfsError::UidConflict { .. }
+            | fracta_vfs::VfsError::InvalidSettings { .. } => FfiError::InvalidArgument {
+                message: e.to_string(),
+            },
             _ => FfiError::Io {
                 message: e.to_string(),
             },
@@ -158,6 +219,14 @@ pub struct FfiEntry {
     pub created: Option<String>,
     /// Scope within the Location.
     pub scope: FfiScope,
+    /// Where the symlink points, if the entry is one.
+    pub symlink_kind: Option<FfiSymlinkKind>,
+    /// The symlink target as stored in the link, if the entry is one.
+    pub symlink_target: Option<String>,
+    /// Set for cloud placeholders and sync-conflict copies.
+    pub cloud_kind: Option<FfiCloudFileKind>,
+    /// The real file a placeholder or conflict copy stands for.
+    pub cloud_original: Option<String>,
 }
 
 impl From<fracta_vfs::Entry> for FfiEntry {
@@ -171,6 +240,290 @@ impl From<fracta_vfs::Entry> for FfiEntry {
             modified: e.modified.map(|m| m.to_rfc3339()).unwrap_or_default(),
             created: e.created.map(|c| c.to_rfc3339()),
             scope: e.scope.into(),
+            symlink_kind: e.symlink.as_ref().map(|s| s.kind.into()),
+            symlink_target: e.symlink.map(|s| s.target.display().to_string()),
+            cloud_kind: e.cloud.as_ref().map(|c| c.kind.into()),
+            cloud_original: e.cloud.map(|c| c.original.display().to_string()),
+        }
+    }
+}
+
+/// Where a symlink points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiSymlinkKind {
+    Internal,
+    External,
+    Broken,
+    Loop,
+}
+
+impl From<fracta_vfs::SymlinkKind> for FfiSymlinkKind {
+    fn from(k: fracta_vfs::SymlinkKind) -> Self {
+        match k {
+            fracta_vfs::SymlinkKind::Internal => FfiSymlinkKind::Internal,
+            fracta_vfs::SymlinkKind::External => FfiSymlinkKind::External,
+            fracta_vfs::SymlinkKind::Broken => FfiSymlinkKind::Broken,
+            fracta_vfs::SymlinkKind::Loop => FfiSymlinkKind::Loop,
+        }
+    }
+}
+
+/// What kind of sync-client file an entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiCloudFileKind {
+    /// Stub for a file that is not downloaded (Plain scope).
+    Placeholder,
+    /// The other side of a conflicting edit.
+    ConflictCopy,
+}
+
+impl From<fracta_vfs::CloudFileKind> for FfiCloudFileKind {
+    fn from(k: fracta_vfs::CloudFileKind) -> Self {
+        match k {
+            fracta_vfs::CloudFileKind::Placeholder => FfiCloudFileKind::Placeholder,
+            fracta_vfs::CloudFileKind::ConflictCopy => FfiCloudFileKind::ConflictCopy,
+        }
+    }
+}
+
+/// How a Location treats symlinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiSymlinkPolicy {
+    /// Never follow symlinks; hide them.
+    Ignore,
+    /// Follow symlinks that stay inside the Location (default).
+    FollowWithin,
+    /// Follow any symlink; outside targets are read-only.
+    FollowAnywhere,
+}
+
+impl From<fracta_vfs::SymlinkPolicy> for FfiSymlinkPolicy {
+    fn from(p: fracta_vfs::SymlinkPolicy) -> Self {
+        match p {
+            fracta_vfs::SymlinkPolicy::Ignore => FfiSymlinkPolicy::Ignore,
+            fracta_vfs::SymlinkPolicy::FollowWithin => FfiSymlinkPolicy::FollowWithin,
+            fracta_vfs::SymlinkPolicy::FollowAnywhere => FfiSymlinkPolicy::FollowAnywhere,
+        }
+    }
+}
+
+impl From<FfiSymlinkPolicy> for fracta_vfs::SymlinkPolicy {
+    fn from(p: FfiSymlinkPolicy) -> Self {
+        match p {
+            FfiSymlinkPolicy::Ignore => fracta_vfs::SymlinkPolicy::Ignore,
+            FfiSymlinkPolicy::FollowWithin => fracta_vfs::SymlinkPolicy::FollowWithin,
+            FfiSymlinkPolicy::FollowAnywhere => fracta_vfs::SymlinkPolicy::FollowAnywhere,
+        }
+    }
+}
+
+/// How colliding sibling names differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiCollisionKind {
+    /// Only in letter case.
+    Case,
+    /// Only in Unicode normalization (NFC vs NFD).
+    Normalization,
+}
+
+impl From<fracta_vfs::CollisionKind> for FfiCollisionKind {
+    fn from(k: fracta_vfs::CollisionKind) -> Self {
+        match k {
+            fracta_vfs::CollisionKind::Case => FfiCollisionKind::Case,
+            fracta_vfs::CollisionKind::Normalization => FfiCollisionKind::Normalization,
+        }
+    }
+}
+
+/// Sibling entries whose names are equal up to case or normalization.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiNameCollision {
+    pub paths: Vec<String>,
+    pub kind: FfiCollisionKind,
+}
+
+impl From<fracta_vfs::NameCollision> for FfiNameCollision {
+    fn from(c: fracta_vfs::NameCollision) -> Self {
+        Self {
+            paths: c.paths.iter().map(|p| p.display().to_string()).collect(),
+            kind: c.kind.into(),
+        }
+    }
+}
+
+/// Privacy level of content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiPrivacy {
+    Public,
+    Internal,
+    Sensitive,
+}
+
+impl From<fracta_vfs::Privacy> for FfiPrivacy {
+    fn from(p: fracta_vfs::Privacy) -> Self {
+        match p {
+            fracta_vfs::Privacy::Public => FfiPrivacy::Public,
+            fracta_vfs::Privacy::Internal => FfiPrivacy::Internal,
+            fracta_vfs::Privacy::Sensitive => FfiPrivacy::Sensitive,
+        }
+    }
+}
+
+impl From<FfiPrivacy> for fracta_vfs::Privacy {
+    fn from(p: FfiPrivacy) -> Self {
+        match p {
+            FfiPrivacy::Public => fracta_vfs::Privacy::Public,
+            FfiPrivacy::Internal => fracta_vfs::Privacy::Internal,
+            FfiPrivacy::Sensitive => fracta_vfs::Privacy::Sensitive,
+        }
+    }
+}
+
+/// The user-editable settings of a Location.
+///
+/// The persistent ID, label, layout version, folder ignore overrides and
+/// keys unknown to this version are not part of it and are kept as they are
+/// by `update_settings`.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct FfiLocationSettings {
+    /// Extensions (lowercase, without dot) whose content is indexed.
+    pub index_extensions: Vec<String>,
+    /// Files larger than this many bytes are indexed by metadata only.
+    pub max_index_file_size: u64,
+    pub symlinks: FfiSymlinkPolicy,
+    pub watcher_debounce_ms: u64,
+    pub default_privacy: FfiPrivacy,
+    pub ai_enabled: bool,
+    pub honor_gitignore: bool,
+    /// Record every operation in the Location's journal.
+    pub journal_enabled: bool,
+    /// Days a trash item is kept before it is purged (0 keeps it until the
+    /// trash is emptied).
+    pub trash_retention_days: u32,
+}
+
+impl From<&fracta_vfs::LocationSettings> for FfiLocationSettings {
+    fn from(s: &fracta_vfs::LocationSettings) -> Self {
+        Self {
+            index_extensions: s.indexing.extensions.clone(),
+            max_index_file_size: s.indexing.max_file_size,
+            symlinks: s.symlinks.into(),
+            watcher_debounce_ms: s.watcher.debounce_ms,
+            default_privacy: s.default_privacy.into(),
+            ai_enabled: s.ai_enabled,
+            honor_gitignore: s.honor_gitignore,
+            journal_enabled: s.journal.enabled,
+            trash_retention_days: s.trash.retention_days,
+        }
+    }
+}
+
+impl FfiLocationSettings {
+    /// Apply these values onto `settings`, leaving everything else alone.
+    fn apply_to(self, settings: &mut fracta_vfs::LocationSettings) {
+        settings.indexing.extensions = self.index_extensions;
+        settings.indexing.max_file_size = self.max_index_file_size;
+        settings.symlinks = self.symlinks.into();
+        settings.watcher.debounce_ms = self.watcher_debounce_ms;
+        settings.default_privacy = self.default_privacy.into();
+        settings.ai_enabled = self.ai_enabled;
+        settings.honor_gitignore = self.honor_gitignore;
+        settings.journal.enabled = self.journal_enabled;
+        settings.trash.retention_days = self.trash_retention_days;
+    }
+}
+
+/// A file version for conflict-checked writes.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiFileVersion {
+    /// Modification time (RFC 3339, nanosecond precision) and size.
+    Stat { modified: Option<String>, size: u64 },
+    /// Hex-encoded BLAKE3 hash of the content.
+    Hash { hash: String },
+}
+
+impl From<fracta_vfs::FileVersion> for FfiFileVersion {
+    fn from(v: fracta_vfs::FileVersion) -> Self {
+        match v {
+            fracta_vfs::FileVersion::Stat { modified, size } => FfiFileVersion::Stat {
+                modified: modified.map(|m| m.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)),
+                size,
+            },
+            fracta_vfs::FileVersion::Hash(hash) => FfiFileVersion::Hash { hash },
+        }
+    }
+}
+
+impl TryFrom<FfiFileVersion> for fracta_vfs::FileVersion {
+    type Error = FfiError;
+
+    fn try_from(v: FfiFileVersion) -> Result<Self, FfiError> {
+        match v {
+            FfiFileVersion::Stat { modified, size } => {
+                let modified = modified
+                    .map(|m| {
+                        chrono::DateTime::parse_from_rfc3339(&m)
+                            .map(|t| t.with_timezone(&chrono::Utc))
+                            .map_err(|e| FfiError::InvalidArgument {
+                                message: format!("invalid modified time {m}: {e}"),
+                            })
+                    })
+                    .transpose()?;
+                Ok(fracta_vfs::FileVersion::Stat { modified, size })
+            }
+            FfiFileVersion::Hash { hash } => Ok(fracta_vfs::FileVersion::Hash(hash)),
+        }
+    }
+}
+
+/// File content together with the version it was read at.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiVersionedContent {
+    pub content: String,
+    pub version: FfiFileVersion,
+}
+
+/// Where an ignore rule was defined.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiIgnoreSource {
+    /// `.fracta/config/ignore` (or the built-in defaults).
+    Config,
+    /// A `.gitignore` file (path relative to the Location root).
+    Gitignore { path: String },
+    /// A per-folder override from settings (folder relative to the root).
+    FolderOverride { folder: String },
+}
+
+/// The ignore rule that decides a path's scope.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiIgnoreMatch {
+    pub source: FfiIgnoreSource,
+    /// 1-based line of the rule within its source.
+    pub line: u32,
+    /// The rule as written.
+    pub pattern: String,
+    /// Whether the rule re-includes (`!`) rather than ignores.
+    pub negated: bool,
+    /// The path the rule matched (the queried path or an ancestor), relative.
+    pub matched_path: String,
+}
+
+impl From<fracta_vfs::IgnoreMatch> for FfiIgnoreMatch {
+    fn from(m: fracta_vfs::IgnoreMatch) -> Self {
+        FfiIgnoreMatch {
+            source: match m.source {
+                fracta_vfs::IgnoreSource::Config => FfiIgnoreSource::Config,
+                fracta_vfs::IgnoreSource::Gitignore(p) => FfiIgnoreSource::Gitignore {
+                    path: p.display().to_string(),
+                },
+                fracta_vfs::IgnoreSource::FolderOverride(p) => FfiIgnoreSource::FolderOverride {
+                    folder: p.display().to_string(),
+                },
+            },
+            line: m.line as u32,
+            pattern: m.pattern,
+            negated: m.negated,
+            matched_path: m.matched_path.display().to_string(),
         }
     }
 }
@@ -293,6 +646,48 @@ impl FfiLocation {
         Ok(())
     }
 
+    /// Read a file as UTF-8 string together with its version.
+    ///
+    /// With `by_hash`, the version is a content hash; otherwise it is the
+    /// cheaper modification time + size.
+    pub fn read_file_versioned(
+        &self,
+        path: String,
+        by_hash: bool,
+    ) -> Result<FfiVersionedContent, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let (content, version) = location.read_file_versioned(&PathBuf::from(path))?;
+        let version = if by_hash {
+            fracta_vfs::FileVersion::of_content(&content)
+        } else {
+            version
+        };
+        let content = String::from_utf8(content).map_err(|_| FfiError::Io {
+            message: "file is not valid UTF-8".to_string(),
+        })?;
+        Ok(FfiVersionedContent {
+            content,
+            version: version.into(),
+        })
+    }
+
+    /// Write a file only if it is still at version `expected`.
+    ///
+    /// Fails with `FfiError::Conflict` if it changed since it was read.
+    /// Returns the new version, to pass to the next save.
+    pub fn write_file_if(
+        &self,
+        path: String,
+        content: String,
+        expected: FfiFileVersion,
+    ) -> Result<FfiFileVersion, FfiError> {
+        let expected = expected.try_into()?;
+        let location = self.inner.lock().unwrap();
+        let version =
+            location.write_file_if(&PathBuf::from(path), content.as_bytes(), &expected)?;
+        Ok(version.into())
+    }
+
     /// Create a new file.
     pub fn create_file(&self, path: String, content: String) -> Result<(), FfiError> {
         let location = self.inner.lock().unwrap();
@@ -307,20 +702,204 @@ impl FfiLocation {
         Ok(())
     }
 
-    /// Delete a file.
+    /// The UID of a file or folder, if one has been assigned.
+    pub fn uid_of(&self, path: String) -> Result<Option<String>, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let uid = location.uid_of(&PathBuf::from(path))?;
+        Ok(uid.map(|u| u.to_string()))
+    }
+
+    /// The UID of a file or folder, assigning one on first use.
+    pub fn ensure_uid(&self, path: String) -> Result<String, FfiError> {
+        let location = self.inner.lock().unwrap();
+        Ok(location.ensure_uid(&PathBuf::from(path))?.to_string())
+    }
+
+    /// The current path of a UID, if known.
+    pub fn path_of_uid(&self, uid: String) -> Result<Option<String>, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let path = location.path_of_uid(parse_uid(&uid)?)?;
+        Ok(path.map(|p| p.display().to_string()))
+    }
+
+    /// Record that `path` carries `uid`; fails if another entry holds it.
+    pub fn claim_uid(&self, path: String, uid: String) -> Result<(), FfiError> {
+        let location = self.inner.lock().unwrap();
+        location.claim_uid(&PathBuf::from(path), parse_uid(&uid)?)?;
+        Ok(())
+    }
+
+    /// Explain which ignore rule decides the scope of `path`, if any.
+    pub fn explain_ignored(&self, path: String) -> Result<Option<FfiIgnoreMatch>, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let found = location.explain_ignored(&PathBuf::from(path))?;
+        Ok(found.map(Into::into))
+    }
+
+    /// `.gitignore` files that could not be read and contribute no rules.
+    pub fn skipped_ignore_files(&self) -> Vec<FfiSkippedEntry> {
+        let location = self.inner.lock().unwrap();
+        location
+            .skipped_ignore_files()
+            .iter()
+            .cloned()
+            .map(Into::into)
+            .collect()
+    }
+
+    /// Sync-conflict copies in this Location that await resolution.
+    pub fn conflict_copies(&self) -> Result<Vec<FfiEntry>, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let copies = location.conflict_copies()?;
+        Ok(copies.into_iter().map(FfiEntry::from).collect())
+    }
+
+    /// How this Location treats symlinks.
+    pub fn symlink_policy(&self) -> FfiSymlinkPolicy {
+        self.inner.lock().unwrap().symlink_policy().into()
+    }
+
+    /// Change (and persist) how this Location treats symlinks.
+    pub fn set_symlink_policy(&self, policy: FfiSymlinkPolicy) -> Result<(), FfiError> {
+        let mut location = self.inner.lock().unwrap();
+        location.set_symlink_policy(policy.into())?;
+        Ok(())
+    }
+
+    /// List entries whose names collide with a sibling up to letter case
+    /// or Unicode normalization.
+    pub fn find_name_collisions(&self) -> Result<Vec<FfiNameCollision>, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let collisions = location.find_collisions(&location.root)?;
+        Ok(collisions.into_iter().map(Into::into).collect())
+    }
+
+    /// The current settings of this Location.
+    pub fn settings(&self) -> Result<FfiLocationSettings, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let settings = fracta_vfs::LocationSettings::load(&location.root)?;
+        Ok((&settings).into())
+    }
+
+    /// Validate and persist new settings, then apply them to this Location.
+    ///
+    /// Fails with `InvalidArgument`, changing nothing, if a value is invalid
+    /// or this Location is not managed.
+    pub fn update_settings(&self, settings: FfiLocationSettings) -> Result<(), FfiError> {
+        let mut location = self.inner.lock().unwrap();
+        let mut current = fracta_vfs::LocationSettings::load(&location.root)?;
+        settings.apply_to(&mut current);
+        location.update_settings(&current)?;
+        Ok(())
+    }
+
+    /// Who this Location's operations are attributed to in the journal.
+    pub fn actor(&self) -> FfiActor {
+        self.inner.lock().unwrap().actor().into()
+    }
+
+    /// Attribute subsequent operations through this object to `actor`.
+    pub fn set_actor(&self, actor: FfiActor) {
+        self.inner.lock().unwrap().set_actor(actor.into());
+    }
+
+    /// Journal entries matching `query`, oldest first.
+    pub fn journal_entries(
+        &self,
+        query: FfiJournalQuery,
+    ) -> Result<Vec<FfiJournalEntry>, FfiError> {
+        let query = fracta_vfs::JournalQuery::try_from(query)?;
+        let location = self.inner.lock().unwrap();
+        let entries = location.journal_entries(&query)?;
+        Ok(entries.into_iter().map(Into::into).collect())
+    }
+
+    /// Opt in to (or out of) honoring `.gitignore` files in this Location.
+    pub fn set_honor_gitignore(&self, enabled: bool) -> Result<(), FfiError> {
+        let mut location = self.inner.lock().unwrap();
+        let mut settings = fracta_vfs::LocationSettings::load(&location.root)?;
+        settings.honor_gitignore = enabled;
+        location.update_settings(&settings)?;
+        Ok(())
+    }
+
+    /// Set the ignore patterns for one folder (relative to the root) without
+    /// writing into it. An empty list removes the override.
+    pub fn set_folder_ignores(
+        &self,
+        folder: String,
+        patterns: Vec<String>,
+    ) -> Result<(), FfiError> {
+        let mut location = self.inner.lock().unwrap();
+        let mut settings = fracta_vfs::LocationSettings::load(&location.root)?;
+        if patterns.is_empty() {
+            settings.folder_ignores.remove(&folder);
+        } else {
+            settings.folder_ignores.insert(folder, patterns);
+        }
+        location.update_settings(&settings)?;
+        Ok(())
+    }
+
+    /// Delete a file (moved to the trash in a managed Location).
     pub fn delete_file(&self, path: String) -> Result<(), FfiError> {
         let location = self.inner.lock().unwrap();
         location.delete_file(&PathBuf::from(path))?;
         Ok(())
     }
 
-    /// Delete a folder and all its contents.
+    /// Delete a folder and all its contents (moved to the trash in a managed Location).
     pub fn delete_folder(&self, path: String) -> Result<(), FfiError> {
         let location = self.inner.lock().unwrap();
         location.delete_folder(&PathBuf::from(path))?;
         Ok(())
     }
 
+    /// Delete a file or folder immediately, bypassing the trash.
+    pub fn delete_permanently(&self, path: String) -> Result<(), FfiError> {
+        let location = self.inner.lock().unwrap();
+        location.delete_permanently(&PathBuf::from(path))?;
+        Ok(())
+    }
+
+    /// List trashed entries, most recently deleted first.
+    pub fn list_trash(&self) -> Result<Vec<FfiTrashItem>, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let items = location.list_trash()?;
+        Ok(items.into_iter().map(FfiTrashItem::from).collect())
+    }
+
+    /// Restore a trashed entry. Returns the path it was restored to.
+    pub fn restore_from_trash(
+        &self,
+        id: String,
+        on_conflict: FfiRestoreConflict,
+    ) -> Result<String, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let restored = location.restore_from_trash(parse_trash_id(&id)?, on_conflict.into())?;
+        Ok(restored.display().to_string())
+    }
+
+    /// Permanently delete one trashed entry.
+    pub fn purge_trash_item(&self, id: String) -> Result<(), FfiError> {
+        let location = self.inner.lock().unwrap();
+        location.purge_trash_item(parse_trash_id(&id)?)?;
+        Ok(())
+    }
+
+    /// Permanently delete everything in the trash. Returns the number purged.
+    pub fn empty_trash(&self) -> Result<u32, FfiError> {
+        let location = self.inner.lock().unwrap();
+        Ok(location.empty_trash()? as u32)
+    }
+
+    /// Purge trashed entries older than `retention_days`. Returns the number purged.
+    pub fn purge_expired_trash(&self, retention_days: u32) -> Result<u32, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let retention = chrono::Duration::days(i64::from(retention_days));
+        Ok(location.purge_expired_trash(retention)? as u32)
+    }
+
     /// Rename an entry (new_name is just the filename, not a full path).
     pub fn rename(&self, path: String, new_name: String) -> Result<String, FfiError> {
         let location = self.inner.lock().unwrap();
@@ -339,6 +918,492 @@ impl FfiLocation {
         let new_path = location.move_entry(&PathBuf::from(path), &PathBuf::from(new_parent))?;
         Ok(new_path.display().to_string())
     }
+
+    /// Duplicate a file or folder next to itself ("Note copy.md").
+    /// Returns the path of the copy.
+    pub fn duplicate(&self, path: String) -> Result<String, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let copy = location.duplicate(&PathBuf::from(path))?;
+        Ok(copy.display().to_string())
+    }
+
+    /// Copy a file or folder within this Location. Returns the path of the copy.
+    pub fn copy_entry(
+        &self,
+        from: String,
+        to: String,
+        options: FfiCopyOptions,
+        listener: Option<Box<dyn FfiCopyListener>>,
+    ) -> Result<String, FfiError> {
+        let location = self.inner.lock().unwrap();
+        let copy = location.copy_entry(
+            &PathBuf::from(from),
+            &PathBuf::from(to),
+            &options.into(),
+            |p| report_copy_progress(listener.as_deref(), p),
+        )?;
+        Ok(copy.display().to_string())
+    }
+
+    /// Copy a file or folder into another Location. Returns the path of the copy.
+    pub fn copy_to_location(
+        &self,
+        from: String,
+        dest: Arc<FfiLocation>,
+        to: String,
+        options: FfiCopyOptions,
+        listener: Option<Box<dyn FfiCopyListener>>,
+    ) -> Result<String, FfiError> {
+        if std::ptr::eq(self, dest.as_ref()) {
+            return self.copy_entry(from, to, options, listener);
+        }
+        // Never hold both locks: copies in opposite directions would deadlock.
+        // The clone shares the destination's state.
+        let dest = dest.inner.lock().unwrap().clone();
+        let location = self.inner.lock().unwrap();
+        let copy = location.copy_to(
+            &PathBuf::from(from),
+            &dest,
+            &PathBuf::from(to),
+            &options.into(),
+            |p| report_copy_progress(listener.as_deref(), p),
+        )?;
+        Ok(copy.display().to_string())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Copy
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Options for copying entries.
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct FfiCopyOptions {
+    /// On collision, pick a free "name copy N" instead of failing.
+    pub auto_rename: bool,
+    /// Copy entries with Ignored scope too.
+    pub include_ignored: bool,
+}
+
+impl From<FfiCopyOptions> for fracta_vfs::CopyOptions {
+    fn from(o: FfiCopyOptions) -> Self {
+        fracta_vfs::CopyOptions {
+            on_collision: if o.auto_rename {
+                fracta_vfs::CopyCollision::AutoRename
+            } else {
+                fracta_vfs::CopyCollision::Fail
+            },
+            include_ignored: o.include_ignored,
+        }
+    }
+}
+
+/// Progress of a running copy.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiCopyProgress {
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Absolute source path of the file just copied.
+    pub current: String,
+}
+
+impl From<&fracta_vfs::CopyProgress> for FfiCopyProgress {
+    fn from(p: &fracta_vfs::CopyProgress) -> Self {
+        FfiCopyProgress {
+            files_done: p.files_done as u64,
+            files_total: p.files_total as u64,
+            bytes_done: p.bytes_done,
+            bytes_total: p.bytes_total,
+            current: p.current.display().to_string(),
+        }
+    }
+}
+
+/// Receives copy progress, called after each copied file.
+#[uniffi::export(callback_interface)]
+pub trait FfiCopyListener: Send + Sync {
+    fn on_progress(&self, progress: FfiCopyProgress);
+}
+
+fn report_copy_progress(
+    listener: Option<&dyn FfiCopyListener>,
+    progress: &fracta_vfs::CopyProgress,
+) {
+    if let Some(listener) = listener {
+        listener.on_progress(progress.into());
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Journal
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Who performed an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiActor {
+    User,
+    Ai,
+    Pipeline,
+    Sync,
+}
+
+impl From<fracta_vfs::Actor> for FfiActor {
+    fn from(a: fracta_vfs::Actor) -> Self {
+        match a {
+            fracta_vfs::Actor::User => FfiActor::User,
+            fracta_vfs::Actor::Ai => FfiActor::Ai,
+            fracta_vfs::Actor::Pipeline => FfiActor::Pipeline,
+            fracta_vfs::Actor::Sync => FfiActor::Sync,
+        }
+    }
+}
+
+impl From<FfiActor> for fracta_vfs::Actor {
+    fn from(a: FfiActor) -> Self {
+        match a {
+            FfiActor::User => fracta_vfs::Actor::User,
+            FfiActor::Ai => fracta_vfs::Actor::Ai,
+            FfiActor::Pipeline => fracta_vfs::Actor::Pipeline,
+            FfiActor::Sync => fracta_vfs::Actor::Sync,
+        }
+    }
+}
+
+/// The kind of a journaled operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiJournalOp {
+    Create,
+    CreateFolder,
+    Write,
+    Rename,
+    Move,
+    Copy,
+    Delete,
+    Trash,
+    Restore,
+}
+
+impl From<fracta_vfs::JournalOp> for FfiJournalOp {
+    fn from(op: fracta_vfs::JournalOp) -> Self {
+        match op {
+            fracta_vfs::JournalOp::Create => FfiJournalOp::Create,
+            fracta_vfs::JournalOp::CreateFolder => FfiJournalOp::CreateFolder,
+            fracta_vfs::JournalOp::Write => FfiJournalOp::Write,
+            fracta_vfs::JournalOp::Rename => FfiJournalOp::Rename,
+            fracta_vfs::JournalOp::Move => FfiJournalOp::Move,
+            fracta_vfs::JournalOp::Copy => FfiJournalOp::Copy,
+            fracta_vfs::JournalOp::Delete => FfiJournalOp::Delete,
+            fracta_vfs::JournalOp::Trash => FfiJournalOp::Trash,
+            fracta_vfs::JournalOp::Restore => FfiJournalOp::Restore,
+        }
+    }
+}
+
+/// One journaled operation.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiJournalEntry {
+    /// When it happened (ISO 8601).
+    pub at: String,
+    pub actor: FfiActor,
+    pub op: FfiJournalOp,
+    /// Absolute path operated on.
+    pub path: String,
+    /// New path of a rename or move.
+    pub to: Option<String>,
+    /// Content hash before the operation (files only).
+    pub before: Option<String>,
+    /// Content hash after the operation (files only).
+    pub after: Option<String>,
+}
+
+impl From<fracta_vfs::JournalEntry> for FfiJournalEntry {
+    fn from(e: fracta_vfs::JournalEntry) -> Self {
+        FfiJournalEntry {
+            at: e.at.to_rfc3339(),
+            actor: e.actor.into(),
+            op: e.op.into(),
+            path: e.path.display().to_string(),
+            to: e.to.map(|p| p.display().to_string()),
+            before: e.before,
+            after: e.after,
+        }
+    }
+}
+
+/// Filter for `journal_entries`. Unset fields match everything.
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct FfiJournalQuery {
+    /// Only entries at or below this absolute path.
+    pub path: Option<String>,
+    pub actor: Option<FfiActor>,
+    /// Entries at or after this time (ISO 8601).
+    pub since: Option<String>,
+    /// Entries before this time (ISO 8601).
+    pub until: Option<String>,
+}
+
+impl TryFrom<FfiJournalQuery> for fracta_vfs::JournalQuery {
+    type Error = FfiError;
+
+    fn try_from(q: FfiJournalQuery) -> Result<Self, FfiError> {
+        Ok(fracta_vfs::JournalQuery {
+            path: q.path.map(PathBuf::from),
+            actor: q.actor.map(Into::into),
+            since: q.since.as_deref().map(parse_timestamp).transpose()?,
+            until: q.until.as_deref().map(parse_timestamp).transpose()?,
+        })
+    }
+}
+
+/// Parse an ISO 8601 timestamp passed in from the shell.
+fn parse_timestamp(s: &str) -> Result<chrono::DateTime<chrono::Utc>, FfiError> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .map_err(|e| FfiError::InvalidArgument {
+            message: format!("invalid timestamp {s}: {e}"),
+        })
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Trash
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A soft-deleted entry in a Location's trash.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiTrashItem {
+    /// Trash item ID (UUID string), used to restore or purge.
+    pub id: String,
+    /// Absolute path the entry was deleted from.
+    pub original_path: String,
+    /// Entry name at deletion time.
+    pub name: String,
+    /// Entry kind (file or folder).
+    pub kind: FfiEntryKind,
+    /// Deletion time (ISO 8601).
+    pub deleted_at: String,
+}
+
+impl From<fracta_vfs::TrashItem> for FfiTrashItem {
+    fn from(item: fracta_vfs::TrashItem) -> Self {
+        FfiTrashItem {
+            id: item.id.to_string(),
+            original_path: item.original_path.display().to_string(),
+            name: item.name,
+            kind: item.kind.into(),
+            deleted_at: item.deleted_at.to_rfc3339(),
+        }
+    }
+}
+
+/// What to do when a restore target is already occupied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiRestoreConflict {
+    /// Fail with AlreadyExists.
+    Fail,
+    /// Restore next to the occupant under a "(restored)" name.
+    KeepBoth,
+    /// Move the occupant to the trash, then restore.
+    Replace,
+}
+
+impl From<FfiRestoreConflict> for fracta_vfs::RestoreConflict {
+    fn from(c: FfiRestoreConflict) -> Self {
+        match c {
+            FfiRestoreConflict::Fail => fracta_vfs::RestoreConflict::Fail,
+            FfiRestoreConflict::KeepBoth => fracta_vfs::RestoreConflict::KeepBoth,
+            FfiRestoreConflict::Replace => fracta_vfs::RestoreConflict::Replace,
+        }
+    }
+}
+
+/// Parse a trash item ID passed in from the shell.
+fn parse_trash_id(id: &str) -> Result<uuid::Uuid, FfiError> {
+    uuid::Uuid::parse_str(id).map_err(|e| FfiError::InvalidArgument {
+        message: format!("invalid trash item id {id}: {e}"),
+    })
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Location Registry (Global View)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Summary of a registered Location.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiLocationInfo {
+    /// Stable Location ID (UUID string).
+    pub id: String,
+    /// Human-readable label.
+    pub label: String,
+    /// Absolute path to the Location root.
+    pub root: String,
+    /// Whether the compute layer is enabled.
+    pub managed: bool,
+    /// Whether the Location was opened and its root is currently reachable.
+    pub available: bool,
+    /// Why the Location could not be opened, if it could not.
+    pub error: Option<String>,
+}
+
+impl FfiLocationInfo {
+    fn new(registry: &fracta_vfs::LocationRegistry, loc: &fracta_vfs::Location) -> Self {
+        FfiLocationInfo {
+            id: loc.id.to_string(),
+            label: loc.label.clone(),
+            root: loc.root.display().to_string(),
+            managed: loc.managed,
+            available: registry.is_available(loc.id),
+            error: registry.open_error(loc.id).map(ToString::to_string),
+        }
+    }
+}
+
+/// Parse an entry UID passed in from the shell.
+fn parse_uid(uid: &str) -> Result<uuid::Uuid, FfiError> {
+    uuid::Uuid::parse_str(uid).map_err(|e| FfiError::InvalidArgument {
+        message: format!("invalid UID {uid}: {e}"),
+    })
+}
+
+/// Parse a Location ID passed in from the shell.
+fn parse_location_id(id: &str) -> Result<uuid::Uuid, FfiError> {
+    uuid::Uuid::parse_str(id).map_err(|e| FfiError::InvalidArgument {
+        message: format!("invalid Location id {id}: {e}"),
+    })
+}
+
+/// The persisted set of Locations that make up the Global View.
+///
+/// Thread-safe wrapper around fracta_vfs::LocationRegistry.
+#[derive(uniffi::Object)]
+pub struct FfiLocationRegistry {
+    inner: Mutex<fracta_vfs::LocationRegistry>,
+}
+
+#[uniffi::export]
+impl FfiLocationRegistry {
+    /// Open (or create) a registry persisted at the given JSON file path.
+    #[uniffi::constructor]
+    pub fn open(path: String) -> Result<Self, FfiError> {
+        let registry = fracta_vfs::LocationRegistry::open(&PathBuf::from(path))?;
+        Ok(FfiLocationRegistry {
+            inner: Mutex::new(registry),
+        })
+    }
+
+    /// Create an in-memory registry (for testing).
+    #[uniffi::constructor]
+    pub fn new_in_memory() -> Self {
+        FfiLocationRegistry {
+            inner: Mutex::new(fracta_vfs::LocationRegistry::new()),
+        }
+    }
+
+    /// Register a Location. Returns its ID.
+    pub fn add(&self, location: &FfiLocation) -> Result<String, FfiError> {
+        let loc = location.inner.lock().unwrap().clone();
+        let mut registry = self.inner.lock().unwrap();
+        let id = registry.add(loc)?;
+        Ok(id.to_string())
+    }
+
+    /// Unregister a Location (its `.fracta/` directory is left in place).
+    pub fn remove(&self, id: String) -> Result<(), FfiError> {
+        let id = parse_location_id(&id)?;
+        let mut registry = self.inner.lock().unwrap();
+        registry.remove(id)?;
+        Ok(())
+    }
+
+    /// Re-bind a Location to the path its folder was moved to.
+    ///
+    /// Fails with `InvalidArgument` if `new_root` holds a different Location.
+    pub fn relocate(&self, id: String, new_root: String) -> Result<(), FfiError> {
+        let id = parse_location_id(&id)?;
+        let mut registry = self.inner.lock().unwrap();
+        registry.relocate(id, &PathBuf::from(new_root))?;
+        Ok(())
+    }
+
+    /// The ID of the registered Location whose folder was moved to `root`,
+    /// if any (its registered root is no longer reachable).
+    pub fn find_relocated(&self, root: String) -> Result<Option<String>, FfiError> {
+        let registry = self.inner.lock().unwrap();
+        let id = registry.find_relocated(&PathBuf::from(root))?;
+        Ok(id.map(|id| id.to_string()))
+    }
+
+    /// Change a Location's label.
+    pub fn relabel(&self, id: String, label: String) -> Result<(), FfiError> {
+        let id = parse_location_id(&id)?;
+        let mut registry = self.inner.lock().unwrap();
+        registry.relabel(id, label)?;
+        Ok(())
+    }
+
+    /// Retry opening a Location that was unavailable when the registry
+    /// was loaded.
+    pub fn reopen(&self, id: String) -> Result<(), FfiError> {
+        let id = parse_location_id(&id)?;
+        let mut registry = self.inner.lock().unwrap();
+        registry.reopen(id)?;
+        Ok(())
+    }
+
+    /// List all registered Locations.
+    pub fn list(&self) -> Vec<FfiLocationInfo> {
+        let registry = self.inner.lock().unwrap();
+        registry
+            .locations()
+            .iter()
+            .map(|loc| FfiLocationInfo::new(&registry, loc))
+            .collect()
+    }
+
+    /// Find the Location that owns an absolute path.
+    pub fn location_for(&self, path: String) -> Option<FfiLocationInfo> {
+        let registry = self.inner.lock().unwrap();
+        registry
+            .location_for(&PathBuf::from(path))
+            .map(|loc| FfiLocationInfo::new(&registry, loc))
+    }
+
+    /// Get a handle to a registered Location for CRUD operations.
+    pub fn location(&self, id: String) -> Result<Arc<FfiLocation>, FfiError> {
+        let id = parse_location_id(&id)?;
+        let registry = self.inner.lock().unwrap();
+        let loc = registry
+            .get(id)
+            .ok_or(fracta_vfs::VfsError::UnknownLocation(id))?;
+        Ok(Arc::new(FfiLocation {
+            inner: Mutex::new(loc.clone()),
+        }))
+    }
+
+    /// Top level of the Global View: one entry per reachable Location root.
+    pub fn list_roots(&self) -> Vec<FfiEntry> {
+        let registry = self.inner.lock().unwrap();
+        registry.list_roots().into_iter().map(Into::into).collect()
+    }
+
+    /// List a directory in whichever Location owns it.
+    pub fn list_directory(&self, path: String) -> Result<Vec<FfiEntry>, FfiError> {
+        let registry = self.inner.lock().unwrap();
+        let entries = registry.list_directory(&PathBuf::from(path))?;
+        Ok(entries.into_iter().map(Into::into).collect())
+    }
+
+    /// Walk every reachable Location and return the merged entries.
+    pub fn walk(&self, options: FfiWalkOptions) -> Result<Vec<FfiEntry>, FfiError> {
+        let registry = self.inner.lock().unwrap();
+        let opts = fracta_vfs::WalkOptions {
+            include_ignored: options.include_ignored,
+            max_depth: options.max_depth.map(|d| d as usize),
+        };
+        let entries = registry.walk(&opts)?;
+        Ok(entries.into_iter().map(Into::into).collect())
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -408,9 +1473,137 @@ impl FfiDocument {
         self.inner.front_matter.as_ref()?.get_bool(&key)
     }
 
-    /// Get the number of blocks in the document.
-    pub fn block_count(&self) -> u32 {
-        self.inner.blocks.len() as u32
+    /// Get the number of blocks in the document.
+    pub fn block_count(&self) -> u32 {
+        self.inner.blocks.len() as u32
+    }
+
+    /// Every block and inline with its position in the source, depth first.
+    pub fn spans(&self) -> Vec<FfiNodeSpan> {
+        self.inner
+            .span_entries()
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    /// Every outgoing reference: links, images, wikilinks, embeds and front
+    /// matter relations.
+    pub fn links(&self) -> Vec<FfiLink> {
+        self.inner.links().into_iter().map(Into::into).collect()
+    }
+}
+
+/// A range of Markdown source; `end_*` is exclusive.
+///
+/// Lines and columns are 1-based, columns and offsets count UTF-8 bytes.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct FfiSourceSpan {
+    pub start_line: u64,
+    pub start_column: u64,
+    pub start_offset: u64,
+    pub end_line: u64,
+    pub end_column: u64,
+    pub end_offset: u64,
+}
+
+impl From<fracta_note::Span> for FfiSourceSpan {
+    fn from(s: fracta_note::Span) -> Self {
+        FfiSourceSpan {
+            start_line: s.start.line as u64,
+            start_column: s.start.column as u64,
+            start_offset: s.start.offset as u64,
+            end_line: s.end.line as u64,
+            end_column: s.end.column as u64,
+            end_offset: s.end.offset as u64,
+        }
+    }
+}
+
+/// One node of a parsed document and where it sits in the source.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiNodeSpan {
+    /// Child indices from the top-level block, e.g. `[2, 0, 1]`.
+    pub path: Vec<u32>,
+    /// Node type: the block/inline JSON `type`, or `list_item`,
+    /// `table_row`, `table_cell`.
+    pub kind: String,
+    pub span: FfiSourceSpan,
+}
+
+impl From<fracta_note::SpanEntry> for FfiNodeSpan {
+    fn from(e: fracta_note::SpanEntry) -> Self {
+        FfiNodeSpan {
+            path: e.path.into_iter().map(|i| i as u32).collect(),
+            kind: e.kind.to_string(),
+            span: e.span.into(),
+        }
+    }
+}
+
+/// Where a reference was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiLinkKind {
+    Link,
+    Image,
+    WikiLink,
+    Embed,
+    FrontMatter,
+}
+
+impl From<fracta_note::LinkKind> for FfiLinkKind {
+    fn from(k: fracta_note::LinkKind) -> Self {
+        match k {
+            fracta_note::LinkKind::Link => FfiLinkKind::Link,
+            fracta_note::LinkKind::Image => FfiLinkKind::Image,
+            fracta_note::LinkKind::WikiLink => FfiLinkKind::WikiLink,
+            fracta_note::LinkKind::Embed => FfiLinkKind::Embed,
+            fracta_note::LinkKind::FrontMatter => FfiLinkKind::FrontMatter,
+        }
+    }
+}
+
+/// Whether a reference stays inside the workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiLinkScope {
+    Internal,
+    /// A URL with a scheme (`https:`, `mailto:`, …).
+    External,
+}
+
+impl From<fracta_note::LinkScope> for FfiLinkScope {
+    fn from(s: fracta_note::LinkScope) -> Self {
+        match s {
+            fracta_note::LinkScope::Internal => FfiLinkScope::Internal,
+            fracta_note::LinkScope::External => FfiLinkScope::External,
+        }
+    }
+}
+
+/// One outgoing reference of a document.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiLink {
+    pub kind: FfiLinkKind,
+    pub scope: FfiLinkScope,
+    /// URL, path or note name, without the anchor of an internal link.
+    pub target: String,
+    /// Heading or `^block` anchor, without the `#`.
+    pub anchor: Option<String>,
+    /// Front matter field holding the reference, e.g. `loot[0]`.
+    pub field: Option<String>,
+    pub span: FfiSourceSpan,
+}
+
+impl From<fracta_note::Link> for FfiLink {
+    fn from(l: fracta_note::Link) -> Self {
+        FfiLink {
+            kind: l.kind.into(),
+            scope: l.scope.into(),
+            target: l.target,
+            anchor: l.anchor,
+            field: l.field.map(|f| f.to_string()),
+            span: l.span.into(),
+        }
     }
 }
 
@@ -448,6 +1641,8 @@ pub struct FfiBuildStats {
     pub markdown_indexed: u32,
     /// Number of metadata entries updated.
     pub metadata_updated: u32,
+    /// Entries the walk could not read.
+    pub walk: FfiWalkReport,
 }
 
 impl From<fracta_index::BuildStats> for FfiBuildStats {
@@ -456,6 +1651,75 @@ impl From<fracta_index::BuildStats> for FfiBuildStats {
             files_scanned: s.files_scanned as u32,
             markdown_indexed: s.markdown_indexed as u32,
             metadata_updated: s.metadata_updated as u32,
+            walk: s.walk.into(),
+        }
+    }
+}
+
+/// Why a walk skipped an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiSkipReason {
+    PermissionDenied,
+    Vanished,
+    MetadataError,
+    SymlinkLoop,
+    Unreadable,
+}
+
+impl From<fracta_vfs::SkipReason> for FfiSkipReason {
+    fn from(r: fracta_vfs::SkipReason) -> Self {
+        match r {
+            fracta_vfs::SkipReason::PermissionDenied => FfiSkipReason::PermissionDenied,
+            fracta_vfs::SkipReason::Vanished => FfiSkipReason::Vanished,
+            fracta_vfs::SkipReason::MetadataError => FfiSkipReason::MetadataError,
+            fracta_vfs::SkipReason::SymlinkLoop => FfiSkipReason::SymlinkLoop,
+            fracta_vfs::SkipReason::Unreadable => FfiSkipReason::Unreadable,
+        }
+    }
+}
+
+/// An entry a walk (or ignore rule loading) could not read.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiSkippedEntry {
+    pub path: String,
+    pub reason: FfiSkipReason,
+    pub message: String,
+}
+
+impl From<fracta_vfs::SkippedEntry> for FfiSkippedEntry {
+    fn from(s: fracta_vfs::SkippedEntry) -> Self {
+        FfiSkippedEntry {
+            path: s.path.display().to_string(),
+            reason: s.reason.into(),
+            message: s.message,
+        }
+    }
+}
+
+/// What a walk left out, with counts per reason.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiWalkReport {
+    pub skipped: Vec<FfiSkippedEntry>,
+    pub permission_denied: u32,
+    pub vanished: u32,
+    pub metadata_errors: u32,
+    pub symlink_loops: u32,
+    pub unreadable: u32,
+    /// The walk was cancelled before it finished.
+    pub cancelled: bool,
+}
+
+impl From<fracta_vfs::WalkReport> for FfiWalkReport {
+    fn from(r: fracta_vfs::WalkReport) -> Self {
+        use fracta_vfs::SkipReason;
+        FfiWalkReport {
+            permission_denied: r.count(SkipReason::PermissionDenied) as u32,
+            vanished: r.count(SkipReason::Vanished) as u32,
+            metadata_errors: r.count(SkipReason::MetadataError) as u32,
+            symlink_loops: r.count(SkipReason::SymlinkLoop) as u32,
+            unreadable: r.count(SkipReason::Unreadable) as u32,
+            cancelled: r.cancelled,
+            skipped: r.skipped.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -706,40 +1970,69 @@ pub struct FfiFsEvent {
     pub path: String,
     /// For rename events, the original path (before rename).
     pub renamed_from: Option<String>,
+    /// Scope of the affected path (of the new path for renames).
+    pub scope: FfiScope,
+}
+
+impl From<fracta_vfs::ScopedEvent> for FfiFsEvent {
+    fn from(e: fracta_vfs::ScopedEvent) -> Self {
+        let (kind, path, renamed_from) = match e.event {
+            fracta_vfs::FsEvent::Created(p) => (FfiFsEventKind::Created, p, None),
+            fracta_vfs::FsEvent::Modified(p) => (FfiFsEventKind::Modified, p, None),
+            fracta_vfs::FsEvent::Deleted(p) => (FfiFsEventKind::Deleted, p, None),
+            fracta_vfs::FsEvent::Renamed { from, to } => (
+                FfiFsEventKind::Renamed,
+                to,
+                Some(from.display().to_string()),
+            ),
+        };
+        FfiFsEvent {
+            kind,
+            path: path.display().to_string(),
+            renamed_from,
+            scope: e.scope.into(),
+        }
+    }
+}
+
+/// An item drained from an `FfiWatcher`'s poll queue.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiWatchEvent {
+    /// A filesystem change.
+    Change { event: FfiFsEvent },
+    /// The queue was not drained in time and `missed` events were dropped; rescan.
+    RescanNeeded { missed: u64 },
 }
 
-impl From<fracta_vfs::FsEvent> for FfiFsEvent {
-    fn from(e: fracta_vfs::FsEvent) -> Self {
+impl From<fracta_vfs::WatchEvent> for FfiWatchEvent {
+    fn from(e: fracta_vfs::WatchEvent) -> Self {
         match e {
-            fracta_vfs::FsEvent::Created(p) => FfiFsEvent {
-                kind: FfiFsEventKind::Created,
-                path: p.display().to_string(),
-                renamed_from: None,
-            },
-            fracta_vfs::FsEvent::Modified(p) => FfiFsEvent {
-                kind: FfiFsEventKind::Modified,
-                path: p.display().to_string(),
-                renamed_from: None,
-            },
-            fracta_vfs::FsEvent::Deleted(p) => FfiFsEvent {
-                kind: FfiFsEventKind::Deleted,
-                path: p.display().to_string(),
-                renamed_from: None,
-            },
-            fracta_vfs::FsEvent::Renamed { from, to } => FfiFsEvent {
-                kind: FfiFsEventKind::Renamed,
-                path: to.display().to_string(),
-                renamed_from: Some(from.display().to_string()),
+            fracta_vfs::WatchEvent::Change(event) => FfiWatchEvent::Change {
+                event: event.into(),
             },
+            fracta_vfs::WatchEvent::RescanNeeded { missed } => {
+                FfiWatchEvent::RescanNeeded { missed }
+            }
         }
     }
 }
 
+/// Receives filesystem events pushed by an `FfiWatcher` subscription.
+///
+/// Called on a background thread owned by the subscription.
+#[uniffi::export(callback_interface)]
+pub trait FfiWatchListener: Send + Sync {
+    /// A filesystem change.
+    fn on_event(&self, event: FfiFsEvent);
+    /// The listener fell behind and `missed` events were dropped; rescan.
+    fn on_rescan_needed(&self, missed: u64);
+}
+
 /// Filesystem watcher for a Location root.
 ///
-/// Watches a directory tree for changes and accumulates events.
-/// Call `drain_events()` periodically (e.g. from a Swift Timer) to
-/// retrieve pending changes and trigger incremental index updates.
+/// Watches a directory tree for changes. Either `subscribe()` a listener to
+/// have changes pushed, or call `drain_watch_events()` periodically to
+/// retrieve them; both can trigger incremental index updates.
 #[derive(uniffi::Object)]
 pub struct FfiWatcher {
     inner: Mutex<Option<fracta_vfs::LocationWatcher>>,
@@ -747,7 +2040,7 @@ pub struct FfiWatcher {
 
 #[uniffi::export]
 impl FfiWatcher {
-    /// Start watching a directory tree.
+    /// Start watching a bare directory tree (all events are Plain).
     #[uniffi::constructor]
     pub fn start(root: String) -> Result<Self, FfiError> {
         let watcher = fracta_vfs::LocationWatcher::start(&PathBuf::from(&root)).map_err(|e| {
@@ -760,18 +2053,76 @@ impl FfiWatcher {
         })
     }
 
+    /// Start watching a Location. Ignored paths are suppressed unless
+    /// `include_ignored` is set; ignore rule edits are picked up live.
+    #[uniffi::constructor]
+    pub fn for_location(location: &FfiLocation, include_ignored: bool) -> Result<Self, FfiError> {
+        let location = location.inner.lock().unwrap();
+        let options = fracta_vfs::WatchOptions {
+            include_ignored,
+            ..Default::default()
+        };
+        let watcher = fracta_vfs::LocationWatcher::for_location(&location, options)?;
+        Ok(FfiWatcher {
+            inner: Mutex::new(Some(watcher)),
+        })
+    }
+
     /// Drain all pending filesystem events.
     ///
     /// Returns accumulated events since the last drain and clears the queue.
-    /// Call this from a periodic Timer on the Swift side.
+    /// The queue is bounded: if it overflowed, the first item is
+    /// `RescanNeeded`. Prefer `subscribe` over polling this from a Timer.
+    pub fn drain_watch_events(&self) -> Vec<FfiWatchEvent> {
+        let guard = self.inner.lock().unwrap();
+        match guard.as_ref() {
+            Some(watcher) => watcher
+                .drain_watch_events()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Drain pending filesystem changes, discarding any overflow notice.
+    ///
+    /// Use `drain_watch_events` to learn when events were dropped.
     pub fn drain_events(&self) -> Vec<FfiFsEvent> {
         let guard = self.inner.lock().unwrap();
         match guard.as_ref() {
-            Some(watcher) => watcher.drain_events().into_iter().map(Into::into).collect(),
+            Some(watcher) => watcher
+                .drain_scoped_events()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
             None => Vec::new(),
         }
     }
 
+    /// Push events to `listener` as they happen, instead of polling.
+    ///
+    /// Delivery runs on a dedicated thread until the watcher is stopped.
+    /// Several listeners may be subscribed at once.
+    pub fn subscribe(&self, listener: Box<dyn FfiWatchListener>) -> Result<(), FfiError> {
+        let guard = self.inner.lock().unwrap();
+        let watcher = guard.as_ref().ok_or_else(|| FfiError::InvalidArgument {
+            message: "watcher is stopped".to_string(),
+        })?;
+        let mut subscription = watcher.subscribe();
+        std::thread::spawn(move || {
+            while let Some(event) = subscription.blocking_recv() {
+                match event {
+                    fracta_vfs::WatchEvent::Change(e) => listener.on_event(e.into()),
+                    fracta_vfs::WatchEvent::RescanNeeded { missed } => {
+                        listener.on_rescan_needed(missed)
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
     /// Check if there are pending events without consuming them.
     pub fn has_pending_events(&self) -> bool {
         let guard = self.inner.lock().unwrap();
@@ -843,6 +2194,395 @@ mod tests {
         assert_eq!(entries[0].scope, FfiScope::Managed);
     }
 
+    #[test]
+    fn test_folder_ignores_and_explain() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        location.init().unwrap();
+        location.create_folder(format!("{}/notes", root)).unwrap();
+        location
+            .create_folder(format!("{}/notes/drafts", root))
+            .unwrap();
+
+        location
+            .set_folder_ignores("notes".to_string(), vec!["drafts/".to_string()])
+            .unwrap();
+        let drafts = format!("{}/notes/drafts", root);
+        assert_eq!(location.scope_of(drafts.clone()), Some(FfiScope::Ignored));
+        let why = location.explain_ignored(drafts.clone()).unwrap().unwrap();
+        assert_eq!(
+            why.source,
+            FfiIgnoreSource::FolderOverride {
+                folder: "notes".to_string()
+            }
+        );
+
+        location
+            .set_folder_ignores("notes".to_string(), Vec::new())
+            .unwrap();
+        assert_eq!(location.scope_of(drafts), Some(FfiScope::Managed));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlink_policy_and_entries() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        std::fs::create_dir(tmp.path().join("real")).unwrap();
+        std::os::unix::fs::symlink(tmp.path().join("real"), tmp.path().join("alias")).unwrap();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        location.init().unwrap();
+        assert_eq!(location.symlink_policy(), FfiSymlinkPolicy::FollowWithin);
+
+        let entries = location.list_directory(root.clone()).unwrap();
+        let alias = entries.iter().find(|e| e.name == "alias").unwrap();
+        assert_eq!(alias.kind, FfiEntryKind::Folder);
+        assert_eq!(alias.symlink_kind, Some(FfiSymlinkKind::Internal));
+        assert!(alias.symlink_target.as_deref().unwrap().ends_with("real"));
+
+        location
+            .set_symlink_policy(FfiSymlinkPolicy::Ignore)
+            .unwrap();
+        let entries = location.list_directory(root).unwrap();
+        assert!(entries.iter().all(|e| e.name != "alias"));
+    }
+
+    #[test]
+    fn test_cloud_files() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        std::fs::write(tmp.path().join(".a.md.icloud"), "").unwrap();
+        std::fs::write(tmp.path().join("b (conflicted copy 2024-05-01).md"), "").unwrap();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        location.init().unwrap();
+
+        let entries = location.list_directory(root.clone()).unwrap();
+        let stub = entries.iter().find(|e| e.name == ".a.md.icloud").unwrap();
+        assert_eq!(stub.cloud_kind, Some(FfiCloudFileKind::Placeholder));
+        assert_eq!(stub.scope, FfiScope::Plain);
+
+        let copies = location.conflict_copies().unwrap();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].cloud_original, Some(format!("{}/b.md", root)));
+    }
+
+    #[test]
+    fn test_settings_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root);
+        location.init().unwrap();
+
+        let mut settings = location.settings().unwrap();
+        assert_eq!(settings.default_privacy, FfiPrivacy::Internal);
+        assert!(!settings.ai_enabled);
+
+        settings.ai_enabled = true;
+        settings.index_extensions.push("txt".to_string());
+        settings.symlinks = FfiSymlinkPolicy::Ignore;
+        location.update_settings(settings.clone()).unwrap();
+        assert_eq!(location.settings().unwrap(), settings);
+        assert_eq!(location.symlink_policy(), FfiSymlinkPolicy::Ignore);
+
+        settings.watcher_debounce_ms = 0;
+        assert!(matches!(
+            location.update_settings(settings),
+            Err(FfiError::InvalidArgument { .. })
+        ));
+        assert_eq!(location.settings().unwrap().watcher_debounce_ms, 500);
+    }
+
+    #[test]
+    fn test_update_settings_requires_managed_location() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root);
+
+        let settings = location.settings().unwrap();
+        assert!(matches!(
+            location.update_settings(settings),
+            Err(FfiError::InvalidArgument { .. })
+        ));
+        assert!(!tmp.path().join(".fracta").exists());
+    }
+
+    #[test]
+    fn test_journal_records_actor() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        location.init().unwrap();
+
+        let mut settings = location.settings().unwrap();
+        settings.journal_enabled = true;
+        location.update_settings(settings).unwrap();
+
+        location
+            .create_file(format!("{}/a.md", root), "user".to_string())
+            .unwrap();
+        location.set_actor(FfiActor::Ai);
+        location
+            .write_file(format!("{}/a.md", root), "ai".to_string())
+            .unwrap();
+
+        let all = location
+            .journal_entries(FfiJournalQuery::default())
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].op, FfiJournalOp::Create);
+
+        let by_ai = location
+            .journal_entries(FfiJournalQuery {
+                actor: Some(FfiActor::Ai),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(by_ai.len(), 1);
+        assert_eq!(by_ai[0].op, FfiJournalOp::Write);
+        assert_eq!(by_ai[0].before, all[0].after);
+
+        let bad = location.journal_entries(FfiJournalQuery {
+            since: Some("yesterday".to_string()),
+            ..Default::default()
+        });
+        assert!(matches!(bad, Err(FfiError::InvalidArgument { .. })));
+    }
+
+    #[test]
+    fn test_name_collisions() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        location.init().unwrap();
+        location
+            .create_file(format!("{}/Note.md", root), "a".to_string())
+            .unwrap();
+
+        let result = location.create_file(format!("{}/note.md", root), "b".to_string());
+        let Err(FfiError::NameCollision { collision }) = result else {
+            panic!("expected a name collision, got {result:?}");
+        };
+        assert_eq!(
+            collision.paths,
+            vec![format!("{}/Note.md", root), format!("{}/note.md", root)]
+        );
+        assert_eq!(collision.kind, FfiCollisionKind::Case);
+        assert!(location.find_name_collisions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_uid_lifecycle() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        location.init().unwrap();
+        let note = format!("{}/note.md", root);
+        location.create_file(note.clone(), "x".to_string()).unwrap();
+
+        assert_eq!(location.uid_of(note.clone()).unwrap(), None);
+        let uid = location.ensure_uid(note.clone()).unwrap();
+        let renamed = location.rename(note, "renamed.md".to_string()).unwrap();
+        assert_eq!(location.path_of_uid(uid.clone()).unwrap(), Some(renamed));
+
+        let copy = location.duplicate(format!("{}/renamed.md", root)).unwrap();
+        assert!(matches!(
+            location.claim_uid(copy, uid),
+            Err(FfiError::InvalidArgument { .. })
+        ));
+    }
+
+    #[test]
+    fn test_write_file_if_conflict() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        let note = format!("{}/note.md", root);
+        location
+            .create_file(note.clone(), "v1".to_string())
+            .unwrap();
+
+        let read = location.read_file_versioned(note.clone(), false).unwrap();
+        assert_eq!(read.content, "v1");
+        // Change the size too: mtimes may be too coarse to tell quick saves apart
+        let next = location
+            .write_file_if(note.clone(), "v2!".to_string(), read.version.clone())
+            .unwrap();
+
+        // A save based on the stale version must not clobber v2
+        let stale = location.write_file_if(note.clone(), "v3".to_string(), read.version);
+        assert!(matches!(stale, Err(FfiError::Conflict { actual: Some(ref a), .. }) if *a == next));
+
+        let hashed = location.read_file_versioned(note.clone(), true).unwrap();
+        assert!(matches!(hashed.version, FfiFileVersion::Hash { .. }));
+        location
+            .write_file_if(note.clone(), "v3".to_string(), hashed.version)
+            .unwrap();
+        assert_eq!(location.read_file(note).unwrap(), "v3");
+    }
+
+    #[test]
+    fn test_trash_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        location.init().unwrap();
+
+        let file_path = format!("{}/note.md", root);
+        location
+            .create_file(file_path.clone(), "hello".to_string())
+            .unwrap();
+        location.delete_file(file_path.clone()).unwrap();
+        assert!(location.list_directory(root.clone()).unwrap().is_empty());
+
+        let trash = location.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].original_path, file_path);
+
+        let restored = location
+            .restore_from_trash(trash[0].id.clone(), FfiRestoreConflict::Fail)
+            .unwrap();
+        assert_eq!(restored, file_path);
+        assert_eq!(location.read_file(file_path.clone()).unwrap(), "hello");
+
+        location.delete_permanently(file_path).unwrap();
+        assert!(location.list_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_copy_with_progress() {
+        struct Recorder(Arc<Mutex<Vec<FfiCopyProgress>>>);
+        impl FfiCopyListener for Recorder {
+            fn on_progress(&self, progress: FfiCopyProgress) {
+                self.0.lock().unwrap().push(progress);
+            }
+        }
+
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_str().unwrap().to_string();
+        let location = FfiLocation::new("test".to_string(), root.clone());
+        location.init().unwrap();
+        location.create_folder(format!("{}/src", root)).unwrap();
+        location
+            .create_file(format!("{}/src/a.md", root), "a".to_string())
+            .unwrap();
+
+        let dup = location.duplicate(format!("{}/src/a.md", root)).unwrap();
+        assert_eq!(dup, format!("{}/src/a copy.md", root));
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let copied = location
+            .copy_entry(
+                format!("{}/src", root),
+                format!("{}/dst", root),
+                FfiCopyOptions::default(),
+                Some(Box::new(Recorder(reports.clone()))),
+            )
+            .unwrap();
+        assert_eq!(copied, format!("{}/dst", root));
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[1].files_done, reports[1].files_total);
+    }
+
+    #[test]
+    fn test_copy_to_location_releases_dest_lock() {
+        // Touches the destination while the copy is running
+        struct Prober(Arc<FfiLocation>);
+        impl FfiCopyListener for Prober {
+            fn on_progress(&self, _progress: FfiCopyProgress) {
+                self.0.settings().unwrap();
+            }
+        }
+
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        let root_a = a.path().to_str().unwrap().to_string();
+        let root_b = b.path().to_str().unwrap().to_string();
+        let loc_a = FfiLocation::new("A".to_string(), root_a.clone());
+        let loc_b = Arc::new(FfiLocation::new("B".to_string(), root_b.clone()));
+        loc_a.init().unwrap();
+        loc_b.init().unwrap();
+        loc_a
+            .create_file(format!("{}/a.md", root_a), "a".to_string())
+            .unwrap();
+
+        let copied = loc_a
+            .copy_to_location(
+                format!("{}/a.md", root_a),
+                loc_b.clone(),
+                format!("{}/a.md", root_b),
+                FfiCopyOptions::default(),
+                Some(Box::new(Prober(loc_b.clone()))),
+            )
+            .unwrap();
+        assert_eq!(loc_b.read_file(copied).unwrap(), "a");
+    }
+
+    #[test]
+    fn test_location_registry() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        let root_a = a.path().to_str().unwrap().to_string();
+        let root_b = b.path().to_str().unwrap().to_string();
+
+        let registry = FfiLocationRegistry::new_in_memory();
+        let loc_a = FfiLocation::new("A".to_string(), root_a.clone());
+        loc_a.init().unwrap();
+        let id_a = registry.add(&loc_a).unwrap();
+        registry
+            .add(&FfiLocation::new("B".to_string(), root_b))
+            .unwrap();
+
+        // Nested roots are rejected
+        let nested = FfiLocation::new("nested".to_string(), format!("{}/sub", root_a));
+        assert!(matches!(
+            registry.add(&nested),
+            Err(FfiError::InvalidArgument { .. })
+        ));
+
+        let owner = registry
+            .location_for(format!("{}/note.md", root_a))
+            .unwrap();
+        assert_eq!(owner.id, id_a);
+
+        registry.relabel(id_a.clone(), "Alpha".to_string()).unwrap();
+        assert_eq!(registry.location(id_a.clone()).unwrap().label(), "Alpha");
+
+        registry.remove(id_a).unwrap();
+        assert_eq!(registry.list().len(), 1);
+    }
+
+    #[test]
+    fn test_registry_relocate() {
+        let tmp = TempDir::new().unwrap();
+        let old_root = tmp.path().join("a");
+        let new_root = tmp.path().join("b");
+        std::fs::create_dir(&old_root).unwrap();
+
+        let registry = FfiLocationRegistry::new_in_memory();
+        let location = FfiLocation::new("A".to_string(), old_root.display().to_string());
+        location.init().unwrap();
+        let id = registry.add(&location).unwrap();
+
+        std::fs::rename(&old_root, &new_root).unwrap();
+        let new_root = new_root.display().to_string();
+        assert_eq!(
+            registry.find_relocated(new_root.clone()).unwrap(),
+            Some(id.clone())
+        );
+        registry.relocate(id.clone(), new_root.clone()).unwrap();
+        assert_eq!(registry.list()[0].root, new_root);
+
+        let other = TempDir::new().unwrap();
+        let other_location = FfiLocation::new("B".to_string(), other.path().display().to_string());
+        other_location.init().unwrap();
+        assert!(matches!(
+            registry.relocate(id, other.path().display().to_string()),
+            Err(FfiError::InvalidArgument { .. })
+        ));
+    }
+
     #[test]
     fn test_document_parsing() {
         let markdown = r#"---
@@ -872,6 +2612,45 @@ This is a test document.
         assert!(plain.contains("test document"));
     }
 
+    #[test]
+    fn test_document_spans() {
+        let markdown = "---\ntitle: T\n---\n\n# Hello\n\n- [ ] Task\n";
+        let doc = FfiDocument::parse(markdown.to_string());
+        let spans = doc.spans();
+
+        let heading = &spans[0];
+        assert_eq!(heading.kind, "heading");
+        assert_eq!(heading.path, vec![0]);
+        assert_eq!(heading.span.start_line, 5);
+        let range = heading.span.start_offset as usize..heading.span.end_offset as usize;
+        assert_eq!(&markdown[range], "# Hello");
+
+        let item = spans.iter().find(|s| s.kind == "list_item").unwrap();
+        assert_eq!(item.path, vec![1, 0]);
+        assert_eq!(item.span.start_line, 7);
+    }
+
+    #[test]
+    fn test_document_links() {
+        let markdown = "---\nloot: [\"./rewards/x.md\"]\n---\n\nSee [[Quest#^goal]] and [docs](https://fracta.app).\n";
+        let doc = FfiDocument::parse(markdown.to_string());
+        let links = doc.links();
+        assert_eq!(links.len(), 3);
+
+        assert_eq!(links[0].kind, FfiLinkKind::FrontMatter);
+        assert_eq!(links[0].field.as_deref(), Some("loot[0]"));
+        assert_eq!(links[0].target, "./rewards/x.md");
+
+        assert_eq!(links[1].kind, FfiLinkKind::WikiLink);
+        assert_eq!(links[1].scope, FfiLinkScope::Internal);
+        assert_eq!(links[1].anchor.as_deref(), Some("^goal"));
+        let range = links[1].span.start_offset as usize..links[1].span.end_offset as usize;
+        assert_eq!(&markdown[range], "[[Quest#^goal]]");
+
+        assert_eq!(links[2].kind, FfiLinkKind::Link);
+        assert_eq!(links[2].scope, FfiLinkScope::External);
+    }
+
     #[test]
     fn test_index_search() {
         let tmp = TempDir::new().unwrap();
@@ -902,6 +2681,8 @@ This is a test document.
         let stats = index.build_full(&location).unwrap();
         assert_eq!(stats.files_scanned, 2);
         assert_eq!(stats.markdown_indexed, 2);
+        assert!(stats.walk.skipped.is_empty());
+        assert_eq!(stats.walk.permission_denied, 0);
 
         // Search
         let hits = index.search("Rust".to_string(), 10).unwrap();
@@ -948,6 +2729,7 @@ This is a test document.
         // Drain events
         let events = watcher.drain_events();
         assert!(!events.is_empty(), "Expected filesystem events");
+        assert!(events.iter().all(|e| e.scope == FfiScope::Plain));
 
         // Stop watcher
         watcher.stop();
@@ -958,6 +2740,37 @@ This is a test document.
         assert!(events2.is_empty());
     }
 
+    #[test]
+    fn test_watcher_subscription_pushes_events() {
+        struct Channel(Mutex<std::sync::mpsc::Sender<FfiFsEvent>>);
+        impl FfiWatchListener for Channel {
+            fn on_event(&self, event: FfiFsEvent) {
+                let _ = self.0.lock().unwrap().send(event);
+            }
+            fn on_rescan_needed(&self, _missed: u64) {}
+        }
+
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let watcher = FfiWatcher::start(root.to_str().unwrap().to_string()).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        watcher
+            .subscribe(Box::new(Channel(Mutex::new(tx))))
+            .unwrap();
+
+        std::fs::write(root.join("pushed.md"), "hello").unwrap();
+        let event = rx
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .expect("Expected a pushed event");
+        assert_eq!(event.kind, FfiFsEventKind::Created);
+
+        watcher.stop();
+        assert!(watcher
+            .subscribe(Box::new(Channel(Mutex::new(std::sync::mpsc::channel().0))))
+            .is_err());
+    }
+
     #[test]
     fn test_ai_engine_echo() {
         let engine = FfiAiEngine::new_echo();
@@ -6,7 +6,9 @@
 //!
 //! Both layers are cache — the filesystem remains the source of truth.
 //! Deleting `.fracta/cache/index.sqlite` and `.fracta/cache/search/`
-//! triggers a full rebuild.
+//! triggers a full rebuild. Entries are keyed by paths relative to the
+//! Location root, so the index stays valid when the root is moved and the
+//! Location relocated.
 //!
 //! ## Usage
 //!
@@ -26,8 +28,13 @@ pub use search::{SearchHit, SearchIndex};
 
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, Utc};
 use fracta_note::Document;
-use fracta_vfs::{Entry, EntryKind, Location, Scope, WalkOptions};
+use fracta_vfs::{
+    normalize_path, CloudFile, CloudFileKind, Entry, EntryKind, IndexingSettings, Location,
+    LocationHandle, LocationSettings, RelPath, Scope, VfsBackend, VfsError, WalkOptions,
+    WalkReport, WalkStream, FRACTA_DIR,
+};
 
 /// Unified index combining SQLite metadata and Tantivy search.
 pub struct Index {
@@ -45,7 +52,7 @@ pub struct Index {
 pub struct BuildStats {
     /// Number of files scanned.
     pub files_scanned: usize,
-    /// Number of Markdown files indexed.
+    /// Number of files whose content was parsed and indexed (Markdown by default).
     pub markdown_indexed: usize,
     /// Number of files added/updated in metadata.
     pub metadata_updated: usize,
@@ -53,6 +60,9 @@ pub struct BuildStats {
     pub stale_removed: usize,
     /// Duration of the build.
     pub duration_ms: u64,
+    /// Entries the walk could not read. Files under them are neither
+    /// updated nor removed from the index.
+    pub walk: WalkReport,
 }
 
 impl Index {
@@ -91,142 +101,210 @@ impl Index {
     ///
     /// Scans all managed files in the Location and indexes them.
     pub fn build_full(&mut self, location: &Location) -> Result<BuildStats> {
+        self.build_location(location, false)
+    }
+
+    /// Incremental update: re-index only changed files.
+    ///
+    /// Compares mtime against the stored value and re-indexes if changed.
+    pub fn update_incremental(&mut self, location: &Location) -> Result<BuildStats> {
+        self.build_location(location, true)
+    }
+
+    /// Build a full index from any [`VfsBackend`] (e.g. an in-memory fixture).
+    ///
+    /// A bare backend has no ignore rules: every file outside `.fracta/` is
+    /// indexed except cloud placeholders. Indexing
+    /// settings are read from the backend's `.fracta/config/settings.json`.
+    pub fn build_full_from(&mut self, backend: &dyn VfsBackend) -> Result<BuildStats> {
+        let settings = Self::backend_settings(backend)?;
+        let files = Self::backend_files(backend)?;
+        self.build(backend, files.into_iter(), false, &settings)
+    }
+
+    /// Incremental counterpart of [`Index::build_full_from`].
+    pub fn update_incremental_from(&mut self, backend: &dyn VfsBackend) -> Result<BuildStats> {
+        let settings = Self::backend_settings(backend)?;
+        let files = Self::backend_files(backend)?;
+        self.build(backend, files.into_iter(), true, &settings)
+    }
+
+    /// Index the managed files of a Location as the walk finds them.
+    ///
+    /// Whatever the walk could not list keeps its index entries: a folder
+    /// skipped for a permission error is not purged.
+    fn build_location(&mut self, location: &Location, incremental: bool) -> Result<BuildStats> {
         let start = std::time::Instant::now();
+        let settings = LocationSettings::load(&location.root)?.indexing;
+        let handle = location.handle()?;
+        let mut walk = Self::walk_managed(location)?;
+        let files = walk
+            .by_ref()
+            .filter(is_managed_file)
+            .filter_map(|entry| SourceFile::from_entry(handle, &entry));
         let mut stats = BuildStats::default();
+        let current_paths = self.index_files(handle, files, incremental, &settings, &mut stats)?;
+        stats.walk = walk.report();
 
-        // Collect all managed files
-        let options = WalkOptions {
-            include_ignored: false,
-            max_depth: None,
+        let unlisted: Vec<String> = if stats.walk.cancelled {
+            vec![String::new()]
+        } else {
+            stats
+                .walk
+                .skipped
+                .iter()
+                .filter_map(|skipped| skipped.path.strip_prefix(&location.root).ok())
+                .map(index_key)
+                .collect()
         };
-        let entries = location.walk(&location.root, &options)?;
-
-        let managed_files: Vec<_> = entries
-            .into_iter()
-            .filter(|e| e.kind == EntryKind::File && e.scope == Scope::Managed)
-            .collect();
+        self.finish(&current_paths, &unlisted, &mut stats, start)?;
+        Ok(stats)
+    }
 
-        stats.files_scanned = managed_files.len();
+    /// Index `files`, reading content through `backend`, then drop entries
+    /// for files that no longer exist.
+    ///
+    /// With `incremental`, files whose mtime matches the stored one are
+    /// skipped. `settings` decides whose content is read and parsed.
+    fn build(
+        &mut self,
+        backend: &dyn VfsBackend,
+        files: impl Iterator<Item = SourceFile>,
+        incremental: bool,
+        settings: &IndexingSettings,
+    ) -> Result<BuildStats> {
+        let start = std::time::Instant::now();
+        let mut stats = BuildStats::default();
+        let current_paths = self.index_files(backend, files, incremental, settings, &mut stats)?;
+        self.finish(&current_paths, &[], &mut stats, start)?;
+        Ok(stats)
+    }
 
+    /// Index `files` in a new write transaction. Returns their keys.
+    fn index_files(
+        &mut self,
+        backend: &dyn VfsBackend,
+        files: impl Iterator<Item = SourceFile>,
+        incremental: bool,
+        settings: &IndexingSettings,
+        stats: &mut BuildStats,
+    ) -> Result<Vec<String>> {
         // Begin write transactions
         self.search.begin_write()?;
 
-        // Index each file
-        let current_paths: Vec<String> = managed_files
-            .iter()
-            .filter_map(|e| self.relative_path(location, &e.path))
-            .collect();
-
-        for entry in &managed_files {
-            self.index_file(location, entry, &mut stats)?;
+        let mut current_paths = Vec::new();
+        for file in files {
+            stats.files_scanned += 1;
+            current_paths.push(file.key.clone());
+            if !incremental || self.needs_update(&file)? {
+                self.index_file(backend, &file, settings, stats)?;
+            }
         }
+        Ok(current_paths)
+    }
 
+    /// Drop entries for files missing from `current_paths`, except under
+    /// `unlisted`, and commit.
+    fn finish(
+        &mut self,
+        current_paths: &[String],
+        unlisted: &[String],
+        stats: &mut BuildStats,
+        start: std::time::Instant,
+    ) -> Result<()> {
         // Remove stale files from metadata
-        stats.stale_removed = self.metadata.remove_stale_files(&current_paths)?;
+        stats.stale_removed = self
+            .metadata
+            .remove_stale_files_except(current_paths, unlisted)?;
 
         // Commit search index
         self.search.commit()?;
 
         stats.duration_ms = start.elapsed().as_millis() as u64;
-        Ok(stats)
+        Ok(())
     }
 
-    /// Incremental update: re-index only changed files.
-    ///
-    /// Compares mtime against the stored value and re-indexes if changed.
-    pub fn update_incremental(&mut self, location: &Location) -> Result<BuildStats> {
-        let start = std::time::Instant::now();
-        let mut stats = BuildStats::default();
+    /// Whether a file changed since it was last indexed.
+    fn needs_update(&self, file: &SourceFile) -> Result<bool> {
+        Ok(match (file.modified, self.metadata.get_file(&file.key)?) {
+            (Some(mtime), Some(existing)) => {
+                // Compare mtime (with 1-second tolerance for filesystem precision)
+                (mtime - existing.mtime).num_seconds().abs() > 1
+            }
+            (None, _) => true, // Missing mtime: conservative, assume needs update
+            (_, None) => true, // New file
+        })
+    }
 
-        // Collect all managed files
+    /// Walk a Location, leaving out Ignored entries.
+    fn walk_managed(location: &Location) -> Result<WalkStream> {
         let options = WalkOptions {
             include_ignored: false,
             max_depth: None,
         };
-        let entries = location.walk(&location.root, &options)?;
-
-        let managed_files: Vec<_> = entries
-            .into_iter()
-            .filter(|e| e.kind == EntryKind::File && e.scope == Scope::Managed)
-            .collect();
-
-        stats.files_scanned = managed_files.len();
-
-        // Begin write transaction
-        self.search.begin_write()?;
-
-        let current_paths: Vec<String> = managed_files
-            .iter()
-            .filter_map(|e| self.relative_path(location, &e.path))
-            .collect();
-
-        // Check each file for changes
-        for entry in &managed_files {
-            let rel_path = match self.relative_path(location, &entry.path) {
-                Some(p) => p,
-                None => continue,
-            };
-
-            let needs_update = match (entry.modified, self.metadata.get_file(&rel_path)?) {
-                (Some(entry_mtime), Some(existing)) => {
-                    // Compare mtime (with 1-second tolerance for filesystem precision)
-                    (entry_mtime - existing.mtime).num_seconds().abs() > 1
-                }
-                (None, _) => true, // Missing mtime: conservative, assume needs update
-                (_, None) => true, // New file
-            };
+        Ok(location.walk_stream(&location.root, &options)?)
+    }
 
-            if needs_update {
-                self.index_file(location, entry, &mut stats)?;
+    /// Indexing settings stored in a bare backend, or the defaults.
+    fn backend_settings(backend: &dyn VfsBackend) -> Result<IndexingSettings> {
+        let rel = RelPath::new(Path::new(FRACTA_DIR).join("config").join("settings.json"))?;
+        match backend.stat(&rel) {
+            Ok(_) => {
+                let content = String::from_utf8_lossy(&backend.read(&rel)?).into_owned();
+                let path = backend.root().join(rel.as_path());
+                Ok(LocationSettings::parse(&content, &path)?.indexing)
             }
+            Err(VfsError::NotFound(_)) => Ok(IndexingSettings::default()),
+            Err(e) => Err(e.into()),
         }
-
-        // Remove stale files
-        stats.stale_removed = self.metadata.remove_stale_files(&current_paths)?;
-
-        // Commit
-        self.search.commit()?;
-
-        stats.duration_ms = start.elapsed().as_millis() as u64;
-        Ok(stats)
     }
 
-    /// Compute relative path from Location root.
-    fn relative_path(&self, location: &Location, abs_path: &Path) -> Option<String> {
-        abs_path
-            .strip_prefix(&location.root)
-            .ok()
-            .map(|p| p.to_string_lossy().to_string())
+    /// The indexable files of a bare backend.
+    fn backend_files(backend: &dyn VfsBackend) -> Result<Vec<SourceFile>> {
+        Ok(backend
+            .walk_files(&RelPath::root())?
+            .into_iter()
+            .filter(|(rel, _)| {
+                !rel.starts_with(FRACTA_DIR)
+                    && !is_placeholder(CloudFile::detect(rel.as_path()).as_ref())
+            })
+            .map(|(rel, stat)| SourceFile {
+                key: index_key(rel.as_path()),
+                rel,
+                modified: stat.modified,
+                size: stat.size,
+            })
+            .collect())
     }
 
     /// Index a single file.
     fn index_file(
         &mut self,
-        location: &Location,
-        entry: &Entry,
+        backend: &dyn VfsBackend,
+        file: &SourceFile,
+        settings: &IndexingSettings,
         stats: &mut BuildStats,
     ) -> Result<()> {
-        let rel_path = match self.relative_path(location, &entry.path) {
-            Some(p) => p,
-            None => return Ok(()),
-        };
+        let rel_path = &file.key;
 
         // Create file entry for metadata
         // Use current time as fallback when mtime is unavailable (conservative: marks as "fresh")
         let file_entry = FileEntry {
             path: rel_path.clone(),
-            mtime: entry.modified.unwrap_or_else(chrono::Utc::now),
-            size: entry.size,
+            mtime: file.modified.unwrap_or_else(chrono::Utc::now),
+            size: file.size,
             content_hash: None, // TODO: compute blake3 hash
             indexed: false,
         };
 
-        // Check if it's a Markdown file
-        let is_markdown = rel_path.ends_with(".md") || rel_path.ends_with(".markdown");
-
-        if is_markdown {
+        // Parse content only for configured extensions within the size limit
+        if settings.indexes(file.rel.as_path(), file.size) {
             // Read and parse the file
-            if let Ok(content) = std::fs::read_to_string(&entry.path) {
+            let content = backend
+                .read(&file.rel)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok());
+            if let Some(content) = content {
                 let doc = Document::parse(&content);
 
                 // Extract metadata from front matter
@@ -255,11 +333,11 @@ impl Index {
                 let mut indexed_entry = file_entry.clone();
                 indexed_entry.indexed = true;
                 self.metadata.upsert_file(&indexed_entry)?;
-                self.metadata.upsert_metadata(&rel_path, &file_meta)?;
+                self.metadata.upsert_metadata(rel_path, &file_meta)?;
 
                 // Update search index
                 self.search
-                    .add_document(&rel_path, file_meta.title.as_deref(), &plain_text)?;
+                    .add_document(rel_path, file_meta.title.as_deref(), &plain_text)?;
 
                 stats.markdown_indexed += 1;
             } else {
@@ -295,17 +373,17 @@ impl Index {
 
     /// Get metadata for a file.
     pub fn get_metadata(&self, path: &str) -> Result<Option<FileMetadata>> {
-        self.metadata.get_metadata(path)
+        self.metadata.get_metadata(&normalize_key(path))
     }
 
     /// Get a file entry.
     pub fn get_file(&self, path: &str) -> Result<Option<FileEntry>> {
-        self.metadata.get_file(path)
+        self.metadata.get_file(&normalize_key(path))
     }
 
     /// List files in a directory (from cache, no disk access).
     pub fn list_directory(&self, dir: &str) -> Result<Vec<FileEntry>> {
-        self.metadata.list_directory(dir)
+        self.metadata.list_directory(&normalize_key(dir))
     }
 
     /// Get total file count.
@@ -324,9 +402,59 @@ impl Index {
     }
 }
 
+/// Whether a walked entry is a file the index covers.
+///
+/// Cloud placeholders are left out, so indexing never forces a download.
+fn is_managed_file(entry: &Entry) -> bool {
+    entry.kind == EntryKind::File
+        && entry.scope == Scope::Managed
+        && !is_placeholder(entry.cloud.as_ref())
+}
+
+/// Whether `cloud` marks a placeholder for a file that is not downloaded.
+fn is_placeholder(cloud: Option<&CloudFile>) -> bool {
+    cloud.is_some_and(|cloud| cloud.kind == CloudFileKind::Placeholder)
+}
+
+/// NFC form of a caller-supplied relative path, matching stored keys.
+fn normalize_key(path: &str) -> String {
+    index_key(Path::new(path))
+}
+
+/// Index key for a path relative to the Location root.
+///
+/// Keys are NFC-normalized so that the same name stored decomposed on disk
+/// (as macOS does) maps to one entry.
+fn index_key(rel: &Path) -> String {
+    normalize_path(rel).to_string_lossy().to_string()
+}
+
+/// A file to (re-)index, found by a Location walk or a backend listing.
+struct SourceFile {
+    /// Path used to read the content.
+    rel: RelPath,
+    /// Index key (see [`index_key`]).
+    key: String,
+    modified: Option<DateTime<Utc>>,
+    size: u64,
+}
+
+impl SourceFile {
+    fn from_entry(handle: &LocationHandle, entry: &Entry) -> Option<Self> {
+        let rel = handle.relativize(&entry.path).ok()?;
+        Some(SourceFile {
+            key: index_key(rel.as_path()),
+            rel,
+            modified: entry.modified,
+            size: entry.size,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fracta_vfs::{BackendOp, MemoryBackend};
     use tempfile::TempDir;
 
     fn create_test_location() -> (TempDir, Location) {
@@ -368,6 +496,7 @@ This is a test document about Rust programming.
 
         assert_eq!(stats.files_scanned, 1);
         assert_eq!(stats.markdown_indexed, 1);
+        assert!(stats.walk.is_complete());
         assert_eq!(index.file_count().unwrap(), 1);
         assert_eq!(index.indexed_count().unwrap(), 1);
 
@@ -455,6 +584,162 @@ tags: [AI, 学习]
         assert_eq!(index.file_count().unwrap(), 1);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_folder_not_purged() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (temp, location) = create_test_location();
+        let locked = temp.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::write(locked.join("secret.md"), "# Secret").unwrap();
+        std::fs::write(temp.path().join("a.md"), "# A").unwrap();
+
+        let mut index = Index::open_in_memory().unwrap();
+        index.build_full(&location).unwrap();
+        assert_eq!(index.file_count().unwrap(), 2);
+
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        if std::fs::read_dir(&locked).is_ok() {
+            // Running as root: permissions aren't enforced
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+            return;
+        }
+        std::fs::remove_file(temp.path().join("a.md")).unwrap();
+
+        let stats = index.build_full(&location).unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(!stats.walk.is_complete());
+        assert_eq!(stats.stale_removed, 1);
+        assert_eq!(
+            index.metadata.list_all_paths().unwrap(),
+            ["locked/secret.md"]
+        );
+    }
+
+    #[test]
+    fn test_index_survives_relocated_root() {
+        let temp = TempDir::new().unwrap();
+        let old_root = temp.path().join("Notes");
+        std::fs::create_dir_all(old_root.join("sub")).unwrap();
+        let mut location = Location::new("test", &old_root);
+        location.init().unwrap();
+        std::fs::write(old_root.join("a.md"), "# Alpha").unwrap();
+        std::fs::write(old_root.join("sub/b.md"), "# Beta").unwrap();
+
+        let cache = location.fracta_dir().join("cache");
+        let mut index = Index::open(&cache).unwrap();
+        index.build_full(&location).unwrap();
+        drop(index);
+
+        let new_root = temp.path().join("Moved");
+        std::fs::rename(&old_root, &new_root).unwrap();
+        location.relocate(&new_root).unwrap();
+
+        // Keys are relative: nothing is stale or re-read after the move
+        let mut index = Index::open(&location.fracta_dir().join("cache")).unwrap();
+        let stats = index.update_incremental(&location).unwrap();
+        assert_eq!(stats.files_scanned, 2);
+        assert_eq!(stats.metadata_updated, 0);
+        assert_eq!(stats.stale_removed, 0);
+        assert_eq!(index.search("Beta", 10).unwrap()[0].path, "sub/b.md");
+    }
+
+    #[test]
+    fn test_decomposed_name_uses_nfc_key() {
+        let (temp, location) = create_test_location();
+        // "é" decomposed, as macOS stores it
+        std::fs::write(temp.path().join("cafe\u{301}.md"), "# Café").unwrap();
+
+        let mut index = Index::open_in_memory().unwrap();
+        index.build_full(&location).unwrap();
+
+        assert!(index.get_file("caf\u{e9}.md").unwrap().is_some());
+        assert!(index.get_file("cafe\u{301}.md").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_cloud_placeholders_not_indexed() {
+        let (temp, location) = create_test_location();
+        std::fs::write(temp.path().join("note.md"), "# Note").unwrap();
+        std::fs::write(temp.path().join(".remote.md.icloud"), "stub").unwrap();
+        std::fs::write(
+            temp.path().join("note (conflicted copy 2024-05-01).md"),
+            "# Note",
+        )
+        .unwrap();
+
+        let mut index = Index::open_in_memory().unwrap();
+        index.build_full(&location).unwrap();
+        // Conflict copies are real files and stay searchable
+        assert_eq!(index.file_count().unwrap(), 2);
+        assert!(index.get_file("note.md").unwrap().is_some());
+        assert!(index
+            .get_file("note (conflicted copy 2024-05-01).md")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_build_from_memory_backend() {
+        let backend = MemoryBackend::default();
+        backend
+            .add_file("notes/ml.md", "---\ntitle: ML\n---\n机器学习笔记")
+            .unwrap();
+        backend.add_file("notes/image.png", [0u8; 4]).unwrap();
+        backend
+            .add_file(".fracta/config/settings.json", "{}")
+            .unwrap();
+
+        let mut index = Index::open_in_memory().unwrap();
+        let stats = index.build_full_from(&backend).unwrap();
+        assert_eq!(stats.files_scanned, 2);
+        assert_eq!(stats.markdown_indexed, 1);
+        assert_eq!(index.search("机器学习", 10).unwrap()[0].path, "notes/ml.md");
+
+        // Unchanged files are skipped, edits picked up
+        backend
+            .write_atomic(&RelPath::new("notes/ml.md").unwrap(), b"# Changed")
+            .unwrap();
+        let stats = index.update_incremental_from(&backend).unwrap();
+        assert_eq!(stats.metadata_updated, 1);
+        let meta = index.get_metadata("notes/ml.md").unwrap().unwrap();
+        assert_eq!(meta.title.as_deref(), Some("Changed"));
+    }
+
+    #[test]
+    fn test_indexing_settings() {
+        let backend = MemoryBackend::default();
+        backend.add_file("small.txt", "searchable words").unwrap();
+        backend.add_file("big.md", "x".repeat(64)).unwrap();
+        backend
+            .add_file(
+                ".fracta/config/settings.json",
+                r#"{"indexing":{"extensions":["md","txt"],"max_file_size":32}}"#,
+            )
+            .unwrap();
+
+        let mut index = Index::open_in_memory().unwrap();
+        let stats = index.build_full_from(&backend).unwrap();
+        assert_eq!(stats.files_scanned, 2);
+        assert_eq!(stats.markdown_indexed, 1);
+        assert!(index.get_file("small.txt").unwrap().unwrap().indexed);
+        assert!(!index.get_file("big.md").unwrap().unwrap().indexed);
+    }
+
+    #[test]
+    fn test_unreadable_file_keeps_metadata() {
+        let backend = MemoryBackend::default();
+        backend.add_file("a.md", "# A").unwrap();
+        backend.fail_next(BackendOp::Read, std::io::ErrorKind::PermissionDenied);
+
+        let mut index = Index::open_in_memory().unwrap();
+        let stats = index.build_full_from(&backend).unwrap();
+        assert_eq!(stats.markdown_indexed, 0);
+        let file = index.get_file("a.md").unwrap().unwrap();
+        assert!(!file.indexed);
+    }
+
     #[test]
     fn test_search_by_metadata() {
         let (temp, location) = create_test_location();
@@ -233,6 +233,31 @@ impl MetadataStore {
 
     /// Remove files that no longer exist in the given set of paths.
     pub fn remove_stale_files(&self, current_paths: &[String]) -> Result<usize> {
+        self.remove_stale_files_except(current_paths, &[])
+    }
+
+    /// Like [`MetadataStore::remove_stale_files`], but keeps files at or
+    /// under any path in `unlisted` (`""` is the whole tree): a walk that
+    /// could not list them proves nothing about them.
+    pub fn remove_stale_files_except(
+        &self,
+        current_paths: &[String],
+        unlisted: &[String],
+    ) -> Result<usize> {
+        let mut current_paths = current_paths.to_vec();
+        if !unlisted.is_empty() {
+            let under = |path: &str, dir: &str| {
+                dir.is_empty()
+                    || path
+                        .strip_prefix(dir)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            };
+            current_paths.extend(
+                self.list_all_paths()?
+                    .into_iter()
+                    .filter(|path| unlisted.iter().any(|dir| under(path, dir))),
+            );
+        }
         if current_paths.is_empty() {
             let deleted = self.conn.execute("DELETE FROM files", [])?;
             return Ok(deleted);
@@ -247,8 +272,8 @@ impl MetadataStore {
 
         let mut stmt = self
             .conn
-            .prepare("INSERT INTO current_paths (path) VALUES (?1)")?;
-        for path in current_paths {
+            .prepare("INSERT OR IGNORE INTO current_paths (path) VALUES (?1)")?;
+        for path in &current_paths {
             stmt.execute(params![path])?;
         }
 
@@ -474,6 +499,40 @@ mod tests {
         assert_eq!(store.file_count().unwrap(), 2);
     }
 
+    #[test]
+    fn test_remove_stale_keeps_unlisted() {
+        let store = MetadataStore::open_in_memory().unwrap();
+        for name in ["a.md", "locked/b.md", "locked/deep/c.md", "lockedout.md"] {
+            store
+                .upsert_file(&FileEntry {
+                    path: name.to_string(),
+                    mtime: Utc::now(),
+                    size: 100,
+                    content_hash: None,
+                    indexed: true,
+                })
+                .unwrap();
+        }
+
+        let current = vec!["a.md".to_string()];
+        let removed = store
+            .remove_stale_files_except(&current, &["locked".to_string()])
+            .unwrap();
+        assert_eq!(removed, 1);
+        let mut paths = store.list_all_paths().unwrap();
+        paths.sort();
+        assert_eq!(paths, ["a.md", "locked/b.md", "locked/deep/c.md"]);
+
+        // The whole tree is unlisted: nothing goes
+        assert_eq!(
+            store
+                .remove_stale_files_except(&[], &[String::new()])
+                .unwrap(),
+            0
+        );
+        assert_eq!(store.file_count().unwrap(), 3);
+    }
+
     #[test]
     fn test_search_by_metadata() {
         let store = MetadataStore::open_in_memory().unwrap();
@@ -44,6 +44,31 @@ pub enum Block {
 
     /// Raw HTML block (preserved as-is).
     HtmlBlock { html: String },
+
+    /// Footnote definition: `[^label]: text`, continued on indented lines.
+    FootnoteDefinition { label: String, children: Vec<Block> },
+
+    /// Source the model has no block for (link reference definitions,
+    /// footnotes nothing refers to), kept as written.
+    Verbatim { source: String },
+}
+
+impl Block {
+    /// The serde `type` tag of this block (`"heading"`, `"code_block"`, …).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Block::Heading { .. } => "heading",
+            Block::Paragraph { .. } => "paragraph",
+            Block::CodeBlock { .. } => "code_block",
+            Block::BlockQuote { .. } => "block_quote",
+            Block::List { .. } => "list",
+            Block::Table { .. } => "table",
+            Block::ThematicBreak => "thematic_break",
+            Block::HtmlBlock { .. } => "html_block",
+            Block::FootnoteDefinition { .. } => "footnote_definition",
+            Block::Verbatim { .. } => "verbatim",
+        }
+    }
 }
 
 /// A list item, optionally a task list item.
@@ -100,10 +125,42 @@ pub enum Inline {
         title: Option<String>,
         alt: String,
     },
+    /// Wikilink: `[[target#anchor|alias]]`, or `![[target]]` to embed.
+    ///
+    /// `target` is empty for a link within the same note (`[[#heading]]`).
+    /// A block anchor keeps its caret (`[[note#^id]]` has anchor `^id`).
+    WikiLink {
+        target: String,
+        anchor: Option<String>,
+        alias: Option<String>,
+        embed: bool,
+    },
     /// Soft line break (rendered as space).
     SoftBreak,
     /// Hard line break (explicit `<br>`).
     HardBreak,
     /// Raw inline HTML.
     Html { value: String },
+    /// Reference to a footnote: `[^label]`.
+    FootnoteReference { label: String },
+}
+
+impl Inline {
+    /// The serde `type` tag of this inline (`"text"`, `"soft_break"`, …).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Inline::Text { .. } => "text",
+            Inline::Code { .. } => "code",
+            Inline::Emphasis { .. } => "emphasis",
+            Inline::Strong { .. } => "strong",
+            Inline::Strikethrough { .. } => "strikethrough",
+            Inline::Link { .. } => "link",
+            Inline::Image { .. } => "image",
+            Inline::WikiLink { .. } => "wiki_link",
+            Inline::SoftBreak => "soft_break",
+            Inline::HardBreak => "hard_break",
+            Inline::Html { .. } => "html",
+            Inline::FootnoteReference { .. } => "footnote_reference",
+        }
+    }
 }
@@ -6,17 +6,96 @@
 use comrak::nodes::{AstNode, ListType, NodeValue, TableAlignment};
 
 use crate::block::*;
+use crate::span::{LineIndex, Span, SpanTree};
+use crate::text;
 
 /// Convert a comrak AST root node into a list of Fracta Blocks.
 ///
 /// Skips the `FrontMatter` node (handled separately) and `Document` wrapper.
+/// Without the source, nothing is kept as [`Block::Verbatim`].
 pub fn ast_to_blocks<'a>(root: &'a AstNode<'a>) -> Vec<Block> {
-    root.children()
-        .filter_map(|child| node_to_block(child))
-        .collect()
+    ast_to_spanned_blocks(root, "").0
 }
 
-fn node_to_block<'a>(node: &'a AstNode<'a>) -> Option<Block> {
+/// Like [`ast_to_blocks`], also returning each block's [`SpanTree`].
+///
+/// `source` must be the text `root` was parsed from; it turns comrak's
+/// line/column positions into byte offsets, and top-level source comrak
+/// keeps no node for is carried as [`Block::Verbatim`].
+pub fn ast_to_spanned_blocks<'a>(
+    root: &'a AstNode<'a>,
+    source: &str,
+) -> (Vec<Block>, Vec<SpanTree>) {
+    let lines = LineIndex::new(source);
+    let (blocks, spans) = children_to_blocks(root, &lines);
+    if source.is_empty() {
+        return (blocks, spans);
+    }
+    // comrak moves footnote definitions to the end; put them back
+    let mut spanned: Vec<(Block, SpanTree)> = blocks.into_iter().zip(spans).collect();
+    spanned.sort_by_key(|(_, tree)| tree.span.start.offset);
+
+    let front_matter = root
+        .children()
+        .find(|child| matches!(child.data.borrow().value, NodeValue::FrontMatter(_)))
+        .map_or(0, |node| node_span(node, &lines).end.offset);
+    let mut out = Vec::new();
+    let mut covered = front_matter;
+    for (block, tree) in spanned {
+        out.extend(verbatim(&lines, covered..tree.span.start.offset));
+        covered = covered.max(tree.span.end.offset);
+        out.push((block, tree));
+    }
+    out.extend(verbatim(&lines, covered..source.len()));
+    out.into_iter().unzip()
+}
+
+/// The definitions written in a gap between top-level blocks, if any.
+///
+/// comrak consumes link reference definitions and drops footnote
+/// definitions nothing refers to, leaving no node; other gaps hold only
+/// blank lines.
+fn verbatim(lines: &LineIndex, gap: std::ops::Range<usize>) -> Option<(Block, SpanTree)> {
+    if gap.is_empty() {
+        return None;
+    }
+    let text = lines.slice(gap.clone());
+    let source = text.trim();
+    if !source.starts_with('[') {
+        return None;
+    }
+    let start = gap.start + (text.len() - text.trim_start().len());
+    let span = Span {
+        start: lines.at(start),
+        end: lines.at(start + source.len()),
+    };
+    Some((
+        Block::Verbatim {
+            source: source.to_string(),
+        },
+        SpanTree {
+            span,
+            children: Vec::new(),
+        },
+    ))
+}
+
+fn children_to_blocks<'a>(node: &'a AstNode<'a>, lines: &LineIndex) -> (Vec<Block>, Vec<SpanTree>) {
+    node.children()
+        .filter_map(|child| node_to_block(child, lines))
+        .unzip()
+}
+
+/// The span comrak recorded for `node`.
+fn node_span<'a>(node: &'a AstNode<'a>, lines: &LineIndex) -> Span {
+    let pos = node.data.borrow().sourcepos;
+    lines.span(
+        (pos.start.line, pos.start.column),
+        (pos.end.line, pos.end.column),
+    )
+}
+
+fn node_to_block<'a>(node: &'a AstNode<'a>, lines: &LineIndex) -> Option<(Block, SpanTree)> {
     // Extract what we need from the node data, then drop the borrow
     // before recursing into children (which also borrow node data).
     enum BlockKind {
@@ -40,6 +119,9 @@ fn node_to_block<'a>(node: &'a AstNode<'a>) -> Option<Block> {
         HtmlBlock {
             html: String,
         },
+        FootnoteDefinition {
+            label: String,
+        },
         Skip,
     }
 
@@ -88,56 +170,122 @@ fn node_to_block<'a>(node: &'a AstNode<'a>) -> Option<Block> {
             NodeValue::HtmlBlock(html) => BlockKind::HtmlBlock {
                 html: html.literal.clone(),
             },
+            NodeValue::FootnoteDefinition(definition) => BlockKind::FootnoteDefinition {
+                label: definition.name.clone(),
+            },
             NodeValue::FrontMatter(_) | NodeValue::Document => BlockKind::Skip,
             _ => BlockKind::Skip,
         }
     }; // data borrow dropped here
 
-    match kind {
-        BlockKind::Heading { level } => Some(Block::Heading {
-            level,
-            content: collect_inlines(node),
-        }),
-        BlockKind::Paragraph => Some(Block::Paragraph {
-            content: collect_inlines(node),
-        }),
-        BlockKind::CodeBlock { language, code } => Some(Block::CodeBlock { language, code }),
-        BlockKind::BlockQuote => Some(Block::BlockQuote {
-            children: ast_to_blocks(node),
-        }),
+    let mut span = node_span(node, lines);
+    let leaf = |block| {
+        Some((
+            block,
+            SpanTree {
+                span,
+                children: Vec::new(),
+            },
+        ))
+    };
+    let (block, children) = match kind {
+        BlockKind::Heading { level } => {
+            let (content, children) = collect_inlines(node, lines);
+            (Block::Heading { level, content }, children)
+        }
+        BlockKind::Paragraph => {
+            let (content, mut children) = collect_inlines(node, lines);
+            // Link reference definitions read off the paragraph's first
+            // lines are not part of it. comrak places the inlines as if the
+            // rest started on the first line; move them down.
+            let content_end = children
+                .iter()
+                .filter(|tree| tree.span.is_known())
+                .map(|tree| tree.span.end.line)
+                .max();
+            let opens_with_definition = span.is_known()
+                && lines
+                    .slice(span.start.offset..span.end.offset)
+                    .trim_start()
+                    .starts_with('[');
+            if let Some(content_end) = content_end.filter(|_| opens_with_definition) {
+                let by = span.end.line.saturating_sub(content_end);
+                for child in &mut children {
+                    child.shift_lines(by, lines);
+                }
+            }
+            if let Some(first) = children.first().filter(|tree| tree.span.is_known()) {
+                if first.span.start.line > span.start.line {
+                    span.start = first.span.start;
+                }
+            }
+            (Block::Paragraph { content }, children)
+        }
+        BlockKind::CodeBlock { language, code } => {
+            return leaf(Block::CodeBlock { language, code })
+        }
+        BlockKind::BlockQuote => {
+            let (blocks, children) = children_to_blocks(node, lines);
+            (Block::BlockQuote { children: blocks }, children)
+        }
+        BlockKind::FootnoteDefinition { label } => {
+            let (blocks, children) = children_to_blocks(node, lines);
+            (
+                Block::FootnoteDefinition {
+                    label,
+                    children: blocks,
+                },
+                children,
+            )
+        }
         BlockKind::List { ordered, start } => {
-            let items = node
+            let (items, children) = node
                 .children()
-                .map(|item| list_item_from_node(item))
-                .collect();
-            Some(Block::List {
-                ordered,
-                start,
-                items,
-            })
+                .map(|item| list_item_from_node(item, lines))
+                .unzip();
+            (
+                Block::List {
+                    ordered,
+                    start,
+                    items,
+                },
+                children,
+            )
         }
         BlockKind::Table { alignments } => {
-            let rows = node
+            let (rows, children) = node
                 .children()
                 .map(|row_node| {
                     let row_data = row_node.data.borrow();
                     let header = matches!(row_data.value, NodeValue::TableRow(true));
                     drop(row_data);
 
-                    let cells = row_node
+                    let (cells, cell_spans) = row_node
                         .children()
-                        .map(|cell_node| collect_inlines(cell_node))
-                        .collect();
+                        .map(|cell_node| {
+                            let (cell, children) = collect_inlines(cell_node, lines);
+                            let span = node_span(cell_node, lines);
+                            (cell, SpanTree { span, children })
+                        })
+                        .unzip();
 
-                    TableRow { header, cells }
+                    let span = node_span(row_node, lines);
+                    (
+                        TableRow { header, cells },
+                        SpanTree {
+                            span,
+                            children: cell_spans,
+                        },
+                    )
                 })
-                .collect();
-            Some(Block::Table { alignments, rows })
+                .unzip();
+            (Block::Table { alignments, rows }, children)
         }
-        BlockKind::ThematicBreak => Some(Block::ThematicBreak),
-        BlockKind::HtmlBlock { html } => Some(Block::HtmlBlock { html }),
-        BlockKind::Skip => None,
-    }
+        BlockKind::ThematicBreak => return leaf(Block::ThematicBreak),
+        BlockKind::HtmlBlock { html } => return leaf(Block::HtmlBlock { html }),
+        BlockKind::Skip => return None,
+    };
+    Some((block, SpanTree::enclosing(span, children)))
 }
 
 /// Process a list item node into a Fracta ListItem.
@@ -145,7 +293,7 @@ fn node_to_block<'a>(node: &'a AstNode<'a>) -> Option<Block> {
 /// In comrak's AST, task list items are represented by replacing the `Item`
 /// node with a `TaskItem` node in-place. So we check the node itself,
 /// not its children, for task status.
-fn list_item_from_node<'a>(node: &'a AstNode<'a>) -> ListItem {
+fn list_item_from_node<'a>(node: &'a AstNode<'a>, lines: &LineIndex) -> (ListItem, SpanTree) {
     // Check if this node is a TaskItem (comrak mutates Item → TaskItem in-place)
     let checked = {
         let data = node.data.borrow();
@@ -156,22 +304,283 @@ fn list_item_from_node<'a>(node: &'a AstNode<'a>) -> ListItem {
         }
     }; // data borrow dropped here
 
-    let children = node
+    let (children, spans) = children_to_blocks(node, lines);
+    let span = node_span(node, lines);
+    (
+        ListItem { checked, children },
+        SpanTree::enclosing(span, spans),
+    )
+}
+
+/// Collect inline content from a node's children.
+///
+/// comrak splits text at every character it tried to read as markup, so
+/// adjacent text nodes are merged; the same text then always gives the same
+/// inlines, however it was escaped in the source.
+///
+/// comrak gives autolinks no position, so an unplaced inline is looked up by
+/// its text in the gap between its neighbours (or the edges of `node`).
+fn collect_inlines<'a>(node: &'a AstNode<'a>, lines: &LineIndex) -> (Vec<Inline>, Vec<SpanTree>) {
+    let mut inlines: Vec<Inline> = Vec::new();
+    let mut spans: Vec<SpanTree> = Vec::new();
+    for (inline, span) in node
         .children()
-        .filter_map(|child| node_to_block(child))
-        .collect();
+        .filter_map(|child| node_to_inline(child, lines))
+    {
+        if matches!(&inline, Inline::Text { value } if value.is_empty()) {
+            continue;
+        }
+        if let (Some(Inline::Text { value: prev }), Inline::Text { value }) =
+            (inlines.last_mut(), &inline)
+        {
+            prev.push_str(value);
+            if let Some(last) = spans.last_mut() {
+                last.span = last.span.cover(span.span);
+            }
+            continue;
+        }
+        if matches!(inline, Inline::SoftBreak | Inline::HardBreak) {
+            // The spaces before a break belong to it, not to the text
+            if let Some(last) = spans.last_mut() {
+                let start = span.span.start.offset;
+                if last.span.is_known()
+                    && (last.span.start.offset..last.span.end.offset).contains(&start)
+                {
+                    last.span.end = span.span.start;
+                }
+            }
+        }
+        inlines.push(inline);
+        spans.push(span);
+    }
 
-    ListItem { checked, children }
+    let bounds = node_span(node, lines);
+    if !bounds.is_known() {
+        // Inside an unplaced autolink; the caller places this whole subtree
+        return (inlines, spans);
+    }
+    for i in 0..spans.len() {
+        if spans[i].span.is_known() {
+            continue;
+        }
+        let mut end = spans[i + 1..]
+            .iter()
+            .find(|tree| tree.span.is_known())
+            .map_or(bounds.end, |tree| tree.span.start);
+        let prev = i.checked_sub(1).map(|prev| spans[prev].span);
+        let mut start = prev.map_or(bounds.start, |prev| prev.end);
+        // An autolink is written as its own text. comrak cuts the scheme out
+        // of the text before it without updating that text's position, so
+        // search from where that text starts.
+        let written = text::inlines_to_text(std::slice::from_ref(&inlines[i]));
+        let from = match (i.checked_sub(1).map(|prev| &inlines[prev]), prev) {
+            (Some(Inline::Text { .. }), Some(prev)) => prev.start,
+            _ => start,
+        };
+        if let Some(found) = lines.rfind(&written, from.offset..end.offset) {
+            start = lines.at(found);
+            end = lines.at(found + written.len());
+            if let Some(prev) = i.checked_sub(1).map(|prev| &mut spans[prev].span) {
+                if prev.end.offset > found {
+                    prev.end = start;
+                }
+            }
+        }
+        spans[i].place(Span { start, end });
+    }
+    split_embeds(&mut inlines, &mut spans, lines);
+    (inlines, spans)
 }
 
-/// Collect inline content from a node's children.
-fn collect_inlines<'a>(node: &'a AstNode<'a>) -> Vec<Inline> {
-    node.children()
-        .filter_map(|child| node_to_inline(child))
-        .collect()
+/// A wikilink to `url` (`target#anchor`), shown as `alias` if it has one.
+fn wiki_link(url: &str, alias: Option<&str>, embed: bool) -> Inline {
+    let (target, anchor) = match url.split_once('#') {
+        Some((target, anchor)) => (target, Some(anchor.to_string())),
+        None => (url, None),
+    };
+    Inline::WikiLink {
+        target: target.to_string(),
+        anchor,
+        alias: alias.map(String::from),
+        embed,
+    }
+}
+
+/// Whether the wikilink written as `raw` has a `|label` part.
+///
+/// comrak gives a wikilink without one its URL as label, so only a label
+/// equal to the URL needs the source checked. A `\|` there is the pipe of
+/// a link in a table row, unless it is part of `url`.
+fn has_alias(raw: &str, url: &str) -> bool {
+    let bytes = raw.as_bytes();
+    let mut at = raw.find("[[").map_or(bytes.len(), |start| start + 2);
+    while at < bytes.len() {
+        match bytes[at] {
+            b'|' => return true,
+            b'[' | b']' => return false,
+            b'\\' if bytes.get(at + 1) == Some(&b'|') && !url.contains('|') => return true,
+            b'\\' if bytes.get(at + 1).is_some_and(u8::is_ascii_punctuation) => at += 1,
+            _ => {}
+        }
+        at += 1;
+    }
+    false
+}
+
+/// Turn `![[embed]]` inside text into wikilinks.
+///
+/// comrak reads `![` as the start of an image and never sees the wikilink,
+/// so embeds come through as text. They are found in the source, which
+/// tells an embed from escaped text that reads the same, and split out
+/// when the text holds each one as it reads (entities are not decoded, so
+/// an embed written with one stays text).
+fn split_embeds(inlines: &mut Vec<Inline>, spans: &mut Vec<SpanTree>, lines: &LineIndex) {
+    let mut i = 0;
+    while i < inlines.len() {
+        let span = spans[i].span;
+        let (Inline::Text { value }, true) = (&inlines[i], span.is_known()) else {
+            i += 1;
+            continue;
+        };
+        let Some((at, embed)) = first_embed(value, lines.slice(span.range())) else {
+            i += 1;
+            continue;
+        };
+
+        let before = value[..at].to_string();
+        let after = value[at + embed.text.len()..].to_string();
+        let embed_span = Span {
+            start: lines.at(span.start.offset + embed.range.start),
+            end: lines.at(span.start.offset + embed.range.end),
+        };
+        let leaf = |span| SpanTree {
+            span,
+            children: Vec::new(),
+        };
+        let mut pieces = Vec::new();
+        if !before.is_empty() {
+            let before_span = Span {
+                start: span.start,
+                end: embed_span.start,
+            };
+            pieces.push((Inline::Text { value: before }, leaf(before_span)));
+        }
+        let embed_at = i + pieces.len();
+        pieces.push((
+            wiki_link(&embed.url, embed.label.as_deref(), true),
+            leaf(embed_span),
+        ));
+        if !after.is_empty() {
+            let after_span = Span {
+                start: embed_span.end,
+                end: span.end,
+            };
+            pieces.push((Inline::Text { value: after }, leaf(after_span)));
+        }
+        let (new_inlines, new_spans): (Vec<_>, Vec<_>) = pieces.into_iter().unzip();
+        inlines.splice(i..=i, new_inlines);
+        spans.splice(i..=i, new_spans);
+        // The text after the embed may hold more
+        i = embed_at + 1;
+    }
+}
+
+/// The first embed in the source `raw` of `value`, with where `value`
+/// holds it; `None` if `value` holds it more or less often than `raw` does.
+fn first_embed(value: &str, raw: &str) -> Option<(usize, Embed)> {
+    let found = embeds(raw);
+    let first = found.first()?;
+    let in_source = found.iter().filter(|e| e.text == first.text).count();
+    if value.matches(&first.text).count() != in_source {
+        return None;
+    }
+    let at = value.find(&first.text)?;
+    found.into_iter().next().map(|embed| (at, embed))
 }
 
-fn node_to_inline<'a>(node: &'a AstNode<'a>) -> Option<Inline> {
+/// An `![[embed]]` found in source.
+struct Embed {
+    /// Where it is written.
+    range: std::ops::Range<usize>,
+    /// How it reads as text, backslash escapes applied.
+    text: String,
+    url: String,
+    /// The `|label` part, if written.
+    label: Option<String>,
+}
+
+/// The unescaped `![[url]]` and `![[url|label]]` embeds in `raw` source,
+/// read the way comrak reads a wikilink: each part stops at `[`, `]` or
+/// `|` unless backslash-escaped, and is trimmed.
+fn embeds(raw: &str) -> Vec<Embed> {
+    let bytes = raw.as_bytes();
+    let escaped =
+        |at: usize| bytes[at] == b'\\' && bytes.get(at + 1).is_some_and(u8::is_ascii_punctuation);
+    // The end of a part starting at `from`, if it ends before the text does
+    let part = |from: usize| {
+        let mut at = from;
+        while at < bytes.len() && !matches!(bytes[at], b'[' | b']' | b'|') {
+            at += if escaped(at) { 2 } else { 1 };
+        }
+        (at < bytes.len()).then_some(at)
+    };
+    let unescape = |range: std::ops::Range<usize>| {
+        let mut text = String::new();
+        let mut at = range.start;
+        while at < range.end {
+            let skip = usize::from(escaped(at));
+            let c = raw[at + skip..].chars().next().unwrap_or_default();
+            text.push(c);
+            at += skip + c.len_utf8();
+        }
+        text
+    };
+    let trimmed = |range: std::ops::Range<usize>| {
+        let part = &raw[range.clone()];
+        let start = range.start + (part.len() - part.trim_start().len());
+        let end = range.end - (part.len() - part.trim_end().len());
+        start..end.max(start)
+    };
+
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = raw[from..].find("![[").map(|i| from + i) {
+        from = start + 1;
+        let backslashes = bytes[..start]
+            .iter()
+            .rev()
+            .take_while(|&&b| b == b'\\')
+            .count();
+        if backslashes % 2 == 1 {
+            continue;
+        }
+        let Some(left_end) = part(start + 3) else {
+            continue;
+        };
+        let url = unescape(trimmed(start + 3..left_end));
+        let (label, close) = if bytes[left_end] == b'|' {
+            match part(left_end + 1) {
+                Some(end) => (Some(unescape(trimmed(left_end + 1..end))), end),
+                None => continue,
+            }
+        } else {
+            (None, left_end)
+        };
+        if !raw[close..].starts_with("]]") {
+            continue;
+        }
+        found.push(Embed {
+            range: start..close + 2,
+            text: unescape(start..close + 2),
+            url,
+            label,
+        });
+        from = close + 2;
+    }
+    found
+}
+
+fn node_to_inline<'a>(node: &'a AstNode<'a>, lines: &LineIndex) -> Option<(Inline, SpanTree)> {
     // Same pattern: extract data, drop borrow, then recurse.
     enum InlineKind {
         Text(String),
@@ -181,9 +590,11 @@ fn node_to_inline<'a>(node: &'a AstNode<'a>) -> Option<Inline> {
         Strikethrough,
         Link { url: String, title: Option<String> },
         Image { url: String, title: Option<String> },
+        WikiLink { url: String },
         SoftBreak,
         HardBreak,
         Html(String),
+        FootnoteReference(String),
         Skip,
     }
 
@@ -211,39 +622,77 @@ fn node_to_inline<'a>(node: &'a AstNode<'a>) -> Option<Inline> {
                     Some(link.title.clone())
                 },
             },
+            NodeValue::WikiLink(link) => InlineKind::WikiLink {
+                url: link.url.clone(),
+            },
             NodeValue::SoftBreak => InlineKind::SoftBreak,
             NodeValue::LineBreak => InlineKind::HardBreak,
             NodeValue::HtmlInline(html) => InlineKind::Html(html.clone()),
+            NodeValue::FootnoteReference(reference) => {
+                InlineKind::FootnoteReference(reference.name.clone())
+            }
             _ => InlineKind::Skip,
         }
     }; // data borrow dropped here
 
-    match kind {
-        InlineKind::Text(value) => Some(Inline::Text { value }),
-        InlineKind::Code(value) => Some(Inline::Code { value }),
-        InlineKind::Emph => Some(Inline::Emphasis {
-            children: collect_inlines(node),
-        }),
-        InlineKind::Strong => Some(Inline::Strong {
-            children: collect_inlines(node),
-        }),
-        InlineKind::Strikethrough => Some(Inline::Strikethrough {
-            children: collect_inlines(node),
-        }),
-        InlineKind::Link { url, title } => Some(Inline::Link {
-            url,
-            title,
-            children: collect_inlines(node),
-        }),
+    let span = match &kind {
+        InlineKind::Code(_) => lines.code_span(node_span(node, lines)),
+        InlineKind::Link { .. } => lines.angle_link(node_span(node, lines)),
+        InlineKind::SoftBreak | InlineKind::HardBreak => lines.line_break(node_span(node, lines)),
+        _ => node_span(node, lines),
+    };
+    let leaf = |inline| {
+        Some((
+            inline,
+            SpanTree {
+                span,
+                children: Vec::new(),
+            },
+        ))
+    };
+    let (inline, children) = match kind {
+        InlineKind::Text(value) => return leaf(Inline::Text { value }),
+        InlineKind::Code(value) => return leaf(Inline::Code { value }),
+        InlineKind::Emph => {
+            let (children, spans) = collect_inlines(node, lines);
+            (Inline::Emphasis { children }, spans)
+        }
+        InlineKind::Strong => {
+            let (children, spans) = collect_inlines(node, lines);
+            (Inline::Strong { children }, spans)
+        }
+        InlineKind::Strikethrough => {
+            let (children, spans) = collect_inlines(node, lines);
+            (Inline::Strikethrough { children }, spans)
+        }
+        InlineKind::Link { url, title } => {
+            let (children, spans) = collect_inlines(node, lines);
+            (
+                Inline::Link {
+                    url,
+                    title,
+                    children,
+                },
+                spans,
+            )
+        }
         InlineKind::Image { url, title } => {
             let alt = collect_plain_text(node);
-            Some(Inline::Image { url, title, alt })
+            return leaf(Inline::Image { url, title, alt });
         }
-        InlineKind::SoftBreak => Some(Inline::SoftBreak),
-        InlineKind::HardBreak => Some(Inline::HardBreak),
-        InlineKind::Html(value) => Some(Inline::Html { value }),
-        InlineKind::Skip => None,
-    }
+        InlineKind::WikiLink { url } => {
+            let label = collect_plain_text(node);
+            let aliased =
+                label != url || (span.is_known() && has_alias(lines.slice(span.range()), &url));
+            return leaf(wiki_link(&url, aliased.then_some(label.as_str()), false));
+        }
+        InlineKind::SoftBreak => return leaf(Inline::SoftBreak),
+        InlineKind::HardBreak => return leaf(Inline::HardBreak),
+        InlineKind::Html(value) => return leaf(Inline::Html { value }),
+        InlineKind::FootnoteReference(label) => return leaf(Inline::FootnoteReference { label }),
+        InlineKind::Skip => return None,
+    };
+    Some((inline, SpanTree { span, children }))
 }
 
 /// Collect all text content from a node's descendants (for alt text, etc.).
@@ -0,0 +1,793 @@
+//! Surgical edits to Markdown source.
+//!
+//! [`apply`] performs one [`Edit`] by patching only the bytes it concerns,
+//! located through the spans recorded at parse time (see [`crate::span`]).
+//! The rest of the file comes back byte for byte, so toggling a checkbox is
+//! a one-byte change rather than a rewrite of the whole note.
+//!
+//! Blocks and list items are named by the same paths as spans: the
+//! top-level block index, then child indices (`[2, 0]` is the first item of
+//! the list that is the third block).
+
+use std::ops::Range;
+
+use serde_yaml::Value;
+
+use crate::block::{Block, ListItem};
+use crate::error::{NoteError, Result};
+use crate::yaml_edit::{self, FieldPath};
+use crate::Document;
+
+/// One targeted change to a Markdown document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Flip the checkbox of the task list item at `path`.
+    ToggleTask { path: Vec<usize> },
+    /// Set the front matter field at `path`, replacing any current value
+    /// and creating the front matter if the document has none.
+    SetFrontMatter { path: FieldPath, value: Value },
+    /// Add a new front matter key, or insert into a list before `path`'s
+    /// index; an existing key is an error.
+    InsertFrontMatter { path: FieldPath, value: Value },
+    /// Remove the front matter field at `path` (nothing changes if it is
+    /// unset).
+    RemoveFrontMatter { path: FieldPath },
+    /// Replace the block at `path` with `markdown`.
+    ReplaceBlock { path: Vec<usize>, markdown: String },
+    /// Insert `markdown` as new blocks directly below the heading at `path`.
+    InsertAfterHeading { path: Vec<usize>, markdown: String },
+}
+
+/// A byte range of the original text and what replaced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Range in the text the edit was applied to (empty for an insertion).
+    pub range: Range<usize>,
+    /// The new text for that range (empty for a deletion).
+    pub text: String,
+}
+
+/// The result of [`apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditOutcome {
+    /// The whole edited Markdown.
+    pub text: String,
+    /// What was patched; empty when the edit was a no-op.
+    pub changes: Vec<Change>,
+    /// A short human-readable summary (`"checked task at line 7"`).
+    pub description: String,
+}
+
+/// Apply `edit` to the Markdown `source`.
+pub fn apply(source: &str, edit: &Edit) -> Result<EditOutcome> {
+    let doc = Document::parse(source);
+    let (changes, description) = match edit {
+        Edit::ToggleTask { path } => toggle_task(source, &doc, path)?,
+        Edit::SetFrontMatter { path, value } => set_front_matter(source, &doc, path, value, false)?,
+        Edit::InsertFrontMatter { path, value } => {
+            set_front_matter(source, &doc, path, value, true)?
+        }
+        Edit::RemoveFrontMatter { path } => remove_front_matter(source, &doc, path)?,
+        Edit::ReplaceBlock { path, markdown } => replace_block(source, &doc, path, markdown)?,
+        Edit::InsertAfterHeading { path, markdown } => {
+            insert_after_heading(source, &doc, path, markdown)?
+        }
+    };
+
+    let mut text = source.to_string();
+    // Changes never overlap; patch from the back so ranges stay valid
+    for change in changes.iter().rev() {
+        text.replace_range(change.range.clone(), &change.text);
+    }
+    Ok(EditOutcome {
+        text,
+        changes,
+        description,
+    })
+}
+
+/// A block or list item in the model.
+enum Node<'a> {
+    Block(&'a Block),
+    Item(&'a ListItem),
+}
+
+fn node_at<'a>(blocks: &'a [Block], path: &[usize]) -> Option<Node<'a>> {
+    let (first, rest) = path.split_first()?;
+    let mut node = Node::Block(blocks.get(*first)?);
+    for &i in rest {
+        node = match node {
+            Node::Block(
+                Block::BlockQuote { children } | Block::FootnoteDefinition { children, .. },
+            ) => Node::Block(children.get(i)?),
+            Node::Block(Block::List { items, .. }) => Node::Item(items.get(i)?),
+            Node::Item(item) => Node::Block(item.children.get(i)?),
+            Node::Block(_) => return None,
+        };
+    }
+    Some(node)
+}
+
+/// The byte range of the node at `path`, without a trailing newline.
+fn node_range(source: &str, doc: &Document, path: &[usize]) -> Result<Range<usize>> {
+    let span = doc
+        .span(path)
+        .ok_or_else(|| NoteError::NodeNotFound(path.to_vec()))?
+        .span;
+    let mut range = span.range();
+    if source[range.clone()].ends_with('\n') {
+        range.end -= 1;
+    }
+    Ok(range)
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// The line ending `source` uses, judged by its first line: `\r\n` or,
+/// by default, `\n`.
+pub(crate) fn line_ending(source: &str) -> &'static str {
+    match source.find('\n') {
+        Some(i) if source[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
+    }
+}
+
+/// The prefix that puts a new line inside the same containers as a block
+/// whose first line starts with `prefix`.
+///
+/// Block quote markers are kept; list markers and indentation become
+/// spaces. A task checkbox belongs to the item's first line only.
+fn continuation(prefix: &str) -> String {
+    let trimmed = prefix.trim_end();
+    let prefix = match trimmed.len().checked_sub(3) {
+        Some(i) if trimmed.ends_with(']') && trimmed[i..].starts_with('[') => &prefix[..i],
+        _ => prefix,
+    };
+    prefix
+        .chars()
+        .map(|c| if c == '>' || c == '\t' { c } else { ' ' })
+        .collect()
+}
+
+/// Put `prefix` in front of every line of `markdown` (after the first if
+/// `skip_first`), leaving blank lines free of trailing whitespace, and
+/// end the lines with `eol`.
+fn prefix_lines(markdown: &str, prefix: &str, skip_first: bool, eol: &str) -> String {
+    let blank = prefix.trim_end();
+    markdown
+        .trim_end_matches(['\n', '\r'])
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .enumerate()
+        .map(|(i, line)| match (i, line.is_empty()) {
+            (0, _) if skip_first => line.to_string(),
+            (_, true) => blank.to_string(),
+            (_, false) => format!("{prefix}{line}"),
+        })
+        .collect::<Vec<_>>()
+        .join(eol)
+}
+
+// ── Tasks ──────────────────────────────────────────────────────────────
+
+fn toggle_task(source: &str, doc: &Document, path: &[usize]) -> Result<(Vec<Change>, String)> {
+    let not_task = || NoteError::WrongNode {
+        path: path.to_vec(),
+        expected: "task list item",
+    };
+    let checked = match node_at(&doc.blocks, path) {
+        Some(Node::Item(item)) => item.checked.ok_or_else(not_task)?,
+        Some(Node::Block(_)) => return Err(not_task()),
+        None => return Err(NoteError::NodeNotFound(path.to_vec())),
+    };
+    let item = node_range(source, doc, path)?;
+    let mark = checkbox(source, item.start).ok_or_else(not_task)?;
+
+    let change = Change {
+        range: mark,
+        text: if checked { " " } else { "x" }.to_string(),
+    };
+    let description = format!(
+        "{} task at line {}",
+        if checked { "unchecked" } else { "checked" },
+        line_of(source, item.start)
+    );
+    Ok((vec![change], description))
+}
+
+/// The range of the character between a task item's brackets, given where
+/// the item's list marker starts.
+fn checkbox(source: &str, item_start: usize) -> Option<Range<usize>> {
+    let bytes = source.as_bytes();
+    let mut i = item_start;
+    while bytes.get(i) == Some(&b' ') {
+        i += 1;
+    }
+    match bytes.get(i)? {
+        b'-' | b'*' | b'+' => i += 1,
+        b'0'..=b'9' => {
+            while bytes.get(i)?.is_ascii_digit() {
+                i += 1;
+            }
+            if !matches!(bytes.get(i)?, b'.' | b')') {
+                return None;
+            }
+            i += 1;
+        }
+        _ => return None,
+    }
+    while matches!(bytes.get(i)?, b' ' | b'\t') {
+        i += 1;
+    }
+    if bytes.get(i)? != &b'[' {
+        return None;
+    }
+    let mark = source[i + 1..].chars().next()?;
+    let end = i + 1 + mark.len_utf8();
+    (bytes.get(end)? == &b']').then_some(i + 1..end)
+}
+
+// ── Blocks ─────────────────────────────────────────────────────────────
+
+fn replace_block(
+    source: &str,
+    doc: &Document,
+    path: &[usize],
+    markdown: &str,
+) -> Result<(Vec<Change>, String)> {
+    let block = match node_at(&doc.blocks, path) {
+        Some(Node::Block(block)) => block,
+        Some(Node::Item(_)) => {
+            return Err(NoteError::WrongNode {
+                path: path.to_vec(),
+                expected: "block",
+            })
+        }
+        None => return Err(NoteError::NodeNotFound(path.to_vec())),
+    };
+    let mut range = node_range(source, doc, path)?;
+    let line = line_start(source, range.start);
+    // An indented code block starts after its indentation
+    if matches!(block, Block::CodeBlock { .. }) && !source[range.start..].starts_with(['`', '~']) {
+        let indent =
+            source[line..range.start].len() - source[line..range.start].trim_end_matches(' ').len();
+        range.start -= indent.min(4);
+    }
+
+    let prefix = continuation(&source[line..range.start]);
+    let change = Change {
+        text: prefix_lines(markdown, &prefix, true, line_ending(source)),
+        range,
+    };
+    let description = format!(
+        "replaced {} at line {}",
+        block.kind().replace('_', " "),
+        line_of(source, change.range.start)
+    );
+    Ok((vec![change], description))
+}
+
+fn insert_after_heading(
+    source: &str,
+    doc: &Document,
+    path: &[usize],
+    markdown: &str,
+) -> Result<(Vec<Change>, String)> {
+    match node_at(&doc.blocks, path) {
+        Some(Node::Block(Block::Heading { .. })) => {}
+        Some(_) => {
+            return Err(NoteError::WrongNode {
+                path: path.to_vec(),
+                expected: "heading",
+            })
+        }
+        None => return Err(NoteError::NodeNotFound(path.to_vec())),
+    }
+    let heading = node_range(source, doc, path)?;
+    let line = line_start(source, heading.start);
+    let prefix = continuation(&source[line..heading.start]);
+    let eol = line_ending(source);
+    let blank = format!("{}{eol}", prefix.trim_end());
+
+    // Insert at the start of the line after the heading, with a blank line
+    // before the new blocks and, unless one is there already, after them
+    let (at, mut text) = match source[heading.end..].find('\n') {
+        Some(i) => (heading.end + i + 1, blank.clone()),
+        None => (source.len(), format!("{eol}{blank}")),
+    };
+    text.push_str(&prefix_lines(markdown, &prefix, false, eol));
+    text.push_str(eol);
+    let next_line = source[at..].split('\n').next().unwrap_or("");
+    let next_is_blank = next_line
+        .trim_matches(|c: char| c == '>' || c.is_whitespace())
+        .is_empty();
+    if at < source.len() && !next_is_blank {
+        text.push_str(&blank);
+    }
+
+    let description = format!("inserted after heading at line {}", line_of(source, line));
+    Ok((
+        vec![Change {
+            range: at..at,
+            text,
+        }],
+        description,
+    ))
+}
+
+// ── Front matter ───────────────────────────────────────────────────────
+
+/// The byte range of the front matter's YAML lines, between the delimiters
+/// (front matter always starts the file, so these are source offsets), or
+/// `None` if there is no front matter.
+pub(crate) fn front_matter_body(doc: &Document) -> Result<Option<Range<usize>>> {
+    let Some(fm) = doc.front_matter_source.as_deref() else {
+        return Ok(None);
+    };
+    let start = fm.find('\n').map_or(fm.len(), |i| i + 1);
+    let mut end = start;
+    for line in fm[start..].split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            break;
+        }
+        end += line.len();
+    }
+    if doc.front_matter.is_none() && !fm[start..end].trim().is_empty() {
+        return Err(NoteError::InvalidFrontMatter);
+    }
+    Ok(Some(start..end))
+}
+
+/// [`front_matter_body`], also finding the blocks comrak does not take for
+/// front matter: an empty one (`---` right below `---`) and one closed with
+/// `...`. Those are only used if their lines are blank or a YAML mapping.
+fn editable_front_matter(source: &str, doc: &Document) -> Result<Option<Range<usize>>> {
+    if let Some(body) = front_matter_body(doc)? {
+        return Ok(Some(body));
+    }
+    let mut lines = source.split_inclusive('\n');
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Ok(None);
+    }
+    let start = source.find('\n').map_or(source.len(), |i| i + 1);
+    let mut end = start;
+    for line in lines {
+        if matches!(line.trim_end(), "---" | "...") {
+            let yaml = &source[start..end];
+            let mapping = yaml.trim().is_empty()
+                || serde_yaml::from_str::<Value>(yaml).is_ok_and(|v| v.is_mapping());
+            return Ok(mapping.then_some(start..end));
+        }
+        end += line.len();
+    }
+    Ok(None)
+}
+
+/// Where and how front matter for `yaml` would be added to a file that
+/// has none, in the file's line ending.
+fn new_front_matter(source: &str, yaml: &str) -> Change {
+    let eol = line_ending(source);
+    let mut text = format!("---\n{yaml}---\n");
+    if !source.is_empty() {
+        text.push('\n');
+    }
+    if eol != "\n" {
+        text = text.replace('\n', eol);
+    }
+    Change { range: 0..0, text }
+}
+
+/// Move a change to the YAML lines at `body` into source offsets.
+fn in_body(change: Change, body: &Range<usize>) -> Change {
+    Change {
+        range: body.start + change.range.start..body.start + change.range.end,
+        text: change.text,
+    }
+}
+
+fn set_front_matter(
+    source: &str,
+    doc: &Document,
+    path: &FieldPath,
+    value: &Value,
+    insert: bool,
+) -> Result<(Vec<Change>, String)> {
+    let change = match editable_front_matter(source, doc)? {
+        None => new_front_matter(source, &yaml_edit::set("", path, value)?.text),
+        Some(body) => {
+            let yaml = &source[body.clone()];
+            let mut change = match insert {
+                true => yaml_edit::insert(yaml, path, value)?,
+                false => yaml_edit::set(yaml, path, value)?,
+            };
+            // An empty block has no line ending of its own to follow
+            let eol = line_ending(source);
+            if yaml.is_empty() && eol != "\n" {
+                change.text = change.text.replace('\n', eol);
+            }
+            in_body(change, &body)
+        }
+    };
+    let verb = if insert { "inserted" } else { "set" };
+    Ok((vec![change], format!("{verb} front matter key `{path}`")))
+}
+
+fn remove_front_matter(
+    source: &str,
+    doc: &Document,
+    path: &FieldPath,
+) -> Result<(Vec<Change>, String)> {
+    let change = match editable_front_matter(source, doc)? {
+        None => None,
+        Some(body) => yaml_edit::remove(&source[body.clone()], path)?.map(|c| in_body(c, &body)),
+    };
+    Ok(match change {
+        Some(change) => (vec![change], format!("removed front matter key `{path}`")),
+        None => (Vec::new(), format!("front matter key `{path}` was not set")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(source: &str, edit: Edit) -> EditOutcome {
+        let outcome = apply(source, &edit).unwrap();
+        // Every byte outside the changes is untouched
+        let mut rebuilt = String::new();
+        let mut at = 0;
+        for change in &outcome.changes {
+            rebuilt.push_str(&source[at..change.range.start]);
+            rebuilt.push_str(&change.text);
+            at = change.range.end;
+        }
+        rebuilt.push_str(&source[at..]);
+        assert_eq!(rebuilt, outcome.text);
+        outcome
+    }
+
+    #[test]
+    fn test_toggle_task() {
+        let md = "# Tasks\n\n- [ ] one\n- [x] two\n\n> 1) [X] quoted\n";
+        let out = edit(md, Edit::ToggleTask { path: vec![1, 0] });
+        assert_eq!(out.text, md.replacen("[ ] one", "[x] one", 1));
+        assert_eq!(out.changes.len(), 1);
+        assert_eq!(out.changes[0].range.len(), 1);
+        assert_eq!(out.description, "checked task at line 3");
+
+        let out = edit(md, Edit::ToggleTask { path: vec![1, 1] });
+        assert_eq!(out.text, md.replacen("[x] two", "[ ] two", 1));
+        assert_eq!(out.description, "unchecked task at line 4");
+
+        let out = edit(
+            md,
+            Edit::ToggleTask {
+                path: vec![2, 0, 0],
+            },
+        );
+        assert_eq!(out.text, md.replacen("[X]", "[ ]", 1));
+        match &Document::parse(&out.text).blocks[2] {
+            Block::BlockQuote { children } => match &children[0] {
+                Block::List { items, .. } => assert_eq!(items[0].checked, Some(false)),
+                other => panic!("expected list, got {other:?}"),
+            },
+            other => panic!("expected block quote, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_toggle_rejects_non_tasks() {
+        let md = "- plain\n\nText\n";
+        assert!(matches!(
+            apply(md, &Edit::ToggleTask { path: vec![0, 0] }),
+            Err(NoteError::WrongNode { .. })
+        ));
+        assert!(matches!(
+            apply(md, &Edit::ToggleTask { path: vec![1] }),
+            Err(NoteError::WrongNode { .. })
+        ));
+        assert!(matches!(
+            apply(md, &Edit::ToggleTask { path: vec![5, 0] }),
+            Err(NoteError::NodeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_and_remove_front_matter() {
+        let md = "---\n# mood log\ntitle:   Old  # keep\ntags:\n  - a\n  - b\nmood: 3\n---\n\nBody *as is*\n";
+
+        let out = edit(
+            md,
+            Edit::SetFrontMatter {
+                path: "mood".into(),
+                value: Value::from(8),
+            },
+        );
+        assert_eq!(out.text, md.replace("mood: 3", "mood: 8"));
+
+        let out = edit(
+            md,
+            Edit::SetFrontMatter {
+                path: "tags".into(),
+                value: Value::Sequence(vec!["x".into()]),
+            },
+        );
+        assert_eq!(
+            out.text,
+            md.replace("tags:\n  - a\n  - b\n", "tags:\n- x\n")
+        );
+
+        let out = edit(
+            md,
+            Edit::SetFrontMatter {
+                path: "status".into(),
+                value: Value::from("done"),
+            },
+        );
+        assert_eq!(out.text, md.replace("mood: 3\n", "mood: 3\nstatus: done\n"));
+        let fm = Document::parse(&out.text).front_matter.unwrap();
+        assert_eq!(fm.get_str("status"), Some("done"));
+        assert_eq!(fm.get_str("title"), Some("Old"));
+
+        let out = edit(
+            md,
+            Edit::RemoveFrontMatter {
+                path: "tags".into(),
+            },
+        );
+        assert_eq!(out.text, md.replace("tags:\n  - a\n  - b\n", ""));
+
+        let out = edit(
+            md,
+            Edit::RemoveFrontMatter {
+                path: "nope".into(),
+            },
+        );
+        assert!(out.changes.is_empty());
+        assert_eq!(out.text, md);
+    }
+
+    #[test]
+    fn test_nested_front_matter() {
+        let md = "---\nprofile:\n  status: active # by hand\nloot: [a.md]\n---\nBody\n";
+
+        let out = edit(
+            md,
+            Edit::SetFrontMatter {
+                path: "profile.status".into(),
+                value: Value::from("done"),
+            },
+        );
+        assert_eq!(out.text, md.replace("active", "done"));
+        assert_eq!(out.description, "set front matter key `profile.status`");
+
+        let out = edit(
+            md,
+            Edit::InsertFrontMatter {
+                path: "loot[0]".into(),
+                value: Value::from("b.md"),
+            },
+        );
+        assert_eq!(out.text, md.replace("[a.md]", "[b.md, a.md]"));
+
+        let out = edit(
+            md,
+            Edit::RemoveFrontMatter {
+                path: "profile.status".into(),
+            },
+        );
+        assert_eq!(out.text, md.replace("  status: active # by hand\n", ""));
+    }
+
+    #[test]
+    fn test_create_front_matter() {
+        let set = Edit::SetFrontMatter {
+            path: "profile.status".into(),
+            value: Value::from("new"),
+        };
+        let out = edit("# No front matter\n", set.clone());
+        assert_eq!(
+            out.text,
+            "---\nprofile:\n  status: new\n---\n\n# No front matter\n"
+        );
+        let doc = Document::parse(&out.text);
+        assert_eq!(doc.blocks, Document::parse("# No front matter\n").blocks);
+        assert!(doc.front_matter.is_some());
+        assert_eq!(edit("", set).text, "---\nprofile:\n  status: new\n---\n");
+
+        let remove = Edit::RemoveFrontMatter { path: "a".into() };
+        assert!(edit("Text\n", remove).changes.is_empty());
+    }
+
+    #[test]
+    fn test_empty_front_matter_block_filled() {
+        let set = Edit::SetFrontMatter {
+            path: "a".into(),
+            value: Value::from(1),
+        };
+        let out = edit("---\n---\nbody\n", set.clone());
+        assert_eq!(out.text, "---\na: 1\n---\nbody\n");
+        let fm = Document::parse(&out.text).front_matter.unwrap();
+        assert_eq!(fm.get_i64("a"), Some(1));
+
+        let out = edit("---\r\n---\r\nbody\r\n", set);
+        assert_eq!(out.text, "---\r\na: 1\r\n---\r\nbody\r\n");
+    }
+
+    #[test]
+    fn test_dot_closed_front_matter_edited_in_place() {
+        let md = "---\na: 1\n...\nbody\n";
+
+        let out = edit(
+            md,
+            Edit::SetFrontMatter {
+                path: "b".into(),
+                value: Value::from(2),
+            },
+        );
+        assert_eq!(out.text, "---\na: 1\nb: 2\n...\nbody\n");
+
+        let out = edit(md, Edit::RemoveFrontMatter { path: "a".into() });
+        assert_eq!(out.text, "---\n...\nbody\n");
+
+        // A thematic break over plain text is not front matter
+        let prose = "---\nJust text\n...\n";
+        let out = edit(
+            prose,
+            Edit::SetFrontMatter {
+                path: "b".into(),
+                value: Value::from(2),
+            },
+        );
+        assert_eq!(out.text, format!("---\nb: 2\n---\n\n{prose}"));
+    }
+
+    #[test]
+    fn test_front_matter_errors() {
+        let insert = Edit::InsertFrontMatter {
+            path: "a".into(),
+            value: Value::from(1),
+        };
+        assert!(matches!(
+            apply("---\n- a list\n---\n", &insert),
+            Err(NoteError::InvalidFrontMatter)
+        ));
+        assert!(matches!(
+            apply("---\na: 0\n---\n", &insert),
+            Err(NoteError::FieldExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_replace_block() {
+        let md = "Intro  \ntext\n\n    code\n\n- item *one*\n\n  second para\n- two\n";
+
+        let out = edit(
+            md,
+            Edit::ReplaceBlock {
+                path: vec![0],
+                markdown: "New intro\n".into(),
+            },
+        );
+        assert_eq!(out.text, md.replace("Intro  \ntext", "New intro"));
+        assert_eq!(out.description, "replaced paragraph at line 1");
+
+        // An indented code block is replaced with its indentation
+        let out = edit(
+            md,
+            Edit::ReplaceBlock {
+                path: vec![1],
+                markdown: "Not code".into(),
+            },
+        );
+        assert_eq!(out.text, md.replace("    code", "Not code"));
+
+        // Continuation lines and new blocks stay inside the list item
+        let out = edit(
+            md,
+            Edit::ReplaceBlock {
+                path: vec![2, 0, 1],
+                markdown: "line a\nline b\n\n> quote".into(),
+            },
+        );
+        assert_eq!(
+            out.text,
+            md.replace("second para", "line a\n  line b\n\n  > quote")
+        );
+        match &Document::parse(&out.text).blocks[2] {
+            Block::List { items, .. } => assert_eq!(items[0].children.len(), 3),
+            other => panic!("expected list, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_insert_after_heading() {
+        let md = "# Title\nText\n\n> ## Quoted\n> more\n";
+
+        let out = edit(
+            md,
+            Edit::InsertAfterHeading {
+                path: vec![0],
+                markdown: "- [ ] new task".into(),
+            },
+        );
+        assert_eq!(
+            out.text,
+            md.replace("# Title\n", "# Title\n\n- [ ] new task\n\n")
+        );
+        assert_eq!(out.changes[0].range, 8..8);
+
+        let out = edit(
+            md,
+            Edit::InsertAfterHeading {
+                path: vec![2, 0],
+                markdown: "Added".into(),
+            },
+        );
+        assert_eq!(
+            out.text,
+            md.replace("> ## Quoted\n", "> ## Quoted\n>\n> Added\n>\n")
+        );
+
+        let out = edit(
+            "# End",
+            Edit::InsertAfterHeading {
+                path: vec![0],
+                markdown: "Tail".into(),
+            },
+        );
+        assert_eq!(out.text, "# End\n\nTail\n");
+
+        assert!(matches!(
+            apply(
+                md,
+                &Edit::InsertAfterHeading {
+                    path: vec![1],
+                    markdown: "x".into()
+                }
+            ),
+            Err(NoteError::WrongNode { .. })
+        ));
+    }
+
+    #[test]
+    fn test_crlf_kept() {
+        let md = "# A\r\npara\r\n\r\n- item\r\n";
+
+        let out = edit(
+            md,
+            Edit::InsertAfterHeading {
+                path: vec![0],
+                markdown: "new\nlines".into(),
+            },
+        );
+        assert_eq!(
+            out.text,
+            "# A\r\n\r\nnew\r\nlines\r\n\r\npara\r\n\r\n- item\r\n"
+        );
+
+        let out = edit(
+            md,
+            Edit::ReplaceBlock {
+                path: vec![1],
+                markdown: "one\r\ntwo\r\n".into(),
+            },
+        );
+        assert_eq!(out.text, "# A\r\none\r\ntwo\r\n\r\n- item\r\n");
+
+        let out = edit(
+            md,
+            Edit::SetFrontMatter {
+                path: "title".into(),
+                value: "T".into(),
+            },
+        );
+        assert_eq!(out.text, format!("---\r\ntitle: T\r\n---\r\n\r\n{md}"));
+    }
+}
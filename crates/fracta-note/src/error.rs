@@ -0,0 +1,43 @@
+//! Note error types.
+
+use thiserror::Error;
+
+/// Errors that can occur when editing a Markdown document or its front matter.
+#[derive(Debug, Error)]
+pub enum NoteError {
+    /// No block or list item at this path (see [`crate::span`]).
+    #[error("no block or list item at path {0:?}")]
+    NodeNotFound(Vec<usize>),
+
+    /// The node at this path is not the kind the edit needs.
+    #[error("node at path {path:?} is not a {expected}")]
+    WrongNode {
+        path: Vec<usize>,
+        expected: &'static str,
+    },
+
+    /// The front matter is not a YAML mapping, so keys cannot be edited.
+    #[error("front matter is not a YAML mapping")]
+    InvalidFrontMatter,
+
+    /// The front matter key to insert is already set.
+    #[error("front matter field `{0}` already exists")]
+    FieldExists(String),
+
+    /// A key in the path is missing and cannot be added (a list index
+    /// follows it), or a list index is past the end of its list.
+    #[error("front matter field `{0}` not found")]
+    FieldNotFound(String),
+
+    /// The value at this path is a scalar or in a layout that cannot be
+    /// edited in place (see [`crate::yaml_edit`]).
+    #[error("front matter field `{0}` cannot be edited in place")]
+    FieldNotEditable(String),
+
+    /// A YAML value could not be written.
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+/// Result type for note operations.
+pub type Result<T> = std::result::Result<T, NoteError>;
@@ -4,9 +4,13 @@
 //! - **YAML front matter** → typed metadata (title, date, tags, etc.)
 //! - **Markdown body** → Block tree (headings, paragraphs, lists, tables, code, etc.)
 //! - **Plain text extraction** → for full-text search indexing (FTS5)
+//! - **Serialization** → Block tree back to Markdown, for structured edits
+//! - **Surgical edits** → patch only the affected bytes of the source
+//! - **Links** → outgoing references: links, wikilinks, embeds, front matter relations
 //!
 //! The source Markdown file is always the SOT (source of truth).
-//! This crate only reads and parses — it never modifies user files.
+//! This crate never touches the filesystem: it turns Markdown text into a
+//! Document and back, and the caller decides what to write.
 //!
 //! ## Architecture
 //!
@@ -15,31 +19,65 @@
 //! - `FrontMatter`: parsed YAML metadata with typed accessors
 //! - `convert`: comrak AST → Block model (the only comrak-coupled code)
 //! - `text`: plain text extraction from blocks
+//! - `serialize`: Block model → Markdown (canonical, minimal-diff output)
+//! - `link`: outgoing references (links, wikilinks, embeds, front matter relations)
+//! - `span`: source positions of blocks and inlines, kept beside the model
+//! - `edit`: targeted source patches (task toggles, front matter keys, blocks)
+//! - `yaml_edit`: front matter field edits that keep comments, order and quoting
 
 pub mod block;
 pub mod convert;
+pub mod edit;
+pub mod error;
 pub mod front_matter;
+pub mod link;
+pub mod serialize;
+pub mod span;
 pub mod text;
+pub mod yaml_edit;
 
 pub use block::{Alignment, Block, Inline, ListItem, TableRow};
+pub use edit::{Change, Edit, EditOutcome};
+pub use error::{NoteError, Result};
 pub use front_matter::FrontMatter;
+pub use link::{Link, LinkKind, LinkScope};
+pub use span::{Position, Span, SpanEntry, SpanTree};
+pub use yaml_edit::{FieldPath, Segment};
 
 use comrak::{Arena, Options};
 
 /// A parsed Markdown document.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Equality compares content only: two texts that parse to the same blocks
+/// are equal even though their spans differ.
+#[derive(Debug, Clone)]
 pub struct Document {
     /// Parsed YAML front matter (if present).
     pub front_matter: Option<FrontMatter>,
+    /// The front matter block as written, delimiters and trailing blank
+    /// lines included (always ending with a newline). Kept even when the
+    /// YAML is not a mapping, so [`Document::to_markdown`] never drops it.
+    pub front_matter_source: Option<String>,
     /// The block-level content of the document.
     pub blocks: Vec<Block>,
+    /// Where each block sits in the source, parallel to `blocks` (see
+    /// [`span`]). Empty for a Document not built by [`Document::parse`].
+    pub spans: Vec<SpanTree>,
+}
+
+impl PartialEq for Document {
+    fn eq(&self, other: &Self) -> bool {
+        self.front_matter == other.front_matter
+            && self.front_matter_source == other.front_matter_source
+            && self.blocks == other.blocks
+    }
 }
 
 impl Document {
     /// Parse a Markdown string into a Document.
     ///
     /// Enables GFM extensions: tables, task lists, strikethrough,
-    /// autolinks, footnotes, and YAML front matter.
+    /// autolinks, footnotes, wikilinks, and YAML front matter.
     pub fn parse(markdown: &str) -> Self {
         let arena = Arena::new();
         let options = Self::comrak_options();
@@ -47,23 +85,43 @@ impl Document {
 
         // Extract front matter from AST
         let mut front_matter = None;
+        let mut front_matter_source = None;
         for child in root.children() {
             let data = child.data.borrow();
             if let comrak::nodes::NodeValue::FrontMatter(ref yaml) = data.value {
                 front_matter = FrontMatter::parse(yaml);
+                // The closing `---` may end the file without a newline
+                let mut source = yaml.clone();
+                if !source.ends_with('\n') {
+                    source.push('\n');
+                }
+                front_matter_source = Some(source);
                 break;
             }
         }
 
         // Convert remaining AST nodes to Block model
-        let blocks = convert::ast_to_blocks(root);
+        let (blocks, spans) = convert::ast_to_spanned_blocks(root, markdown);
 
         Document {
             front_matter,
+            front_matter_source,
             blocks,
+            spans,
         }
     }
 
+    /// Serialize the document back to Markdown.
+    ///
+    /// The front matter is re-emitted byte for byte; the body is written in
+    /// canonical form (see [`serialize`]). Parsing the result gives back an
+    /// equal Document.
+    pub fn to_markdown(&self) -> String {
+        let mut out = self.front_matter_source.clone().unwrap_or_default();
+        out.push_str(&serialize::blocks_to_markdown(&self.blocks));
+        out
+    }
+
     /// Extract all plain text content (for full-text search indexing).
     pub fn plain_text(&self) -> String {
         text::extract_text(&self.blocks)
@@ -97,6 +155,7 @@ impl Document {
         options.extension.tasklist = true;
         options.extension.autolink = true;
         options.extension.footnotes = true;
+        options.extension.wikilinks_title_after_pipe = true;
         options.extension.front_matter_delimiter = Some("---".to_owned());
         options
     }
@@ -287,6 +346,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_wiki_links() {
+        let doc =
+            Document::parse("[[Note#^id|Alias]] ![[a.png]] \\![[b]] [[#Top]] [[c|c]] ![[d|d]]\n");
+        let wiki =
+            |target: &str, anchor: Option<&str>, alias: Option<&str>, embed| Inline::WikiLink {
+                target: target.to_string(),
+                anchor: anchor.map(String::from),
+                alias: alias.map(String::from),
+                embed,
+            };
+        let text = |value: &str| Inline::Text {
+            value: value.to_string(),
+        };
+
+        match &doc.blocks[0] {
+            Block::Paragraph { content } => assert_eq!(
+                content,
+                &[
+                    wiki("Note", Some("^id"), Some("Alias"), false),
+                    text(" "),
+                    wiki("a.png", None, None, true),
+                    // An escaped `!` leaves a plain wikilink
+                    text(" !"),
+                    wiki("b", None, None, false),
+                    text(" "),
+                    wiki("", Some("Top"), None, false),
+                    text(" "),
+                    // An alias is kept even when it repeats the target
+                    wiki("c", None, Some("c"), false),
+                    text(" "),
+                    wiki("d", None, Some("d"), true),
+                ]
+            ),
+            _ => panic!("expected Paragraph"),
+        }
+        assert_eq!(doc.plain_text(), "Alias a.png !b Top c d");
+    }
+
     // ── Plain text extraction ──────────────────────────────────────────
 
     #[test]
@@ -0,0 +1,389 @@
+//! Outgoing references of a note.
+//!
+//! [`Document::links`] lists every place a note points elsewhere: Markdown
+//! links and images, wikilinks and embeds in the body, and front matter
+//! values that name another note or a URL (relations such as
+//! `loot: ["./rewards/x.md"]`). Front matter has no schema here, so a value
+//! counts as a reference when it reads as one: a `[[wikilink]]`, a path
+//! starting with `./` or `../` or ending in `.md`, or a `scheme://` URL.
+//! References by UID look like any other string and are not found.
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::block::{Block, Inline};
+use crate::edit::front_matter_body;
+use crate::span::{LineIndex, Span, SpanTree};
+use crate::yaml_edit::{self, FieldPath, Segment};
+use crate::Document;
+
+/// Where a reference was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// `[text](url)`, or a bare URL.
+    Link,
+    /// `![alt](url)`.
+    Image,
+    /// `[[target]]`.
+    WikiLink,
+    /// `![[target]]`.
+    Embed,
+    /// A front matter value.
+    FrontMatter,
+}
+
+/// Whether a reference stays inside the workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkScope {
+    /// A note, file or heading: a relative path, a note name or `#anchor`.
+    Internal,
+    /// A URL with a scheme (`https:`, `mailto:`, …).
+    External,
+}
+
+/// One outgoing reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub kind: LinkKind,
+    pub scope: LinkScope,
+    /// The URL, path or note name as written, without the anchor of an
+    /// internal link; empty for a link within the same note.
+    pub target: String,
+    /// The heading (`#Heading`) or block (`#^id`) an internal link points
+    /// into, without the `#`.
+    pub anchor: Option<String>,
+    /// The field holding a front matter reference (`loot[0]`).
+    pub field: Option<FieldPath>,
+    /// Where the link is written; for front matter, the value (quotes
+    /// included). Unknown for a front matter value spread over lines.
+    pub span: Span,
+}
+
+impl Document {
+    /// Every outgoing reference, front matter first, then the body in
+    /// document order.
+    pub fn links(&self) -> Vec<Link> {
+        let mut links = Vec::new();
+        front_matter_links(self, &mut links);
+        for (block, tree) in self.blocks.iter().zip(&self.spans) {
+            block_links(block, tree, &mut links);
+        }
+        links
+    }
+}
+
+/// Whether `url` starts with a URI scheme.
+fn has_scheme(url: &str) -> bool {
+    let Some((scheme, _)) = url.split_once(':') else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    // A single letter is a Windows drive
+    scheme.len() >= 2
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+}
+
+/// A link to `url`, split into target and anchor when it is internal.
+fn link(kind: LinkKind, url: &str, span: Span) -> Link {
+    let external = has_scheme(url) || url.starts_with("//");
+    let (target, anchor) = match url.split_once('#') {
+        Some((target, anchor)) if !external => (target, Some(anchor.to_string())),
+        _ => (url, None),
+    };
+    Link {
+        kind,
+        scope: if external {
+            LinkScope::External
+        } else {
+            LinkScope::Internal
+        },
+        target: target.to_string(),
+        anchor,
+        field: None,
+        span,
+    }
+}
+
+fn block_links(block: &Block, tree: &SpanTree, links: &mut Vec<Link>) {
+    let children = tree.children.iter();
+    match block {
+        Block::Heading { content, .. } | Block::Paragraph { content } => {
+            inline_links(content, tree, links)
+        }
+        Block::BlockQuote { children: blocks }
+        | Block::FootnoteDefinition {
+            children: blocks, ..
+        } => {
+            for (child, block) in children.zip(blocks) {
+                block_links(block, child, links);
+            }
+        }
+        Block::List { items, .. } => {
+            for (item_tree, item) in children.zip(items) {
+                for (child, block) in item_tree.children.iter().zip(&item.children) {
+                    block_links(block, child, links);
+                }
+            }
+        }
+        Block::Table { rows, .. } => {
+            for (row_tree, row) in children.zip(rows) {
+                for (cell_tree, cell) in row_tree.children.iter().zip(&row.cells) {
+                    inline_links(cell, cell_tree, links);
+                }
+            }
+        }
+        Block::CodeBlock { .. }
+        | Block::ThematicBreak
+        | Block::HtmlBlock { .. }
+        | Block::Verbatim { .. } => {}
+    }
+}
+
+fn inline_links(inlines: &[Inline], tree: &SpanTree, links: &mut Vec<Link>) {
+    for (child, inline) in tree.children.iter().zip(inlines) {
+        match inline {
+            Inline::Link { url, children, .. } => {
+                links.push(link(LinkKind::Link, url, child.span));
+                inline_links(children, child, links);
+            }
+            Inline::Image { url, .. } => links.push(link(LinkKind::Image, url, child.span)),
+            Inline::WikiLink {
+                target,
+                anchor,
+                embed,
+                ..
+            } => {
+                // `[[]]` points nowhere
+                if target.is_empty() && anchor.is_none() {
+                    continue;
+                }
+                let kind = if *embed {
+                    LinkKind::Embed
+                } else {
+                    LinkKind::WikiLink
+                };
+                let mut wiki = link(kind, target, child.span);
+                wiki.anchor = anchor.clone();
+                links.push(wiki);
+            }
+            Inline::Emphasis { children }
+            | Inline::Strong { children }
+            | Inline::Strikethrough { children } => inline_links(children, child, links),
+            Inline::Text { .. }
+            | Inline::Code { .. }
+            | Inline::SoftBreak
+            | Inline::HardBreak
+            | Inline::Html { .. }
+            | Inline::FootnoteReference { .. } => {}
+        }
+    }
+}
+
+fn front_matter_links(doc: &Document, links: &mut Vec<Link>) {
+    let (Some(front_matter), Some(source)) = (&doc.front_matter, &doc.front_matter_source) else {
+        return;
+    };
+    let Some(body) = front_matter_body(doc).ok().flatten() else {
+        return;
+    };
+    let yaml = &source[body.clone()];
+    // Front matter starts the file, so its offsets are the note's
+    let lines = LineIndex::new(source);
+    let mut path = Vec::new();
+    walk_values(&front_matter.fields, &mut path, &mut |path, value| {
+        let Some(mut link) = front_matter_link(value) else {
+            return;
+        };
+        let field = FieldPath(path.to_vec());
+        if let Some(range) = yaml_edit::value_range(yaml, &field) {
+            link.span = Span {
+                start: lines.at(body.start + range.start),
+                end: lines.at(body.start + range.end),
+            };
+        }
+        link.field = Some(field);
+        links.push(link);
+    });
+}
+
+/// Call `visit` with every string in `value` and its path.
+fn walk_values(value: &Value, path: &mut Vec<Segment>, visit: &mut impl FnMut(&[Segment], &str)) {
+    match value {
+        Value::String(s) => visit(path, s),
+        Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                path.push(Segment::Index(i));
+                walk_values(item, path, visit);
+                path.pop();
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                path.push(Segment::Key(key.to_string()));
+                walk_values(value, path, visit);
+                path.pop();
+            }
+        }
+        Value::Tagged(tagged) => walk_values(&tagged.value, path, visit),
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
+
+/// The reference a front matter string makes, if it reads as one.
+fn front_matter_link(value: &str) -> Option<Link> {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix("[[").and_then(|v| v.strip_suffix("]]")) {
+        let url = inner.split_once('|').map_or(inner, |(url, _)| url).trim();
+        return (!url.is_empty()).then(|| link(LinkKind::FrontMatter, url, Span::default()));
+    }
+    let path = value.starts_with("./") || value.starts_with("../") || value.ends_with(".md");
+    let url = has_scheme(value) && value.contains("://");
+    (path && !value.contains(char::is_whitespace) || url)
+        .then(|| link(LinkKind::FrontMatter, value, Span::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTE: &str = "\
+---
+id: \"01HQ\"
+title: \"Complete Index implementation\"
+blockedBy: []
+loot: [\"./rewards/index-loot.md\"]
+source: https://fracta.app/docs
+parent: \"[[Quests#Active]]\"
+---
+
+# Quest
+
+See [[Design Notes#^goal|the goal]], ![[diagram.png]] and [[#Quest]].
+
+- [Spec](../spec.md#scope) and <https://example.com/a#b>
+- ![shot](img/shot.png) in **[[Rust]]**
+
+| Ref |
+| --- |
+| [mail](mailto:a@b.io) |
+";
+
+    fn text(link: &Link) -> &str {
+        &NOTE[link.span.range()]
+    }
+
+    #[test]
+    fn test_body_links() {
+        let doc = Document::parse(NOTE);
+        let links: Vec<_> = doc
+            .links()
+            .into_iter()
+            .filter(|l| l.field.is_none())
+            .collect();
+        let summary: Vec<_> = links
+            .iter()
+            .map(|l| {
+                (
+                    l.kind,
+                    l.scope,
+                    l.target.as_str(),
+                    l.anchor.as_deref(),
+                    text(l),
+                )
+            })
+            .collect();
+        use LinkKind::*;
+        use LinkScope::*;
+        assert_eq!(
+            summary,
+            [
+                (
+                    WikiLink,
+                    Internal,
+                    "Design Notes",
+                    Some("^goal"),
+                    "[[Design Notes#^goal|the goal]]"
+                ),
+                (Embed, Internal, "diagram.png", None, "![[diagram.png]]"),
+                (WikiLink, Internal, "", Some("Quest"), "[[#Quest]]"),
+                (
+                    Link,
+                    Internal,
+                    "../spec.md",
+                    Some("scope"),
+                    "[Spec](../spec.md#scope)"
+                ),
+                (
+                    Link,
+                    External,
+                    "https://example.com/a#b",
+                    None,
+                    "<https://example.com/a#b>"
+                ),
+                (
+                    Image,
+                    Internal,
+                    "img/shot.png",
+                    None,
+                    "![shot](img/shot.png)"
+                ),
+                (WikiLink, Internal, "Rust", None, "[[Rust]]"),
+                (
+                    Link,
+                    External,
+                    "mailto:a@b.io",
+                    None,
+                    "[mail](mailto:a@b.io)"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_front_matter_links() {
+        let doc = Document::parse(NOTE);
+        let links: Vec<_> = doc
+            .links()
+            .into_iter()
+            .filter(|l| l.field.is_some())
+            .collect();
+        assert_eq!(links.len(), 3);
+
+        assert_eq!(links[0].field, Some("loot[0]".into()));
+        assert_eq!(links[0].target, "./rewards/index-loot.md");
+        assert_eq!(links[0].scope, LinkScope::Internal);
+        assert_eq!(text(&links[0]), "\"./rewards/index-loot.md\"");
+
+        assert_eq!(links[1].field, Some("source".into()));
+        assert_eq!(links[1].scope, LinkScope::External);
+        assert_eq!(text(&links[1]), "https://fracta.app/docs");
+
+        assert_eq!(links[2].target, "Quests");
+        assert_eq!(links[2].anchor.as_deref(), Some("Active"));
+        assert_eq!(links[2].span.start.line, 7);
+    }
+
+    #[test]
+    fn test_scope() {
+        for (url, scope) in [
+            ("notes/a.md", LinkScope::Internal),
+            ("C:/notes/a.md", LinkScope::Internal),
+            ("#top", LinkScope::Internal),
+            ("https://x.io", LinkScope::External),
+            ("//cdn.x.io/a.png", LinkScope::External),
+            ("obsidian://open", LinkScope::External),
+        ] {
+            assert_eq!(
+                link(LinkKind::Link, url, Span::default()).scope,
+                scope,
+                "{url}"
+            );
+        }
+    }
+}
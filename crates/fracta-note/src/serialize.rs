@@ -0,0 +1,976 @@
+//! Serialize the Block model back to Markdown.
+//!
+//! The output is canonical rather than a copy of the original source: ATX
+//! headings, `-` bullets, fenced code, `*`/`**` emphasis, `---` breaks. A
+//! document already written in that style serializes to itself, so edits
+//! made through the model (toggling a task, renaming a heading) change only
+//! the lines they touch. Text is escaped where it could be read as markup,
+//! and parsing the output gives back the same blocks.
+//!
+//! Inline code, HTML, URLs and [`Block::Verbatim`] source are written as
+//! they are; adjacent text inlines come back merged, as [`crate::convert`]
+//! always merges them.
+
+use crate::block::*;
+
+/// Serialize blocks as a Markdown body, ending with a newline.
+///
+/// Returns an empty string for no blocks.
+pub fn blocks_to_markdown(blocks: &[Block]) -> String {
+    let mut out = render_blocks(blocks, Container::Document);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Serialize inline content as it would appear inside a paragraph.
+pub fn inlines_to_markdown(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    write_inlines(inlines, InlineContext::Paragraph, &mut out);
+    out
+}
+
+/// What holds a run of sibling blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    /// The document body, where a leading `---` would open front matter.
+    Document,
+    BlockQuote,
+    FootnoteDefinition,
+    /// An item of a list using the alternate marker (or not).
+    ListItem {
+        alternate: bool,
+    },
+}
+
+/// Render sibling blocks separated by blank lines, without a final newline.
+fn render_blocks(blocks: &[Block], container: Container) -> String {
+    let mut out = String::new();
+    let mut alternate = false;
+    for (i, block) in blocks.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| &blocks[p]);
+        if let Some(prev) = prev {
+            let tight = matches!(container, Container::ListItem { .. }) && tight_after(prev, block);
+            out.push_str(if tight { "\n" } else { "\n\n" });
+        }
+        // A list right after another of the same kind switches marker, or
+        // the two would merge into one. A list opening an item switches too,
+        // so `- - -` is not read as a break.
+        alternate = match (prev, block, container) {
+            (Some(Block::List { ordered: a, .. }), Block::List { ordered: b, .. }, _) if a == b => {
+                !alternate
+            }
+            (None, Block::List { .. }, Container::ListItem { alternate }) => !alternate,
+            _ => false,
+        };
+        let next = blocks.get(i + 1);
+        out.push_str(&render_block(block, alternate, container, i == 0, next));
+    }
+    out
+}
+
+/// Whether `block` can follow `prev` in a list item without a blank line: a
+/// nested list written right under its item's text.
+fn tight_after(prev: &Block, block: &Block) -> bool {
+    let Block::List {
+        ordered,
+        start,
+        items,
+    } = block
+    else {
+        return false;
+    };
+    // Only a list that can interrupt a paragraph
+    matches!(prev, Block::Paragraph { .. })
+        && (!ordered || start.unwrap_or(1) == 1)
+        && items.first().is_some_and(|item| !item.children.is_empty())
+}
+
+/// Render one block; `alternate` picks the second list marker style.
+fn render_block(
+    block: &Block,
+    alternate: bool,
+    container: Container,
+    first: bool,
+    next: Option<&Block>,
+) -> String {
+    match block {
+        // Only setext headings span lines
+        Block::Heading { level, content } if *level <= 2 && has_break(content) => {
+            let mut text = String::new();
+            write_inlines(content, InlineContext::Paragraph, &mut text);
+            let underline = if *level == 1 { "===" } else { "---" };
+            format!("{text}\n{underline}")
+        }
+        Block::Heading { level, content } => {
+            let mut text = String::new();
+            write_inlines(content, InlineContext::Heading, &mut text);
+            let hashes = "#".repeat(usize::from((*level).clamp(1, 6)));
+            if text.is_empty() {
+                hashes
+            } else {
+                format!("{hashes} {text}")
+            }
+        }
+        Block::Paragraph { content } => {
+            let mut text = String::new();
+            write_inlines(content, InlineContext::Paragraph, &mut text);
+            text
+        }
+        Block::CodeBlock { language, code } => render_code_block(language.as_deref(), code),
+        Block::BlockQuote { children } => prefix_lines(
+            &render_blocks(children, Container::BlockQuote),
+            "> ",
+            "> ",
+            ">",
+        ),
+        Block::List {
+            ordered,
+            start,
+            items,
+        } => {
+            // An indented HTML block after the list must not be read as a
+            // continuation of its last item
+            let indent_after = match next {
+                Some(Block::HtmlBlock { html }) => html.len() - html.trim_start_matches(' ').len(),
+                _ => 0,
+            };
+            render_list(*ordered, start.unwrap_or(1), items, alternate, indent_after)
+        }
+        Block::Table { alignments, rows } => render_table(alignments, rows),
+        // `---` would open front matter, or read as a list marker run
+        Block::ThematicBreak if first && container == Container::Document => "***".to_string(),
+        Block::ThematicBreak if first && matches!(container, Container::ListItem { .. }) => {
+            "___".to_string()
+        }
+        Block::ThematicBreak => "---".to_string(),
+        Block::HtmlBlock { html } => html.trim_end_matches('\n').to_string(),
+        Block::FootnoteDefinition { label, children } => prefix_lines(
+            &render_blocks(children, Container::FootnoteDefinition),
+            &format!("[^{label}]: "),
+            "    ",
+            "",
+        ),
+        Block::Verbatim { source } => source.trim_end_matches('\n').to_string(),
+    }
+}
+
+fn has_break(inlines: &[Inline]) -> bool {
+    inlines.iter().any(|inline| match inline {
+        Inline::SoftBreak | Inline::HardBreak => true,
+        Inline::Emphasis { children }
+        | Inline::Strong { children }
+        | Inline::Strikethrough { children }
+        | Inline::Link { children, .. } => has_break(children),
+        _ => false,
+    })
+}
+
+fn render_code_block(language: Option<&str>, code: &str) -> String {
+    let info = language.unwrap_or("");
+    let fence_char = if info.contains('`') { '~' } else { '`' };
+
+    // Longer than any fence-like run inside, so the code cannot close it
+    let longest = code
+        .lines()
+        .map(|line| {
+            line.trim_start_matches(' ')
+                .chars()
+                .take_while(|&c| c == fence_char)
+                .count()
+        })
+        .max()
+        .unwrap_or(0);
+    let fence = fence_char.to_string().repeat(longest.max(2) + 1);
+
+    let mut out = format!("{fence}{info}\n{code}");
+    if !code.is_empty() && !code.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&fence);
+    out
+}
+
+/// Render a list whose item content starts past column `indent_after`.
+fn render_list(
+    ordered: bool,
+    start: usize,
+    items: &[ListItem],
+    alternate: bool,
+    indent_after: usize,
+) -> String {
+    let mut lines = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let marker = match (ordered, alternate) {
+            (true, false) => format!("{}.", start + i),
+            (true, true) => format!("{})", start + i),
+            (false, false) => "-".to_string(),
+            (false, true) => "*".to_string(),
+        };
+        let width = (marker.len() + 1).max(indent_after + 1);
+        let indent = " ".repeat(width);
+        let task = match item.checked {
+            Some(true) => " [x]",
+            Some(false) => " [ ]",
+            None => "",
+        };
+
+        let body = render_blocks(&item.children, Container::ListItem { alternate });
+        if body.is_empty() {
+            lines.push(format!("{marker}{task}"));
+            continue;
+        }
+        if body.starts_with(' ') {
+            // Leading spaces are content (an indented HTML block): start it
+            // on the next line, where they are not taken as marker padding
+            lines.push(format!(
+                "{marker}\n{}",
+                prefix_lines(&body, &indent, &indent, "")
+            ));
+            continue;
+        }
+        let first = format!("{marker:<pad$}{task} ", pad = width - 1);
+        lines.push(prefix_lines(&body, &first, &indent, ""));
+    }
+    lines.join("\n")
+}
+
+fn render_table(alignments: &[Alignment], rows: &[TableRow]) -> String {
+    let columns = alignments
+        .len()
+        .max(rows.iter().map(|r| r.cells.len()).max().unwrap_or(0));
+    let render_row = |cells: &[Vec<Inline>]| {
+        let mut line = String::from("|");
+        for column in 0..columns {
+            let mut text = String::new();
+            if let Some(cell) = cells.get(column) {
+                write_inlines(cell, InlineContext::TableCell, &mut text);
+            }
+            line.push(' ');
+            line.push_str(&text);
+            line.push_str(" |");
+        }
+        line
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    let (header, body) = match rows.split_first() {
+        Some((first, rest)) if first.header => (first.cells.as_slice(), rest),
+        _ => (&[][..], rows),
+    };
+    lines.push(render_row(header));
+
+    let mut delimiter = String::from("|");
+    for column in 0..columns {
+        let cell = match alignments.get(column).copied().unwrap_or(Alignment::None) {
+            Alignment::Left => ":---",
+            Alignment::Center => ":---:",
+            Alignment::Right => "---:",
+            Alignment::None => "---",
+        };
+        delimiter.push(' ');
+        delimiter.push_str(cell);
+        delimiter.push_str(" |");
+    }
+    lines.push(delimiter);
+
+    for row in body {
+        lines.push(render_row(&row.cells));
+    }
+    lines.join("\n")
+}
+
+/// Prefix the first line with `first` and the rest with `rest`; empty lines
+/// get `blank` instead.
+fn prefix_lines(text: &str, first: &str, rest: &str, blank: &str) -> String {
+    if text.is_empty() {
+        return first.trim_end().to_string();
+    }
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            if line.is_empty() && i > 0 {
+                blank.to_string()
+            } else {
+                format!("{prefix}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ── Inlines ────────────────────────────────────────────────────────────
+
+/// Where inline content is written; decides what needs escaping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InlineContext {
+    Paragraph,
+    Heading,
+    TableCell,
+}
+
+fn write_inlines(inlines: &[Inline], context: InlineContext, out: &mut String) {
+    let start = out.len();
+    let scope = Scope {
+        context,
+        star: false,
+        underscore: false,
+    };
+    write_sequence(inlines, scope, true, out);
+    // A trailing `#` run would be read as the closing sequence
+    if context == InlineContext::Heading && out.ends_with('#') {
+        let run = out[start..].len() - out[start..].trim_end_matches('#').len();
+        let at = out.len() - run;
+        if at == start || out[..at].ends_with([' ', '\t']) {
+            out.insert(at, '\\');
+        }
+    }
+}
+
+/// What comes right after an inline in the output.
+#[derive(Debug, Clone, Copy)]
+enum Following<'a> {
+    /// A sibling; `then_end` if the block ends right after it.
+    Inline { inline: &'a Inline, then_end: bool },
+    /// The closing delimiter of the enclosing span.
+    Delimiter,
+    /// The end of the block's text.
+    End,
+}
+
+/// The context plus which emphasis delimiters enclosing spans opened.
+#[derive(Debug, Clone, Copy)]
+struct Scope {
+    context: InlineContext,
+    star: bool,
+    underscore: bool,
+}
+
+/// Write sibling inlines; `end` if nothing follows them in the block.
+fn write_sequence(inlines: &[Inline], scope: Scope, end: bool, out: &mut String) {
+    for (i, inline) in inlines.iter().enumerate() {
+        let following = match inlines.get(i + 1) {
+            Some(next) => Following::Inline {
+                inline: next,
+                then_end: end && i + 2 == inlines.len(),
+            },
+            None if end => Following::End,
+            None => Following::Delimiter,
+        };
+        write_inline(inline, following, scope, out);
+    }
+}
+
+fn write_inline(inline: &Inline, following: Following, scope: Scope, out: &mut String) {
+    match inline {
+        Inline::Text { value } => write_text(value, scope.context, out),
+        Inline::Code { value } => write_code_span(value, out),
+        Inline::Emphasis { children } => {
+            let c = emphasis_char(children, following, scope, out);
+            write_wrapped(children, &c.to_string(), scope, out);
+        }
+        Inline::Strong { children } => {
+            let c = emphasis_char(children, following, scope, out);
+            write_wrapped(children, &c.to_string().repeat(2), scope, out);
+        }
+        Inline::Strikethrough { children } => write_wrapped(children, "~~", scope, out),
+        Inline::Link {
+            url,
+            title,
+            children,
+        } => write_link(url, title.as_deref(), children, following, scope, out),
+        Inline::Image { url, title, alt } => {
+            out.push_str("![");
+            // Alt text never starts a line, so table-cell escaping fits
+            write_text(alt, InlineContext::TableCell, out);
+            out.push(']');
+            write_destination(url, title.as_deref(), out);
+        }
+        Inline::WikiLink {
+            target,
+            anchor,
+            alias,
+            embed,
+        } => write_wiki_link(
+            target,
+            anchor.as_deref(),
+            alias.as_deref(),
+            *embed,
+            scope,
+            out,
+        ),
+        Inline::SoftBreak => out.push('\n'),
+        Inline::HardBreak => out.push_str("\\\n"),
+        Inline::Html { value } => out.push_str(value),
+        Inline::FootnoteReference { label } => {
+            out.push_str("[^");
+            out.push_str(label);
+            out.push(']');
+        }
+    }
+}
+
+/// Pick `*` or `_` for an emphasis or strong span.
+///
+/// `*` is the default. `_` cannot open or close inside a word, and is used
+/// where a `*` would pair up with a nested span's `*` instead.
+fn emphasis_char(children: &[Inline], following: Following, scope: Scope, out: &str) -> char {
+    let word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    let next = match following {
+        Following::Inline {
+            inline: Inline::Text { value },
+            ..
+        } => value.chars().next(),
+        // May be written as a bare URL
+        Following::Inline {
+            inline: Inline::Link { .. },
+            ..
+        } => Some('h'),
+        _ => None,
+    };
+    if word(out.chars().next_back()) || word(next) {
+        return '*';
+    }
+
+    // A nested span inside a word needs `*`, so this one takes `_`
+    let nested_in_word = children.iter().enumerate().any(|(i, child)| {
+        matches!(child, Inline::Emphasis { .. } | Inline::Strong { .. })
+            && (matches!(i.checked_sub(1).map(|p| &children[p]),
+                    Some(Inline::Text { value }) if word(value.chars().next_back()))
+                || matches!(children.get(i + 1),
+                    Some(Inline::Text { value }) if word(value.chars().next())))
+    });
+    if nested_in_word || scope.star && !scope.underscore {
+        '_'
+    } else {
+        '*'
+    }
+}
+
+fn write_wrapped(children: &[Inline], delimiter: &str, scope: Scope, out: &mut String) {
+    let inner = Scope {
+        star: scope.star || delimiter.starts_with('*'),
+        underscore: scope.underscore || delimiter.starts_with('_'),
+        ..scope
+    };
+    out.push_str(delimiter);
+    write_sequence(children, inner, false, out);
+    out.push_str(delimiter);
+}
+
+fn write_code_span(value: &str, out: &mut String) {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in value.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest + 1);
+    // One space on each side is stripped when both are present
+    let pad = value.starts_with('`')
+        || value.ends_with('`')
+        || (value.starts_with(' ') && value.ends_with(' ') && !value.trim().is_empty());
+    out.push_str(&fence);
+    if pad {
+        out.push(' ');
+    }
+    out.push_str(value);
+    if pad {
+        out.push(' ');
+    }
+    out.push_str(&fence);
+}
+
+fn write_link(
+    url: &str,
+    title: Option<&str>,
+    children: &[Inline],
+    following: Following,
+    scope: Scope,
+    out: &mut String,
+) {
+    // Autolinks stay bare, the way they were written
+    if title.is_none() {
+        if let [Inline::Text { value }] = children {
+            let bare = value == url && (url.starts_with("http://") || url.starts_with("https://"))
+                || url.strip_prefix("http://") == Some(value.as_str()) && value.starts_with("www.")
+                || url.strip_prefix("mailto:") == Some(value.as_str());
+            let boundary = out
+                .chars()
+                .next_back()
+                .is_none_or(|c| c.is_whitespace() || "(*_~".contains(c));
+            // Nothing may follow that would extend the bare URL
+            let ends = match following {
+                Following::End
+                | Following::Inline {
+                    inline: Inline::SoftBreak | Inline::HardBreak,
+                    ..
+                } => true,
+                Following::Inline {
+                    inline: Inline::Text { value },
+                    then_end,
+                } => {
+                    // Trailing punctuation is not taken into the URL
+                    let mut rest = value.chars();
+                    match rest.next() {
+                        Some(c) if c.is_whitespace() => true,
+                        Some('.' | ',' | ':' | ';' | '!' | '?' | '\'' | '"') => match rest.next() {
+                            Some(c) => c.is_whitespace(),
+                            None => then_end,
+                        },
+                        _ => false,
+                    }
+                }
+                _ => false,
+            };
+            if bare && boundary && ends {
+                out.push_str(value);
+                return;
+            }
+            if value == url && is_uri_autolink(url) {
+                out.push('<');
+                out.push_str(url);
+                out.push('>');
+                return;
+            }
+        }
+    }
+
+    // `!` right before the link would turn it into an image
+    if out.ends_with('!') && !out.ends_with("\\!") {
+        out.insert(out.len() - 1, '\\');
+    }
+    out.push('[');
+    write_sequence(children, scope, false, out);
+    out.push(']');
+    write_destination(url, title, out);
+}
+
+/// `[[target#anchor|alias]]`, or `![[...]]` for an embed.
+fn write_wiki_link(
+    target: &str,
+    anchor: Option<&str>,
+    alias: Option<&str>,
+    embed: bool,
+    scope: Scope,
+    out: &mut String,
+) {
+    if embed {
+        // Embeds are read from text (see `crate::convert`), so their parts
+        // are escaped as text, never starting a line
+        out.push_str("![[");
+        write_text(target, InlineContext::TableCell, out);
+        if let Some(anchor) = anchor {
+            out.push('#');
+            write_text(anchor, InlineContext::TableCell, out);
+        }
+        if let Some(alias) = alias {
+            out.push('|');
+            write_text(alias, InlineContext::TableCell, out);
+        }
+        out.push_str("]]");
+        return;
+    }
+
+    // `!` right before the link would turn it into an embed
+    if out.ends_with('!') && !out.ends_with("\\!") {
+        out.insert(out.len() - 1, '\\');
+    }
+    out.push_str("[[");
+    write_wiki_part(target, out);
+    if let Some(anchor) = anchor {
+        out.push('#');
+        write_wiki_part(anchor, out);
+    }
+    if let Some(alias) = alias {
+        // A table row reads `\|` as a `|` that does not end the cell
+        if scope.context == InlineContext::TableCell {
+            out.push('\\');
+        }
+        out.push('|');
+        write_wiki_part(alias, out);
+    }
+    out.push_str("]]");
+}
+
+/// A wikilink's target, anchor or alias: brackets and pipes end the part
+/// unless escaped, and entities are decoded.
+fn write_wiki_part(value: &str, out: &mut String) {
+    let chars: Vec<char> = value.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '&' && looks_like_entity(&chars[i + 1..]) {
+            out.push_str("&amp;");
+            continue;
+        }
+        if matches!(c, '\\' | '[' | ']' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// Whether `<url>` is read back as a link to exactly `url`.
+fn is_uri_autolink(url: &str) -> bool {
+    let Some((scheme, rest)) = url.split_once(':') else {
+        return false;
+    };
+    let mut scheme_chars = scheme.chars();
+    (2..=32).contains(&scheme.len())
+        && scheme_chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+        && !rest.is_empty()
+        && !rest.contains(|c: char| c.is_whitespace() || c.is_control() || "<>&\\".contains(c))
+}
+
+/// `(url "title")` for links and images.
+fn write_destination(url: &str, title: Option<&str>, out: &mut String) {
+    out.push('(');
+    let mut depth = 0i32;
+    let balanced = url.chars().all(|c| {
+        depth += match c {
+            '(' => 1,
+            ')' => -1,
+            _ => 0,
+        };
+        depth >= 0
+    }) && depth == 0;
+    let angle = url.is_empty() || url.contains([' ', '\t', '<', '>']) || !balanced;
+    if angle {
+        out.push('<');
+    }
+    // Destinations and titles decode entities after escapes, so a literal
+    // `&name;` is written as `&amp;name;`
+    let chars: Vec<char> = url.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '&' && looks_like_entity(&chars[i + 1..]) {
+            out.push_str("&amp;");
+            continue;
+        }
+        if c == '\\' || angle && matches!(c, '<' | '>') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    if angle {
+        out.push('>');
+    }
+    if let Some(title) = title {
+        out.push_str(" \"");
+        let chars: Vec<char> = title.chars().collect();
+        for (i, &c) in chars.iter().enumerate() {
+            if c == '&' && looks_like_entity(&chars[i + 1..]) {
+                out.push_str("&amp;");
+                continue;
+            }
+            if matches!(c, '"' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push('"');
+    }
+    out.push(')');
+}
+
+/// Write literal text, escaping what would otherwise be read as markup.
+fn write_text(value: &str, context: InlineContext, out: &mut String) {
+    let chars: Vec<char> = value.chars().collect();
+    // Whether the current output line has only digits so far (for `1.`)
+    let mut line_start =
+        context == InlineContext::Paragraph && (out.is_empty() || out.ends_with('\n'));
+    let mut digits_only = line_start;
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = match i {
+            0 => out.chars().next_back(),
+            _ => Some(chars[i - 1]),
+        };
+        let next = chars.get(i + 1).copied();
+        let word = |ch: Option<char>| ch.is_some_and(char::is_alphanumeric);
+
+        let escape = match c {
+            '\\' | '`' | '*' | '[' | ']' | '~' | '|' => true,
+            '_' => !(word(prev) && word(next)),
+            '<' => next.is_some_and(|n| n.is_ascii_alphabetic() || matches!(n, '/' | '!' | '?')),
+            '&' => looks_like_entity(&chars[i + 1..]),
+            '!' => next == Some('['),
+            // `![[embed]](url)` would be an image
+            '(' => i == 0 && out.ends_with("]]"),
+            // Text that reads as a bare URL would turn into a link
+            ':' => {
+                chars[i + 1..].starts_with(&['/', '/'])
+                    && prev.is_some_and(|p| p.is_ascii_alphabetic())
+            }
+            '.' if chars[..i].ends_with(&['w', 'w', 'w']) => true,
+            '#' | '>' | '-' | '+' | '=' => line_start,
+            // `1.` / `1)` at the start of a line opens an ordered list
+            '.' | ')' => digits_only && !line_start,
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+
+        line_start = context == InlineContext::Paragraph && c == '\n';
+        digits_only = line_start || (digits_only && c.is_ascii_digit());
+    }
+}
+
+/// Whether `rest` (after an `&`) would be read as an entity reference.
+fn looks_like_entity(rest: &[char]) -> bool {
+    let rest = match rest.first() {
+        Some('#') => &rest[1..],
+        _ => rest,
+    };
+    let name = rest
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .count();
+    name > 0 && rest.get(name) == Some(&';')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Document;
+    use proptest::prelude::*;
+
+    /// Parse, serialize, and check the result parses back to the same
+    /// Document and serializes to itself.
+    fn assert_round_trip(markdown: &str) -> String {
+        let doc = Document::parse(markdown);
+        let out = doc.to_markdown();
+        let reparsed = Document::parse(&out);
+        assert_eq!(
+            reparsed, doc,
+            "round trip changed the document\n--- input\n{markdown}\n--- output\n{out}"
+        );
+        assert_eq!(reparsed.to_markdown(), out, "output is not stable");
+        out
+    }
+
+    #[test]
+    fn test_canonical_document_is_unchanged() {
+        let md = "\
+---
+title:   Keep  my  spacing
+tags: [a, b]
+---
+
+# Heading
+
+A paragraph with **bold**, *italic*, `code` and a [link](https://fracta.app \"Fracta\").
+Second line of the same paragraph.
+
+- [x] Done
+- [ ] Todo
+  - Nested
+
+1. One
+2. Two
+
+> Quoted
+>
+> Twice
+
+```rust
+fn main() {}
+```
+
+| Name | Age |
+| :--- | ---: |
+| Alice | 30 |
+
+---
+
+![logo](logo.png)
+";
+        assert_eq!(assert_round_trip(md), md);
+    }
+
+    #[test]
+    fn test_front_matter_is_verbatim() {
+        let md = "---\n# comment\nkey:    value\n---\n\nBody\n";
+        assert_eq!(assert_round_trip(md), md);
+
+        // Not a mapping: not parsed, but still kept
+        let md = "---\njust a string\n---\n\nBody\n";
+        let doc = Document::parse(md);
+        assert!(doc.front_matter.is_none());
+        assert_eq!(doc.to_markdown(), md);
+    }
+
+    #[test]
+    fn test_toggle_task_touches_one_line() {
+        let md = "# Todo\n\n- [ ] First\n- [ ] Second\n";
+        let mut doc = Document::parse(md);
+        if let Block::List { items, .. } = &mut doc.blocks[1] {
+            items[1].checked = Some(true);
+        }
+        assert_eq!(doc.to_markdown(), "# Todo\n\n- [ ] First\n- [x] Second\n");
+    }
+
+    #[test]
+    fn test_escapes_only_markup() {
+        let text = |value: &str| Block::Paragraph {
+            content: vec![Inline::Text {
+                value: value.to_string(),
+            }],
+        };
+        assert_eq!(
+            blocks_to_markdown(&[text("Plain text, snake_case and 3.14!")]),
+            "Plain text, snake_case and 3.14!\n"
+        );
+        assert_eq!(
+            blocks_to_markdown(&[text("# not a heading *or* [link]")]),
+            "\\# not a heading \\*or\\* \\[link\\]\n"
+        );
+        assert_eq!(
+            blocks_to_markdown(&[text("1. not a list")]),
+            "1\\. not a list\n"
+        );
+    }
+
+    #[test]
+    fn test_footnotes_and_reference_definitions_kept() {
+        let md = "\
+Text[^1] more, see [the site][site].
+
+[^1]: A note
+    over two lines.
+
+[site]: https://fracta.app \"Fracta\"
+
+[^unused]: Nothing refers to this.
+";
+        // The reference link is written inline; its definition stays
+        assert_eq!(
+            assert_round_trip(md),
+            md.replace("[site].", "(https://fracta.app \"Fracta\").")
+        );
+
+        let doc = Document::parse(md);
+        let kinds: Vec<_> = doc.blocks.iter().map(Block::kind).collect();
+        // Both definitions after the footnote share one gap
+        assert_eq!(kinds, ["paragraph", "footnote_definition", "verbatim"]);
+        assert!(matches!(
+            &doc.blocks[0],
+            Block::Paragraph { content } if content[1] == Inline::FootnoteReference {
+                label: "1".to_string()
+            }
+        ));
+
+        // A definition opening a paragraph is kept apart from it
+        let md = "[a]: /url\nText [a].\n";
+        let doc = Document::parse(md);
+        assert_eq!(doc.blocks[0].kind(), "verbatim");
+        let text = doc.span(&[1, 0]).unwrap().span;
+        assert_eq!(&md[text.range()], "Text ");
+        assert_eq!(doc.to_markdown(), "[a]: /url\n\nText [a](/url).\n");
+        assert_round_trip(md);
+    }
+
+    #[test]
+    fn test_edge_cases_round_trip() {
+        for md in [
+            "",
+            "***\n",
+            "- a\n\n* b\n",
+            "1. a\n\n1) b\n",
+            "```\n```` inner\n```\n",
+            "~~~ a`b\ncode\n~~~\n",
+            "`` a`b ``\n",
+            "Hello\\\nworld\n",
+            "<div>\nraw\n</div>\n",
+            "Visit https://fracta.app or www.fracta.app now\n",
+            "<https://fracta.app>\n",
+            "[x](<a b>) and [y](a(b)c)\n",
+            "# Heading #\n",
+            "a&amp;b &copy; \\<b>\n",
+            "!\\[not image\\]\n",
+            "| a |\n| - |\n| \\| |\n",
+            "> - [ ] quoted task\n",
+            "10. ten\n11. eleven\n",
+            "[[Note]] [[a\\|b#h|c\\]d]] [[#Top]] [[x&amp;y]]\n",
+            "!\\[[a]] ![[a.png|200]]![[b.png]](c) \\![[d]]\n",
+            "| [[a\\|b]] | ![[c]] |\n| - | - |\n",
+            "[[a|a]] [[a\\|a]] ![[b|b]]\n",
+            "| [[a\\|a]] |\n| - |\n",
+        ] {
+            assert_round_trip(md);
+        }
+    }
+
+    /// Words with characters that need escaping in some position.
+    fn word() -> impl Strategy<Value = &'static str> {
+        let words = "word Fracta snake_case 3.14 é x_y_ a*b #tag 1. 2) - + = * _ ~ | ! [x] \
+                     <b> </b> &amp; & \\* https://x.io https://x.io. (https://x.io/a) \
+                     www.x.io a@b.io <https://x.io>";
+        prop::sample::select(words.split_whitespace().collect::<Vec<_>>())
+    }
+
+    /// A word, possibly wrapped in inline markup.
+    fn token() -> impl Strategy<Value = String> {
+        let wrappers = prop::sample::select(vec![
+            "{}",
+            "{}",
+            "{}",
+            "*{}*",
+            "_{}_",
+            "**{}**",
+            "***{}***",
+            "~~{}~~",
+            "`{}`",
+            "[{}](url)",
+            "[{}](<a b> \"t\")",
+            "![{}](img.png)",
+            "[[{}]]",
+            "[[{}#^id|alias]]",
+            "![[{}]]",
+        ]);
+        (wrappers, word()).prop_map(|(wrapper, word)| wrapper.replace("{}", word))
+    }
+
+    /// One source line, or a multi-line block.
+    fn line() -> impl Strategy<Value = String> {
+        let prefixes = prop::sample::select(vec![
+            "", "", "", "# ", "### ", "- ", "* ", "1. ", "3) ", "> ", "- [ ] ", "- [x] ", "  ",
+            "  - ", "> - ", "    ",
+        ]);
+        let text = (prefixes, prop::collection::vec(token(), 1..6))
+            .prop_map(|(prefix, tokens)| format!("{prefix}{}", tokens.join(" ")));
+        let blocks = prop::sample::select(vec![
+            "",
+            "```rust\ncode\n```",
+            "~~~\n```\n~~~",
+            "| a | b |\n|---|:-:|\n| c | d |",
+            "---",
+            "***",
+            "<div>\nhtml\n</div>",
+        ])
+        .prop_map(String::from);
+        prop_oneof![4 => text, 1 => blocks]
+    }
+
+    fn markdown() -> impl Strategy<Value = String> {
+        prop::collection::vec(line(), 0..12).prop_map(|lines| lines.join("\n"))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_serialize_parse(md in markdown()) {
+            assert_round_trip(&md);
+        }
+
+        #[test]
+        fn prop_front_matter_survives(yaml in "[a-z]{1,6}: [ a-z0-9]{0,10}", body in markdown()) {
+            let md = format!("---\n{yaml}\n---\n\n{body}");
+            let doc = Document::parse(&md);
+            let out = doc.to_markdown();
+            prop_assert!(out.starts_with(doc.front_matter_source.as_deref().unwrap()));
+            prop_assert_eq!(Document::parse(&out), doc);
+        }
+    }
+}
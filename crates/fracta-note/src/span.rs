@@ -0,0 +1,460 @@
+//! Source spans for the Block model.
+//!
+//! Spans are kept in a side table next to the blocks rather than on `Block`
+//! and `Inline`, so the model's JSON shape and equality stay as they are. A
+//! [`SpanTree`] mirrors one block, with a child for each nested node in the
+//! model's order:
+//!
+//! - `Heading`, `Paragraph`: one per inline
+//! - `BlockQuote`, `FootnoteDefinition`: one per child block
+//! - `List`: one per item; an item has one per child block
+//! - `Table`: one per row; a row has one per cell; a cell one per inline
+//! - `Emphasis`, `Strong`, `Strikethrough`, `Link`: one per child inline
+//! - everything else: none
+//!
+//! A path of child indices, starting with the block's index, names a node.
+
+use serde::{Deserialize, Serialize};
+
+use crate::block::{Block, Inline};
+use crate::Document;
+
+/// A point in the source text.
+///
+/// Lines and columns are 1-based and columns count bytes, as comrak reports
+/// them; `offset` is the 0-based byte offset into the whole text (front
+/// matter included).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+/// A range of source text; `end` is exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// The byte range, for slicing the source.
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start.offset..self.end.offset
+    }
+
+    /// Whether the byte at `offset` lies inside the span.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.range().contains(&offset)
+    }
+
+    /// Whether the span was recorded (comrak leaves some inlines unplaced).
+    pub(crate) fn is_known(&self) -> bool {
+        self.start.line > 0
+    }
+
+    /// The smallest span covering both.
+    pub(crate) fn cover(self, other: Span) -> Span {
+        if !self.is_known() {
+            return other;
+        }
+        if !other.is_known() {
+            return self;
+        }
+        Span {
+            start: if other.start.offset < self.start.offset {
+                other.start
+            } else {
+                self.start
+            },
+            end: if other.end.offset > self.end.offset {
+                other.end
+            } else {
+                self.end
+            },
+        }
+    }
+}
+
+/// The span of one node of the model, and its children's (see module docs).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanTree {
+    pub span: Span,
+    pub children: Vec<SpanTree>,
+}
+
+impl SpanTree {
+    /// A container node, widened to cover its children: comrak sometimes
+    /// ends a container before its last child (an unclosed fence, say).
+    pub(crate) fn enclosing(span: Span, children: Vec<SpanTree>) -> Self {
+        let span = children
+            .iter()
+            .fold(span, |span, child| span.cover(child.span));
+        SpanTree { span, children }
+    }
+
+    /// Follow child indices down from this node.
+    pub fn get(&self, path: &[usize]) -> Option<&SpanTree> {
+        path.iter().try_fold(self, |node, &i| node.children.get(i))
+    }
+
+    /// Move this node and its descendants `by` lines down.
+    pub(crate) fn shift_lines(&mut self, by: usize, lines: &LineIndex) {
+        if self.span.is_known() {
+            let (start, end) = (self.span.start, self.span.end);
+            self.span = Span {
+                start: lines.position(start.line + by, start.column),
+                end: lines.position(end.line + by, end.column),
+            };
+        }
+        for child in &mut self.children {
+            child.shift_lines(by, lines);
+        }
+    }
+
+    /// Give this node and its unplaced descendants `span`.
+    pub(crate) fn place(&mut self, span: Span) {
+        if !self.span.is_known() {
+            self.span = span;
+        }
+        for child in &mut self.children {
+            child.place(span);
+        }
+    }
+}
+
+/// One node of a document with its span, for consumers that want a flat
+/// list (the FFI).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanEntry {
+    /// Child indices from the top-level block (see module docs).
+    pub path: Vec<usize>,
+    /// The node's serde `type` tag (`"heading"`, `"text"`, …), or
+    /// `"list_item"`, `"table_row"`, `"table_cell"`.
+    pub kind: &'static str,
+    pub span: Span,
+}
+
+impl Document {
+    /// The span tree at `path` (block index first, then child indices).
+    pub fn span(&self, path: &[usize]) -> Option<&SpanTree> {
+        let (first, rest) = path.split_first()?;
+        self.spans.get(*first)?.get(rest)
+    }
+
+    /// Every node with its span, depth first in document order.
+    pub fn span_entries(&self) -> Vec<SpanEntry> {
+        let mut entries = Vec::new();
+        for (i, (block, tree)) in self.blocks.iter().zip(&self.spans).enumerate() {
+            walk_block(block, tree, &mut vec![i], &mut entries);
+        }
+        entries
+    }
+}
+
+fn push(entries: &mut Vec<SpanEntry>, path: &[usize], kind: &'static str, tree: &SpanTree) {
+    entries.push(SpanEntry {
+        path: path.to_vec(),
+        kind,
+        span: tree.span,
+    });
+}
+
+fn walk_block(block: &Block, tree: &SpanTree, path: &mut Vec<usize>, entries: &mut Vec<SpanEntry>) {
+    push(entries, path, block.kind(), tree);
+    let children = tree.children.iter().enumerate();
+    match block {
+        Block::Heading { content, .. } | Block::Paragraph { content } => {
+            walk_inlines(content, tree, path, entries);
+        }
+        Block::BlockQuote { children: blocks }
+        | Block::FootnoteDefinition {
+            children: blocks, ..
+        } => {
+            for ((i, child), block) in children.zip(blocks) {
+                path.push(i);
+                walk_block(block, child, path, entries);
+                path.pop();
+            }
+        }
+        Block::List { items, .. } => {
+            for ((i, item_tree), item) in children.zip(items) {
+                path.push(i);
+                push(entries, path, "list_item", item_tree);
+                for ((j, child), block) in item_tree.children.iter().enumerate().zip(&item.children)
+                {
+                    path.push(j);
+                    walk_block(block, child, path, entries);
+                    path.pop();
+                }
+                path.pop();
+            }
+        }
+        Block::Table { rows, .. } => {
+            for ((i, row_tree), row) in children.zip(rows) {
+                path.push(i);
+                push(entries, path, "table_row", row_tree);
+                for ((j, cell_tree), cell) in row_tree.children.iter().enumerate().zip(&row.cells) {
+                    path.push(j);
+                    push(entries, path, "table_cell", cell_tree);
+                    walk_inlines(cell, cell_tree, path, entries);
+                    path.pop();
+                }
+                path.pop();
+            }
+        }
+        Block::CodeBlock { .. }
+        | Block::ThematicBreak
+        | Block::HtmlBlock { .. }
+        | Block::Verbatim { .. } => {}
+    }
+}
+
+fn walk_inlines(
+    inlines: &[Inline],
+    tree: &SpanTree,
+    path: &mut Vec<usize>,
+    entries: &mut Vec<SpanEntry>,
+) {
+    for ((i, child), inline) in tree.children.iter().enumerate().zip(inlines) {
+        path.push(i);
+        push(entries, path, inline.kind(), child);
+        match inline {
+            Inline::Emphasis { children }
+            | Inline::Strong { children }
+            | Inline::Strikethrough { children }
+            | Inline::Link { children, .. } => walk_inlines(children, child, path, entries),
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+/// Maps comrak's line/column positions to byte offsets.
+pub(crate) struct LineIndex<'a> {
+    source: &'a str,
+    /// Byte offset where each line starts.
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+        LineIndex { source, starts }
+    }
+
+    /// The source text in `range`.
+    pub(crate) fn slice(&self, range: std::ops::Range<usize>) -> &'a str {
+        &self.source[range]
+    }
+
+    /// The offset of the last `needle` inside `range`.
+    pub(crate) fn rfind(&self, needle: &str, range: std::ops::Range<usize>) -> Option<usize> {
+        if needle.is_empty() {
+            return None;
+        }
+        let haystack = self.source.get(range.clone())?;
+        haystack.rfind(needle).map(|i| range.start + i)
+    }
+
+    /// The position at 1-based `line` and byte `column`.
+    pub(crate) fn position(&self, line: usize, column: usize) -> Position {
+        let start = self.starts[line.clamp(1, self.starts.len()) - 1];
+        self.at(start + column.saturating_sub(1))
+    }
+
+    /// The position of byte `offset`, clamped back to a char boundary
+    /// inside the text.
+    pub(crate) fn at(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.starts.partition_point(|&start| start <= offset);
+        Position {
+            line,
+            column: offset - self.starts[line - 1] + 1,
+            offset,
+        }
+    }
+
+    /// comrak places a code span at its content; widen it over the
+    /// backticks (and the space padding just inside them).
+    pub(crate) fn code_span(&self, span: Span) -> Span {
+        let bytes = self.source.as_bytes();
+        let (mut start, mut end) = (span.start.offset, span.end.offset);
+        let ticks_before = |at: usize| bytes[..at].iter().rev().take_while(|&&b| b == b'`').count();
+        if ticks_before(start) == 0 && start > 0 && bytes[start - 1] == b' ' {
+            start -= 1;
+        }
+        let ticks = ticks_before(start);
+        if ticks == 0 {
+            return span;
+        }
+        if bytes.get(end) == Some(&b' ') && bytes.get(end + 1) == Some(&b'`') {
+            end += 1;
+        }
+        Span {
+            start: self.at(start - ticks),
+            end: self.at(end + ticks),
+        }
+    }
+
+    /// comrak places `<url>` autolinks one byte late; move them back over
+    /// the angle brackets.
+    pub(crate) fn angle_link(&self, span: Span) -> Span {
+        let (start, end) = (span.start.offset, span.end.offset);
+        let bytes = self.source.as_bytes();
+        if start == 0 || bytes[start - 1] != b'<' || bytes[start] == b'[' || bytes[end - 2] != b'>'
+        {
+            return span;
+        }
+        Span {
+            start: self.at(start - 1),
+            end: self.at(end - 1),
+        }
+    }
+
+    /// comrak places a line break at its first byte (the `\r` of a
+    /// `\r\n`); widen it over the whole line ending, and a hard break over
+    /// the spaces written before it.
+    pub(crate) fn line_break(&self, span: Span) -> Span {
+        if !span.is_known() {
+            return span;
+        }
+        let bytes = self.source.as_bytes();
+        let (mut start, mut end) = (span.start.offset, span.end.offset);
+        if end > 0 && bytes[end - 1] == b'\r' && bytes.get(end) == Some(&b'\n') {
+            end += 1;
+        }
+        if matches!(bytes.get(start), Some(b'\r' | b'\n')) {
+            while start > 0 && matches!(bytes[start - 1], b' ' | b'\t') {
+                start -= 1;
+            }
+        }
+        Span {
+            start: self.at(start),
+            end: self.at(end),
+        }
+    }
+
+    /// The span from `start` to the inclusive `end` comrak reports.
+    ///
+    /// Line 0 means comrak did not place the node; it gets the default span.
+    pub(crate) fn span(&self, start: (usize, usize), end: (usize, usize)) -> Span {
+        if start.0 == 0 {
+            return Span::default();
+        }
+        let start = self.position(start.0, start.1);
+        let end = self.position(end.0, end.1 + 1);
+        // Cells padded onto a short table row come back ending before
+        // they start
+        if end.offset < start.offset {
+            return Span { start, end: start };
+        }
+        Span { start, end }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(md: &str, span: Span) -> &str {
+        &md[span.range()]
+    }
+
+    #[test]
+    fn test_block_and_inline_spans() {
+        let md = "---\ntitle: T\n---\n\n# Hé *there*\n\n- [ ] task\n- done\n";
+        let doc = Document::parse(md);
+        assert_eq!(doc.spans.len(), doc.blocks.len());
+
+        let heading = doc.span(&[0]).unwrap().span;
+        assert_eq!(heading.start.line, 5);
+        assert_eq!(text(md, heading), "# Hé *there*");
+
+        // Inlines: "Hé ", then the emphasis and its text
+        assert_eq!(text(md, doc.span(&[0, 0]).unwrap().span), "Hé ");
+        assert_eq!(text(md, doc.span(&[0, 1]).unwrap().span), "*there*");
+        assert_eq!(text(md, doc.span(&[0, 1, 0]).unwrap().span), "there");
+
+        // List → item → paragraph
+        let item = doc.span(&[1, 0]).unwrap().span;
+        assert_eq!(item.start.line, 7);
+        assert_eq!(text(md, doc.span(&[1, 0, 0]).unwrap().span), "task");
+        assert_eq!(text(md, doc.span(&[1, 1, 0]).unwrap().span), "done");
+        assert!(doc.span(&[1, 2]).is_none());
+    }
+
+    #[test]
+    fn test_merged_text_and_autolink_spans() {
+        let md = "a\\*b see https://fracta.app now\n";
+        let doc = Document::parse(md);
+        // Text split by comrak at the escape is merged, and so is its span
+        assert_eq!(text(md, doc.span(&[0, 0]).unwrap().span), "a\\*b see ");
+        // comrak leaves bare URLs unplaced; they get the gap between siblings
+        assert_eq!(
+            text(md, doc.span(&[0, 1]).unwrap().span),
+            "https://fracta.app"
+        );
+        assert_eq!(
+            text(md, doc.span(&[0, 1, 0]).unwrap().span),
+            "https://fracta.app"
+        );
+    }
+
+    #[test]
+    fn test_break_spans_cover_the_line_ending() {
+        for eol in ["\n", "\r\n"] {
+            let md = format!("a{eol}b  {eol}c\\{eol}d{eol}");
+            let doc = Document::parse(&md);
+            let span = |i| text(&md, doc.span(&[0, i]).unwrap().span);
+            assert_eq!(span(0), "a");
+            assert_eq!(span(1), eol);
+            assert_eq!(span(2), "b");
+            assert_eq!(span(3), format!("  {eol}"));
+            assert_eq!(span(4), "c");
+            assert_eq!(span(5), format!("\\{eol}"));
+        }
+    }
+
+    #[test]
+    fn test_table_spans_and_entries() {
+        let md = "| a | b |\n|---|---|\n| c | **d** |\n";
+        let doc = Document::parse(md);
+        assert_eq!(text(md, doc.span(&[0, 1, 1, 0]).unwrap().span), "**d**");
+
+        let entries = doc.span_entries();
+        let kinds: Vec<_> = entries.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                "table",
+                "table_row",
+                "table_cell",
+                "text",
+                "table_cell",
+                "text",
+                "table_row",
+                "table_cell",
+                "text",
+                "table_cell",
+                "strong",
+                "text"
+            ]
+        );
+        let strong = entries.iter().find(|e| e.kind == "strong").unwrap();
+        assert_eq!(strong.path, [0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_spans_do_not_affect_equality() {
+        let a = Document::parse("# Title\n");
+        let b = Document::parse("\n\n#   Title\n");
+        assert_ne!(a.spans, b.spans);
+        assert_eq!(a, b);
+    }
+}
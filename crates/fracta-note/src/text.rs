@@ -42,7 +42,7 @@ fn extract_block_text(block: &Block, buf: &mut String) {
                 buf.push('\n');
             }
         }
-        Block::BlockQuote { children } => {
+        Block::BlockQuote { children } | Block::FootnoteDefinition { children, .. } => {
             for child in children {
                 extract_block_text(child, buf);
             }
@@ -66,7 +66,7 @@ fn extract_block_text(block: &Block, buf: &mut String) {
             }
         }
         Block::ThematicBreak => {}
-        Block::HtmlBlock { .. } => {}
+        Block::HtmlBlock { .. } | Block::Verbatim { .. } => {}
     }
 }
 
@@ -84,9 +84,27 @@ fn extract_inline_text(inlines: &[Inline], buf: &mut String) {
                 extract_inline_text(children, buf);
             }
             Inline::Image { alt, .. } => buf.push_str(alt),
+            Inline::WikiLink {
+                target,
+                anchor,
+                alias,
+                ..
+            } => match alias {
+                Some(alias) => buf.push_str(alias),
+                // `[[note#Heading]]` reads as "note Heading"
+                None => {
+                    buf.push_str(target);
+                    if let Some(anchor) = anchor {
+                        if !target.is_empty() {
+                            buf.push(' ');
+                        }
+                        buf.push_str(anchor);
+                    }
+                }
+            },
             Inline::SoftBreak => buf.push(' '),
             Inline::HardBreak => buf.push('\n'),
-            Inline::Html { .. } => {}
+            Inline::Html { .. } | Inline::FootnoteReference { .. } => {}
         }
     }
 }
@@ -0,0 +1,1095 @@
+//! Format-preserving edits to YAML front matter.
+//!
+//! Writing a `serde_yaml::Value` back loses comments, key order and quoting
+//! style. The functions here find the lines that hold one field and return a
+//! [`Change`] to just those bytes, so every other line, comment and quote
+//! stays as written. They follow block mappings and sequences (the way front
+//! matter is written by hand) and one-line flow sequences such as
+//! `tags: [a, b]`; other values, including those written over several
+//! lines, can be replaced but not edited inside. Anchors, aliases and
+//! `?` keys are refused, and so is any edit whose result would not parse.
+//!
+//! Fields are named by a [`FieldPath`], written `profile.status` or
+//! `loot[0]`.
+
+use std::fmt;
+use std::ops::Range;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::edit::{line_ending, Change};
+use crate::error::{NoteError, Result};
+
+/// One step of a [`FieldPath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment {
+    /// A key in a mapping.
+    Key(String),
+    /// A position in a sequence.
+    Index(usize),
+}
+
+/// The location of a field in front matter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FieldPath(pub Vec<Segment>);
+
+impl From<&str> for FieldPath {
+    /// Parse dotted keys with `[n]` indices: `profile.status`, `loot[0]`.
+    /// A part that does not parse is taken as a key as written.
+    fn from(path: &str) -> Self {
+        let mut segments = Vec::new();
+        for part in path.split('.') {
+            let key_end = part.find('[').unwrap_or(part.len());
+            let mut indices = Vec::new();
+            let mut rest = &part[key_end..];
+            while let Some((index, after)) = rest
+                .strip_prefix('[')
+                .and_then(|inner| inner.split_once(']'))
+                .and_then(|(n, after)| Some((n.parse().ok()?, after)))
+            {
+                indices.push(Segment::Index(index));
+                rest = after;
+            }
+            if !rest.is_empty() {
+                segments.push(Segment::Key(part.to_string()));
+                continue;
+            }
+            if key_end > 0 {
+                segments.push(Segment::Key(part[..key_end].to_string()));
+            }
+            segments.extend(indices);
+        }
+        FieldPath(segments)
+    }
+}
+
+impl From<String> for FieldPath {
+    fn from(path: String) -> Self {
+        FieldPath::from(path.as_str())
+    }
+}
+
+impl From<Vec<Segment>> for FieldPath {
+    fn from(segments: Vec<Segment>) -> Self {
+        FieldPath(segments)
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Key(key) if i == 0 => write!(f, "{key}")?,
+                Segment::Key(key) => write!(f, ".{key}")?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Set the field at `path` to `value`, adding it (and any missing parent
+/// mappings) if it is not there. A sequence index may be one past the end.
+pub fn set(yaml: &str, path: &FieldPath, value: &Value) -> Result<Change> {
+    let change = match lookup(yaml, path)? {
+        Spot::Field(field) => replace(&field, value),
+        Spot::FlowItem { flow, index } => Ok(Change {
+            range: flow.items[index].clone(),
+            text: flow_value(value)?,
+        }),
+        Spot::Missing(missing) => add(yaml, path, &missing, value),
+    }?;
+    checked(yaml, path, with_line_ending(yaml, change))
+}
+
+/// Insert `value` at `path`: before the item at a sequence index (or at the
+/// end), or as a new key, which must not exist yet.
+pub fn insert(yaml: &str, path: &FieldPath, value: &Value) -> Result<Change> {
+    let change = match lookup(yaml, path)? {
+        Spot::Field(field) if field.key.is_some() => Err(NoteError::FieldExists(path.to_string())),
+        Spot::Field(field) => {
+            // `- new` goes where `- old` starts, pushing it to the next line
+            let at = field.lines[0].at;
+            let mut text = indent_rest(&format!("-{}", after_marker(value, true)?), field.indent);
+            text.push('\n');
+            text.push_str(&" ".repeat(field.indent));
+            Ok(Change {
+                range: at..at,
+                text,
+            })
+        }
+        Spot::FlowItem { flow, index } => {
+            let at = flow.items[index].start;
+            Ok(Change {
+                range: at..at,
+                text: format!("{}, ", flow_value(value)?),
+            })
+        }
+        Spot::Missing(missing) => add(yaml, path, &missing, value),
+    }?;
+    checked(yaml, path, with_line_ending(yaml, change))
+}
+
+/// Remove the field at `path`; `None` if it is not there.
+pub fn remove(yaml: &str, path: &FieldPath) -> Result<Option<Change>> {
+    let change = match lookup(yaml, path)? {
+        Spot::Missing(_) => return Ok(None),
+        Spot::Field(field) => {
+            let first = &field.lines[0];
+            if first.inner {
+                // The first key of a mapping that starts on its item's `- `
+                // line: pull the next key up, or leave an empty mapping
+                match field.next_at {
+                    Some(next) => Change {
+                        range: first.at..next,
+                        text: String::new(),
+                    },
+                    None => Change {
+                        range: first.at..field.content_end(),
+                        text: "{}".to_string(),
+                    },
+                }
+            } else {
+                Change {
+                    range: first.line.0..field.lines[field.lines.len() - 1].line.1,
+                    text: String::new(),
+                }
+            }
+        }
+        Spot::FlowItem { flow, index } => {
+            let items = &flow.items;
+            let range = if items.len() == 1 {
+                items[0].clone()
+            } else if index + 1 < items.len() {
+                items[index].start..items[index + 1].start
+            } else {
+                items[index - 1].end..items[index].end
+            };
+            Change {
+                range,
+                text: String::new(),
+            }
+        }
+    };
+    checked(yaml, path, change).map(Some)
+}
+
+/// Where the scalar value at `path` is written (quotes included), if it is
+/// on one line.
+pub(crate) fn value_range(yaml: &str, path: &FieldPath) -> Option<Range<usize>> {
+    match lookup(yaml, path).ok()? {
+        Spot::Field(Field {
+            value: Val::Inline { range, .. },
+            ..
+        }) => Some(range),
+        Spot::FlowItem { flow, index } => Some(flow.items[index].clone()),
+        _ => None,
+    }
+}
+
+// ── Reading the layout ─────────────────────────────────────────────────
+
+/// A line of YAML, or the part of one after a sequence item's `- ` when a
+/// mapping or sequence starts there.
+#[derive(Debug, Clone, Copy)]
+struct Ln<'a> {
+    /// Where `text` starts.
+    at: usize,
+    /// The column `text` starts at.
+    indent: usize,
+    /// The content, without the newline.
+    text: &'a str,
+    /// The whole line, newline included.
+    line: (usize, usize),
+    /// Whether this starts after a `- ` on the same line.
+    inner: bool,
+}
+
+impl Ln<'_> {
+    fn is_content(&self) -> bool {
+        !self.text.trim().is_empty() && !self.text.starts_with('#')
+    }
+
+    fn end(&self) -> usize {
+        self.at + self.text.len()
+    }
+}
+
+fn lines(yaml: &str) -> Vec<Ln<'_>> {
+    let mut start = 0;
+    yaml.split_inclusive('\n')
+        .map(|raw| {
+            let line = (start, start + raw.len());
+            start = line.1;
+            let content = raw.trim_end_matches(['\n', '\r']);
+            let text = content.trim_start_matches(' ');
+            let indent = content.len() - text.len();
+            Ln {
+                at: line.0 + indent,
+                indent,
+                text,
+                line,
+                inner: false,
+            }
+        })
+        .collect()
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// An entry of a block mapping or an item of a block sequence.
+#[derive(Debug, Clone)]
+struct Field<'a> {
+    /// `None` for a sequence item.
+    key: Option<String>,
+    /// Its lines, up to the last one with content; the first may be virtual.
+    lines: Vec<Ln<'a>>,
+    /// The column the key or `-` is at.
+    indent: usize,
+    /// Just after the `key:` or `-`.
+    value_at: usize,
+    value: Val<'a>,
+    /// Where the next field of the same mapping or sequence starts.
+    next_at: Option<usize>,
+}
+
+impl Field<'_> {
+    fn content_end(&self) -> usize {
+        self.lines[self.lines.len() - 1].end()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Val<'a> {
+    /// Written on the field's own line (a block scalar runs on over the
+    /// following lines); `range` stops before any comment.
+    Inline { range: Range<usize>, text: &'a str },
+    /// A mapping or sequence on the following lines; empty for null.
+    Block(Vec<Ln<'a>>),
+}
+
+/// The fields of the mapping or sequence made of `region`; `None` if a
+/// line at its indent is not one (a `? complex` key, an anchored key, …).
+fn fields<'a>(region: &[Ln<'a>]) -> Option<Vec<Field<'a>>> {
+    let Some(first) = region.iter().find(|ln| ln.is_content()) else {
+        return Some(Vec::new());
+    };
+    let (indent, items) = (first.indent, is_item(first.text));
+    // Items at a key's own indent are that key's value (`tags:\n- a`)
+    let starts: Vec<usize> = (0..region.len())
+        .filter(|&i| {
+            let ln = &region[i];
+            ln.is_content() && ln.indent == indent && is_item(ln.text) == items
+        })
+        .collect();
+
+    let mut fields: Vec<Field<'a>> = Vec::new();
+    for (n, &start) in starts.iter().enumerate() {
+        let end = starts.get(n + 1).copied().unwrap_or(region.len());
+        let last = (start..end)
+            .rev()
+            .find(|&i| region[i].is_content())
+            .unwrap_or(start);
+        let lines = &region[start..=last];
+        let mut field = if items { item(lines) } else { entry(lines) }?;
+        field.next_at = starts.get(n + 1).map(|&next| region[next].at);
+        fields.push(field);
+    }
+    Some(fields)
+}
+
+fn entry<'a>(lines: &[Ln<'a>]) -> Option<Field<'a>> {
+    let first = lines[0];
+    let (key, after) = split_key(first.text)?;
+    Some(Field {
+        key: Some(key),
+        lines: lines.to_vec(),
+        indent: first.indent,
+        value_at: first.at + after,
+        value: value(lines, after),
+        next_at: None,
+    })
+}
+
+fn item<'a>(lines: &[Ln<'a>]) -> Option<Field<'a>> {
+    let first = lines[0];
+    let rest = &first.text[1..];
+    let inner = rest.trim_start();
+    let nested = is_item(inner) || (split_key(inner).is_some() && !inner.starts_with('#'));
+    let value = if nested {
+        // `- key: v` or `- - v`: a collection starting on this line
+        let lead = first.text.len() - inner.len();
+        let mut region = vec![Ln {
+            at: first.at + lead,
+            indent: first.indent + lead,
+            text: inner,
+            line: first.line,
+            inner: true,
+        }];
+        region.extend_from_slice(&lines[1..]);
+        Val::Block(region)
+    } else {
+        value(lines, 1)
+    };
+    Some(Field {
+        key: None,
+        lines: lines.to_vec(),
+        indent: first.indent,
+        value_at: first.at + 1,
+        value,
+        next_at: None,
+    })
+}
+
+/// The value of a field whose first line's value starts at byte `after`.
+fn value<'a>(lines: &[Ln<'a>], after: usize) -> Val<'a> {
+    let first = lines[0];
+    let rest = strip_comment(&first.text[after..]);
+    let text = rest.trim_start();
+    if text.is_empty() {
+        return Val::Block(lines[1..].to_vec());
+    }
+    let start = first.at + after + (rest.len() - text.len());
+    let last = lines[lines.len() - 1];
+    let end = if text.starts_with(['|', '>']) {
+        last.end()
+    } else if lines.len() == 1 {
+        start + text.len()
+    } else if text.starts_with(['"', '\'', '[', '{']) {
+        // A quoted scalar or flow collection running on over the next lines
+        let from = start - first.at;
+        let parts = std::iter::once((start, &first.text[from..]))
+            .chain(lines[1..].iter().map(|ln| (ln.at, ln.text)));
+        closing(parts).unwrap_or(last.end())
+    } else {
+        // A plain scalar folded over the next lines
+        last.at + strip_comment(last.text).len()
+    };
+    Val::Inline {
+        range: start..end,
+        text,
+    }
+}
+
+/// Just past the quote or bracket that closes the one the first part
+/// starts with, reading `(offset, text)` parts (lines) in order.
+fn closing<'a>(parts: impl Iterator<Item = (usize, &'a str)>) -> Option<usize> {
+    let (mut depth, mut quote, mut reopen) = (0, None, None);
+    for (at, text) in parts {
+        let mut escaped = false;
+        for (i, c) in text.char_indices() {
+            if let Some(q) = reopen.take() {
+                // `''` inside a single-quoted scalar is an escaped quote
+                if c == q {
+                    quote = Some(q);
+                    continue;
+                }
+                if depth == 0 {
+                    return Some(at + i);
+                }
+            }
+            if let Some(q) = quote {
+                if c == q && !escaped {
+                    quote = None;
+                    if q == '\'' {
+                        reopen = Some(q);
+                    } else if depth == 0 {
+                        return Some(at + i + 1);
+                    }
+                }
+                escaped = q == '"' && c == '\\' && !escaped;
+                continue;
+            }
+            match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(at + i + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if reopen.take().is_some() && depth == 0 {
+            return Some(at + text.len());
+        }
+    }
+    None
+}
+
+/// Split `key: value`, returning the key and the offset just past the `:`.
+fn split_key(text: &str) -> Option<(String, usize)> {
+    let (key, key_end) = match text.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let mut escaped = false;
+            let close = text
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| {
+                    let closes = c == quote && !escaped;
+                    escaped = quote == '"' && c == '\\' && !escaped;
+                    closes
+                })?
+                .0;
+            (serde_yaml::from_str(&text[..=close]).ok()?, close + 1)
+        }
+        '[' | '{' | '#' | '&' | '*' | '!' | '|' | '>' | '?' | '%' | '@' | '`' => return None,
+        _ if is_item(text) => return None,
+        _ => {
+            let colon = text
+                .match_indices(':')
+                .map(|(i, _)| i)
+                .find(|&i| text[i + 1..].is_empty() || text[i + 1..].starts_with([' ', '\t']))?;
+            (text[..colon].trim_end().to_string(), colon)
+        }
+    };
+    let rest = &text[key_end..];
+    let colon = key_end + (rest.len() - rest.trim_start().len());
+    let after = &text[colon..];
+    let is_colon =
+        after.starts_with(':') && (after.len() == 1 || after[1..].starts_with([' ', '\t']));
+    is_colon.then_some((key, colon + 1))
+}
+
+/// `text` without a trailing `# comment`.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && (prev.is_whitespace() || "[{,:".contains(prev)) => {
+                quote = Some(c)
+            }
+            None if c == '#' && prev.is_whitespace() => return text[..i].trim_end(),
+            None => {}
+        }
+        prev = c;
+    }
+    text.trim_end()
+}
+
+/// A one-line flow sequence.
+#[derive(Debug, Clone)]
+struct Flow {
+    /// Just after the `[`.
+    open: usize,
+    /// Each item, without surrounding spaces.
+    items: Vec<Range<usize>>,
+}
+
+/// Parse the flow sequence `text` (starting with `[`) found at byte `at`.
+fn flow(text: &str, at: usize) -> Option<Flow> {
+    let mut items = Vec::new();
+    let mut item: Option<Range<usize>> = None;
+    let (mut depth, mut quote, mut escaped) = (0, None, false);
+    for (i, c) in text.char_indices() {
+        let here = at + i..at + i + c.len_utf8();
+        if let Some(q) = quote {
+            if c == q && !escaped {
+                quote = None;
+            }
+            escaped = q == '"' && c == '\\' && !escaped;
+        } else {
+            match c {
+                '[' | '{' => depth += 1,
+                ']' | '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        items.extend(item);
+                        return Some(Flow {
+                            open: at + 1,
+                            items,
+                        });
+                    }
+                }
+                ',' if depth == 1 => {
+                    items.extend(item.take());
+                    continue;
+                }
+                '"' | '\'' => quote = Some(c),
+                _ => {}
+            }
+        }
+        if depth >= 1 && !c.is_whitespace() && !(depth == 1 && c == '[' && i == 0) {
+            let range = item.get_or_insert(here.clone());
+            range.end = here.end;
+        }
+    }
+    None
+}
+
+/// Where a missing field would go.
+struct Missing {
+    /// How much of the path exists.
+    depth: usize,
+    /// Where to add it, and at what indent.
+    at: usize,
+    indent: usize,
+    /// Set when the container is a flow sequence.
+    flow: Option<Flow>,
+}
+
+enum Spot<'a> {
+    Field(Field<'a>),
+    FlowItem { flow: Flow, index: usize },
+    Missing(Missing),
+}
+
+fn lookup<'a>(yaml: &'a str, path: &FieldPath) -> Result<Spot<'a>> {
+    let not_editable =
+        |depth: usize| NoteError::FieldNotEditable(FieldPath(path.0[..depth].to_vec()).to_string());
+    if path.0.is_empty() {
+        return Err(not_editable(0));
+    }
+    let mut region = lines(yaml);
+    let mut parent: Option<Field<'a>> = None;
+    for (depth, segment) in path.0.iter().enumerate() {
+        let Some(fields) = fields(&region) else {
+            return Err(not_editable(depth));
+        };
+        let is_sequence = fields.first().is_some_and(|f| f.key.is_none());
+        let found = match segment {
+            Segment::Key(_) if is_sequence => return Err(not_editable(depth)),
+            Segment::Index(_) if !is_sequence && !fields.is_empty() => {
+                return Err(not_editable(depth))
+            }
+            Segment::Key(key) => fields.into_iter().find(|f| f.key.as_ref() == Some(key)),
+            Segment::Index(index) => {
+                if *index > fields.len() {
+                    return Err(NoteError::FieldNotFound(path.to_string()));
+                }
+                fields.into_iter().nth(*index)
+            }
+        };
+
+        let Some(field) = found else {
+            let last = region.iter().rev().find(|ln| ln.is_content());
+            let (at, indent) = match (last, &parent) {
+                (Some(last), _) => (last.line.1, fields_indent(&region)),
+                (None, Some(parent)) => {
+                    let nested = matches!(segment, Segment::Key(_)) as usize * 2;
+                    (
+                        parent.lines[parent.lines.len() - 1].line.1,
+                        parent.indent + nested,
+                    )
+                }
+                (None, None) => (yaml.len(), 0),
+            };
+            return Ok(Spot::Missing(Missing {
+                depth,
+                at,
+                indent,
+                flow: None,
+            }));
+        };
+        if matches!(&field.value, Val::Inline { text, .. } if text.starts_with(['&', '*'])) {
+            // Rewriting an anchor or alias would change (or break) the
+            // values that share it
+            return Err(not_editable(depth + 1));
+        }
+        if depth + 1 == path.0.len() {
+            return Ok(Spot::Field(field));
+        }
+        match &field.value {
+            Val::Block(lines) => {
+                region = lines.clone();
+                parent = Some(field);
+            }
+            // An item of a one-line flow sequence
+            Val::Inline { range, text } if text.starts_with('[') && depth + 2 == path.0.len() => {
+                let (Segment::Index(index), Some(flow)) =
+                    (&path.0[depth + 1], flow(text, range.start))
+                else {
+                    return Err(not_editable(depth + 1));
+                };
+                return match index.cmp(&flow.items.len()) {
+                    std::cmp::Ordering::Less => Ok(Spot::FlowItem {
+                        flow,
+                        index: *index,
+                    }),
+                    std::cmp::Ordering::Equal => Ok(Spot::Missing(Missing {
+                        depth: depth + 1,
+                        at: 0,
+                        indent: 0,
+                        flow: Some(flow),
+                    })),
+                    std::cmp::Ordering::Greater => Err(NoteError::FieldNotFound(path.to_string())),
+                };
+            }
+            Val::Inline { .. } => return Err(not_editable(depth + 1)),
+        }
+    }
+    unreachable!("the loop returns at the last segment")
+}
+
+fn fields_indent(region: &[Ln<'_>]) -> usize {
+    region
+        .iter()
+        .find(|ln| ln.is_content())
+        .map_or(0, |ln| ln.indent)
+}
+
+// ── Writing ────────────────────────────────────────────────────────────
+
+/// `change`, if `yaml` still parses once it is made. The layout rules above
+/// cover what front matter is written like, not all of YAML; this catches
+/// an edit they got wrong before it reaches the file.
+fn checked(yaml: &str, path: &FieldPath, change: Change) -> Result<Change> {
+    let mut out = yaml.to_string();
+    out.replace_range(change.range.clone(), &change.text);
+    match serde_yaml::from_str::<Value>(&out) {
+        Ok(_) => Ok(change),
+        Err(_) => Err(NoteError::FieldNotEditable(path.to_string())),
+    }
+}
+
+/// End the lines `change` writes the way `yaml` ends its own.
+fn with_line_ending(yaml: &str, mut change: Change) -> Change {
+    let eol = line_ending(yaml);
+    if eol != "\n" {
+        change.text = change.text.replace('\n', eol);
+    }
+    change
+}
+
+/// Give a new value to an existing field.
+fn replace(field: &Field<'_>, value: &Value) -> Result<Change> {
+    if let Val::Inline { range, text } = &field.value {
+        if let Some(inline) = inline_value(value, text)? {
+            // Only the old value's bytes; a trailing comment stays
+            return Ok(Change {
+                range: range.clone(),
+                text: inline,
+            });
+        }
+    }
+    Ok(Change {
+        range: field.value_at..field.content_end(),
+        text: indent_rest(&after_marker(value, field.key.is_none())?, field.indent),
+    })
+}
+
+/// Add the missing part of `path` with `value` as its leaf.
+fn add(yaml: &str, path: &FieldPath, missing: &Missing, value: &Value) -> Result<Change> {
+    let rest = &path.0[missing.depth..];
+    // Missing parents become mappings around the value
+    let mut value = value.clone();
+    for segment in rest[1..].iter().rev() {
+        let Segment::Key(key) = segment else {
+            return Err(NoteError::FieldNotFound(path.to_string()));
+        };
+        let mut mapping = Mapping::new();
+        mapping.insert(Value::String(key.clone()), value);
+        value = Value::Mapping(mapping);
+    }
+
+    if let Some(flow) = &missing.flow {
+        let item = flow_value(&value)?;
+        return Ok(match flow.items.last() {
+            Some(last) => Change {
+                range: last.end..last.end,
+                text: format!(", {item}"),
+            },
+            None => Change {
+                range: flow.open..flow.open,
+                text: item,
+            },
+        });
+    }
+
+    let field = match &rest[0] {
+        Segment::Key(key) => {
+            let key = serde_yaml::to_string(&Value::String(key.clone()))?;
+            format!("{}:{}", key.trim_end(), after_marker(&value, false)?)
+        }
+        Segment::Index(_) => format!("-{}", after_marker(&value, true)?),
+    };
+    let mut text = String::new();
+    if missing.at > 0 && !yaml[..missing.at].ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&" ".repeat(missing.indent));
+    text.push_str(&indent_rest(&field, missing.indent));
+    text.push('\n');
+    Ok(Change {
+        range: missing.at..missing.at,
+        text,
+    })
+}
+
+/// `value` as written after `key:` (or after `-` for an item), in
+/// serde_yaml's block style, with lines after the first relative to the
+/// field's column.
+fn after_marker(value: &Value, is_item: bool) -> Result<String> {
+    let (yaml, marker) = if is_item {
+        (serde_yaml::to_string(&vec![value])?, "-")
+    } else {
+        let mut mapping = Mapping::new();
+        mapping.insert(Value::String("k".to_string()), value.clone());
+        (serde_yaml::to_string(&mapping)?, "k:")
+    };
+    Ok(yaml[marker.len()..].trim_end_matches('\n').to_string())
+}
+
+/// Indent every line after the first by `indent` columns.
+fn indent_rest(text: &str, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => line.to_string(),
+            _ if line.is_empty() => String::new(),
+            _ => format!("{pad}{line}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `value` written on one line in the style of the `old` value, or `None`
+/// if it needs a block.
+fn inline_value(value: &Value, old: &str) -> Result<Option<String>> {
+    match value {
+        Value::Sequence(_) | Value::Mapping(_) if old.starts_with(['[', '{']) => {
+            return flow_value(value).map(Some)
+        }
+        Value::String(s) if !s.contains('\n') && old.starts_with('"') => {
+            return Ok(Some(double_quoted(s)))
+        }
+        Value::String(s) if !s.contains('\n') && old.starts_with('\'') => {
+            return Ok(Some(format!("'{}'", s.replace('\'', "''"))))
+        }
+        _ => {}
+    }
+    let text = after_marker(value, false)?;
+    Ok(text
+        .strip_prefix(' ')
+        .filter(|inline| !inline.contains('\n'))
+        .map(str::to_string))
+}
+
+/// `value` in flow style, for inside `[...]`.
+fn flow_value(value: &Value) -> Result<String> {
+    Ok(match value {
+        Value::Sequence(items) => {
+            let items: Vec<String> = items.iter().map(flow_value).collect::<Result<_>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::Mapping(mapping) => {
+            let entries: Vec<String> = mapping
+                .iter()
+                .map(|(k, v)| Ok(format!("{}: {}", flow_value(k)?, flow_value(v)?)))
+                .collect::<Result<_>>()?;
+            format!("{{{}}}", entries.join(", "))
+        }
+        _ => {
+            let text = serde_yaml::to_string(value)?;
+            let text = text.trim_end_matches('\n');
+            let plain = !text.starts_with(['"', '\'']);
+            match value {
+                Value::String(s)
+                    if text.contains('\n') || (plain && s.contains([',', '[', ']', '{', '}'])) =>
+                {
+                    double_quoted(s)
+                }
+                _ => text.to_string(),
+            }
+        }
+    })
+}
+
+fn double_quoted(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = "\
+# Quest profile
+id: q-17   # stable
+type: quest
+title: \"Slay the 'dragon'\"
+tags: [rust, fracta]
+profile:
+  schema: quest/v2
+  status: active  # set by Fracta
+loot:
+- ./rewards/x.md
+- path: ./rewards/y.md
+  qty: 2
+";
+
+    fn apply(yaml: &str, change: Change) -> String {
+        let mut out = yaml.to_string();
+        out.replace_range(change.range, &change.text);
+        // Whatever the edit, the result is still valid YAML
+        serde_yaml::from_str::<Value>(&out).unwrap();
+        out
+    }
+
+    fn set_at(yaml: &str, path: &str, value: Value) -> String {
+        apply(yaml, set(yaml, &path.into(), &value).unwrap())
+    }
+
+    fn parsed(yaml: &str, path: &str) -> Value {
+        let mut value: Value = serde_yaml::from_str(yaml).unwrap();
+        for segment in FieldPath::from(path).0 {
+            value = match segment {
+                Segment::Key(key) => value[key.as_str()].clone(),
+                Segment::Index(index) => value[index].clone(),
+            };
+        }
+        value
+    }
+
+    #[test]
+    fn test_field_path() {
+        let path = FieldPath::from("loot[1].path");
+        assert_eq!(
+            path.0,
+            [
+                Segment::Key("loot".into()),
+                Segment::Index(1),
+                Segment::Key("path".into())
+            ]
+        );
+        assert_eq!(path.to_string(), "loot[1].path");
+        assert_eq!(FieldPath::from("a[x]").0, [Segment::Key("a[x]".into())]);
+    }
+
+    #[test]
+    fn test_set_keeps_comments_and_quotes() {
+        let out = set_at(PROFILE, "profile.status", "done".into());
+        assert_eq!(out, PROFILE.replace("status: active", "status: done"));
+
+        let out = set_at(PROFILE, "id", "q-18".into());
+        assert_eq!(out, PROFILE.replace("q-17   # stable", "q-18   # stable"));
+
+        // Quoting style follows the old value
+        let out = set_at(PROFILE, "title", "Tame it".into());
+        assert_eq!(out, PROFILE.replace("\"Slay the 'dragon'\"", "\"Tame it\""));
+
+        // A flow sequence stays a flow sequence
+        let tags = Value::Sequence(vec!["a".into(), "b, c".into()]);
+        let out = set_at(PROFILE, "tags", tags);
+        assert_eq!(out, PROFILE.replace("[rust, fracta]", "[a, \"b, c\"]"));
+    }
+
+    #[test]
+    fn test_set_nested_and_new_keys() {
+        let out = set_at(PROFILE, "loot[1].qty", 3.into());
+        assert_eq!(out, PROFILE.replace("qty: 2", "qty: 3"));
+
+        let out = set_at(PROFILE, "loot[1].path", "./z.md".into());
+        assert_eq!(
+            out,
+            PROFILE
+                .replace("y.md", "z.md")
+                .replace("./rewards/z", "./z")
+        );
+
+        // New keys go after the last one of their mapping
+        let out = set_at(PROFILE, "profile.owner", "me".into());
+        assert_eq!(out, PROFILE.replace("Fracta\n", "Fracta\n  owner: me\n"));
+
+        let out = set_at(PROFILE, "area", "games".into());
+        assert_eq!(out, format!("{PROFILE}area: games\n"));
+
+        // Missing parents are created as mappings
+        let out = set_at(PROFILE, "meta.review.due", "2025-02-01".into());
+        assert_eq!(parsed(&out, "meta.review.due"), Value::from("2025-02-01"));
+        assert!(out.starts_with(PROFILE));
+
+        // Replacing a block value with a scalar and back
+        let out = set_at(PROFILE, "profile", Value::Null);
+        assert_eq!(parsed(&out, "profile"), Value::Null);
+        assert_eq!(parsed(&out, "loot[0]"), Value::from("./rewards/x.md"));
+        let mut mapping = Mapping::new();
+        mapping.insert("schema".into(), "v3".into());
+        let out = set_at(&out, "profile", Value::Mapping(mapping));
+        assert_eq!(parsed(&out, "profile.schema"), Value::from("v3"));
+    }
+
+    #[test]
+    fn test_lists() {
+        let out = set_at(PROFILE, "loot[2]", "./rewards/z.md".into());
+        assert_eq!(out, format!("{PROFILE}- ./rewards/z.md\n"));
+
+        let change = insert(PROFILE, &"loot[1]".into(), &"./first.md".into()).unwrap();
+        let out = apply(PROFILE, change);
+        assert_eq!(out, PROFILE.replace("- path", "- ./first.md\n- path"));
+
+        let change = insert(PROFILE, &"tags[0]".into(), &"new".into()).unwrap();
+        assert_eq!(
+            apply(PROFILE, change),
+            PROFILE.replace("[rust", "[new, rust")
+        );
+        let change = insert(PROFILE, &"tags[2]".into(), &"end".into()).unwrap();
+        assert_eq!(
+            apply(PROFILE, change),
+            PROFILE.replace("fracta]", "fracta, end]")
+        );
+
+        let change = remove(PROFILE, &"tags[0]".into()).unwrap().unwrap();
+        assert_eq!(apply(PROFILE, change), PROFILE.replace("rust, ", ""));
+        let change = remove(PROFILE, &"loot[0]".into()).unwrap().unwrap();
+        assert_eq!(
+            apply(PROFILE, change),
+            PROFILE.replace("- ./rewards/x.md\n", "")
+        );
+
+        assert!(matches!(
+            set(PROFILE, &"loot[5]".into(), &Value::Null),
+            Err(NoteError::FieldNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_remove_and_insert_keys() {
+        let change = remove(PROFILE, &"profile".into()).unwrap().unwrap();
+        assert_eq!(
+            apply(PROFILE, change),
+            PROFILE.replace(
+                "profile:\n  schema: quest/v2\n  status: active  # set by Fracta\n",
+                ""
+            )
+        );
+
+        // The first key of an item's mapping pulls the next one up
+        let change = remove(PROFILE, &"loot[1].path".into()).unwrap().unwrap();
+        assert_eq!(
+            apply(PROFILE, change),
+            PROFILE.replace("- path: ./rewards/y.md\n  qty: 2", "- qty: 2")
+        );
+
+        assert!(remove(PROFILE, &"nope".into()).unwrap().is_none());
+        assert!(remove(PROFILE, &"profile.nope".into()).unwrap().is_none());
+
+        assert!(matches!(
+            insert(PROFILE, &"type".into(), &"x".into()),
+            Err(NoteError::FieldExists(_))
+        ));
+        let change = insert(PROFILE, &"due".into(), &"soon".into()).unwrap();
+        assert_eq!(apply(PROFILE, change), format!("{PROFILE}due: soon\n"));
+    }
+
+    #[test]
+    fn test_unsupported_paths() {
+        assert!(matches!(
+            set(PROFILE, &"type.sub".into(), &Value::Null),
+            Err(NoteError::FieldNotEditable(_))
+        ));
+        assert!(matches!(
+            set(PROFILE, &"loot.key".into(), &Value::Null),
+            Err(NoteError::FieldNotEditable(_))
+        ));
+    }
+
+    #[test]
+    fn test_multi_line_values_replaced_whole() {
+        let cases = [
+            ("tags: [a,\n  b]\nn: 1\n", "tags", "tags: [c]\nn: 1\n"),
+            (
+                "title: \"multi\n  line\"\nn: 1\n",
+                "title",
+                "title: \"c\"\nn: 1\n",
+            ),
+            ("m: {a: 1,\n  b: 2}\nn: 1\n", "m", "m: {c: 1}\nn: 1\n"),
+            (
+                "title: long\n  continued\nn: 1\n",
+                "title",
+                "title: c\nn: 1\n",
+            ),
+            ("t: 'it''s\n  x'  # note\n", "t", "t: 'c'  # note\n"),
+        ];
+        for (yaml, path, expected) in cases {
+            let value = match path {
+                "tags" => Value::Sequence(vec!["c".into()]),
+                "m" => {
+                    let mut mapping = Mapping::new();
+                    mapping.insert("c".into(), 1.into());
+                    Value::Mapping(mapping)
+                }
+                _ => "c".into(),
+            };
+            assert_eq!(set_at(yaml, path, value), expected);
+        }
+        // An item of a flow sequence spread over lines is not edited inside
+        assert!(matches!(
+            set("tags: [a,\n  b]\n", &"tags[0]".into(), &"c".into()),
+            Err(NoteError::FieldNotEditable(_))
+        ));
+    }
+
+    #[test]
+    fn test_anchors_and_complex_keys_refused() {
+        let yaml = "a: &anchor 1\nb: *anchor\n";
+        for path in ["a", "b"] {
+            assert!(matches!(
+                set(yaml, &path.into(), &2.into()),
+                Err(NoteError::FieldNotEditable(_))
+            ));
+        }
+        // Removing a block that holds an anchor would orphan its alias
+        let yaml = "a:\n  x: &anchor 1\nb: *anchor\n";
+        assert!(matches!(
+            remove(yaml, &"a".into()),
+            Err(NoteError::FieldNotEditable(_))
+        ));
+
+        let yaml = "a: 1\n? [x, y]\n: 2\n";
+        assert!(matches!(
+            set(yaml, &"a".into(), &3.into()),
+            Err(NoteError::FieldNotEditable(_))
+        ));
+    }
+
+    #[test]
+    fn test_crlf_kept() {
+        let yaml = "a: 1\r\nprofile:\r\n  tags:\r\n  - x\r\n";
+        assert_eq!(set_at(yaml, "b", "x".into()), format!("{yaml}b: x\r\n"));
+        assert_eq!(
+            set_at(yaml, "profile.status.state", "on".into()),
+            format!("{yaml}  status:\r\n    state: on\r\n")
+        );
+        assert_eq!(
+            set_at(yaml, "a", Value::Sequence(vec![1.into(), 2.into()])),
+            yaml.replace("a: 1", "a:\r\n- 1\r\n- 2")
+        );
+        let change = insert(yaml, &"profile.tags[0]".into(), &"w".into()).unwrap();
+        assert_eq!(apply(yaml, change), yaml.replace("  - x", "  - w\r\n  - x"));
+        assert_eq!(
+            set_at("a: 1\r\nb: 2", "c", 3.into()),
+            "a: 1\r\nb: 2\r\nc: 3\r\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_and_unterminated_yaml() {
+        assert_eq!(set_at("", "title", "New".into()), "title: New\n");
+        assert_eq!(set_at("a: 1", "b", 2.into()), "a: 1\nb: 2\n");
+        assert_eq!(set_at("tags:\n", "tags[0]", "x".into()), "tags:\n- x\n");
+        assert_eq!(
+            set_at("profile:\n", "profile.status", "x".into()),
+            "profile:\n  status: x\n"
+        );
+    }
+}
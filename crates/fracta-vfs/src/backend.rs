@@ -0,0 +1,226 @@
+//! Pluggable storage backend (SPEC §4.1: "VFS exposes a trait interface").
+//!
+//! [`VfsBackend`] is the minimal set of storage operations the layers above
+//! VFS need: read, atomic write, list, stat, rename, remove and watch, all
+//! addressed by [`RelPath`] under a root. Two implementations ship:
+//!
+//! - [`LocationHandle`]: the local filesystem, with the same path-safety
+//!   and symlink rules as every other Location operation.
+//! - [`MemoryBackend`](crate::memory::MemoryBackend): a deterministic
+//!   in-memory tree for hermetic tests, with fault injection.
+//!
+//! `Location` itself stays on the local filesystem; code that only needs
+//! file access (e.g. the index) can take a `&dyn VfsBackend` instead.
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::entry::EntryKind;
+use crate::error::VfsResult;
+use crate::handle::{LocationHandle, RelPath};
+use crate::watcher::{FsEvent, LocationWatcher};
+
+/// Stat information for one entry of a backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    /// File or folder.
+    pub kind: EntryKind,
+    /// Size in bytes. 0 for folders.
+    pub size: u64,
+    /// Last modification time, if known.
+    pub modified: Option<DateTime<Utc>>,
+}
+
+impl FileStat {
+    /// Stat from filesystem metadata.
+    pub fn of_metadata(metadata: &fs::Metadata) -> Self {
+        let kind = if metadata.is_dir() {
+            EntryKind::Folder
+        } else {
+            EntryKind::File
+        };
+        FileStat {
+            kind,
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            modified: metadata.modified().ok().map(DateTime::from),
+        }
+    }
+
+    /// Whether this is a folder.
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Folder
+    }
+}
+
+/// Changes observed by [`VfsBackend::watch`].
+pub trait BackendWatch: Send {
+    /// Take every event observed since the last call.
+    ///
+    /// Paths are absolute (under [`VfsBackend::root`]).
+    fn drain_events(&self) -> Vec<FsEvent>;
+}
+
+/// Storage operations relative to a root.
+pub trait VfsBackend: Send + Sync {
+    /// The absolute path this backend's `RelPath`s are relative to.
+    fn root(&self) -> &Path;
+
+    /// Read a file's contents.
+    fn read(&self, rel: &RelPath) -> VfsResult<Vec<u8>>;
+
+    /// Create or overwrite a file atomically: readers see either the old
+    /// content or the new, never a mix. The parent folder must exist.
+    fn write_atomic(&self, rel: &RelPath, content: &[u8]) -> VfsResult<()>;
+
+    /// Create a folder and any missing parents.
+    fn create_dir_all(&self, rel: &RelPath) -> VfsResult<()>;
+
+    /// The children of a folder, sorted by path.
+    fn list(&self, dir: &RelPath) -> VfsResult<Vec<(RelPath, FileStat)>>;
+
+    /// Stat a file or folder.
+    fn stat(&self, rel: &RelPath) -> VfsResult<FileStat>;
+
+    /// Rename or move an entry. Fails if the destination already exists.
+    fn rename(&self, from: &RelPath, to: &RelPath) -> VfsResult<()>;
+
+    /// Remove a file, or a folder with everything in it.
+    fn remove(&self, rel: &RelPath) -> VfsResult<()>;
+
+    /// Start observing changes. Watching stops when the result is dropped.
+    fn watch(&self) -> VfsResult<Box<dyn BackendWatch>>;
+
+    /// Every file under `dir`, depth-first in path order.
+    fn walk_files(&self, dir: &RelPath) -> VfsResult<Vec<(RelPath, FileStat)>> {
+        let mut files = Vec::new();
+        let mut pending = vec![dir.clone()];
+        while let Some(dir) = pending.pop() {
+            let mut subfolders = Vec::new();
+            for (rel, stat) in self.list(&dir)? {
+                if stat.is_dir() {
+                    subfolders.push(rel);
+                } else {
+                    files.push((rel, stat));
+                }
+            }
+            pending.extend(subfolders.into_iter().rev());
+        }
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(files)
+    }
+}
+
+impl BackendWatch for LocationWatcher {
+    fn drain_events(&self) -> Vec<FsEvent> {
+        LocationWatcher::drain_events(self)
+    }
+}
+
+impl VfsBackend for LocationHandle {
+    fn root(&self) -> &Path {
+        LocationHandle::root(self)
+    }
+
+    fn read(&self, rel: &RelPath) -> VfsResult<Vec<u8>> {
+        LocationHandle::read(self, rel)
+    }
+
+    fn write_atomic(&self, rel: &RelPath, content: &[u8]) -> VfsResult<()> {
+        if self.exists(rel) {
+            self.write_file(rel, content)
+        } else {
+            self.create_file(rel, content)
+        }
+    }
+
+    fn create_dir_all(&self, rel: &RelPath) -> VfsResult<()> {
+        LocationHandle::create_dir_all(self, rel)
+    }
+
+    fn list(&self, dir: &RelPath) -> VfsResult<Vec<(RelPath, FileStat)>> {
+        let mut children = Vec::new();
+        for child in self.read_dir(dir)? {
+            let (rel, _) = child?;
+            // Entries that vanished or are unreachable symlinks are left out
+            if let Ok(metadata) = self.metadata(&rel) {
+                children.push((rel, FileStat::of_metadata(&metadata)));
+            }
+        }
+        children.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(children)
+    }
+
+    fn stat(&self, rel: &RelPath) -> VfsResult<FileStat> {
+        Ok(FileStat::of_metadata(&self.metadata(rel)?))
+    }
+
+    fn rename(&self, from: &RelPath, to: &RelPath) -> VfsResult<()> {
+        LocationHandle::rename(self, from, to)
+    }
+
+    fn remove(&self, rel: &RelPath) -> VfsResult<()> {
+        if self.symlink_metadata(rel)?.is_dir() {
+            self.remove_dir_all(rel)
+        } else {
+            self.remove_file(rel)
+        }
+    }
+
+    fn watch(&self) -> VfsResult<Box<dyn BackendWatch>> {
+        Ok(Box::new(LocationWatcher::start(self.root())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::VfsError;
+    use tempfile::TempDir;
+
+    fn rel(path: &str) -> RelPath {
+        RelPath::new(path).unwrap()
+    }
+
+    #[test]
+    fn test_local_backend_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let handle = LocationHandle::open(tmp.path()).unwrap();
+        let backend: &dyn VfsBackend = &handle;
+
+        backend.create_dir_all(&rel("a/b")).unwrap();
+        backend.write_atomic(&rel("a/b/note.md"), b"one").unwrap();
+        backend.write_atomic(&rel("a/b/note.md"), b"two").unwrap();
+        backend.write_atomic(&rel("top.md"), b"top").unwrap();
+        assert_eq!(backend.read(&rel("a/b/note.md")).unwrap(), b"two");
+        assert_eq!(backend.stat(&rel("a/b/note.md")).unwrap().size, 3);
+
+        let listed: Vec<_> = backend.list(&RelPath::root()).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed[0].1.is_dir());
+
+        backend.rename(&rel("top.md"), &rel("a/top.md")).unwrap();
+        let files: Vec<_> = backend
+            .walk_files(&RelPath::root())
+            .unwrap()
+            .into_iter()
+            .map(|(rel, _)| rel)
+            .collect();
+        assert_eq!(files, vec![rel("a/b/note.md"), rel("a/top.md")]);
+
+        backend.remove(&rel("a")).unwrap();
+        assert!(matches!(
+            backend.stat(&rel("a")),
+            Err(VfsError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_local_write_needs_parent() {
+        let tmp = TempDir::new().unwrap();
+        let handle = LocationHandle::open(tmp.path()).unwrap();
+        let result = VfsBackend::write_atomic(&handle, &rel("missing/note.md"), b"x");
+        assert!(matches!(result, Err(VfsError::NotFound(_))));
+    }
+}
@@ -0,0 +1,255 @@
+//! Cloud-drive placeholders and sync-conflict copies.
+//!
+//! Cloud-sync folders are first-class Locations (SPEC §4.1), but their
+//! clients leave files behind that are not ordinary content:
+//!
+//! - iCloud Drive replaces files that are not downloaded with a hidden stub,
+//!   `Note.md` becoming `.Note.md.icloud`. Reading it gives nothing useful,
+//!   and opening the real file forces a download, so placeholders get Plain
+//!   scope and are left alone by indexing.
+//! - When two devices edit the same file, Dropbox (and Nextcloud) keep the
+//!   losing side as `Note (… conflicted copy 2024-05-01).md`, Syncthing as
+//!   `Note.sync-conflict-20240501-120000-DEVICE.md`. These stay Managed so
+//!   the user can open and resolve them; [`Location::conflict_copies`] lists
+//!   the ones still around.
+//!
+//! Detection is by name only and never touches the disk.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::entry::Entry;
+use crate::error::VfsResult;
+use crate::location::{Location, WalkOptions};
+
+/// What kind of sync-client file an entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudFileKind {
+    /// Stub for a file whose content is not downloaded.
+    Placeholder,
+    /// The other side of a conflicting edit, kept next to the original.
+    ConflictCopy,
+}
+
+/// The sync client whose naming pattern matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncProvider {
+    #[serde(rename = "icloud")]
+    ICloud,
+    /// Dropbox, and clients that copied its pattern (Nextcloud, ownCloud).
+    Dropbox,
+    Syncthing,
+}
+
+/// A placeholder or conflict copy, with the file it stands for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudFile {
+    pub kind: CloudFileKind,
+    pub provider: SyncProvider,
+    /// Path of the real file (same folder, same form as the detected path).
+    pub original: PathBuf,
+}
+
+impl CloudFile {
+    /// Recognize a placeholder or conflict copy from its path.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let (kind, provider, original) = if let Some(original) = placeholder_original(name) {
+            (CloudFileKind::Placeholder, SyncProvider::ICloud, original)
+        } else if let Some(original) = syncthing_original(name) {
+            (
+                CloudFileKind::ConflictCopy,
+                SyncProvider::Syncthing,
+                original,
+            )
+        } else if let Some(original) = dropbox_original(name) {
+            (CloudFileKind::ConflictCopy, SyncProvider::Dropbox, original)
+        } else {
+            return None;
+        };
+        Some(CloudFile {
+            kind,
+            provider,
+            original: path.with_file_name(original),
+        })
+    }
+}
+
+/// Whether `name` is an iCloud placeholder stub.
+pub(crate) fn is_placeholder(name: &OsStr) -> bool {
+    name.to_str().and_then(placeholder_original).is_some()
+}
+
+/// `.Note.md.icloud` → `Note.md`
+fn placeholder_original(name: &str) -> Option<String> {
+    let original = name.strip_prefix('.')?.strip_suffix(".icloud")?;
+    (!original.is_empty()).then(|| original.to_string())
+}
+
+/// `Note.sync-conflict-20240501-120000-ABCDEF1.md` → `Note.md`
+fn syncthing_original(name: &str) -> Option<String> {
+    const MARKER: &str = ".sync-conflict-";
+    let start = name.find(MARKER)?;
+    let (stem, rest) = (&name[..start], &name[start + MARKER.len()..]);
+    let (tag, ext) = match rest.find('.') {
+        Some(dot) => (&rest[..dot], &rest[dot..]),
+        None => (rest, ""),
+    };
+
+    let mut parts = tag.splitn(3, '-');
+    let date = parts.next()?;
+    let time = parts.next()?;
+    let device = parts.next()?;
+    let digits = |s: &str, n: usize| s.len() == n && s.bytes().all(|b| b.is_ascii_digit());
+    if stem.is_empty()
+        || !digits(date, 8)
+        || !digits(time, 6)
+        || device.is_empty()
+        || !device.bytes().all(|b| b.is_ascii_alphanumeric())
+    {
+        return None;
+    }
+    Some(format!("{stem}{ext}"))
+}
+
+/// `Note (Alice's conflicted copy 2024-05-01).md` → `Note.md`
+fn dropbox_original(name: &str) -> Option<String> {
+    let path = Path::new(name);
+    let stem = path.file_stem()?.to_str()?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{e}"))
+        .unwrap_or_default();
+
+    let marker = stem.find("conflicted copy")?;
+    let open = stem[..marker].rfind(" (")?;
+    if !stem.ends_with(')') || open == 0 {
+        return None;
+    }
+    Some(format!("{}{ext}", &stem[..open]))
+}
+
+impl Location {
+    /// All sync-conflict copies in this Location that still await resolution.
+    ///
+    /// Ignored folders are not searched. Each entry's `cloud.original` names
+    /// the file it conflicts with.
+    pub fn conflict_copies(&self) -> VfsResult<Vec<Entry>> {
+        let options = WalkOptions {
+            include_ignored: false,
+            max_depth: None,
+        };
+        Ok(self
+            .walk_stream(&self.root, &options)?
+            .filter(|entry| {
+                entry
+                    .cloud
+                    .as_ref()
+                    .is_some_and(|c| c.kind == CloudFileKind::ConflictCopy)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::Scope;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn detect(name: &str) -> Option<(CloudFileKind, SyncProvider, PathBuf)> {
+        CloudFile::detect(&Path::new("/docs").join(name)).map(|c| (c.kind, c.provider, c.original))
+    }
+
+    #[test]
+    fn test_detect_patterns() {
+        use CloudFileKind::*;
+        use SyncProvider::*;
+
+        assert_eq!(
+            detect(".Note.md.icloud"),
+            Some((Placeholder, ICloud, PathBuf::from("/docs/Note.md")))
+        );
+        assert_eq!(
+            detect("Note.sync-conflict-20240501-120000-ABCDEF1.md"),
+            Some((ConflictCopy, Syncthing, PathBuf::from("/docs/Note.md")))
+        );
+        assert_eq!(
+            detect("Note (Alice's conflicted copy 2024-05-01).md"),
+            Some((ConflictCopy, Dropbox, PathBuf::from("/docs/Note.md")))
+        );
+        assert_eq!(
+            detect("Plan (conflicted copy 2024-05-01 (1))"),
+            Some((ConflictCopy, Dropbox, PathBuf::from("/docs/Plan")))
+        );
+
+        // Look-alikes
+        assert_eq!(detect("Note.md"), None);
+        assert_eq!(detect(".icloud"), None);
+        assert_eq!(detect("Note.sync-conflict-notes.md"), None);
+        assert_eq!(detect("On conflicted copy handling.md"), None);
+    }
+
+    #[test]
+    fn test_walk_marks_cloud_files() {
+        let tmp = TempDir::new().unwrap();
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        fs::write(tmp.path().join("Note.md"), "mine").unwrap();
+        fs::write(tmp.path().join(".Remote.md.icloud"), "stub").unwrap();
+        fs::write(
+            tmp.path().join("Note (conflicted copy 2024-05-01).md"),
+            "theirs",
+        )
+        .unwrap();
+
+        let entries = loc.list_directory(tmp.path()).unwrap();
+        let find = |name: &str| entries.iter().find(|e| e.name == name).unwrap();
+
+        assert!(find("Note.md").cloud.is_none());
+        assert_eq!(find("Note.md").scope, Scope::Managed);
+
+        let stub = find(".Remote.md.icloud");
+        assert_eq!(stub.scope, Scope::Plain);
+        assert_eq!(
+            stub.cloud.as_ref().unwrap().original,
+            tmp.path().join("Remote.md")
+        );
+
+        let copy = find("Note (conflicted copy 2024-05-01).md");
+        assert_eq!(copy.scope, Scope::Managed);
+        assert_eq!(
+            copy.cloud.as_ref().unwrap().kind,
+            CloudFileKind::ConflictCopy
+        );
+    }
+
+    #[test]
+    fn test_conflict_copies() {
+        let tmp = TempDir::new().unwrap();
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        fs::create_dir(tmp.path().join("sub")).unwrap();
+        fs::write(tmp.path().join("sub/a.md"), "").unwrap();
+        fs::write(
+            tmp.path()
+                .join("sub/a.sync-conflict-20240501-120000-XYZ.md"),
+            "",
+        )
+        .unwrap();
+        fs::write(tmp.path().join(".b.md.icloud"), "").unwrap();
+
+        let copies = loc.conflict_copies().unwrap();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(
+            copies[0].cloud.as_ref().unwrap().original,
+            tmp.path().join("sub/a.md")
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Unicode normalization and case-collision detection.
+//!
+//! The same name can reach disk in several byte forms: macOS hands out
+//! decomposed (NFD) names while most other systems produce composed (NFC)
+//! ones, and case-insensitive filesystems treat `Note.md` and `note.md` as
+//! one file while case-sensitive ones keep both. Synced between the two,
+//! a Location can end up with entries that are different on disk but the
+//! same to the user.
+//!
+//! [`normalize_path`] gives the NFC form used for identity (e.g. index
+//! keys). [`collision_key`] additionally folds case; two siblings with the
+//! same key collide. Create, rename, move and copy refuse to introduce a
+//! collision, and [`Location::find_collisions`] lists existing ones.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::error::{VfsError, VfsResult};
+use crate::handle::RelPath;
+use crate::location::{Location, WalkOptions};
+
+/// How colliding names differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionKind {
+    /// Only in letter case (possibly also in normalization).
+    Case,
+    /// Only in Unicode normalization (NFC vs NFD).
+    Normalization,
+}
+
+impl fmt::Display for CollisionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollisionKind::Case => f.write_str("letter case"),
+            CollisionKind::Normalization => f.write_str("Unicode normalization"),
+        }
+    }
+}
+
+/// Sibling entries whose names collide.
+#[derive(Debug, Clone)]
+pub struct NameCollision {
+    /// Absolute paths of the colliding entries, sorted.
+    pub paths: Vec<PathBuf>,
+    /// How the names differ.
+    pub kind: CollisionKind,
+}
+
+/// Normalize every component of `path` to NFC.
+///
+/// Components that are not valid UTF-8 are kept as they are.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => match name.to_str() {
+                Some(name) => PathBuf::from(name.nfc().collect::<String>()),
+                None => PathBuf::from(name),
+            },
+            other => PathBuf::from(other.as_os_str()),
+        })
+        .collect()
+}
+
+/// Case- and normalization-insensitive key for a file name.
+///
+/// `None` for names that are not valid UTF-8; those are never considered
+/// colliding.
+pub fn collision_key(name: &OsStr) -> Option<String> {
+    let nfc: String = name.to_str()?.nfc().collect();
+    Some(nfc.to_lowercase())
+}
+
+/// How two names with the same [`collision_key`] differ.
+fn collision_kind(a: &OsStr, b: &OsStr) -> CollisionKind {
+    let nfc = |name: &OsStr| name.to_str().map(|s| s.nfc().collect::<String>());
+    if nfc(a) == nfc(b) {
+        CollisionKind::Normalization
+    } else {
+        CollisionKind::Case
+    }
+}
+
+impl Location {
+    /// Fail with `NameCollision` if `rel` would collide with a sibling.
+    ///
+    /// An entry named exactly like `rel` is not a collision (that is
+    /// `AlreadyExists`), and neither is `except` — the entry being renamed,
+    /// so that changing only the case of a name stays possible.
+    pub(crate) fn check_collision(&self, rel: &RelPath, except: Option<&RelPath>) -> VfsResult<()> {
+        let (Some(parent), Some(name)) = (rel.parent(), rel.file_name()) else {
+            return Ok(());
+        };
+        let Some(key) = collision_key(name) else {
+            return Ok(());
+        };
+        let handle = self.handle()?;
+        let Ok(siblings) = handle.read_dir(&parent) else {
+            // A missing parent is reported by the operation itself
+            return Ok(());
+        };
+
+        for sibling in siblings.flatten() {
+            let (sibling_rel, dir_entry) = sibling;
+            let sibling_name = dir_entry.file_name();
+            if sibling_name == name || Some(&sibling_rel) == except {
+                continue;
+            }
+            if collision_key(&sibling_name).as_ref() == Some(&key) {
+                return Err(VfsError::NameCollision {
+                    path: handle.absolute(rel),
+                    existing: handle.absolute(&sibling_rel),
+                    kind: collision_kind(name, &sibling_name),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// List every set of colliding siblings under `dir`, ignored entries
+    /// included.
+    pub fn find_collisions(&self, dir: &Path) -> VfsResult<Vec<NameCollision>> {
+        let options = WalkOptions {
+            include_ignored: true,
+            max_depth: None,
+        };
+        let mut groups: HashMap<(PathBuf, String), Vec<PathBuf>> = HashMap::new();
+        for entry in self.walk_stream(dir, &options)? {
+            let (Some(parent), Some(name)) = (entry.path.parent(), entry.path.file_name()) else {
+                continue;
+            };
+            if let Some(key) = collision_key(name) {
+                groups
+                    .entry((parent.to_path_buf(), key))
+                    .or_default()
+                    .push(entry.path);
+            }
+        }
+
+        let mut collisions: Vec<_> = groups
+            .into_values()
+            .filter(|paths| paths.len() > 1)
+            .map(|mut paths| {
+                paths.sort();
+                let names: Vec<_> = paths.iter().filter_map(|p| p.file_name()).collect();
+                let kind = if names
+                    .windows(2)
+                    .all(|w| collision_kind(w[0], w[1]) == CollisionKind::Normalization)
+                {
+                    CollisionKind::Normalization
+                } else {
+                    CollisionKind::Case
+                };
+                NameCollision { paths, kind }
+            })
+            .collect();
+        collisions.sort_by(|a, b| a.paths.cmp(&b.paths));
+        Ok(collisions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    // "é" composed (NFC) and decomposed (NFD)
+    const CAFE_NFC: &str = "caf\u{e9}.md";
+    const CAFE_NFD: &str = "cafe\u{301}.md";
+
+    fn managed_location(tmp: &TempDir) -> Location {
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        loc
+    }
+
+    /// Whether the filesystem under `tmp` keeps names differing only in
+    /// case or normalization apart (e.g. not APFS / NTFS defaults).
+    fn distinguishes(tmp: &TempDir, a: &str, b: &str) -> bool {
+        fs::write(tmp.path().join(a), "").unwrap();
+        let distinct = !tmp.path().join(b).exists();
+        fs::remove_file(tmp.path().join(a)).unwrap();
+        distinct
+    }
+
+    #[test]
+    fn test_normalize_path_and_keys() {
+        assert_eq!(
+            normalize_path(Path::new(&format!("dir/{CAFE_NFD}"))),
+            PathBuf::from(format!("dir/{CAFE_NFC}"))
+        );
+        assert_eq!(
+            collision_key(OsStr::new("Caf\u{e9}.MD")),
+            collision_key(OsStr::new(CAFE_NFD))
+        );
+        assert_eq!(
+            collision_kind(OsStr::new(CAFE_NFC), OsStr::new(CAFE_NFD)),
+            CollisionKind::Normalization
+        );
+        assert_eq!(
+            collision_kind(OsStr::new("Note.md"), OsStr::new("note.md")),
+            CollisionKind::Case
+        );
+    }
+
+    #[test]
+    fn test_create_rejects_case_collision() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        loc.create_file(&tmp.path().join("Note.md"), b"a").unwrap();
+
+        let result = loc.create_file(&tmp.path().join("note.md"), b"b");
+        match result {
+            Err(VfsError::NameCollision { existing, kind, .. }) => {
+                assert_eq!(existing, tmp.path().join("Note.md"));
+                assert_eq!(kind, CollisionKind::Case);
+            }
+            // Case-insensitive filesystem: the OS already refuses
+            Err(VfsError::AlreadyExists(_)) => {}
+            other => panic!("expected collision, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_rename_case_only_is_allowed() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        loc.create_file(&tmp.path().join("Note.md"), b"a").unwrap();
+        loc.create_file(&tmp.path().join("other.md"), b"b").unwrap();
+
+        if distinguishes(&tmp, "x.md", "X.md") {
+            loc.rename(&tmp.path().join("Note.md"), &tmp.path().join("note.md"))
+                .unwrap();
+            let result = loc.rename(&tmp.path().join("other.md"), &tmp.path().join("NOTE.md"));
+            assert!(matches!(result, Err(VfsError::NameCollision { .. })));
+        }
+    }
+
+    #[test]
+    fn test_move_rejects_normalization_collision() {
+        let tmp = TempDir::new().unwrap();
+        if !distinguishes(&tmp, CAFE_NFC, CAFE_NFD) {
+            return;
+        }
+        let loc = managed_location(&tmp);
+        fs::create_dir(tmp.path().join("inbox")).unwrap();
+        fs::write(tmp.path().join(CAFE_NFC), "composed").unwrap();
+        fs::write(tmp.path().join("inbox").join(CAFE_NFD), "decomposed").unwrap();
+
+        let result = loc.move_entry(&tmp.path().join("inbox").join(CAFE_NFD), tmp.path());
+        assert!(matches!(
+            result,
+            Err(VfsError::NameCollision {
+                kind: CollisionKind::Normalization,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_find_collisions() {
+        let tmp = TempDir::new().unwrap();
+        if !distinguishes(&tmp, "x.md", "X.md") || !distinguishes(&tmp, CAFE_NFC, CAFE_NFD) {
+            return;
+        }
+        let loc = managed_location(&tmp);
+        fs::create_dir(tmp.path().join("a")).unwrap();
+        fs::write(tmp.path().join("a/Note.md"), "").unwrap();
+        fs::write(tmp.path().join("a/note.md"), "").unwrap();
+        fs::write(tmp.path().join(CAFE_NFC), "").unwrap();
+        fs::write(tmp.path().join(CAFE_NFD), "").unwrap();
+        fs::write(tmp.path().join("unique.md"), "").unwrap();
+
+        let collisions = loc.find_collisions(tmp.path()).unwrap();
+        assert_eq!(collisions.len(), 2);
+        let kinds: Vec<_> = collisions.iter().map(|c| c.kind).collect();
+        assert!(kinds.contains(&CollisionKind::Case));
+        assert!(kinds.contains(&CollisionKind::Normalization));
+        assert!(collisions.iter().all(|c| c.paths.len() == 2));
+    }
+}
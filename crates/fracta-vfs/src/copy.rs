@@ -0,0 +1,388 @@
+//! Copy and duplicate.
+//!
+//! Files are copied through the same temp → fsync → rename path as
+//! [`atomic_write`](crate::writer::atomic_write), so a copy is never visible
+//! half-written. Folders are planned up front (walking the source with the
+//! usual scope rules) and then copied entry by entry. A folder with entries
+//! the walk could not read is not copied at all, and if anything fails
+//! part-way, the partially created destination is removed again.
+
+use std::path::{Path, PathBuf};
+
+use crate::entry::EntryKind;
+use crate::error::{VfsError, VfsResult};
+use crate::handle::RelPath;
+use crate::journal::JournalOp;
+use crate::location::{Location, WalkOptions};
+use crate::scope::Scope;
+
+/// What to do when the copy destination already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CopyCollision {
+    /// Fail with `AlreadyExists`.
+    #[default]
+    Fail,
+    /// Copy next to the occupant as "name copy.ext", "name copy 2.ext", ...
+    AutoRename,
+}
+
+/// Options for [`Location::copy_entry`] and [`Location::copy_to`].
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    /// Behaviour when the destination path is taken.
+    pub on_collision: CopyCollision,
+
+    /// Copy entries with Ignored scope too.
+    /// When false, ignored entries inside a copied folder are left out, and
+    /// copying an ignored entry directly fails with `IgnoredScope`.
+    pub include_ignored: bool,
+}
+
+/// Progress of a running copy, reported after each file.
+#[derive(Debug, Clone)]
+pub struct CopyProgress {
+    /// Files copied so far.
+    pub files_done: usize,
+    /// Files in the whole copy.
+    pub files_total: usize,
+    /// Bytes copied so far.
+    pub bytes_done: u64,
+    /// Bytes in the whole copy (as measured when the copy was planned).
+    pub bytes_total: u64,
+    /// Absolute source path of the file just copied.
+    pub current: PathBuf,
+}
+
+/// One planned step of a copy.
+struct CopyStep {
+    source: RelPath,
+    target: RelPath,
+    kind: EntryKind,
+}
+
+impl Location {
+    /// Copy a file or folder to `to` within this Location.
+    ///
+    /// Returns the path the copy was created at, which differs from `to`
+    /// when [`CopyCollision::AutoRename`] picked a new name.
+    pub fn copy_entry(
+        &self,
+        from: &Path,
+        to: &Path,
+        options: &CopyOptions,
+        progress: impl FnMut(&CopyProgress),
+    ) -> VfsResult<PathBuf> {
+        self.copy_to(from, self, to, options, progress)
+    }
+
+    /// Duplicate a file or folder next to itself ("Note copy.md").
+    pub fn duplicate(&self, path: &Path) -> VfsResult<PathBuf> {
+        let options = CopyOptions {
+            on_collision: CopyCollision::AutoRename,
+            include_ignored: true,
+        };
+        self.copy_entry(path, path, &options, |_| {})
+    }
+
+    /// Copy a file or folder from this Location to `to` in `dest`.
+    ///
+    /// `dest` may be this Location or another one. `progress` is called
+    /// after every copied file.
+    pub fn copy_to(
+        &self,
+        from: &Path,
+        dest: &Location,
+        to: &Path,
+        options: &CopyOptions,
+        mut progress: impl FnMut(&CopyProgress),
+    ) -> VfsResult<PathBuf> {
+        let source = self.writable_rel(from)?;
+        if source.is_root() {
+            return Err(VfsError::PermissionDenied(from.to_path_buf()));
+        }
+        let src_handle = self.handle()?;
+        let metadata = src_handle.metadata(&source)?;
+        if !options.include_ignored
+            && self.scope_of_rel(&source, metadata.is_dir()) == Scope::Ignored
+        {
+            return Err(VfsError::IgnoredScope(from.to_path_buf()));
+        }
+
+        let dest_handle = dest.handle()?;
+        let mut target = dest.writable_rel(to)?;
+        if target.is_root() {
+            return Err(VfsError::AlreadyExists(dest_handle.absolute(&target)));
+        }
+        let collision = dest.check_collision(&target, None);
+        if dest_handle.exists(&target) || collision.is_err() {
+            match options.on_collision {
+                CopyCollision::Fail => {
+                    collision?;
+                    return Err(VfsError::AlreadyExists(dest_handle.absolute(&target)));
+                }
+                CopyCollision::AutoRename => {
+                    target = dest.unique_rel(&target, |n| match n {
+                        1 => " copy".to_string(),
+                        n => format!(" copy {n}"),
+                    })?;
+                }
+            }
+        }
+
+        let steps = self.plan_copy(&source, &target, metadata.is_dir(), options)?;
+        let mut state = CopyProgress {
+            files_done: 0,
+            files_total: steps.iter().filter(|s| s.kind == EntryKind::File).count(),
+            bytes_done: 0,
+            bytes_total: 0,
+            current: PathBuf::new(),
+        };
+        for step in steps.iter().filter(|s| s.kind == EntryKind::File) {
+            if let Ok(m) = src_handle.metadata(&step.source) {
+                state.bytes_total += m.len();
+            }
+        }
+
+        for (i, step) in steps.iter().enumerate() {
+            let result = match step.kind {
+                EntryKind::Folder => dest_handle.create_dir(&step.target),
+                EntryKind::File => src_handle
+                    .copy_file(&step.source, dest_handle, &step.target)
+                    .map(|bytes| {
+                        state.bytes_done += bytes;
+                        state.files_done += 1;
+                        state.current = src_handle.absolute(&step.source);
+                        progress(&state);
+                    }),
+            };
+            if let Err(e) = result {
+                // Best effort: don't leave a partial copy behind. The first
+                // step creates the top-level target, so later failures remove it.
+                if i > 0 {
+                    let _ = dest_handle.remove_dir_all(&target);
+                }
+                return Err(e);
+            }
+        }
+
+        let after = dest.journal_hash(&target);
+        dest.record_op(JournalOp::Copy, &target, None, None, after);
+        Ok(dest_handle.absolute(&target))
+    }
+
+    /// List the folders and files to create, parents before children.
+    ///
+    /// Fails if the walk skipped anything, rather than copy a folder with
+    /// content silently missing. Ignored entries are left out by the scope
+    /// rules, not skipped.
+    fn plan_copy(
+        &self,
+        source: &RelPath,
+        target: &RelPath,
+        is_dir: bool,
+        options: &CopyOptions,
+    ) -> VfsResult<Vec<CopyStep>> {
+        if !is_dir {
+            return Ok(vec![CopyStep {
+                source: source.clone(),
+                target: target.clone(),
+                kind: EntryKind::File,
+            }]);
+        }
+
+        let mut steps = vec![CopyStep {
+            source: source.clone(),
+            target: target.clone(),
+            kind: EntryKind::Folder,
+        }];
+        let walk_options = WalkOptions {
+            include_ignored: options.include_ignored,
+            max_depth: None,
+        };
+        let handle = self.handle()?;
+        let (entries, report) = self.walk_with_report(&handle.absolute(source), &walk_options)?;
+        if let Some(skipped) = report.skipped.into_iter().next() {
+            return Err(skipped.into_error());
+        }
+        for entry in entries {
+            let rel = self.relativize(&entry.path)?;
+            let suffix = rel
+                .as_path()
+                .strip_prefix(source.as_path())
+                .map_err(|_| VfsError::OutsideLocation(entry.path.clone()))?;
+            steps.push(CopyStep {
+                target: RelPath::new(target.as_path().join(suffix))?,
+                source: rel,
+                kind: entry.kind,
+            });
+        }
+        Ok(steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn managed_location(tmp: &TempDir) -> Location {
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        loc
+    }
+
+    #[test]
+    fn test_duplicate_auto_renames() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let note = tmp.path().join("Note.md");
+        loc.create_file(&note, b"hello").unwrap();
+
+        let first = loc.duplicate(&note).unwrap();
+        assert_eq!(first, tmp.path().join("Note copy.md"));
+        let second = loc.duplicate(&note).unwrap();
+        assert_eq!(second, tmp.path().join("Note copy 2.md"));
+        assert_eq!(fs::read_to_string(&second).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_copy_folder_recursively_with_progress() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        fs::create_dir_all(tmp.path().join("src/nested")).unwrap();
+        fs::write(tmp.path().join("src/a.md"), "aaa").unwrap();
+        fs::write(tmp.path().join("src/nested/b.md"), "bb").unwrap();
+
+        let mut reports = Vec::new();
+        let copied = loc
+            .copy_entry(
+                &tmp.path().join("src"),
+                &tmp.path().join("dst"),
+                &CopyOptions::default(),
+                |p| reports.push(p.clone()),
+            )
+            .unwrap();
+
+        assert_eq!(copied, tmp.path().join("dst"));
+        assert_eq!(
+            fs::read_to_string(copied.join("nested/b.md")).unwrap(),
+            "bb"
+        );
+        assert_eq!(reports.len(), 2);
+        let last = reports.last().unwrap();
+        assert_eq!((last.files_done, last.files_total), (2, 2));
+        assert_eq!((last.bytes_done, last.bytes_total), (5, 5));
+    }
+
+    #[test]
+    fn test_copy_collision_fails_by_default() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        fs::write(tmp.path().join("a.md"), "a").unwrap();
+        fs::write(tmp.path().join("b.md"), "b").unwrap();
+
+        let result = loc.copy_entry(
+            &tmp.path().join("a.md"),
+            &tmp.path().join("b.md"),
+            &CopyOptions::default(),
+            |_| {},
+        );
+        assert!(matches!(result, Err(VfsError::AlreadyExists(_))));
+        assert_eq!(fs::read_to_string(tmp.path().join("b.md")).unwrap(), "b");
+    }
+
+    #[test]
+    fn test_copy_respects_ignore_scope() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        fs::create_dir_all(tmp.path().join("proj/node_modules/pkg")).unwrap();
+        fs::write(tmp.path().join("proj/main.md"), "main").unwrap();
+        fs::write(tmp.path().join("proj/node_modules/pkg/index.js"), "x").unwrap();
+
+        loc.copy_entry(
+            &tmp.path().join("proj"),
+            &tmp.path().join("proj2"),
+            &CopyOptions::default(),
+            |_| {},
+        )
+        .unwrap();
+        assert!(tmp.path().join("proj2/main.md").exists());
+        assert!(!tmp.path().join("proj2/node_modules").exists());
+
+        // Copying an ignored entry directly needs include_ignored
+        let ignored = tmp.path().join("proj/node_modules");
+        let result = loc.copy_entry(
+            &ignored,
+            &tmp.path().join("deps"),
+            &CopyOptions::default(),
+            |_| {},
+        );
+        assert!(matches!(result, Err(VfsError::IgnoredScope(_))));
+    }
+
+    #[test]
+    fn test_copy_across_locations() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        let src = managed_location(&a);
+        let dst = Location::new("plain", b.path());
+        fs::write(a.path().join("note.md"), "shared").unwrap();
+
+        let copied = src
+            .copy_to(
+                &a.path().join("note.md"),
+                &dst,
+                &b.path().join("note.md"),
+                &CopyOptions::default(),
+                |_| {},
+            )
+            .unwrap();
+        assert_eq!(fs::read_to_string(copied).unwrap(), "shared");
+        assert!(a.path().join("note.md").exists());
+    }
+
+    #[test]
+    fn test_copy_rejects_fracta_dir() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        fs::write(tmp.path().join("a.md"), "a").unwrap();
+
+        let result = loc.copy_entry(
+            &tmp.path().join("a.md"),
+            &tmp.path().join(".fracta/a.md"),
+            &CopyOptions::default(),
+            |_| {},
+        );
+        assert!(matches!(result, Err(VfsError::PermissionDenied(_))));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_copy_fails_on_unreadable_subfolder() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let locked = tmp.path().join("src/locked");
+        fs::create_dir_all(&locked).unwrap();
+        fs::write(tmp.path().join("src/a.md"), "a").unwrap();
+        fs::write(locked.join("b.md"), "b").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read_dir(&locked).is_ok() {
+            // Running as root: permissions are not enforced
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            return;
+        }
+
+        let result = loc.copy_entry(
+            &tmp.path().join("src"),
+            &tmp.path().join("dst"),
+            &CopyOptions::default(),
+            |_| {},
+        );
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(matches!(result, Err(VfsError::PermissionDenied(p)) if p == locked));
+        assert!(!tmp.path().join("dst").exists());
+    }
+}
@@ -1,11 +1,14 @@
 //! File and folder entries.
 
+use std::fs::Metadata;
 use std::path::PathBuf;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::cloud::CloudFile;
 use crate::scope::Scope;
+use crate::symlink::Symlink;
 
 /// Whether an entry is a file or a folder.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -44,4 +47,54 @@ pub struct Entry {
 
     /// Managed / Ignored / Plain scope of this entry.
     pub scope: Scope,
+
+    /// Set if the entry is a symlink. `kind`, `size` and the timestamps then
+    /// describe the target when it resolves, the link itself otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink: Option<Symlink>,
+
+    /// Set if the entry is a cloud placeholder or sync-conflict copy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud: Option<CloudFile>,
+}
+
+impl Entry {
+    /// Build an entry for `path` from already-fetched metadata.
+    pub(crate) fn from_metadata(path: PathBuf, metadata: &Metadata, scope: Scope) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let kind = if metadata.is_dir() {
+            EntryKind::Folder
+        } else {
+            EntryKind::File
+        };
+
+        let cloud = if kind == EntryKind::File {
+            CloudFile::detect(&path)
+        } else {
+            None
+        };
+
+        let extension = if kind == EntryKind::File {
+            path.extension().map(|e| e.to_string_lossy().to_lowercase())
+        } else {
+            None
+        };
+
+        Entry {
+            path,
+            kind,
+            name,
+            extension,
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(DateTime::from),
+            created: metadata.created().ok().map(DateTime::from),
+            scope,
+            symlink: None,
+            cloud,
+        }
+    }
 }
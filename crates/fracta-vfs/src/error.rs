@@ -2,6 +2,9 @@
 
 use std::path::PathBuf;
 
+use crate::collision::CollisionKind;
+use crate::version::FileVersion;
+
 /// All errors that VFS operations can produce.
 #[derive(Debug, thiserror::Error)]
 pub enum VfsError {
@@ -17,6 +20,55 @@ pub enum VfsError {
     #[error("path is outside any registered Location: {0}")]
     OutsideLocation(PathBuf),
 
+    #[error("Location root {path} overlaps registered Location {existing}")]
+    OverlappingLocation { path: PathBuf, existing: PathBuf },
+
+    #[error("invalid settings in {path}: {reason}")]
+    InvalidSettings { path: PathBuf, reason: String },
+
+    #[error("{path} has layout version {found}, newer than the supported {supported}")]
+    UnsupportedLayout {
+        path: PathBuf,
+        found: u32,
+        supported: u32,
+    },
+
+    #[error("{path} belongs to Location {found:?}, not {expected}")]
+    LocationMismatch {
+        path: PathBuf,
+        expected: uuid::Uuid,
+        /// The ID found there; `None` if it is not a managed Location.
+        found: Option<uuid::Uuid>,
+    },
+
+    #[error("no registered Location with id {0}")]
+    UnknownLocation(uuid::Uuid),
+
+    #[error("Location is not managed: {0}")]
+    NotManaged(PathBuf),
+
+    #[error("UID {uid} is held by {existing}, cannot also be claimed by {claimed_by}")]
+    UidConflict {
+        uid: uuid::Uuid,
+        existing: PathBuf,
+        claimed_by: PathBuf,
+    },
+
+    #[error("file changed since it was read: {path}")]
+    Conflict {
+        path: PathBuf,
+        expected: FileVersion,
+        /// `None` if the file no longer exists.
+        actual: Option<FileVersion>,
+    },
+
+    #[error("{path} differs from existing {existing} only in {kind}")]
+    NameCollision {
+        path: PathBuf,
+        existing: PathBuf,
+        kind: CollisionKind,
+    },
+
     #[error("path is in Ignored scope: {0}")]
     IgnoredScope(PathBuf),
 
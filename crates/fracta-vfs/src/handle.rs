@@ -0,0 +1,857 @@
+//! Capability-based Location access (ENGINEERING §5.3).
+//!
+//! A `LocationHandle` is opened once per Location root. Every operation takes
+//! a `RelPath` — a normalized path relative to that root that cannot express
+//! `..` escapes — and is performed relative to the handle instead of by
+//! re-resolving a raw absolute path.
+//!
+//! On Linux the handle keeps the root directory open and walks each path
+//! from it one component at a time: every component is opened relative to
+//! its parent's descriptor without following symlinks, and a symlink is only
+//! followed once the object it leads to is open and its real path has been
+//! checked against the root. The operation then runs on the descriptors
+//! opened by that walk (through `/proc/self/fd/<fd>/…`), so renaming the
+//! root or swapping a component for a symlink after the check cannot
+//! redirect it. Other platforms check by path (`lstat` plus `canonicalize`
+//! against the root canonicalized at open time) and keep that race.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+#[cfg(target_os = "linux")]
+use std::sync::Arc;
+
+use crate::error::{VfsError, VfsResult};
+use crate::symlink::SymlinkPolicy;
+use crate::writer::{atomic_copy_new, atomic_write, atomic_write_if, atomic_write_new};
+
+/// A normalized path relative to a Location root.
+///
+/// Construction rejects absolute paths and any `..` that would climb above
+/// the root. `.` components are dropped and inner `..` segments are folded
+/// lexically, so `a/../b` becomes `b`. The empty path denotes the root itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct RelPath(PathBuf);
+
+impl RelPath {
+    /// The Location root itself.
+    pub fn root() -> Self {
+        Self(PathBuf::new())
+    }
+
+    /// Normalize a relative path, rejecting anything that escapes the root.
+    pub fn new(path: impl AsRef<Path>) -> VfsResult<Self> {
+        let path = path.as_ref();
+        let mut normalized = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => normalized.push(part),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !normalized.pop() {
+                        return Err(VfsError::OutsideLocation(path.to_path_buf()));
+                    }
+                }
+                Component::RootDir | Component::Prefix(_) => {
+                    return Err(VfsError::OutsideLocation(path.to_path_buf()));
+                }
+            }
+        }
+        Ok(Self(normalized))
+    }
+
+    /// Whether this is the Location root.
+    pub fn is_root(&self) -> bool {
+        self.0.as_os_str().is_empty()
+    }
+
+    /// Borrow as a `Path` (relative, no leading `/`).
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Append a single path component (e.g. a directory entry name).
+    ///
+    /// Rejects names that are empty, `.`, `..` or contain a separator.
+    pub fn join(&self, name: impl AsRef<OsStr>) -> VfsResult<Self> {
+        let name = name.as_ref();
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(part)), None) if part == name => Ok(Self(self.0.join(part))),
+            _ => Err(VfsError::OutsideLocation(self.0.join(name))),
+        }
+    }
+
+    /// The parent path, or `None` for the root.
+    pub fn parent(&self) -> Option<Self> {
+        if self.is_root() {
+            None
+        } else {
+            Some(Self(
+                self.0.parent().map(Path::to_path_buf).unwrap_or_default(),
+            ))
+        }
+    }
+
+    /// The final component, or `None` for the root.
+    pub fn file_name(&self) -> Option<&OsStr> {
+        self.0.file_name()
+    }
+
+    /// Whether this path equals `base` or lies underneath it.
+    pub fn starts_with(&self, base: impl AsRef<Path>) -> bool {
+        self.0.starts_with(base)
+    }
+}
+
+impl AsRef<Path> for RelPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RelPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
+
+/// The path one operation runs on, as returned by [`LocationHandle::resolve`].
+///
+/// On Linux it addresses the descriptors opened while checking the path and
+/// keeps them open, so use it for the operation at hand rather than storing
+/// it.
+#[derive(Debug)]
+pub struct Resolved {
+    path: PathBuf,
+    #[cfg(target_os = "linux")]
+    _pinned: Option<Arc<fs::File>>,
+}
+
+impl Resolved {
+    fn unpinned(path: PathBuf) -> Self {
+        Self {
+            path,
+            #[cfg(target_os = "linux")]
+            _pinned: None,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn pinned(path: PathBuf, file: Option<Arc<fs::File>>) -> Self {
+        Self {
+            path,
+            _pinned: file,
+        }
+    }
+}
+
+impl Deref for Resolved {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for Resolved {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// What an operation does with the final path component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Read it, following a final symlink.
+    Read,
+    /// Inspect it without following a final symlink.
+    Lstat,
+    /// Modify it; a final symlink is replaced or removed, not followed.
+    Write,
+}
+
+/// An opened Location root that all VFS operations are performed against.
+#[derive(Debug, Clone)]
+pub struct LocationHandle {
+    /// Root path as the user granted it (used for user-facing paths).
+    root: PathBuf,
+    /// Root with symlinks resolved at open time. Symlink checks use
+    /// [`LocationHandle::canonical_root`], which follows a moved root.
+    canonical_root: PathBuf,
+    /// Open directory handle that pins the root inode (Linux only).
+    #[cfg(target_os = "linux")]
+    anchor: Option<Arc<fs::File>>,
+    /// How symlinks below the root are resolved.
+    symlinks: SymlinkPolicy,
+}
+
+impl LocationHandle {
+    /// Open a Location root. The root must exist and be a directory.
+    pub fn open(root: &Path) -> VfsResult<Self> {
+        let canonical_root = root.canonicalize().map_err(|e| map_io_error(root, e))?;
+        if !canonical_root.is_dir() {
+            return Err(VfsError::NotFound(root.to_path_buf()));
+        }
+
+        #[cfg(target_os = "linux")]
+        let anchor = fs::File::open(&canonical_root)
+            .ok()
+            .map(Arc::new)
+            .filter(|file| fd_path(file).is_dir());
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            canonical_root,
+            #[cfg(target_os = "linux")]
+            anchor,
+            symlinks: SymlinkPolicy::default(),
+        })
+    }
+
+    /// Use `policy` when resolving symlinks.
+    pub fn with_symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// The symlink policy this handle resolves paths with.
+    pub fn symlink_policy(&self) -> SymlinkPolicy {
+        self.symlinks
+    }
+
+    /// Root path as the user granted it.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Root path with symlinks resolved.
+    ///
+    /// On Linux this is where the pinned root currently lives, so it stays
+    /// correct after the root folder is renamed.
+    pub fn canonical_root(&self) -> PathBuf {
+        #[cfg(target_os = "linux")]
+        if let Some(anchor) = &self.anchor {
+            if let Ok(current) = fs::read_link(fd_path(anchor)) {
+                if current.is_absolute() && current.is_dir() {
+                    return current;
+                }
+            }
+        }
+        self.canonical_root.clone()
+    }
+
+    /// User-facing absolute path for a relative path.
+    pub fn absolute(&self, rel: &RelPath) -> PathBuf {
+        if rel.is_root() {
+            self.root.clone()
+        } else {
+            self.root.join(rel.as_path())
+        }
+    }
+
+    /// Convert an absolute path into a `RelPath` without touching the disk.
+    ///
+    /// Accepts paths under either the granted root or its canonical form.
+    pub fn relativize(&self, path: &Path) -> VfsResult<RelPath> {
+        let rest = path
+            .strip_prefix(&self.root)
+            .or_else(|_| path.strip_prefix(&self.canonical_root))
+            .map_err(|_| VfsError::OutsideLocation(path.to_path_buf()))?;
+        RelPath::new(rest).map_err(|_| VfsError::OutsideLocation(path.to_path_buf()))
+    }
+
+    /// Verify that `rel` may be read under the symlink policy and return the
+    /// path operations should use.
+    ///
+    /// Symlinks that escape the root are only followed under
+    /// [`SymlinkPolicy::FollowAnywhere`]; none are under `Ignore`.
+    pub fn resolve(&self, rel: &RelPath) -> VfsResult<Resolved> {
+        self.resolve_for(rel, Access::Read)
+    }
+
+    /// Like [`LocationHandle::resolve`], for operations that modify `rel`.
+    ///
+    /// Never passes through a symlink that escapes the root, whatever the
+    /// policy: what lies outside the Location is read-only. A symlink at
+    /// `rel` itself is neither followed nor checked, so it can be removed or
+    /// replaced wherever (and whether) it points.
+    pub fn resolve_writable(&self, rel: &RelPath) -> VfsResult<Resolved> {
+        self.resolve_for(rel, Access::Write)
+    }
+
+    fn resolve_for(&self, rel: &RelPath, access: Access) -> VfsResult<Resolved> {
+        #[cfg(target_os = "linux")]
+        if let Some(anchor) = &self.anchor {
+            return self.resolve_pinned(anchor, rel, access);
+        }
+        self.resolve_by_path(rel, access)
+    }
+
+    /// Walk `rel` from the root descriptor, opening each component relative
+    /// to its parent with `O_NOFOLLOW`.
+    #[cfg(target_os = "linux")]
+    fn resolve_pinned(
+        &self,
+        anchor: &Arc<fs::File>,
+        rel: &RelPath,
+        access: Access,
+    ) -> VfsResult<Resolved> {
+        let root = self.canonical_root();
+        let mut dir = Arc::clone(anchor);
+        let mut components = rel.as_path().components().peekable();
+        while let Some(component) = components.next() {
+            let path = fd_path(&dir).join(component);
+            let last = components.peek().is_none();
+            if last && access != Access::Read {
+                // The entry itself is inspected or modified, never followed
+                return Ok(Resolved::pinned(path, Some(dir)));
+            }
+            let opened = match open_path(&path, false) {
+                Ok(opened) => opened,
+                Err(e) if is_missing(&e) => {
+                    // Missing or not a directory: the operation fails (or
+                    // creates it) relative to the last directory opened.
+                    let path = components.fold(path, |path, rest| path.join(rest));
+                    return Ok(Resolved::pinned(path, Some(dir)));
+                }
+                Err(e) => return Err(map_io_error(&self.absolute(rel), e)),
+            };
+            let is_symlink = opened
+                .metadata()
+                .map_err(|e| map_io_error(&self.absolute(rel), e))?
+                .file_type()
+                .is_symlink();
+            let opened = if is_symlink {
+                self.follow(&path, &root, rel, access)?
+            } else {
+                opened
+            };
+            if last {
+                return Ok(Resolved::pinned(fd_path(&opened), Some(Arc::new(opened))));
+            }
+            dir = Arc::new(opened);
+        }
+        // The root itself
+        Ok(Resolved::pinned(fd_path(&dir), Some(dir)))
+    }
+
+    /// Open what the symlink at `link` points to and check its real path
+    /// against the policy.
+    #[cfg(target_os = "linux")]
+    fn follow(
+        &self,
+        link: &Path,
+        root: &Path,
+        rel: &RelPath,
+        access: Access,
+    ) -> VfsResult<fs::File> {
+        if self.symlinks == SymlinkPolicy::Ignore {
+            return Err(VfsError::PermissionDenied(self.absolute(rel)));
+        }
+        let outside = || VfsError::OutsideLocation(self.absolute(rel));
+        let target = open_path(link, true).map_err(|_| outside())?;
+        let real = fs::read_link(fd_path(&target)).map_err(|_| outside())?;
+        if !real.starts_with(root) {
+            self.check_outside(rel, access)?;
+        }
+        Ok(target)
+    }
+
+    /// Check `rel` by path with `lstat` and `canonicalize`.
+    fn resolve_by_path(&self, rel: &RelPath, access: Access) -> VfsResult<Resolved> {
+        let base = self.canonical_root.clone();
+        let mut current = base.clone();
+        let mut components = rel.as_path().components().peekable();
+        while let Some(component) = components.next() {
+            current.push(component);
+            if components.peek().is_none() && access != Access::Read {
+                // The entry itself is inspected or modified, never followed
+                break;
+            }
+            match fs::symlink_metadata(&current) {
+                Ok(meta) if meta.file_type().is_symlink() => {
+                    if self.symlinks == SymlinkPolicy::Ignore {
+                        return Err(VfsError::PermissionDenied(self.absolute(rel)));
+                    }
+                    let target = current
+                        .canonicalize()
+                        .map_err(|_| VfsError::OutsideLocation(self.absolute(rel)))?;
+                    if !target.starts_with(&base) {
+                        self.check_outside(rel, access)?;
+                    }
+                }
+                Ok(_) => {}
+                // Nothing below a missing component can be a symlink.
+                Err(_) => break,
+            }
+        }
+        Ok(Resolved::unpinned(base.join(rel.as_path())))
+    }
+
+    /// Whether a symlink leading outside the root may be passed for `access`.
+    fn check_outside(&self, rel: &RelPath, access: Access) -> VfsResult<()> {
+        match (self.symlinks, access) {
+            (SymlinkPolicy::FollowAnywhere, Access::Read | Access::Lstat) => Ok(()),
+            (SymlinkPolicy::FollowAnywhere, Access::Write) => {
+                Err(VfsError::PermissionDenied(self.absolute(rel)))
+            }
+            _ => Err(VfsError::OutsideLocation(self.absolute(rel))),
+        }
+    }
+
+    /// Whether `rel` exists (following symlinks that stay inside the root).
+    pub fn exists(&self, rel: &RelPath) -> bool {
+        self.resolve(rel).map(|p| p.exists()).unwrap_or(false)
+    }
+
+    /// Filesystem metadata for `rel`, following in-Location symlinks.
+    pub fn metadata(&self, rel: &RelPath) -> VfsResult<fs::Metadata> {
+        let path = self.resolve(rel)?;
+        fs::metadata(&path).map_err(|e| map_io_error(&self.absolute(rel), e))
+    }
+
+    /// Filesystem metadata for `rel` without following a final symlink.
+    pub fn symlink_metadata(&self, rel: &RelPath) -> VfsResult<fs::Metadata> {
+        let path = self.resolve_for(rel, Access::Lstat)?;
+        fs::symlink_metadata(&path).map_err(|e| map_io_error(&self.absolute(rel), e))
+    }
+
+    /// Iterate the children of a directory.
+    ///
+    /// Yields each child's `RelPath` with the raw `DirEntry`. Do not use
+    /// `DirEntry::path()` for anything user-facing — it may be anchor-relative.
+    pub fn read_dir(
+        &self,
+        rel: &RelPath,
+    ) -> VfsResult<impl Iterator<Item = std::io::Result<(RelPath, fs::DirEntry)>>> {
+        let path = self.resolve(rel)?;
+        let read_dir = fs::read_dir(&path).map_err(|e| map_io_error(&self.absolute(rel), e))?;
+        let parent = rel.clone();
+        Ok(read_dir.map(move |entry| {
+            let entry = entry?;
+            let child = parent
+                .join(entry.file_name())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+            Ok((child, entry))
+        }))
+    }
+
+    /// Read a file's contents.
+    pub fn read(&self, rel: &RelPath) -> VfsResult<Vec<u8>> {
+        let path = self.resolve(rel)?;
+        fs::read(&path).map_err(|e| map_io_error(&self.absolute(rel), e))
+    }
+
+    /// Create a new file atomically. Fails if the path already exists.
+    pub fn create_file(&self, rel: &RelPath, content: &[u8]) -> VfsResult<()> {
+        let path = self.resolve_writable(rel)?;
+        if path.exists() {
+            return Err(VfsError::AlreadyExists(self.absolute(rel)));
+        }
+        if let Some(parent) = rel.parent() {
+            if !self.exists(&parent) {
+                return Err(VfsError::NotFound(self.absolute(&parent)));
+            }
+        }
+        atomic_write_new(&path, content).map_err(|e| self.user_facing(rel, e))
+    }
+
+    /// Copy a file to `dest_rel` in `dest` (which may be this handle).
+    ///
+    /// The copy is streamed into a temp file and committed atomically; it
+    /// fails if the destination already exists. Permissions are carried
+    /// over. Returns the number of bytes copied.
+    pub fn copy_file(
+        &self,
+        rel: &RelPath,
+        dest: &LocationHandle,
+        dest_rel: &RelPath,
+    ) -> VfsResult<u64> {
+        let path = self.resolve(rel)?;
+        let mut source = fs::File::open(&path).map_err(|e| map_io_error(&self.absolute(rel), e))?;
+        let permissions = source
+            .metadata()
+            .map_err(|e| map_io_error(&self.absolute(rel), e))?
+            .permissions();
+
+        let dest_path = dest.resolve_writable(dest_rel)?;
+        if fs::symlink_metadata(&dest_path).is_ok() {
+            return Err(VfsError::AlreadyExists(dest.absolute(dest_rel)));
+        }
+        if let Some(parent) = dest_rel.parent() {
+            if !dest.exists(&parent) {
+                return Err(VfsError::NotFound(dest.absolute(&parent)));
+            }
+        }
+        let bytes =
+            atomic_copy_new(&mut source, &dest_path).map_err(|e| dest.user_facing(dest_rel, e))?;
+        fs::set_permissions(&dest_path, permissions)
+            .map_err(|e| map_io_error(&dest.absolute(dest_rel), e))?;
+        Ok(bytes)
+    }
+
+    /// Atomically overwrite an existing file.
+    pub fn write_file(&self, rel: &RelPath, content: &[u8]) -> VfsResult<()> {
+        let path = self.resolve_writable(rel)?;
+        if !path.exists() {
+            return Err(VfsError::NotFound(self.absolute(rel)));
+        }
+        atomic_write(&path, content).map_err(|e| self.user_facing(rel, e))
+    }
+
+    /// Atomically overwrite an existing file if `precondition` passes.
+    ///
+    /// `precondition` runs after the new content is synced, just before
+    /// the commit (see [`atomic_write_if`]).
+    pub fn write_file_if(
+        &self,
+        rel: &RelPath,
+        content: &[u8],
+        precondition: impl FnOnce() -> VfsResult<()>,
+    ) -> VfsResult<()> {
+        let path = self.resolve_writable(rel)?;
+        atomic_write_if(&path, content, precondition).map_err(|e| self.user_facing(rel, e))
+    }
+
+    /// Create a single directory. Fails if the path already exists.
+    pub fn create_dir(&self, rel: &RelPath) -> VfsResult<()> {
+        let path = self.resolve_writable(rel)?;
+        fs::create_dir(&path).map_err(|e| map_io_error(&self.absolute(rel), e))
+    }
+
+    /// Create a directory and any missing parents.
+    pub fn create_dir_all(&self, rel: &RelPath) -> VfsResult<()> {
+        let path = self.resolve_writable(rel)?;
+        fs::create_dir_all(&path).map_err(|e| map_io_error(&self.absolute(rel), e))
+    }
+
+    /// Rename or move an entry. Fails if the destination already exists.
+    pub fn rename(&self, from: &RelPath, to: &RelPath) -> VfsResult<()> {
+        let from_path = self.resolve_writable(from)?;
+        let to_path = self.resolve_writable(to)?;
+        if fs::symlink_metadata(&from_path).is_err() {
+            return Err(VfsError::NotFound(self.absolute(from)));
+        }
+        if fs::symlink_metadata(&to_path).is_ok() {
+            return Err(VfsError::AlreadyExists(self.absolute(to)));
+        }
+        fs::rename(&from_path, &to_path).map_err(|e| map_io_error(&self.absolute(from), e))
+    }
+
+    /// Remove a file (or symlink).
+    pub fn remove_file(&self, rel: &RelPath) -> VfsResult<()> {
+        let path = self.resolve_writable(rel)?;
+        fs::remove_file(&path).map_err(|e| map_io_error(&self.absolute(rel), e))
+    }
+
+    /// Remove a directory and all its contents.
+    pub fn remove_dir_all(&self, rel: &RelPath) -> VfsResult<()> {
+        if rel.is_root() {
+            return Err(VfsError::PermissionDenied(self.root.clone()));
+        }
+        let path = self.resolve_writable(rel)?;
+        fs::remove_dir_all(&path).map_err(|e| map_io_error(&self.absolute(rel), e))
+    }
+
+    /// Replace anchor-relative paths in errors with the user-facing path.
+    fn user_facing(&self, rel: &RelPath, err: VfsError) -> VfsError {
+        match err {
+            VfsError::AtomicWriteFailed { reason, .. } => VfsError::AtomicWriteFailed {
+                path: self.absolute(rel),
+                reason,
+            },
+            VfsError::AlreadyExists(_) => VfsError::AlreadyExists(self.absolute(rel)),
+            other => other,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn fd_path(file: &fs::File) -> PathBuf {
+    use std::os::fd::AsRawFd;
+    PathBuf::from(format!("/proc/self/fd/{}", file.as_raw_fd()))
+}
+
+/// Open `path` as an `O_PATH` descriptor, which pins the object without
+/// granting access to its contents.
+#[cfg(target_os = "linux")]
+fn open_path(path: &Path, follow: bool) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut flags = libc::O_PATH;
+    if !follow {
+        flags |= libc::O_NOFOLLOW;
+    }
+    fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(flags)
+        .open(path)
+}
+
+/// Whether an open failed because the path does not lead anywhere (a
+/// missing entry or a non-directory in the middle), rather than being refused.
+#[cfg(target_os = "linux")]
+fn is_missing(e: &std::io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(libc::ENOENT | libc::ENOTDIR))
+}
+
+/// Map an IO error to the matching `VfsError` variant for `path`.
+pub(crate) fn map_io_error(path: &Path, e: std::io::Error) -> VfsError {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => VfsError::NotFound(path.to_path_buf()),
+        std::io::ErrorKind::PermissionDenied => VfsError::PermissionDenied(path.to_path_buf()),
+        std::io::ErrorKind::AlreadyExists => VfsError::AlreadyExists(path.to_path_buf()),
+        _ => VfsError::Io { source: e },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rel_path_normalizes() {
+        assert_eq!(RelPath::new("a/./b").unwrap().as_path(), Path::new("a/b"));
+        assert_eq!(RelPath::new("a/../b").unwrap().as_path(), Path::new("b"));
+        assert!(RelPath::new("").unwrap().is_root());
+    }
+
+    #[test]
+    fn test_rel_path_rejects_escape() {
+        assert!(RelPath::new("../etc").is_err());
+        assert!(RelPath::new("a/../../etc").is_err());
+        assert!(RelPath::new("/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_rel_path_join_single_component() {
+        let base = RelPath::new("notes").unwrap();
+        assert_eq!(
+            base.join("a.md").unwrap().as_path(),
+            Path::new("notes/a.md")
+        );
+        assert!(base.join("..").is_err());
+        assert!(base.join("x/y").is_err());
+        assert!(base.join("").is_err());
+    }
+
+    #[test]
+    fn test_relativize_and_absolute() {
+        let tmp = TempDir::new().unwrap();
+        let handle = LocationHandle::open(tmp.path()).unwrap();
+
+        let rel = handle.relativize(&tmp.path().join("a/b.md")).unwrap();
+        assert_eq!(rel.as_path(), Path::new("a/b.md"));
+        assert_eq!(handle.absolute(&rel), tmp.path().join("a/b.md"));
+
+        assert!(handle.relativize(Path::new("/somewhere/else")).is_err());
+        assert!(handle
+            .relativize(&tmp.path().join("a/../../escape"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_operations_relative_to_handle() {
+        let tmp = TempDir::new().unwrap();
+        let handle = LocationHandle::open(tmp.path()).unwrap();
+
+        let dir = RelPath::new("notes").unwrap();
+        let file = dir.join("a.md").unwrap();
+        handle.create_dir(&dir).unwrap();
+        handle.create_file(&file, b"hello").unwrap();
+        assert_eq!(handle.read(&file).unwrap(), b"hello");
+        assert!(matches!(
+            handle.create_file(&file, b"again"),
+            Err(VfsError::AlreadyExists(p)) if p == tmp.path().join("notes/a.md")
+        ));
+
+        let renamed = dir.join("b.md").unwrap();
+        handle.rename(&file, &renamed).unwrap();
+        assert!(!handle.exists(&file));
+        assert!(handle.exists(&renamed));
+
+        let names: Vec<_> = handle
+            .read_dir(&dir)
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(names, vec![renamed.clone()]);
+
+        handle.remove_dir_all(&dir).unwrap();
+        assert!(!tmp.path().join("notes").exists());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_handle_survives_root_rename() {
+        let parent = TempDir::new().unwrap();
+        let original = parent.path().join("original");
+        std::fs::create_dir(&original).unwrap();
+        let handle = LocationHandle::open(&original).unwrap();
+
+        // Swap the root path out from under the handle.
+        let moved = parent.path().join("moved");
+        std::fs::rename(&original, &moved).unwrap();
+        std::fs::create_dir(&original).unwrap();
+
+        handle
+            .create_file(&RelPath::new("note.md").unwrap(), b"pinned")
+            .unwrap();
+        assert!(moved.join("note.md").exists());
+        assert!(!original.join("note.md").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlink_policies() {
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::fs::write(outside.path().join("shared.md"), "outside").unwrap();
+        std::fs::create_dir(tmp.path().join("real")).unwrap();
+        std::fs::write(tmp.path().join("real/a.md"), "inside").unwrap();
+        symlink(tmp.path().join("real"), tmp.path().join("alias")).unwrap();
+        symlink(outside.path(), tmp.path().join("ext")).unwrap();
+        let internal = RelPath::new("alias/a.md").unwrap();
+        let external = RelPath::new("ext/shared.md").unwrap();
+
+        let ignore = LocationHandle::open(tmp.path())
+            .unwrap()
+            .with_symlink_policy(SymlinkPolicy::Ignore);
+        assert!(matches!(
+            ignore.read(&internal),
+            Err(VfsError::PermissionDenied(_))
+        ));
+
+        let within = LocationHandle::open(tmp.path()).unwrap();
+        assert_eq!(within.read(&internal).unwrap(), b"inside");
+        assert!(matches!(
+            within.read(&external),
+            Err(VfsError::OutsideLocation(_))
+        ));
+
+        let anywhere = LocationHandle::open(tmp.path())
+            .unwrap()
+            .with_symlink_policy(SymlinkPolicy::FollowAnywhere);
+        assert_eq!(anywhere.read(&external).unwrap(), b"outside");
+        assert!(matches!(
+            anywhere.write_file(&external, b"changed"),
+            Err(VfsError::PermissionDenied(_))
+        ));
+        assert_eq!(
+            std::fs::read_to_string(outside.path().join("shared.md")).unwrap(),
+            "outside"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_final_symlink_removed_not_followed() {
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::fs::write(outside.path().join("shared.md"), "outside").unwrap();
+        symlink(tmp.path().join("gone.md"), tmp.path().join("dangling")).unwrap();
+        symlink(outside.path().join("shared.md"), tmp.path().join("ext")).unwrap();
+        symlink(tmp.path().join("dangling"), tmp.path().join("link")).unwrap();
+        let dangling = RelPath::new("dangling").unwrap();
+        let ext = RelPath::new("ext").unwrap();
+        let link = RelPath::new("link").unwrap();
+
+        let within = LocationHandle::open(tmp.path()).unwrap();
+        assert!(within
+            .symlink_metadata(&dangling)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        within.remove_file(&dangling).unwrap();
+
+        let anywhere = LocationHandle::open(tmp.path())
+            .unwrap()
+            .with_symlink_policy(SymlinkPolicy::FollowAnywhere);
+        anywhere.remove_file(&ext).unwrap();
+        assert!(outside.path().join("shared.md").exists());
+
+        let ignore = LocationHandle::open(tmp.path())
+            .unwrap()
+            .with_symlink_policy(SymlinkPolicy::Ignore);
+        ignore.remove_file(&link).unwrap();
+
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlink_inside_root_allowed() {
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("real")).unwrap();
+        std::fs::write(tmp.path().join("real/a.md"), "x").unwrap();
+        symlink(tmp.path().join("real"), tmp.path().join("alias")).unwrap();
+
+        let handle = LocationHandle::open(tmp.path()).unwrap();
+        let rel = RelPath::new("alias/a.md").unwrap();
+        assert_eq!(handle.read(&rel).unwrap(), b"x");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_resolved_path_survives_symlink_swap() {
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("sub")).unwrap();
+        std::fs::write(tmp.path().join("sub/a.md"), "inside").unwrap();
+        std::fs::write(outside.path().join("a.md"), "outside").unwrap();
+        let handle = LocationHandle::open(tmp.path()).unwrap();
+        let rel = RelPath::new("sub/a.md").unwrap();
+
+        let read = handle.resolve(&rel).unwrap();
+        let write = handle.resolve_writable(&rel).unwrap();
+
+        // Replace `sub` with a symlink that leaves the root after the check.
+        std::fs::rename(tmp.path().join("sub"), tmp.path().join("old")).unwrap();
+        symlink(outside.path(), tmp.path().join("sub")).unwrap();
+
+        assert_eq!(std::fs::read(&read).unwrap(), b"inside");
+        std::fs::write(&write, "changed").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("old/a.md")).unwrap(),
+            "changed"
+        );
+        assert_eq!(
+            std::fs::read_to_string(outside.path().join("a.md")).unwrap(),
+            "outside"
+        );
+        assert!(matches!(
+            handle.read(&rel),
+            Err(VfsError::OutsideLocation(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_symlink_inside_moved_root() {
+        use std::os::unix::fs::symlink;
+
+        let parent = TempDir::new().unwrap();
+        let original = parent.path().join("original");
+        std::fs::create_dir_all(original.join("real")).unwrap();
+        std::fs::write(original.join("real/a.md"), "x").unwrap();
+        symlink("real", original.join("alias")).unwrap();
+        let handle = LocationHandle::open(&original).unwrap();
+
+        let moved = parent.path().join("moved");
+        std::fs::rename(&original, &moved).unwrap();
+
+        assert_eq!(handle.canonical_root(), moved.canonicalize().unwrap());
+        let rel = RelPath::new("alias/a.md").unwrap();
+        assert_eq!(handle.read(&rel).unwrap(), b"x");
+    }
+}
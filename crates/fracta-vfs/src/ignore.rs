@@ -2,21 +2,89 @@
 //!
 //! Fracta uses `.fracta/config/ignore` to determine which paths within a managed
 //! Location should be treated as Ignored (visible but not indexed/processed).
+//! Rules can come from several sources, each anchored at a folder:
+//!
+//! - `.fracta/config/ignore` at the Location root
+//! - `.gitignore` files anywhere in the tree (opt-in, `honor_gitignore` setting)
+//! - per-folder overrides stored in settings (`folder_ignores`), so rules can
+//!   be added for a folder without writing files into it
+//!
+//! `.gitignore` files are found with one search of the tree when the rules
+//! are first loaded. Afterwards only what changed is re-read: a single
+//! `.gitignore` (see [`IgnoreRules::reload_gitignore`]), or folders that
+//! become visible again once the rule ignoring them is gone.
+//!
+//! Precedence follows git: rules in deeper folders override shallower ones,
+//! and within one folder the Fracta override beats `.gitignore`, which beats
+//! the root config. The last matching rule wins, so `!` can re-include a path
+//! a shallower level excluded — unless a parent directory is excluded, which
+//! (as in git) excludes everything below it.
 //!
 //! Syntax follows `.gitignore` conventions:
 //! - `#` comments, blank lines skipped
 //! - `!` prefix negates a rule
 //! - Trailing `/` matches directories only
 //! - `*` and `**` wildcards
-//! - Patterns without `/` match anywhere in the tree
+//! - Patterns without `/` match anywhere below the folder they are defined in
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 
 use globset::{Glob, GlobMatcher};
 
-/// A compiled set of ignore rules.
+use crate::error::{VfsError, VfsResult};
+use crate::handle::{LocationHandle, RelPath};
+use crate::location::FRACTA_DIR;
+use crate::settings::LocationSettings;
+use crate::walk::{SkipReason, SkippedEntry};
+
+/// Name of per-folder git ignore files.
+pub const GITIGNORE_FILE: &str = ".gitignore";
+
+/// A compiled set of ignore rules from one or more sources.
 #[derive(Debug, Clone)]
 pub struct IgnoreRules {
+    /// Rule sets keyed by the folder they are anchored at (relative to the
+    /// Location root), each list ordered from lowest to highest precedence.
+    sets: BTreeMap<PathBuf, Vec<RuleSet>>,
+    /// Whether `.gitignore` files are collected.
+    gitignore: bool,
+    /// Ignored folders whose `.gitignore` files were not collected; searched
+    /// once they are no longer ignored.
+    unsearched: BTreeSet<PathBuf>,
+    /// `.gitignore` files that could not be read.
+    skipped: Vec<SkippedEntry>,
+}
+
+/// Where an ignore rule was defined.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IgnoreSource {
+    /// `.fracta/config/ignore` (or the built-in defaults).
+    Config,
+    /// A `.gitignore` file, by path relative to the Location root.
+    Gitignore(PathBuf),
+    /// A per-folder override from settings, by folder relative to the root.
+    FolderOverride(PathBuf),
+}
+
+/// The rule that decided whether a path is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreMatch {
+    /// Where the rule is defined.
+    pub source: IgnoreSource,
+    /// 1-based line of the rule within its source.
+    pub line: usize,
+    /// The rule as written.
+    pub pattern: String,
+    /// Whether the rule is a `!` re-include.
+    pub negated: bool,
+    /// The path the rule matched: the queried path or one of its ancestors.
+    pub matched_path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+struct RuleSet {
+    source: IgnoreSource,
     rules: Vec<Rule>,
 }
 
@@ -25,6 +93,8 @@ struct Rule {
     matcher: GlobMatcher,
     negated: bool,
     dir_only: bool,
+    line: usize,
+    pattern: String,
 }
 
 /// Default ignore patterns applied to every managed Location.
@@ -60,7 +130,12 @@ dist/
 impl IgnoreRules {
     /// Create an empty ruleset (nothing is ignored).
     pub fn empty() -> Self {
-        Self { rules: Vec::new() }
+        Self {
+            sets: BTreeMap::new(),
+            gitignore: false,
+            unsearched: BTreeSet::new(),
+            skipped: Vec::new(),
+        }
     }
 
     /// Load rules from a file path. Returns empty rules if the file does not exist.
@@ -72,22 +147,135 @@ impl IgnoreRules {
         Ok(Self::parse(&content))
     }
 
+    /// Load every rule source for a Location through its handle.
+    ///
+    /// Reads `.fracta/config/ignore`, the folder overrides in `settings`, and —
+    /// if `settings.honor_gitignore` is set — every `.gitignore` in the tree.
+    /// Folders that are already ignored are not searched for `.gitignore`
+    /// files, matching git. A `.gitignore` that cannot be read contributes
+    /// no rules and is reported by [`IgnoreRules::skipped`].
+    pub fn load_for_location(
+        handle: &LocationHandle,
+        settings: &LocationSettings,
+    ) -> VfsResult<Self> {
+        let mut rules = Self::load_config(handle, settings)?;
+        if settings.honor_gitignore {
+            rules.gitignore = true;
+            rules.collect_gitignores(handle, Path::new(""));
+            rules.reconcile(handle);
+        }
+        Ok(rules)
+    }
+
+    /// Reload `.fracta/config/ignore` and the folder overrides, keeping the
+    /// `.gitignore` rules already collected.
+    ///
+    /// Only folders whose ignored state changed are searched again.
+    pub fn reload_config(
+        &self,
+        handle: &LocationHandle,
+        settings: &LocationSettings,
+    ) -> VfsResult<Self> {
+        if !self.gitignore {
+            return Self::load_for_location(handle, settings);
+        }
+        let mut rules = Self::load_config(handle, settings)?;
+        if settings.honor_gitignore {
+            rules.gitignore = true;
+            for (base, sets) in &self.sets {
+                for set in sets {
+                    if matches!(set.source, IgnoreSource::Gitignore(_)) {
+                        rules.insert_set(base, set.clone());
+                    }
+                }
+            }
+            rules.unsearched = self.unsearched.clone();
+            rules.skipped = self.skipped.clone();
+            rules.reconcile(handle);
+        }
+        Ok(rules)
+    }
+
+    /// Re-read the `.gitignore` in `dir` (relative to the root) after it was
+    /// created, changed or removed.
+    ///
+    /// Does nothing unless `.gitignore` files are honored, or if `dir` is
+    /// ignored (its rules would not apply).
+    pub fn reload_gitignore(&mut self, handle: &LocationHandle, dir: &RelPath) {
+        if !self.gitignore {
+            return;
+        }
+        let Ok(file) = dir.join(GITIGNORE_FILE) else {
+            return;
+        };
+        let absolute = handle.absolute(&file);
+        let (dir, file) = (dir.as_path(), file.as_path().to_path_buf());
+        if let Some(sets) = self.sets.get_mut(dir) {
+            sets.retain(|set| set.source != IgnoreSource::Gitignore(file.clone()));
+        }
+        self.skipped.retain(|s| s.path != absolute);
+        let searched = !self.unsearched.iter().any(|u| dir.starts_with(u));
+        if searched && !self.is_ignored(dir, true) {
+            self.read_gitignore(handle, dir);
+        }
+        self.reconcile(handle);
+    }
+
+    /// `.gitignore` files that could not be read, and why.
+    pub fn skipped(&self) -> &[SkippedEntry] {
+        &self.skipped
+    }
+
+    /// The root config (defaults if absent) and the per-folder overrides.
+    fn load_config(handle: &LocationHandle, settings: &LocationSettings) -> VfsResult<Self> {
+        let config = RelPath::new(Path::new(FRACTA_DIR).join("config").join("ignore"))?;
+        let mut rules = match handle.read(&config) {
+            Ok(bytes) => Self::parse(&String::from_utf8_lossy(&bytes)),
+            Err(VfsError::NotFound(_)) => Self::empty(),
+            Err(e) => return Err(e),
+        };
+        for (folder, patterns) in &settings.folder_ignores {
+            let folder = PathBuf::from(folder.trim_matches('/'));
+            rules.add_source(
+                &folder,
+                IgnoreSource::FolderOverride(folder.clone()),
+                &patterns.join("\n"),
+            );
+        }
+        Ok(rules)
+    }
+
     /// Parse rules from a string in gitignore syntax.
     pub fn parse(content: &str) -> Self {
+        let mut rules = Self::empty();
+        rules.add_source(Path::new(""), IgnoreSource::Config, content);
+        rules
+    }
+
+    /// Add rules in gitignore syntax anchored at `base` (relative to the root).
+    pub fn add_source(&mut self, base: &Path, source: IgnoreSource, content: &str) {
         let rules = content
             .lines()
-            .filter_map(|line| {
+            .enumerate()
+            .filter_map(|(i, line)| {
                 let trimmed = line.trim();
                 if trimmed.is_empty() || trimmed.starts_with('#') {
                     return None;
                 }
-                Self::compile_rule(trimmed)
+                Self::compile_rule(trimmed, i + 1)
             })
             .collect();
-        Self { rules }
+
+        self.insert_set(base, RuleSet { source, rules });
     }
 
-    fn compile_rule(line: &str) -> Option<Rule> {
+    fn insert_set(&mut self, base: &Path, set: RuleSet) {
+        let sets = self.sets.entry(base.to_path_buf()).or_default();
+        let at = sets.partition_point(|s| precedence(&s.source) <= precedence(&set.source));
+        sets.insert(at, set);
+    }
+
+    fn compile_rule(line: &str, line_no: usize) -> Option<Rule> {
         let mut pattern = line;
 
         // Check for negation prefix
@@ -120,6 +308,8 @@ impl IgnoreRules {
             matcher: glob.compile_matcher(),
             negated,
             dir_only,
+            line: line_no,
+            pattern: line.to_string(),
         })
     }
 
@@ -132,38 +322,194 @@ impl IgnoreRules {
     /// directories match a directory-ignore rule. This mirrors gitignore behavior
     /// where ignoring a directory implicitly ignores all its contents.
     pub fn is_ignored(&self, rel_path: &Path, is_dir: bool) -> bool {
+        self.explain(rel_path, is_dir).is_some_and(|m| !m.negated)
+    }
+
+    /// Whether `rel_path` is ignored, given that its parent folder is not.
+    ///
+    /// Equivalent to [`IgnoreRules::is_ignored`] under that assumption, but
+    /// only checks rules against the path itself, not every ancestor.
+    pub(crate) fn is_ignored_in_parent(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let parent = rel_path.parent().unwrap_or(Path::new(""));
+        self.last_match(parent, rel_path, is_dir)
+            .is_some_and(|m| !m.negated)
+    }
+
+    /// Explain why a relative path is (or is not) ignored.
+    ///
+    /// Returns the deciding rule: the ignore rule that excludes the path or
+    /// one of its ancestors, or else the `!` rule that re-included the path.
+    /// Returns `None` if no rule matches at all.
+    pub fn explain(&self, rel_path: &Path, is_dir: bool) -> Option<IgnoreMatch> {
         // Check each prefix of the path from root to leaf.
         let mut accumulated = PathBuf::new();
         let components: Vec<_> = rel_path.components().collect();
+        let mut last = None;
 
         for (i, component) in components.iter().enumerate() {
+            let parent = accumulated.clone();
             accumulated.push(component);
             let is_last = i == components.len() - 1;
             let check_is_dir = if is_last { is_dir } else { true };
 
-            if self.matches_rules(&accumulated, check_is_dir) {
-                return true;
+            last = self.last_match(&parent, &accumulated, check_is_dir);
+            if last.as_ref().is_some_and(|m| !m.negated) {
+                return last;
             }
         }
 
-        false
+        last
+    }
+
+    /// The last rule matching `rel_path`, considering sets anchored at `parent`
+    /// and its ancestors (no ancestor-directory checking).
+    fn last_match(&self, parent: &Path, rel_path: &Path, is_dir: bool) -> Option<IgnoreMatch> {
+        let mut found = None;
+        for base in std::iter::once(Path::new("")).chain(prefixes(parent)) {
+            let Some(sets) = self.sets.get(base) else {
+                continue;
+            };
+            let Ok(relative) = rel_path.strip_prefix(base) else {
+                continue;
+            };
+            for set in sets {
+                for rule in &set.rules {
+                    if rule.dir_only && !is_dir {
+                        continue;
+                    }
+                    if rule.matcher.is_match(relative) {
+                        found = Some((set, rule));
+                    }
+                }
+            }
+        }
+        found.map(|(set, rule)| IgnoreMatch {
+            source: set.source.clone(),
+            line: rule.line,
+            pattern: rule.pattern.clone(),
+            negated: rule.negated,
+            matched_path: rel_path.to_path_buf(),
+        })
     }
 
-    /// Evaluate rules against a single path segment (no ancestor checking).
-    fn matches_rules(&self, rel_path: &Path, is_dir: bool) -> bool {
-        let mut ignored = false;
-        for rule in &self.rules {
-            if rule.dir_only && !is_dir {
+    /// Add `.gitignore` files under `dir`, skipping folders already ignored.
+    fn collect_gitignores(&mut self, handle: &LocationHandle, dir: &Path) {
+        self.read_gitignore(handle, dir);
+
+        let Ok(read_dir) = RelPath::new(dir).and_then(|rel| handle.read_dir(&rel)) else {
+            // Unreadable folders simply contribute no rules
+            return;
+        };
+        for (child, entry) in read_dir.flatten() {
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            if !is_dir || entry.file_name() == FRACTA_DIR {
                 continue;
             }
-            if rule.matcher.is_match(rel_path) {
-                ignored = !rule.negated;
+            let child = child.as_path().to_path_buf();
+            if self.is_ignored(&child, true) {
+                self.unsearched.insert(child);
+            } else {
+                self.collect_gitignores(handle, &child);
             }
         }
-        ignored
+    }
+
+    /// Add the rules of the `.gitignore` in `dir`, if there is one. A file
+    /// that cannot be read is recorded in `skipped` instead.
+    fn read_gitignore(&mut self, handle: &LocationHandle, dir: &Path) {
+        let file = dir.join(GITIGNORE_FILE);
+        let Ok(rel) = RelPath::new(&file) else { return };
+        if !handle.metadata(&rel).is_ok_and(|m| m.is_file()) {
+            return;
+        }
+        let (reason, message) = match handle.read(&rel).map(String::from_utf8) {
+            Ok(Ok(content)) => {
+                self.add_source(dir, IgnoreSource::Gitignore(file), &content);
+                return;
+            }
+            Ok(Err(e)) => (SkipReason::Unreadable, e.to_string()),
+            Err(e) => (SkipReason::of_vfs_error(&e), e.to_string()),
+        };
+        self.skipped.push(SkippedEntry {
+            path: handle.absolute(&rel),
+            reason,
+            message,
+        });
+    }
+
+    /// Bring the collected `.gitignore` rules in line with what is ignored
+    /// now: drop rules from folders that became ignored and search folders
+    /// that no longer are. Repeats until nothing changes, since either step
+    /// can change what else is ignored.
+    fn reconcile(&mut self, handle: &LocationHandle) {
+        loop {
+            let hidden: Vec<PathBuf> = self
+                .sets
+                .iter()
+                .filter(|(_, sets)| {
+                    sets.iter()
+                        .any(|s| matches!(s.source, IgnoreSource::Gitignore(_)))
+                })
+                .filter_map(|(base, _)| self.ignored_ancestor(base))
+                .collect();
+            let revealed: Vec<PathBuf> = self
+                .unsearched
+                .iter()
+                .filter(|dir| !self.is_ignored(dir, true))
+                .cloned()
+                .collect();
+            if hidden.is_empty() && revealed.is_empty() {
+                return;
+            }
+
+            for dir in hidden {
+                for (base, sets) in self.sets.range_mut(dir.clone()..) {
+                    if !base.starts_with(&dir) {
+                        break;
+                    }
+                    sets.retain(|s| !matches!(s.source, IgnoreSource::Gitignore(_)));
+                }
+                if let Ok(rel) = RelPath::new(&dir) {
+                    let absolute = handle.absolute(&rel);
+                    self.skipped.retain(|s| !s.path.starts_with(&absolute));
+                }
+                self.unsearched.retain(|u| !u.starts_with(&dir));
+                self.unsearched.insert(dir);
+            }
+            for dir in revealed {
+                self.unsearched.remove(&dir);
+                self.collect_gitignores(handle, &dir);
+            }
+        }
+    }
+
+    /// The shallowest ignored folder among `dir` and its ancestors.
+    fn ignored_ancestor(&self, dir: &Path) -> Option<PathBuf> {
+        prefixes(dir)
+            .find(|p| self.is_ignored(p, true))
+            .map(Path::to_path_buf)
     }
 }
 
+/// Precedence of a source within a single folder (higher wins).
+fn precedence(source: &IgnoreSource) -> u8 {
+    match source {
+        IgnoreSource::Config => 0,
+        IgnoreSource::Gitignore(_) => 1,
+        IgnoreSource::FolderOverride(_) => 2,
+    }
+}
+
+/// `a`, `a/b`, `a/b/c` for `a/b/c`.
+fn prefixes(path: &Path) -> impl Iterator<Item = &Path> {
+    let mut all: Vec<&Path> = path
+        .ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .collect();
+    all.reverse();
+    all.into_iter()
+}
+
 impl Default for IgnoreRules {
     fn default() -> Self {
         Self::parse(DEFAULT_IGNORE)
@@ -260,4 +606,188 @@ mod tests {
         let rules = IgnoreRules::load(Path::new("/nonexistent/path")).unwrap();
         assert!(!rules.is_ignored(Path::new("anything"), false));
     }
+
+    #[test]
+    fn test_deeper_source_overrides_shallower() {
+        let mut rules = IgnoreRules::parse("*.log");
+        rules.add_source(
+            Path::new("sub"),
+            IgnoreSource::Gitignore(PathBuf::from("sub/.gitignore")),
+            "!keep.log",
+        );
+        assert!(rules.is_ignored(Path::new("debug.log"), false));
+        assert!(rules.is_ignored(Path::new("sub/debug.log"), false));
+        assert!(!rules.is_ignored(Path::new("sub/keep.log"), false));
+        assert!(!rules.is_ignored(Path::new("sub/deeper/keep.log"), false));
+        // The negation only applies below the folder that defines it
+        assert!(rules.is_ignored(Path::new("keep.log"), false));
+    }
+
+    #[test]
+    fn test_nested_source_patterns_are_relative() {
+        let mut rules = IgnoreRules::empty();
+        rules.add_source(
+            Path::new("repo"),
+            IgnoreSource::Gitignore(PathBuf::from("repo/.gitignore")),
+            "/out\nlogs/*.txt",
+        );
+        assert!(rules.is_ignored(Path::new("repo/out"), true));
+        assert!(!rules.is_ignored(Path::new("repo/sub/out"), true));
+        assert!(!rules.is_ignored(Path::new("out"), true));
+        assert!(rules.is_ignored(Path::new("repo/logs/a.txt"), false));
+    }
+
+    #[test]
+    fn test_cannot_reinclude_inside_excluded_dir() {
+        let mut rules = IgnoreRules::parse("vendor/");
+        rules.add_source(
+            Path::new("vendor"),
+            IgnoreSource::FolderOverride(PathBuf::from("vendor")),
+            "!lib.rs",
+        );
+        assert!(rules.is_ignored(Path::new("vendor/lib.rs"), false));
+    }
+
+    #[test]
+    fn test_folder_override_beats_gitignore_in_same_folder() {
+        let mut rules = IgnoreRules::empty();
+        rules.add_source(
+            Path::new("app"),
+            IgnoreSource::FolderOverride(PathBuf::from("app")),
+            "!dist/",
+        );
+        rules.add_source(
+            Path::new("app"),
+            IgnoreSource::Gitignore(PathBuf::from("app/.gitignore")),
+            "dist/",
+        );
+        assert!(!rules.is_ignored(Path::new("app/dist"), true));
+    }
+
+    #[test]
+    fn test_explain_reports_rule_and_source() {
+        let mut rules = IgnoreRules::parse("# comment\nnode_modules/\n*.log");
+        rules.add_source(
+            Path::new("sub"),
+            IgnoreSource::Gitignore(PathBuf::from("sub/.gitignore")),
+            "!keep.log",
+        );
+
+        let m = rules
+            .explain(Path::new("web/node_modules/pkg/index.js"), false)
+            .unwrap();
+        assert_eq!(m.source, IgnoreSource::Config);
+        assert_eq!(m.line, 2);
+        assert_eq!(m.pattern, "node_modules/");
+        assert!(!m.negated);
+        assert_eq!(m.matched_path, PathBuf::from("web/node_modules"));
+
+        let m = rules.explain(Path::new("sub/keep.log"), false).unwrap();
+        assert_eq!(
+            m.source,
+            IgnoreSource::Gitignore(PathBuf::from("sub/.gitignore"))
+        );
+        assert!(m.negated);
+
+        assert!(rules.explain(Path::new("readme.md"), false).is_none());
+    }
+
+    #[test]
+    fn test_load_for_location_sources() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join(".fracta/config")).unwrap();
+        std::fs::write(root.join(".fracta/config/ignore"), "node_modules/\n").unwrap();
+        std::fs::create_dir_all(root.join("repo/node_modules/pkg")).unwrap();
+        std::fs::write(root.join("repo/.gitignore"), "*.o\n").unwrap();
+        // Inside an ignored folder: never read
+        std::fs::write(root.join("repo/node_modules/pkg/.gitignore"), "*.md\n").unwrap();
+
+        let mut settings = LocationSettings::default();
+        settings
+            .folder_ignores
+            .insert("notes/".to_string(), vec!["drafts/".to_string()]);
+
+        let handle = LocationHandle::open(root).unwrap();
+        let rules = IgnoreRules::load_for_location(&handle, &settings).unwrap();
+        assert!(!rules.is_ignored(Path::new("repo/main.o"), false));
+        assert!(rules.is_ignored(Path::new("notes/drafts"), true));
+        assert!(!rules.is_ignored(Path::new("drafts"), true));
+
+        settings.honor_gitignore = true;
+        let rules = IgnoreRules::load_for_location(&handle, &settings).unwrap();
+        assert!(rules.is_ignored(Path::new("repo/main.o"), false));
+        assert!(!rules.is_ignored(Path::new("main.o"), false));
+        let sources: Vec<_> = rules.sets.values().flatten().map(|s| &s.source).collect();
+        assert!(!sources.contains(&&IgnoreSource::Gitignore(PathBuf::from(
+            "repo/node_modules/pkg/.gitignore"
+        ))));
+    }
+
+    #[test]
+    fn test_bad_gitignore_skipped_and_reported() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join(".fracta/config")).unwrap();
+        std::fs::write(root.join(".fracta/config/ignore"), "*.tmp\n").unwrap();
+        std::fs::create_dir_all(root.join("bad")).unwrap();
+        std::fs::write(root.join("bad/.gitignore"), b"\xff\xfe*.o\n").unwrap();
+        std::fs::create_dir_all(root.join("good")).unwrap();
+        std::fs::write(root.join("good/.gitignore"), "*.o\n").unwrap();
+
+        let settings = LocationSettings {
+            honor_gitignore: true,
+            ..Default::default()
+        };
+        let handle = LocationHandle::open(root).unwrap();
+        let rules = IgnoreRules::load_for_location(&handle, &settings).unwrap();
+        assert!(rules.is_ignored(Path::new("a.tmp"), false));
+        assert!(rules.is_ignored(Path::new("good/main.o"), false));
+        assert!(!rules.is_ignored(Path::new("bad/main.o"), false));
+
+        let skipped = rules.skipped();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, root.join("bad/.gitignore"));
+        assert_eq!(skipped[0].reason, SkipReason::Unreadable);
+    }
+
+    #[test]
+    fn test_reload_gitignore_updates_only_what_changed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("repo/vendor/lib")).unwrap();
+        std::fs::write(root.join("repo/.gitignore"), "vendor/\n").unwrap();
+        std::fs::write(root.join("repo/vendor/lib/.gitignore"), "*.a\n").unwrap();
+
+        let settings = LocationSettings {
+            honor_gitignore: true,
+            ..Default::default()
+        };
+        let handle = LocationHandle::open(root).unwrap();
+        let mut rules = IgnoreRules::load_for_location(&handle, &settings).unwrap();
+        assert!(rules.is_ignored(Path::new("repo/vendor/lib/x.a"), false));
+
+        // Un-ignoring a folder searches it for the rules it holds
+        std::fs::write(root.join("repo/.gitignore"), "*.log\n").unwrap();
+        let repo = RelPath::new("repo").unwrap();
+        rules.reload_gitignore(&handle, &repo);
+        assert!(!rules.is_ignored(Path::new("repo/vendor"), true));
+        assert!(rules.is_ignored(Path::new("repo/vendor/lib/x.a"), false));
+        assert!(rules.is_ignored(Path::new("repo/a.log"), false));
+
+        // Ignoring it again drops them
+        std::fs::write(root.join("repo/.gitignore"), "lib/\n").unwrap();
+        rules.reload_gitignore(&handle, &repo);
+        let sources: Vec<_> = rules.sets.values().flatten().map(|s| &s.source).collect();
+        assert!(!sources.contains(&&IgnoreSource::Gitignore(PathBuf::from(
+            "repo/vendor/lib/.gitignore"
+        ))));
+
+        // A config reload keeps what was collected without searching again
+        std::fs::remove_file(root.join("repo/.gitignore")).unwrap();
+        let reloaded = rules.reload_config(&handle, &settings).unwrap();
+        assert!(reloaded.is_ignored(Path::new("repo/vendor/lib"), true));
+        rules.reload_gitignore(&handle, &repo);
+        assert!(rules.is_ignored(Path::new("repo/vendor/lib/x.a"), false));
+    }
 }
@@ -7,12 +7,15 @@ use std::path::Path;
 
 use crate::error::VfsResult;
 use crate::ignore::DEFAULT_IGNORE;
+use crate::layout::{migrate_fracta_dir, LAYOUT_VERSION};
 use crate::location::FRACTA_DIR;
+use crate::settings::LocationSettings;
 use crate::writer::{atomic_write_string, ensure_dir};
 
 /// Subdirectories created during initialization.
 const INIT_DIRS: &[&str] = &[
     "config",
+    "config/profiles",
     "config/schemas",
     "config/views",
     "meta",
@@ -24,9 +27,14 @@ const INIT_DIRS: &[&str] = &[
 ///
 /// Creates the directory tree and default configuration files.
 /// Safe to call on an already-initialized Location (idempotent — does not
-/// overwrite existing files).
+/// overwrite existing files). An existing older tree is migrated to the
+/// current layout first; a newer one is refused.
 pub fn init_fracta_dir(root: &Path) -> VfsResult<()> {
     let fracta = root.join(FRACTA_DIR);
+    let settings_path = fracta.join("config").join("settings.json");
+    if settings_path.exists() {
+        migrate_fracta_dir(root)?;
+    }
 
     // Create all subdirectories
     for subdir in INIT_DIRS {
@@ -40,9 +48,12 @@ pub fn init_fracta_dir(root: &Path) -> VfsResult<()> {
     }
 
     // Write default settings.json (only if it doesn't exist)
-    let settings_path = fracta.join("config").join("settings.json");
     if !settings_path.exists() {
-        atomic_write_string(&settings_path, "{}\n")?;
+        let settings = LocationSettings {
+            layout_version: Some(LAYOUT_VERSION),
+            ..Default::default()
+        };
+        settings.save(root)?;
     }
 
     Ok(())
@@ -62,6 +73,7 @@ mod tests {
 
         assert!(root.join(".fracta").is_dir());
         assert!(root.join(".fracta/config").is_dir());
+        assert!(root.join(".fracta/config/profiles").is_dir());
         assert!(root.join(".fracta/config/schemas").is_dir());
         assert!(root.join(".fracta/config/views").is_dir());
         assert!(root.join(".fracta/meta").is_dir());
@@ -0,0 +1,540 @@
+//! Append-only operation journal (SPEC §12.2).
+//!
+//! When `journal.enabled` is set in the Location settings, every mutation
+//! made through `Location` appends one JSON line to
+//! `.fracta/state/journal.jsonl`: when it happened, who did it ([`Actor`]),
+//! what it was ([`JournalOp`]), the paths involved, and BLAKE3 hashes of the
+//! file content before and after. Operations on folders carry no hashes.
+//!
+//! Once the active file would grow past `journal.max_file_size` it is
+//! rotated: `journal.jsonl` becomes `journal.1.jsonl`, older files shift up
+//! by one, and anything beyond `journal.keep_files` is deleted. Lines are
+//! fsynced one at a time, so a crash can at worst leave a torn last line,
+//! which reading skips.
+//!
+//! Journaling never fails the operation it records: an append that fails is
+//! logged and dropped.
+
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::MutexGuard;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{VfsError, VfsResult};
+use crate::handle::{LocationHandle, RelPath};
+use crate::location::{Location, FRACTA_DIR};
+use crate::settings::{JournalSettings, LocationSettings};
+
+/// Active journal file name within `.fracta/state/`.
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// Who performed an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Actor {
+    /// The user, through the app.
+    #[default]
+    User,
+    /// An AI feature acting on the user's behalf.
+    Ai,
+    /// An automated pipeline (import, template, Profile action).
+    Pipeline,
+    /// A sync client applying remote changes.
+    Sync,
+}
+
+/// The kind of operation a journal entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalOp {
+    Create,
+    CreateFolder,
+    Write,
+    /// `path` renamed to `to` in the same folder.
+    Rename,
+    /// `path` moved to `to` in another folder.
+    Move,
+    /// A new copy created at `path`.
+    Copy,
+    /// Deleted without going through the trash.
+    Delete,
+    /// Moved to the trash.
+    Trash,
+    /// Restored from the trash to `path`.
+    Restore,
+}
+
+/// One recorded operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub at: DateTime<Utc>,
+    pub actor: Actor,
+    pub op: JournalOp,
+    /// The entry operated on. Absolute when returned by
+    /// [`Location::journal_entries`]; stored relative to the Location root.
+    pub path: PathBuf,
+    /// New path of a rename or move.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<PathBuf>,
+    /// Content hash before the operation (files only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Content hash after the operation (files only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+/// Filter for [`Location::journal_entries`]. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct JournalQuery {
+    /// Entries whose `path` or `to` is this absolute path or below it.
+    pub path: Option<PathBuf>,
+    pub actor: Option<Actor>,
+    /// Entries at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Entries before this time.
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl JournalQuery {
+    /// Whether `entry` (with relative paths) matches, `rel` being the
+    /// relativized query path.
+    fn matches(&self, entry: &JournalEntry, rel: Option<&RelPath>) -> bool {
+        let on_path = |rel: &RelPath| {
+            entry.path.starts_with(rel.as_path())
+                || entry
+                    .to
+                    .as_ref()
+                    .is_some_and(|to| to.starts_with(rel.as_path()))
+        };
+        rel.is_none_or(on_path)
+            && self.actor.is_none_or(|actor| entry.actor == actor)
+            && self.since.is_none_or(|since| entry.at >= since)
+            && self.until.is_none_or(|until| entry.at < until)
+    }
+}
+
+/// Journal configuration as loaded from settings, shared between clones.
+#[derive(Debug, Default)]
+pub(crate) struct JournalState {
+    /// `None` until first use and after [`Location::reload_journal_settings`].
+    settings: Option<JournalSettings>,
+}
+
+impl Location {
+    /// Who this Location's operations are attributed to in the journal.
+    pub fn actor(&self) -> Actor {
+        self.actor
+    }
+
+    /// Attribute subsequent operations through this value to `actor`.
+    ///
+    /// Clones keep their own actor, so e.g. an AI pipeline can work on a
+    /// clone without affecting the user's operations.
+    pub fn set_actor(&mut self, actor: Actor) {
+        self.actor = actor;
+    }
+
+    /// Re-read the journal settings after they were changed on disk.
+    pub fn reload_journal_settings(&self) {
+        self.journal_state().settings = None;
+    }
+
+    /// Journal entries matching `query`, oldest first.
+    ///
+    /// Reads every retained file, whether or not journaling is currently on.
+    pub fn journal_entries(&self, query: &JournalQuery) -> VfsResult<Vec<JournalEntry>> {
+        let rel = query
+            .path
+            .as_deref()
+            .map(|path| self.relativize(path))
+            .transpose()?;
+        let handle = self.handle()?;
+        let _guard = self.journal_state();
+
+        let mut files = Vec::new();
+        for n in (1..).take_while(|&n| handle.exists(&journal_rel(n))) {
+            files.push(journal_rel(n));
+        }
+        files.reverse();
+        files.push(journal_rel(0));
+
+        let mut entries = Vec::new();
+        for file in files {
+            let content = match handle.read(&file) {
+                Ok(content) => content,
+                Err(VfsError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            for line in content.split(|&b| b == b'\n') {
+                // Skip blank and torn lines
+                let Ok(mut entry) = serde_json::from_slice::<JournalEntry>(line) else {
+                    continue;
+                };
+                if query.matches(&entry, rel.as_ref()) {
+                    entry.path = self.root.join(&entry.path);
+                    entry.to = entry.to.map(|to| self.root.join(to));
+                    entries.push(entry);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Whether operations are currently journaled.
+    pub(crate) fn journaling(&self) -> bool {
+        self.journal_settings().is_some()
+    }
+
+    /// Content hash of the file at `rel` for the journal.
+    ///
+    /// `None` if journaling is off or `rel` is not a readable file.
+    pub(crate) fn journal_hash(&self, rel: &RelPath) -> Option<String> {
+        if !self.journaling() {
+            return None;
+        }
+        let handle = self.handle().ok()?;
+        if !handle.metadata(rel).ok()?.is_file() {
+            return None;
+        }
+        handle.read(rel).ok().map(|content| hash_content(&content))
+    }
+
+    /// Content hash of data about to be written, if journaling is on.
+    pub(crate) fn journal_content_hash(&self, content: &[u8]) -> Option<String> {
+        self.journaling().then(|| hash_content(content))
+    }
+
+    /// Append an entry for an operation that already succeeded.
+    pub(crate) fn record_op(
+        &self,
+        op: JournalOp,
+        path: &RelPath,
+        to: Option<&RelPath>,
+        before: Option<String>,
+        after: Option<String>,
+    ) {
+        let mut state = self.journal_state();
+        let Some(settings) = self.loaded_journal_settings(&mut state) else {
+            return;
+        };
+        let entry = JournalEntry {
+            at: Utc::now(),
+            actor: self.actor,
+            op,
+            path: path.as_path().to_path_buf(),
+            to: to.map(|to| to.as_path().to_path_buf()),
+            before,
+            after,
+        };
+        let result = self
+            .handle()
+            .and_then(|handle| append(handle, &settings, &entry));
+        if let Err(e) = result {
+            tracing::warn!(root = %self.root.display(), ?op, "journal append failed: {e}");
+        }
+    }
+
+    /// Journal settings if journaling is enabled.
+    fn journal_settings(&self) -> Option<JournalSettings> {
+        let mut state = self.journal_state();
+        self.loaded_journal_settings(&mut state)
+    }
+
+    /// Load the settings into `state` if needed; `None` if disabled.
+    ///
+    /// Unmanaged Locations and unreadable settings never journal.
+    fn loaded_journal_settings(
+        &self,
+        state: &mut MutexGuard<'_, JournalState>,
+    ) -> Option<JournalSettings> {
+        if state.settings.is_none() {
+            let settings = if self.uses_trash() {
+                LocationSettings::load(&self.root)
+                    .map(|s| s.journal)
+                    .unwrap_or_default()
+            } else {
+                JournalSettings::default()
+            };
+            state.settings = Some(settings);
+        }
+        state.settings.clone().filter(|s| s.enabled)
+    }
+
+    fn journal_state(&self) -> MutexGuard<'_, JournalState> {
+        self.journal.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// BLAKE3 hex digest, as used for [`crate::FileVersion::Hash`].
+fn hash_content(content: &[u8]) -> String {
+    blake3::hash(content).to_hex().to_string()
+}
+
+/// `.fracta/state/journal.jsonl` for `n == 0`, `journal.<n>.jsonl` otherwise.
+fn journal_rel(n: usize) -> RelPath {
+    let name = match n {
+        0 => JOURNAL_FILE.to_string(),
+        n => format!("journal.{n}.jsonl"),
+    };
+    RelPath::new(Path::new(FRACTA_DIR).join("state").join(name)).expect("journal path is relative")
+}
+
+/// Append one entry, rotating first if it would overflow the active file.
+fn append(
+    handle: &LocationHandle,
+    settings: &JournalSettings,
+    entry: &JournalEntry,
+) -> VfsResult<()> {
+    let mut line = serde_json::to_string(entry).map_err(|e| VfsError::Io {
+        source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
+    })?;
+    line.push('\n');
+
+    let active = journal_rel(0);
+    if let Some(parent) = active.parent() {
+        handle.create_dir_all(&parent)?;
+    }
+    let size = handle.metadata(&active).map(|m| m.len()).unwrap_or(0);
+    if size > 0 && size + line.len() as u64 > settings.max_file_size {
+        rotate(handle, settings.keep_files as usize)?;
+    }
+
+    let path = handle.resolve_writable(&active)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(&path)?;
+    // Terminate a torn line left by a crash so this entry stays readable
+    if ends_torn(&mut file)? {
+        line.insert(0, '\n');
+    }
+    file.write_all(line.as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Whether `file` is non-empty and does not end with a newline.
+fn ends_torn(file: &mut std::fs::File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    let mut last = [0u8];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+/// Shift `journal.<n>.jsonl` to `n + 1`, dropping those past `keep`.
+fn rotate(handle: &LocationHandle, keep: usize) -> VfsResult<()> {
+    let mut last = 0;
+    while handle.exists(&journal_rel(last + 1)) {
+        last += 1;
+    }
+    for n in (keep..=last).rev() {
+        handle.remove_file(&journal_rel(n))?;
+    }
+    for n in (0..keep.min(last + 1)).rev() {
+        handle.rename(&journal_rel(n), &journal_rel(n + 1))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trash::RestoreConflict;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn journaled_location(tmp: &TempDir, configure: impl FnOnce(&mut JournalSettings)) -> Location {
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        let mut settings = LocationSettings::load(tmp.path()).unwrap();
+        settings.journal.enabled = true;
+        configure(&mut settings.journal);
+        settings.save(tmp.path()).unwrap();
+        loc.reload_journal_settings();
+        loc
+    }
+
+    fn state_dir(tmp: &TempDir) -> PathBuf {
+        tmp.path().join(FRACTA_DIR).join("state")
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let tmp = TempDir::new().unwrap();
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        loc.create_file(&tmp.path().join("a.md"), b"a").unwrap();
+
+        assert!(!state_dir(&tmp).join(JOURNAL_FILE).exists());
+        assert!(loc
+            .journal_entries(&JournalQuery::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_records_operations_with_hashes() {
+        let tmp = TempDir::new().unwrap();
+        let loc = journaled_location(&tmp, |_| {});
+        let note = tmp.path().join("note.md");
+        fs::create_dir(tmp.path().join("archive")).unwrap();
+
+        loc.create_file(&note, b"one").unwrap();
+        loc.write_file(&note, b"two").unwrap();
+        loc.rename(&note, &tmp.path().join("renamed.md")).unwrap();
+        let moved = loc
+            .move_entry(&tmp.path().join("renamed.md"), &tmp.path().join("archive"))
+            .unwrap();
+        let item = loc.move_to_trash(&moved).unwrap();
+        loc.restore_from_trash(item.id, RestoreConflict::Fail)
+            .unwrap();
+        loc.delete_permanently(&moved).unwrap();
+
+        let entries = loc.journal_entries(&JournalQuery::default()).unwrap();
+        let ops: Vec<_> = entries.iter().map(|e| e.op).collect();
+        assert_eq!(
+            ops,
+            vec![
+                JournalOp::Create,
+                JournalOp::Write,
+                JournalOp::Rename,
+                JournalOp::Move,
+                JournalOp::Trash,
+                JournalOp::Restore,
+                JournalOp::Delete,
+            ]
+        );
+
+        let one = hash_content(b"one");
+        let two = hash_content(b"two");
+        assert_eq!(
+            (&entries[0].before, &entries[0].after),
+            (&None, &Some(one.clone()))
+        );
+        assert_eq!(
+            (&entries[1].before, &entries[1].after),
+            (&Some(one), &Some(two.clone()))
+        );
+        assert_eq!(entries[2].path, note);
+        assert_eq!(entries[2].to, Some(tmp.path().join("renamed.md")));
+        assert_eq!(entries[3].to, Some(moved.clone()));
+        assert_eq!(entries[4].before, Some(two.clone()));
+        assert_eq!(entries[5].path, moved);
+        assert_eq!(entries[6].before, Some(two));
+        assert!(entries.iter().all(|e| e.actor == Actor::User));
+    }
+
+    #[test]
+    fn test_query_by_actor_path_and_time() {
+        let tmp = TempDir::new().unwrap();
+        let loc = journaled_location(&tmp, |_| {});
+        let mut ai = loc.clone();
+        ai.set_actor(Actor::Ai);
+
+        loc.create_folder(&tmp.path().join("docs")).unwrap();
+        ai.create_file(&tmp.path().join("docs/summary.md"), b"ai")
+            .unwrap();
+        loc.create_file(&tmp.path().join("other.md"), b"me")
+            .unwrap();
+        assert_eq!(loc.actor(), Actor::User);
+
+        let by_ai = loc
+            .journal_entries(&JournalQuery {
+                actor: Some(Actor::Ai),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(by_ai.len(), 1);
+        assert_eq!(by_ai[0].path, tmp.path().join("docs/summary.md"));
+
+        let in_docs = loc
+            .journal_entries(&JournalQuery {
+                path: Some(tmp.path().join("docs")),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(in_docs.len(), 2);
+        assert_eq!(in_docs[0].op, JournalOp::CreateFolder);
+
+        let all = loc.journal_entries(&JournalQuery::default()).unwrap();
+        let since_second = loc
+            .journal_entries(&JournalQuery {
+                since: Some(all[1].at),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(since_second.len() >= 2);
+        assert!(since_second.iter().all(|e| e.at >= all[1].at));
+        let before_first = loc
+            .journal_entries(&JournalQuery {
+                until: Some(all[0].at),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(before_first.is_empty());
+    }
+
+    #[test]
+    fn test_rotation_keeps_newest_files() {
+        let tmp = TempDir::new().unwrap();
+        let loc = journaled_location(&tmp, |journal| {
+            journal.max_file_size = 1;
+            journal.keep_files = 2;
+        });
+
+        for i in 0..5 {
+            loc.create_file(&tmp.path().join(format!("{i}.md")), b"x")
+                .unwrap();
+        }
+
+        // One entry per file: the active one plus two rotated
+        let state = state_dir(&tmp);
+        assert!(state.join("journal.jsonl").exists());
+        assert!(state.join("journal.1.jsonl").exists());
+        assert!(state.join("journal.2.jsonl").exists());
+        assert!(!state.join("journal.3.jsonl").exists());
+
+        let paths: Vec<_> = loc
+            .journal_entries(&JournalQuery::default())
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                tmp.path().join("2.md"),
+                tmp.path().join("3.md"),
+                tmp.path().join("4.md"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_torn_line_skipped() {
+        let tmp = TempDir::new().unwrap();
+        let loc = journaled_location(&tmp, |_| {});
+        loc.create_file(&tmp.path().join("a.md"), b"a").unwrap();
+
+        let mut log = fs::OpenOptions::new()
+            .append(true)
+            .open(state_dir(&tmp).join(JOURNAL_FILE))
+            .unwrap();
+        log.write_all(b"{\"at\":\"2024").unwrap();
+        drop(log);
+
+        // The next append starts on a fresh line
+        loc.create_file(&tmp.path().join("b.md"), b"b").unwrap();
+        let entries = loc.journal_entries(&JournalQuery::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].path, tmp.path().join("b.md"));
+    }
+}
@@ -0,0 +1,298 @@
+//! Versioned `.fracta/` layout and migrations (ENGINEERING §2.1).
+//!
+//! The layout version is stored as `layout_version` in
+//! `.fracta/config/settings.json`. Trees created before it was recorded are
+//! version 1. Opening a Location upgrades an older tree one step at a time:
+//!
+//! 1. `config/` is copied to `.fracta/backups/config-v<N>-<timestamp>/`
+//!    (staged under a temp name and renamed, so a backup is never partial).
+//!    Symlinks are neither copied nor followed.
+//! 2. Each step is idempotent and the new version is written to settings
+//!    atomically only after the step finished, so an interrupted migration
+//!    re-runs the unfinished step on the next open.
+//!
+//! A tree written by a newer engine is refused with
+//! `VfsError::UnsupportedLayout` rather than being touched.
+
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+
+use crate::error::{VfsError, VfsResult};
+use crate::handle::{LocationHandle, RelPath};
+use crate::location::FRACTA_DIR;
+use crate::settings::LocationSettings;
+use crate::symlink::SymlinkPolicy;
+
+/// The layout version this engine writes.
+pub const LAYOUT_VERSION: u32 = 2;
+
+/// Version of trees that predate `layout_version`.
+const UNVERSIONED: u32 = 1;
+
+/// Folder under `.fracta/` holding pre-migration config backups.
+pub const BACKUPS_DIR: &str = "backups";
+
+/// One upgrade step, from `to - 1` to `to`.
+struct Migration {
+    to: u32,
+    apply: fn(&LocationHandle) -> VfsResult<()>,
+}
+
+/// Every step, in order. `MIGRATIONS.last().to` must equal `LAYOUT_VERSION`.
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    apply: migrate_to_v2,
+}];
+
+/// What [`migrate_fracta_dir`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Version found on disk.
+    pub from: u32,
+    /// Version after migrating (`from` if nothing was to do).
+    pub to: u32,
+    /// Where `config/` was backed up, if any step ran.
+    pub backup: Option<PathBuf>,
+}
+
+impl MigrationReport {
+    /// Whether any step ran.
+    pub fn migrated(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// The layout version of the `.fracta/` tree at `root`.
+pub fn layout_version(root: &Path) -> VfsResult<u32> {
+    let settings = LocationSettings::load(root)?;
+    Ok(settings.layout_version.unwrap_or(UNVERSIONED))
+}
+
+/// Fail with `UnsupportedLayout` if `root` was written by a newer engine.
+pub fn check_layout(root: &Path) -> VfsResult<u32> {
+    let found = layout_version(root)?;
+    if found > LAYOUT_VERSION {
+        return Err(VfsError::UnsupportedLayout {
+            path: root.join(FRACTA_DIR),
+            found,
+            supported: LAYOUT_VERSION,
+        });
+    }
+    Ok(found)
+}
+
+/// Upgrade the `.fracta/` tree at `root` to [`LAYOUT_VERSION`].
+///
+/// Works through a [`LocationHandle`] that follows no symlinks: one inside
+/// `.fracta/` is never copied, removed through or written through.
+pub fn migrate_fracta_dir(root: &Path) -> VfsResult<MigrationReport> {
+    let from = check_layout(root)?;
+    let mut report = MigrationReport {
+        from,
+        to: from,
+        backup: None,
+    };
+    if from == LAYOUT_VERSION {
+        return Ok(report);
+    }
+
+    let handle = LocationHandle::open(root)?.with_symlink_policy(SymlinkPolicy::Ignore);
+    report.backup = Some(backup_config(&handle, from)?);
+    for step in MIGRATIONS.iter().filter(|m| m.to > from) {
+        (step.apply)(&handle)?;
+        let mut settings = LocationSettings::load(root)?;
+        settings.layout_version = Some(step.to);
+        settings.save(root)?;
+        report.to = step.to;
+    }
+    Ok(report)
+}
+
+/// `name` under `.fracta/`.
+fn fracta_rel(name: &str) -> VfsResult<RelPath> {
+    RelPath::new(Path::new(FRACTA_DIR).join(name))
+}
+
+/// Copy `config/` into a fresh backup folder and return its path.
+fn backup_config(handle: &LocationHandle, version: u32) -> VfsResult<PathBuf> {
+    let backups = fracta_rel(BACKUPS_DIR)?;
+    handle.create_dir_all(&backups)?;
+
+    let stamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    let name = format!("config-v{version}-{stamp}");
+    let staging = backups.join(format!(".{name}.tmp"))?;
+    let backup = backups.join(&name)?;
+
+    if handle.symlink_metadata(&staging).is_ok() {
+        handle.remove_dir_all(&staging)?;
+    }
+    copy_tree(handle, &fracta_rel("config")?, &staging)?;
+    handle.rename(&staging, &backup)?;
+    Ok(handle.absolute(&backup))
+}
+
+/// Recursively copy a folder of regular files, skipping symlinks.
+fn copy_tree(handle: &LocationHandle, from: &RelPath, to: &RelPath) -> VfsResult<()> {
+    handle.create_dir_all(to)?;
+    let is_dir = handle.symlink_metadata(from).is_ok_and(|m| m.is_dir());
+    if !is_dir {
+        return Ok(());
+    }
+    for entry in handle.read_dir(from)? {
+        let (child, entry) = entry?;
+        let dest = to.join(entry.file_name())?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_tree(handle, &child, &dest)?;
+        } else if file_type.is_file() {
+            handle.copy_file(&child, handle, &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// v2: `config/profiles/` for installed Profiles, and the search index
+/// lives in `cache/search/` (drop the never-populated legacy `cache/fts/`;
+/// cache is rebuildable).
+fn migrate_to_v2(handle: &LocationHandle) -> VfsResult<()> {
+    handle.create_dir_all(&fracta_rel("config/profiles")?)?;
+    // Left alone if it (or `cache/`) is a symlink
+    let legacy_fts = fracta_rel("cache/fts")?;
+    if handle
+        .symlink_metadata(&legacy_fts)
+        .is_ok_and(|m| m.is_dir())
+    {
+        handle.remove_dir_all(&legacy_fts)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use crate::init::init_fracta_dir;
+    use crate::location::Location;
+    use tempfile::TempDir;
+
+    /// A `.fracta/` tree as the first engine release left it.
+    fn v1_tree(root: &Path) {
+        let fracta = root.join(FRACTA_DIR);
+        for dir in [
+            "config/schemas",
+            "config/views",
+            "meta",
+            "cache/fts",
+            "state",
+        ] {
+            fs::create_dir_all(fracta.join(dir)).unwrap();
+        }
+        fs::write(fracta.join("config/ignore"), "custom/\n").unwrap();
+        fs::write(fracta.join("config/settings.json"), "{\"label\":\"Old\"}").unwrap();
+    }
+
+    #[test]
+    fn test_migrations_reach_current_version() {
+        assert_eq!(MIGRATIONS.last().unwrap().to, LAYOUT_VERSION);
+        assert!(MIGRATIONS.windows(2).all(|w| w[1].to == w[0].to + 1));
+    }
+
+    #[test]
+    fn test_new_tree_is_current() {
+        let tmp = TempDir::new().unwrap();
+        init_fracta_dir(tmp.path()).unwrap();
+        assert_eq!(layout_version(tmp.path()).unwrap(), LAYOUT_VERSION);
+
+        let report = migrate_fracta_dir(tmp.path()).unwrap();
+        assert!(!report.migrated());
+        assert!(!tmp.path().join(".fracta/backups").exists());
+    }
+
+    #[test]
+    fn test_migrate_v1() {
+        let tmp = TempDir::new().unwrap();
+        v1_tree(tmp.path());
+
+        let report = migrate_fracta_dir(tmp.path()).unwrap();
+        assert_eq!((report.from, report.to), (1, LAYOUT_VERSION));
+        assert_eq!(layout_version(tmp.path()).unwrap(), LAYOUT_VERSION);
+
+        let fracta = tmp.path().join(FRACTA_DIR);
+        assert!(fracta.join("config/profiles").is_dir());
+        assert!(!fracta.join("cache/fts").exists());
+
+        // Config backed up as it was, settings kept
+        let backup = report.backup.unwrap();
+        assert!(backup.starts_with(fracta.join(BACKUPS_DIR)));
+        assert_eq!(
+            fs::read_to_string(backup.join("settings.json")).unwrap(),
+            "{\"label\":\"Old\"}"
+        );
+        assert_eq!(
+            fs::read_to_string(backup.join("ignore")).unwrap(),
+            "custom/\n"
+        );
+        let settings = LocationSettings::load(tmp.path()).unwrap();
+        assert_eq!(settings.label.as_deref(), Some("Old"));
+
+        // Second run is a no-op
+        assert!(!migrate_fracta_dir(tmp.path()).unwrap().migrated());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_migrate_does_not_follow_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("root");
+        let outside = tmp.path().join("outside");
+        fs::create_dir_all(&root).unwrap();
+        v1_tree(&root);
+        fs::create_dir_all(outside.join("fts")).unwrap();
+        fs::write(outside.join("secret"), "secret").unwrap();
+
+        let fracta = root.join(FRACTA_DIR);
+        fs::remove_dir_all(fracta.join("cache")).unwrap();
+        symlink(&outside, fracta.join("cache")).unwrap();
+        symlink(outside.join("secret"), fracta.join("config/linked")).unwrap();
+
+        let report = migrate_fracta_dir(&root).unwrap();
+        assert_eq!(report.to, LAYOUT_VERSION);
+        // Nothing removed through the symlinked cache/, nothing copied
+        // through the symlink in config/
+        assert!(outside.join("fts").is_dir());
+        let backup = report.backup.unwrap();
+        assert!(backup.join("ignore").is_file());
+        assert!(fs::symlink_metadata(backup.join("linked")).is_err());
+    }
+
+    #[test]
+    fn test_open_migrates_and_refuses_newer() {
+        let tmp = TempDir::new().unwrap();
+        v1_tree(tmp.path());
+        Location::open("old", tmp.path()).unwrap();
+        assert_eq!(layout_version(tmp.path()).unwrap(), LAYOUT_VERSION);
+
+        let mut settings = LocationSettings::load(tmp.path()).unwrap();
+        settings.layout_version = Some(LAYOUT_VERSION + 1);
+        settings.save(tmp.path()).unwrap();
+
+        match Location::open("new", tmp.path()) {
+            Err(VfsError::UnsupportedLayout {
+                found, supported, ..
+            }) => {
+                assert_eq!(found, LAYOUT_VERSION + 1);
+                assert_eq!(supported, LAYOUT_VERSION);
+            }
+            other => panic!(
+                "expected UnsupportedLayout, got {:?}",
+                other.map(|l| l.root)
+            ),
+        }
+        // Left untouched
+        assert_eq!(layout_version(tmp.path()).unwrap(), LAYOUT_VERSION + 1);
+    }
+}
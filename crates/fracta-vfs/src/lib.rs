@@ -10,10 +10,28 @@
 //! ## Architecture
 //!
 //! - `Location`: a user-granted directory tree (local folder, cloud-sync folder, etc.)
-//! - `IgnoreRules`: gitignore-style patterns that determine Managed vs Ignored scope
+//! - `LocationHandle` / `RelPath`: capability for a Location root; all operations
+//!   are performed relative to it with paths that cannot escape (ENGINEERING §5.3)
+//! - `VfsBackend`: storage trait (read, atomic write, list, stat, rename, remove,
+//!   watch), implemented by `LocationHandle` and the in-memory `MemoryBackend`
+//! - `LocationRegistry`: the persisted set of Locations that form the Global View;
+//!   a moved root is recognized by its persisted ID and re-bound with `relocate`
+//! - `IgnoreRules`: gitignore-style patterns (config, `.gitignore`, folder overrides)
+//!   that determine Managed vs Ignored scope
 //! - `Scope`: each path within a Location is Managed, Ignored, or Plain
-//! - `Entry`: metadata about a file or folder (name, size, timestamps, scope)
-//! - `Watcher`: observes filesystem changes and emits events
+//! - `Entry`: metadata about a file or folder (name, size, timestamps, scope, symlink)
+//! - `NameCollision`: sibling names equal up to case or Unicode normalization;
+//!   `normalize_path` gives the NFC form used for path identity
+//! - `CloudFile`: iCloud placeholders (Plain scope) and sync-conflict copies
+//! - `SymlinkPolicy`: per-Location choice of which symlinks are followed
+//! - `FileVersion`: stat or content-hash version for conflict-checked writes
+//! - UIDs: lazily assigned stable identities kept in `.fracta/meta/uids.jsonl`
+//! - Layout versions: `.fracta/` trees are upgraded by `migrate_fracta_dir`; newer
+//!   ones are refused
+//! - Journal: opt-in append-only log of every mutation (actor, paths, content
+//!   hashes) in `.fracta/state/`, rotated by size and queryable
+//! - `TrashItem`: a soft-deleted entry in `.fracta/trash/`, restorable or purgeable
+//! - `Watcher`: observes filesystem changes and emits scope-tagged events
 //! - `AtomicWriter`: ensures crash-safe writes (temp → fsync → rename)
 //!
 //! ## Design rules (from SPEC §4.1)
@@ -22,21 +40,60 @@
 //! - All writes use atomic patterns (temp file → fsync → rename).
 //! - No `.DS_Store`-style pollution: system data lives in `.fracta/` at Location root.
 
+pub mod backend;
+pub mod cloud;
+pub mod collision;
+pub mod copy;
 pub mod entry;
 pub mod error;
+pub mod handle;
 pub mod ignore;
 pub mod init;
+pub mod journal;
+pub mod layout;
 pub mod location;
+pub mod memory;
+pub mod registry;
+pub mod relocate;
 pub mod scope;
 pub mod settings;
+pub mod symlink;
+pub mod trash;
+pub mod uid;
+pub mod version;
+pub mod walk;
 pub mod watcher;
 pub mod writer;
 
+pub use backend::{BackendWatch, FileStat, VfsBackend};
+pub use cloud::{CloudFile, CloudFileKind, SyncProvider};
+pub use collision::{collision_key, normalize_path, CollisionKind, NameCollision};
+pub use copy::{CopyCollision, CopyOptions, CopyProgress};
 pub use entry::{Entry, EntryKind};
 pub use error::{VfsError, VfsResult};
-pub use ignore::IgnoreRules;
+pub use handle::{LocationHandle, RelPath, Resolved};
+pub use ignore::{IgnoreMatch, IgnoreRules, IgnoreSource};
 pub use init::init_fracta_dir;
+pub use journal::{Actor, JournalEntry, JournalOp, JournalQuery, JOURNAL_FILE};
+pub use layout::{
+    check_layout, layout_version, migrate_fracta_dir, MigrationReport, LAYOUT_VERSION,
+};
 pub use location::{Location, WalkOptions, FRACTA_DIR};
+pub use memory::{BackendOp, MemoryBackend};
+pub use registry::LocationRegistry;
+pub use relocate::location_id_at;
 pub use scope::Scope;
-pub use settings::LocationSettings;
-pub use watcher::{FsEvent, LocationWatcher};
+pub use settings::{
+    IndexingSettings, JournalSettings, LocationSettings, Privacy, TrashSettings, WatcherSettings,
+    DEFAULT_JOURNAL_KEEP_FILES, DEFAULT_JOURNAL_MAX_FILE_SIZE, DEFAULT_MAX_INDEX_FILE_SIZE,
+    DEFAULT_TRASH_RETENTION_DAYS, DEFAULT_WATCH_DEBOUNCE_MS,
+};
+pub use symlink::{Symlink, SymlinkKind, SymlinkPolicy};
+pub use trash::{RestoreConflict, TrashItem};
+pub use uid::UIDS_FILE;
+pub use version::FileVersion;
+pub use walk::{SkipReason, SkippedEntry, WalkCancel, WalkReport, WalkStream};
+pub use watcher::{
+    EventSubscription, FsEvent, LocationWatcher, ScopedEvent, WatchEvent, WatchOptions,
+    DEFAULT_SUBSCRIPTION_CAPACITY,
+};
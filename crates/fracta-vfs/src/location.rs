@@ -9,20 +9,30 @@
 //! - Determining Scope (Managed/Ignored/Plain) for any path
 //! - Providing CRUD operations scoped to Locations
 //! - Recursive directory traversal with scope filtering
+//!
+//! All filesystem access goes through a [`LocationHandle`] opened once for the
+//! root. Public methods still accept absolute paths for convenience; each one
+//! is converted to a [`RelPath`] at the boundary and rejected if it escapes.
 
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
 
-use chrono::DateTime;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::cloud;
 use crate::entry::{Entry, EntryKind};
 use crate::error::{VfsError, VfsResult};
-use crate::ignore::IgnoreRules;
+use crate::handle::{map_io_error, LocationHandle, RelPath};
+use crate::ignore::{IgnoreMatch, IgnoreRules};
 use crate::init::init_fracta_dir;
+use crate::journal::{Actor, JournalOp, JournalState};
+use crate::layout::migrate_fracta_dir;
 use crate::scope::Scope;
 use crate::settings::LocationSettings;
-use crate::writer::atomic_write;
+use crate::symlink::{SymlinkKind, SymlinkPolicy};
+use crate::uid::UidMap;
+use crate::walk::SkippedEntry;
 
 /// The `.fracta/` directory name within a managed Location.
 pub const FRACTA_DIR: &str = ".fracta";
@@ -46,6 +56,37 @@ pub struct Location {
     /// Skipped during serialization — reload after deserializing.
     #[serde(skip)]
     ignore_rules: IgnoreRules,
+
+    /// How symlinks are treated (persisted in settings for managed Locations).
+    #[serde(default, skip_serializing_if = "SymlinkPolicy::is_default")]
+    symlink_policy: SymlinkPolicy,
+
+    /// Capability handle for the root, opened on first use.
+    #[serde(skip)]
+    handle: OnceLock<LocationHandle>,
+
+    /// UID map from `.fracta/meta/uids.jsonl`, looked up on first use and
+    /// shared by every Location on this root so they never write diverging
+    /// logs.
+    #[serde(skip)]
+    pub(crate) uids: OnceLock<Arc<Mutex<UidMap>>>,
+
+    /// Who operations are attributed to in the journal. Not shared between
+    /// clones.
+    #[serde(skip)]
+    pub(crate) actor: Actor,
+
+    /// Journal settings, loaded on first use; the lock also serializes
+    /// appends from all clones.
+    #[serde(skip)]
+    pub(crate) journal: Arc<Mutex<JournalState>>,
+}
+
+/// A directory child read by [`Location::read_child`].
+pub(crate) struct Child {
+    pub(crate) entry: Entry,
+    /// Resolved target of a symlinked folder the policy says to descend into.
+    pub(crate) follow: Option<PathBuf>,
 }
 
 /// Options for recursive directory traversal.
@@ -70,39 +111,57 @@ impl Location {
             root: root.into(),
             managed: false,
             ignore_rules: IgnoreRules::empty(),
+            symlink_policy: SymlinkPolicy::default(),
+            handle: OnceLock::new(),
+            uids: Default::default(),
+            actor: Actor::default(),
+            journal: Default::default(),
         }
     }
 
     /// Open an existing managed Location, loading settings and ignore rules from disk.
     ///
     /// The Location ID is loaded from `.fracta/config/settings.json` if it exists,
-    /// ensuring the same ID persists across sessions. Falls back to default ignore
-    /// rules if `.fracta/config/ignore` is missing.
+    /// ensuring the same ID persists across sessions. Ignore rules are loaded
+    /// from every source the settings enable (see [`IgnoreRules::load_for_location`]);
+    /// opening fails if the root config cannot be read. An older `.fracta/`
+    /// layout is migrated first (see [`crate::layout`]), and trash items past
+    /// the `trash.retention_days` setting are purged.
     pub fn open(label: impl Into<String>, root: impl Into<PathBuf>) -> VfsResult<Self> {
         let root = root.into();
         if !root.is_dir() {
             return Err(VfsError::NotFound(root));
         }
-
-        let ignore_path = root.join(FRACTA_DIR).join("config").join("ignore");
-        let ignore_rules = IgnoreRules::load(&ignore_path).unwrap_or_default();
-
+        // Bring an older .fracta/ up to date; refuse one from a newer engine
+        if root.join(FRACTA_DIR).is_dir() {
+            migrate_fracta_dir(&root)?;
+        }
         // Load persistent ID from settings, or generate a new one
         let mut settings = LocationSettings::load(&root)?;
         let id = settings.get_or_create_id();
+        let handle = LocationHandle::open(&root)?.with_symlink_policy(settings.symlinks);
+
+        let ignore_rules = IgnoreRules::load_for_location(&handle, &settings)?;
 
         // If we generated a new ID, persist it
         if settings.id.is_some() {
             settings.save(&root)?;
         }
 
-        Ok(Self {
+        let location = Self {
             id,
             label: label.into(),
             root,
             managed: true,
             ignore_rules,
-        })
+            symlink_policy: settings.symlinks,
+            handle: OnceLock::from(handle),
+            uids: Default::default(),
+            actor: Actor::default(),
+            journal: Default::default(),
+        };
+        location.purge_trash_on_open(settings.trash.retention_days);
+        Ok(location)
     }
 
     /// Initialize this Location: create `.fracta/` structure and mark as managed.
@@ -122,11 +181,78 @@ impl Location {
         Ok(())
     }
 
-    /// Reload ignore rules from disk.
+    /// Reload ignore rules from disk after the config or settings changed.
+    ///
+    /// `.gitignore` files already read are kept; only folders whose ignored
+    /// state changed are searched again.
     pub fn reload_ignore_rules(&mut self) -> VfsResult<()> {
-        let ignore_path = self.root.join(FRACTA_DIR).join("config").join("ignore");
-        self.ignore_rules =
-            IgnoreRules::load(&ignore_path).map_err(|e| VfsError::Io { source: e })?;
+        let settings = LocationSettings::load(&self.root)?;
+        let rules = self.ignore_rules.reload_config(self.handle()?, &settings)?;
+        self.ignore_rules = rules;
+        Ok(())
+    }
+
+    /// Re-read one `.gitignore` (by absolute path) after it was created,
+    /// changed or removed.
+    pub fn reload_gitignore(&mut self, path: &Path) -> VfsResult<()> {
+        let handle = self.handle()?.clone();
+        let rel = handle.relativize(path)?;
+        if let Some(dir) = rel.parent() {
+            self.ignore_rules.reload_gitignore(&handle, &dir);
+        }
+        Ok(())
+    }
+
+    /// `.gitignore` files that could not be read and contribute no rules.
+    pub fn skipped_ignore_files(&self) -> &[SkippedEntry] {
+        self.ignore_rules.skipped()
+    }
+
+    /// The capability handle for this Location's root.
+    ///
+    /// Opened on first use and reused for every subsequent operation.
+    pub fn handle(&self) -> VfsResult<&LocationHandle> {
+        if let Some(handle) = self.handle.get() {
+            return Ok(handle);
+        }
+        let handle = LocationHandle::open(&self.root)?.with_symlink_policy(self.symlink_policy);
+        Ok(self.handle.get_or_init(|| handle))
+    }
+
+    /// How this Location treats symlinks.
+    pub fn symlink_policy(&self) -> SymlinkPolicy {
+        self.symlink_policy
+    }
+
+    /// Change the symlink policy, persisting it for a managed Location.
+    pub fn set_symlink_policy(&mut self, policy: SymlinkPolicy) -> VfsResult<()> {
+        if self.managed {
+            let mut settings = LocationSettings::load(&self.root)?;
+            settings.symlinks = policy;
+            settings.save(&self.root)?;
+        }
+        self.symlink_policy = policy;
+        // Reopen the handle with the new policy on next use
+        self.handle = OnceLock::new();
+        Ok(())
+    }
+
+    /// Validate and persist new settings, then apply them.
+    ///
+    /// Fails with `NotManaged` for an unmanaged Location. The ignore rules
+    /// are rebuilt before the settings are saved, so a failure changes
+    /// nothing.
+    pub fn update_settings(&mut self, settings: &LocationSettings) -> VfsResult<()> {
+        if !self.managed {
+            return Err(VfsError::NotManaged(self.root.clone()));
+        }
+        let handle = LocationHandle::open(&self.root)?.with_symlink_policy(settings.symlinks);
+        let rules = self.ignore_rules.reload_config(&handle, settings)?;
+        settings.save(&self.root)?;
+        self.ignore_rules = rules;
+        self.symlink_policy = settings.symlinks;
+        self.handle = OnceLock::from(handle);
+        self.reload_journal_settings();
         Ok(())
     }
 }
@@ -141,100 +267,107 @@ impl Location {
 
     /// Whether a given path is inside this Location.
     ///
-    /// This method resolves symlinks to prevent path traversal attacks.
-    /// For non-existent paths, it resolves the existing parent and checks
-    /// if the remaining components would stay within the Location.
+    /// Rejects `..` traversal lexically and any symlink whose target resolves
+    /// outside the root. Non-existent paths are accepted if they would be
+    /// created inside the Location.
     pub fn contains(&self, path: &Path) -> bool {
-        self.resolve_and_check(path).is_some()
+        self.relativize(path).is_ok()
     }
 
-    /// Resolve a path and verify it stays within this Location.
+    /// Convert an absolute path into a `RelPath` for this Location.
     ///
-    /// Returns the canonicalized path if it's inside the Location, None otherwise.
-    /// This prevents symlink escape attacks like `managed_dir/link_to_parent/../../../etc`.
-    fn resolve_and_check(&self, path: &Path) -> Option<PathBuf> {
-        // Canonicalize the Location root (resolve symlinks in root itself)
-        let canonical_root = self.root.canonicalize().ok()?;
-
-        // Try to canonicalize the full path first (works for existing paths)
-        if let Ok(canonical_path) = path.canonicalize() {
-            if canonical_path.starts_with(&canonical_root) {
-                return Some(canonical_path);
-            }
-            return None;
-        }
-
-        // Path doesn't exist yet - resolve the existing parent portion
-        // and verify the full constructed path stays within bounds
-        let mut existing = path.to_path_buf();
-        let mut pending_components = Vec::new();
-
-        // Walk up until we find an existing ancestor
-        while !existing.exists() {
-            if let Some(file_name) = existing.file_name() {
-                pending_components.push(file_name.to_os_string());
-                existing = match existing.parent() {
-                    Some(p) => p.to_path_buf(),
-                    None => return None, // No existing ancestor found
-                };
-            } else {
-                return None;
-            }
-        }
-
-        // Canonicalize the existing ancestor
-        let mut resolved = existing.canonicalize().ok()?;
-
-        // Append the pending components back (in reverse order)
-        for component in pending_components.into_iter().rev() {
-            // Block ".." in pending components to prevent escape
-            if component == ".." {
-                return None;
-            }
-            resolved.push(component);
-        }
-
-        // Final check: is the resolved path inside our root?
-        if resolved.starts_with(&canonical_root) {
-            Some(resolved)
-        } else {
-            None
-        }
-    }
-
-    /// Get the relative path from Location root.
-    fn relative_path(&self, path: &Path) -> Option<PathBuf> {
-        path.strip_prefix(&self.root).ok().map(PathBuf::from)
+    /// Fails with `OutsideLocation` if the path is not under the root, uses
+    /// `..` to climb out, or passes through a symlink that escapes.
+    pub fn relativize(&self, path: &Path) -> VfsResult<RelPath> {
+        let handle = self
+            .handle()
+            .map_err(|_| VfsError::OutsideLocation(path.to_path_buf()))?;
+        let rel = handle.relativize(path)?;
+        handle.resolve(&rel)?;
+        Ok(rel)
     }
 
     /// Determine the scope of a path within this Location.
     ///
     /// Returns `None` if the path is not inside this Location.
     pub fn scope_of(&self, path: &Path) -> Option<Scope> {
-        if !self.contains(path) {
-            return None;
+        let rel = self.relativize(path).ok()?;
+        let is_dir = self
+            .handle()
+            .and_then(|h| h.metadata(&rel))
+            .map(|m| m.is_dir())
+            .unwrap_or(false);
+        Some(self.scope_of_rel(&rel, is_dir))
+    }
+
+    /// Explain which ignore rule decides the scope of `path`, if any.
+    ///
+    /// Returns `None` for unmanaged Locations and for paths no rule matches.
+    pub fn explain_ignored(&self, path: &Path) -> VfsResult<Option<IgnoreMatch>> {
+        let rel = self.relativize(path)?;
+        if !self.managed || rel.is_root() || rel.starts_with(FRACTA_DIR) {
+            return Ok(None);
         }
+        let is_dir = self
+            .handle()?
+            .metadata(&rel)
+            .map(|m| m.is_dir())
+            .unwrap_or(false);
+        Ok(self.ignore_rules.explain(rel.as_path(), is_dir))
+    }
 
+    /// Determine the scope of a relative path without touching the disk.
+    pub fn scope_of_rel(&self, rel: &RelPath, is_dir: bool) -> Scope {
         if !self.managed {
-            return Some(Scope::Plain);
+            return Scope::Plain;
         }
 
-        let rel_path = match self.relative_path(path) {
-            Some(p) if p.as_os_str().is_empty() => return Some(Scope::Managed),
-            Some(p) => p,
-            None => return Some(Scope::Managed),
-        };
-
-        // .fracta/ itself is always Managed (internal system directory)
-        if rel_path.starts_with(FRACTA_DIR) {
-            return Some(Scope::Managed);
+        // The root and .fracta/ itself are always Managed
+        if rel.is_root() || rel.starts_with(FRACTA_DIR) {
+            return Scope::Managed;
         }
 
-        let is_dir = path.is_dir();
-        if self.ignore_rules.is_ignored(&rel_path, is_dir) {
-            Some(Scope::Ignored)
+        if self.ignore_rules.is_ignored(rel.as_path(), is_dir) {
+            Scope::Ignored
         } else {
-            Some(Scope::Managed)
+            Self::managed_or_placeholder(rel, is_dir)
+        }
+    }
+}
+
+impl Location {
+    /// Scope of `rel`, given the already-known scope of its parent folder.
+    ///
+    /// Avoids re-matching the ancestors: below an Ignored folder everything
+    /// is Ignored, below a Managed one only the entry's own name is checked.
+    pub(crate) fn scope_in_parent(
+        &self,
+        rel: &RelPath,
+        is_dir: bool,
+        parent_scope: Scope,
+    ) -> Scope {
+        match parent_scope {
+            Scope::Plain | Scope::Ignored => parent_scope,
+            Scope::Managed if rel.starts_with(FRACTA_DIR) => Scope::Managed,
+            Scope::Managed => {
+                if self
+                    .ignore_rules
+                    .is_ignored_in_parent(rel.as_path(), is_dir)
+                {
+                    Scope::Ignored
+                } else {
+                    Self::managed_or_placeholder(rel, is_dir)
+                }
+            }
+        }
+    }
+
+    /// Managed, except for cloud placeholders: those are Plain so that
+    /// nothing indexes them or forces a download.
+    fn managed_or_placeholder(rel: &RelPath, is_dir: bool) -> Scope {
+        match rel.file_name() {
+            Some(name) if !is_dir && cloud::is_placeholder(name) => Scope::Plain,
+            _ => Scope::Managed,
         }
     }
 }
@@ -244,28 +377,21 @@ impl Location {
 impl Location {
     /// List the immediate children of a directory within this Location.
     pub fn list_directory(&self, dir: &Path) -> VfsResult<Vec<Entry>> {
-        if !self.contains(dir) {
-            return Err(VfsError::OutsideLocation(dir.to_path_buf()));
-        }
+        let rel = self.relativize(dir)?;
+        let handle = self.handle()?;
 
         let mut entries = Vec::new();
-
-        let read_dir = std::fs::read_dir(dir).map_err(|e| match e.kind() {
-            std::io::ErrorKind::NotFound => VfsError::NotFound(dir.to_path_buf()),
-            std::io::ErrorKind::PermissionDenied => VfsError::PermissionDenied(dir.to_path_buf()),
-            _ => VfsError::Io { source: e },
-        })?;
-
-        for dir_entry in read_dir {
-            let dir_entry = dir_entry?;
-            let name = dir_entry.file_name().to_string_lossy().into_owned();
+        for child in handle.read_dir(&rel)? {
+            let (child_rel, dir_entry) = child?;
 
             // Skip .fracta directory in listings (internal system dir)
-            if name == FRACTA_DIR {
+            if dir_entry.file_name() == FRACTA_DIR {
                 continue;
             }
 
-            entries.push(self.build_entry(&dir_entry.path(), &dir_entry.metadata()?));
+            if let Some(child) = self.read_child(handle, &child_rel, &dir_entry, None)? {
+                entries.push(child.entry);
+            }
         }
 
         // Sort: folders first, then alphabetical (case-insensitive)
@@ -280,81 +406,12 @@ impl Location {
 
     /// Recursively walk the directory tree starting from `dir`.
     ///
-    /// Returns a flat list of all entries. Use `WalkOptions` to control
-    /// whether ignored entries are included and maximum depth.
+    /// Returns a flat list of all entries, parents before children. Use
+    /// `WalkOptions` to control whether ignored entries are included and
+    /// maximum depth. For large trees prefer [`Location::walk_stream`],
+    /// which yields entries as they are found and reports skipped ones.
     pub fn walk(&self, dir: &Path, options: &WalkOptions) -> VfsResult<Vec<Entry>> {
-        if !self.contains(dir) {
-            return Err(VfsError::OutsideLocation(dir.to_path_buf()));
-        }
-
-        let mut results = Vec::new();
-        self.walk_recursive(dir, options, 0, &mut results)?;
-        Ok(results)
-    }
-
-    fn walk_recursive(
-        &self,
-        dir: &Path,
-        options: &WalkOptions,
-        depth: usize,
-        results: &mut Vec<Entry>,
-    ) -> VfsResult<()> {
-        if let Some(max) = options.max_depth {
-            if depth >= max {
-                return Ok(());
-            }
-        }
-
-        // Gracefully handle permission denied - skip inaccessible directories
-        let read_dir = match std::fs::read_dir(dir) {
-            Ok(rd) => rd,
-            Err(e) => match e.kind() {
-                std::io::ErrorKind::PermissionDenied => {
-                    // Skip directories we can't access (e.g., ~/Library/Application Support/MobileSync)
-                    return Ok(());
-                }
-                std::io::ErrorKind::NotFound => return Err(VfsError::NotFound(dir.to_path_buf())),
-                _ => return Err(VfsError::Io { source: e }),
-            },
-        };
-
-        for dir_entry in read_dir {
-            // Skip entries we can't read
-            let dir_entry = match dir_entry {
-                Ok(de) => de,
-                Err(_) => continue,
-            };
-            let path = dir_entry.path();
-            let name = dir_entry.file_name().to_string_lossy().into_owned();
-
-            // Always skip .fracta directory
-            if name == FRACTA_DIR {
-                continue;
-            }
-
-            // Skip entries where we can't get metadata
-            let metadata = match dir_entry.metadata() {
-                Ok(m) => m,
-                Err(_) => continue,
-            };
-
-            let entry = self.build_entry(&path, &metadata);
-
-            // Skip ignored entries unless explicitly requested
-            if entry.scope == Scope::Ignored && !options.include_ignored {
-                continue;
-            }
-
-            let should_recurse = entry.kind == EntryKind::Folder;
-            results.push(entry);
-
-            if should_recurse {
-                // Continue walking even if a subdirectory fails
-                let _ = self.walk_recursive(&path, options, depth + 1, results);
-            }
-        }
-
-        Ok(())
+        Ok(self.walk_stream(dir, options)?.collect())
     }
 }
 
@@ -363,49 +420,37 @@ impl Location {
 impl Location {
     /// Create a new file with the given content (atomic write).
     pub fn create_file(&self, path: &Path, content: &[u8]) -> VfsResult<()> {
-        self.check_writable(path)?;
-        if path.exists() {
-            return Err(VfsError::AlreadyExists(path.to_path_buf()));
-        }
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                return Err(VfsError::NotFound(parent.to_path_buf()));
-            }
-        }
-        atomic_write(path, content)
+        let rel = self.writable_rel(path)?;
+        self.check_collision(&rel, None)?;
+        self.handle()?.create_file(&rel, content)?;
+        let after = self.journal_content_hash(content);
+        self.record_op(JournalOp::Create, &rel, None, None, after);
+        Ok(())
     }
 
     /// Create a new directory.
     pub fn create_folder(&self, path: &Path) -> VfsResult<()> {
-        self.check_writable(path)?;
-        if path.exists() {
-            return Err(VfsError::AlreadyExists(path.to_path_buf()));
-        }
-        std::fs::create_dir(path).map_err(|e| match e.kind() {
-            std::io::ErrorKind::PermissionDenied => VfsError::PermissionDenied(path.to_path_buf()),
-            _ => VfsError::Io { source: e },
-        })
+        let rel = self.writable_rel(path)?;
+        self.check_collision(&rel, None)?;
+        self.handle()?.create_dir(&rel)?;
+        self.record_op(JournalOp::CreateFolder, &rel, None, None, None);
+        Ok(())
     }
 
     /// Write content to an existing file (atomic overwrite).
     pub fn write_file(&self, path: &Path, content: &[u8]) -> VfsResult<()> {
-        self.check_writable(path)?;
-        if !path.exists() {
-            return Err(VfsError::NotFound(path.to_path_buf()));
-        }
-        atomic_write(path, content)
+        let rel = self.writable_rel(path)?;
+        let before = self.journal_hash(&rel);
+        self.handle()?.write_file(&rel, content)?;
+        let after = self.journal_content_hash(content);
+        self.record_op(JournalOp::Write, &rel, None, before, after);
+        Ok(())
     }
 
     /// Read a file's contents as bytes.
     pub fn read_file(&self, path: &Path) -> VfsResult<Vec<u8>> {
-        if !self.contains(path) {
-            return Err(VfsError::OutsideLocation(path.to_path_buf()));
-        }
-        std::fs::read(path).map_err(|e| match e.kind() {
-            std::io::ErrorKind::NotFound => VfsError::NotFound(path.to_path_buf()),
-            std::io::ErrorKind::PermissionDenied => VfsError::PermissionDenied(path.to_path_buf()),
-            _ => VfsError::Io { source: e },
-        })
+        let rel = self.relativize(path)?;
+        self.handle()?.read(&rel)
     }
 
     /// Read a file's contents as a UTF-8 string.
@@ -418,116 +463,227 @@ impl Location {
 
     /// Rename a file or folder (stays in the same parent directory).
     pub fn rename(&self, from: &Path, to: &Path) -> VfsResult<()> {
-        self.check_writable(from)?;
-        self.check_writable(to)?;
-        if !from.exists() {
-            return Err(VfsError::NotFound(from.to_path_buf()));
-        }
-        if to.exists() {
-            return Err(VfsError::AlreadyExists(to.to_path_buf()));
-        }
-        std::fs::rename(from, to).map_err(|e| VfsError::Io { source: e })
+        let from_rel = self.writable_rel(from)?;
+        let to_rel = self.writable_rel(to)?;
+        self.check_collision(&to_rel, Some(&from_rel))?;
+        self.handle()?.rename(&from_rel, &to_rel)?;
+        self.record_uid_move(&from_rel, &to_rel);
+        let hash = self.journal_hash(&to_rel);
+        self.record_op(
+            JournalOp::Rename,
+            &from_rel,
+            Some(&to_rel),
+            hash.clone(),
+            hash,
+        );
+        Ok(())
     }
 
     /// Move a file or folder to a different directory. Returns the new path.
     pub fn move_entry(&self, from: &Path, to_dir: &Path) -> VfsResult<PathBuf> {
-        self.check_writable(from)?;
-        if !self.contains(to_dir) {
-            return Err(VfsError::OutsideLocation(to_dir.to_path_buf()));
-        }
-        if !from.exists() {
+        let from_rel = self.writable_rel(from)?;
+        let dir_rel = self.relativize(to_dir)?;
+        let handle = self.handle()?;
+
+        if !handle.exists(&from_rel) {
             return Err(VfsError::NotFound(from.to_path_buf()));
         }
-        if !to_dir.is_dir() {
+        if !handle
+            .metadata(&dir_rel)
+            .map(|m| m.is_dir())
+            .unwrap_or(false)
+        {
             return Err(VfsError::NotFound(to_dir.to_path_buf()));
         }
 
-        let file_name = from.file_name().ok_or_else(|| VfsError::Io {
+        let file_name = from_rel.file_name().ok_or_else(|| VfsError::Io {
             source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name"),
         })?;
-        let dest = to_dir.join(file_name);
-
-        if dest.exists() {
-            return Err(VfsError::AlreadyExists(dest));
-        }
-
-        self.check_writable(&dest)?;
-        std::fs::rename(from, &dest).map_err(|e| VfsError::Io { source: e })?;
+        let dest_rel = dir_rel.join(file_name)?;
+        let dest = handle.absolute(&dest_rel);
+        self.check_writable(&dest_rel, &dest)?;
+        self.check_collision(&dest_rel, Some(&from_rel))?;
+
+        handle.rename(&from_rel, &dest_rel)?;
+        self.record_uid_move(&from_rel, &dest_rel);
+        let hash = self.journal_hash(&dest_rel);
+        self.record_op(
+            JournalOp::Move,
+            &from_rel,
+            Some(&dest_rel),
+            hash.clone(),
+            hash,
+        );
         Ok(dest)
     }
 
     /// Delete a file.
+    ///
+    /// In a managed Location the file is moved to the trash (see
+    /// [`Location::move_to_trash`]) so it can be restored. Use
+    /// [`Location::delete_permanently`] to bypass the trash. Fails, leaving
+    /// it in place, if `path` is a folder.
     pub fn delete_file(&self, path: &Path) -> VfsResult<()> {
-        self.check_writable(path)?;
-        if !path.exists() {
-            return Err(VfsError::NotFound(path.to_path_buf()));
+        if self.uses_trash() {
+            let rel = self.writable_rel(path)?;
+            if self.handle()?.symlink_metadata(&rel)?.is_dir() {
+                return Err(map_io_error(path, std::io::ErrorKind::IsADirectory.into()));
+            }
+            return self.move_to_trash(path).map(|_| ());
         }
-        std::fs::remove_file(path).map_err(|e| match e.kind() {
-            std::io::ErrorKind::PermissionDenied => VfsError::PermissionDenied(path.to_path_buf()),
-            _ => VfsError::Io { source: e },
-        })
+        let rel = self.writable_rel(path)?;
+        let before = self.journal_hash(&rel);
+        self.handle()?.remove_file(&rel)?;
+        self.record_uid_removal(&rel);
+        self.record_op(JournalOp::Delete, &rel, None, before, None);
+        Ok(())
     }
 
     /// Delete a folder and all its contents.
+    ///
+    /// Goes to the trash in a managed Location, like [`Location::delete_file`].
+    /// Fails, leaving it in place, if `path` is not a folder.
     pub fn delete_folder(&self, path: &Path) -> VfsResult<()> {
-        self.check_writable(path)?;
-        if !path.exists() {
-            return Err(VfsError::NotFound(path.to_path_buf()));
+        if self.uses_trash() {
+            let rel = self.writable_rel(path)?;
+            if !self.handle()?.symlink_metadata(&rel)?.is_dir() {
+                return Err(map_io_error(path, std::io::ErrorKind::NotADirectory.into()));
+            }
+            return self.move_to_trash(path).map(|_| ());
         }
-        std::fs::remove_dir_all(path).map_err(|e| match e.kind() {
-            std::io::ErrorKind::PermissionDenied => VfsError::PermissionDenied(path.to_path_buf()),
-            _ => VfsError::Io { source: e },
-        })
+        let rel = self.writable_rel(path)?;
+        self.handle()?.remove_dir_all(&rel)?;
+        self.record_uid_removal(&rel);
+        self.record_op(JournalOp::Delete, &rel, None, None, None);
+        Ok(())
+    }
+
+    /// Delete a file or folder immediately, without going through the trash.
+    pub fn delete_permanently(&self, path: &Path) -> VfsResult<()> {
+        let rel = self.writable_rel(path)?;
+        let handle = self.handle()?;
+        let before = self.journal_hash(&rel);
+        if handle.symlink_metadata(&rel)?.is_dir() {
+            handle.remove_dir_all(&rel)?;
+        } else {
+            handle.remove_file(&rel)?;
+        }
+        self.record_uid_removal(&rel);
+        self.record_op(JournalOp::Delete, &rel, None, before, None);
+        Ok(())
     }
 }
 
 // ── Internal helpers ───────────────────────────────────────────────────
 
 impl Location {
-    /// Build an Entry from a path and its filesystem metadata.
-    fn build_entry(&self, path: &Path, metadata: &std::fs::Metadata) -> Entry {
-        let name = path
-            .file_name()
-            .map(|n| n.to_string_lossy().into_owned())
-            .unwrap_or_default();
-
-        let kind = if metadata.is_dir() {
-            EntryKind::Folder
-        } else {
-            EntryKind::File
+    /// Build the Entry for a directory child, applying the symlink policy.
+    ///
+    /// `parent_scope` is the scope of the listed folder if already known
+    /// (see [`Location::scope_in_parent`]). Returns `None` for symlinks the
+    /// policy hides.
+    pub(crate) fn read_child(
+        &self,
+        handle: &LocationHandle,
+        rel: &RelPath,
+        dir_entry: &std::fs::DirEntry,
+        parent_scope: Option<Scope>,
+    ) -> std::io::Result<Option<Child>> {
+        let scope_of = |is_dir| match parent_scope {
+            Some(parent) => self.scope_in_parent(rel, is_dir, parent),
+            None => self.scope_of_rel(rel, is_dir),
         };
 
-        let extension = if kind == EntryKind::File {
-            path.extension().map(|e| e.to_string_lossy().to_lowercase())
-        } else {
-            None
-        };
+        if !dir_entry.file_type()?.is_symlink() {
+            let metadata = dir_entry.metadata()?;
+            let scope = scope_of(metadata.is_dir());
+            return Ok(Some(Child {
+                entry: Entry::from_metadata(handle.absolute(rel), &metadata, scope),
+                follow: None,
+            }));
+        }
 
-        let scope = self.scope_of(path).unwrap_or(Scope::Plain);
-
-        Entry {
-            path: path.to_path_buf(),
-            kind,
-            name,
-            extension,
-            size: metadata.len(),
-            modified: metadata.modified().ok().map(DateTime::from),
-            created: metadata.created().ok().map(DateTime::from),
-            scope,
+        let policy = handle.symlink_policy();
+        if policy == SymlinkPolicy::Ignore {
+            return Ok(None);
         }
+        let (symlink, resolved) = handle
+            .inspect_symlink(rel)
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+        // Describe the target when it resolves, the link itself otherwise
+        let metadata = match &resolved {
+            Some(target) => std::fs::metadata(target)?,
+            None => handle
+                .symlink_metadata(rel)
+                .map_err(|e| std::io::Error::other(e.to_string()))?,
+        };
+        let scope = match symlink.kind {
+            SymlinkKind::Internal => scope_of(metadata.is_dir()),
+            // Outside the Location, or nowhere at all
+            _ => Scope::Plain,
+        };
+        let follow = resolved.filter(|_| metadata.is_dir() && policy.follows(symlink.kind));
+
+        let mut entry = Entry::from_metadata(handle.absolute(rel), &metadata, scope);
+        entry.symlink = Some(symlink);
+        Ok(Some(Child { entry, follow }))
+    }
+
+    /// Resolve a path for mutation: inside this Location and not in `.fracta/`.
+    ///
+    /// A symlink at `path` itself is not followed, so it can be removed or
+    /// replaced wherever it points.
+    pub(crate) fn writable_rel(&self, path: &Path) -> VfsResult<RelPath> {
+        let handle = self
+            .handle()
+            .map_err(|_| VfsError::OutsideLocation(path.to_path_buf()))?;
+        let rel = handle.relativize(path)?;
+        handle.resolve_writable(&rel)?;
+        self.check_writable(&rel, path)?;
+        Ok(rel)
     }
 
-    /// Check that a path is within this Location and not inside `.fracta/`.
-    fn check_writable(&self, path: &Path) -> VfsResult<()> {
-        if !self.contains(path) {
-            return Err(VfsError::OutsideLocation(path.to_path_buf()));
+    /// First free sibling of `desired`, found by appending a suffix to its stem.
+    ///
+    /// Returns `desired` itself if it is free, otherwise tries
+    /// "stem{suffix(1)}.ext", "stem{suffix(2)}.ext", and so on.
+    pub(crate) fn unique_rel(
+        &self,
+        desired: &RelPath,
+        suffix: impl Fn(usize) -> String,
+    ) -> VfsResult<RelPath> {
+        let handle = self.handle()?;
+        let free = |rel: &RelPath| !handle.exists(rel) && self.check_collision(rel, None).is_ok();
+        if free(desired) {
+            return Ok(desired.clone());
         }
-        // Prevent CRUD operations inside .fracta/ (use init/writer directly for that)
-        if let Some(rel) = self.relative_path(path) {
-            if rel.starts_with(FRACTA_DIR) {
-                return Err(VfsError::PermissionDenied(path.to_path_buf()));
+
+        let parent = desired.parent().unwrap_or_default();
+        let name = Path::new(desired.file_name().unwrap_or_default());
+        let stem = name
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let ext = name
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+
+        for n in 1.. {
+            let candidate = parent.join(format!("{stem}{}{ext}", suffix(n)))?;
+            if free(&candidate) {
+                return Ok(candidate);
             }
         }
+        unreachable!("exhausted candidate names")
+    }
+
+    /// Prevent CRUD operations inside .fracta/ (use init/writer directly for that).
+    fn check_writable(&self, rel: &RelPath, path: &Path) -> VfsResult<()> {
+        if rel.starts_with(FRACTA_DIR) {
+            return Err(VfsError::PermissionDenied(path.to_path_buf()));
+        }
         Ok(())
     }
 }
@@ -535,8 +691,17 @@ impl Location {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ignore::IgnoreSource;
     use tempfile::TempDir;
 
+    /// A managed Location at `root`, without writing `.fracta/`.
+    fn managed_location(root: &Path) -> Location {
+        Location {
+            managed: true,
+            ..Location::new("test", root)
+        }
+    }
+
     // ── Basic Location tests ───────────────────────────────────────────
 
     #[test]
@@ -608,13 +773,7 @@ mod tests {
         std::fs::write(root.join("notes.md"), "# Hello").unwrap();
         std::fs::write(root.join("data.json"), "{}").unwrap();
 
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let entries = loc.list_directory(&root).unwrap();
         assert_eq!(entries.len(), 4);
@@ -632,13 +791,7 @@ mod tests {
         std::fs::create_dir(root.join(".fracta")).unwrap();
         std::fs::write(root.join("visible.md"), "hello").unwrap();
 
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let entries = loc.list_directory(&root).unwrap();
         assert_eq!(entries.len(), 1);
@@ -693,6 +846,47 @@ mod tests {
         assert_eq!(loc.scope_of(&root.join(".fracta")), Some(Scope::Managed));
     }
 
+    #[test]
+    fn test_gitignore_opt_in_and_explain() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_path_buf();
+
+        let mut loc = Location::new("test", &root);
+        loc.init().unwrap();
+        std::fs::create_dir(root.join("repo")).unwrap();
+        std::fs::write(root.join("repo/.gitignore"), "*.o\n").unwrap();
+        std::fs::write(root.join("repo/main.o"), "").unwrap();
+
+        // Not honored until the setting is enabled
+        assert_eq!(
+            loc.scope_of(&root.join("repo/main.o")),
+            Some(Scope::Managed)
+        );
+        assert_eq!(
+            loc.explain_ignored(&root.join("repo/main.o")).unwrap(),
+            None
+        );
+
+        let mut settings = LocationSettings::load(&root).unwrap();
+        settings.honor_gitignore = true;
+        settings.save(&root).unwrap();
+        loc.reload_ignore_rules().unwrap();
+
+        assert_eq!(
+            loc.scope_of(&root.join("repo/main.o")),
+            Some(Scope::Ignored)
+        );
+        let why = loc
+            .explain_ignored(&root.join("repo/main.o"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            why.source,
+            IgnoreSource::Gitignore(PathBuf::from("repo/.gitignore"))
+        );
+        assert_eq!(why.pattern, "*.o");
+    }
+
     #[test]
     fn test_scope_unmanaged_is_plain() {
         let tmp = TempDir::new().unwrap();
@@ -717,13 +911,7 @@ mod tests {
         std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
         std::fs::write(root.join("readme.md"), "# Hello").unwrap();
 
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let entries = loc.walk(&root, &WalkOptions::default()).unwrap();
         assert_eq!(entries.len(), 3); // src/, src/main.rs, readme.md
@@ -768,13 +956,7 @@ mod tests {
         std::fs::create_dir_all(root.join("a/b/c")).unwrap();
         std::fs::write(root.join("a/b/c/deep.txt"), "deep").unwrap();
 
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         // Depth 1: only immediate children
         let opts = WalkOptions {
@@ -792,13 +974,7 @@ mod tests {
     fn test_create_and_read_file() {
         let tmp = TempDir::new().unwrap();
         let root = tmp.path().to_path_buf();
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let path = root.join("test.md");
         loc.create_file(&path, b"# Hello").unwrap();
@@ -809,13 +985,7 @@ mod tests {
     fn test_create_file_already_exists() {
         let tmp = TempDir::new().unwrap();
         let root = tmp.path().to_path_buf();
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let path = root.join("test.md");
         loc.create_file(&path, b"v1").unwrap();
@@ -828,13 +998,7 @@ mod tests {
     fn test_write_file_overwrites() {
         let tmp = TempDir::new().unwrap();
         let root = tmp.path().to_path_buf();
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let path = root.join("test.md");
         loc.create_file(&path, b"v1").unwrap();
@@ -846,13 +1010,7 @@ mod tests {
     fn test_create_and_delete_folder() {
         let tmp = TempDir::new().unwrap();
         let root = tmp.path().to_path_buf();
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let folder = root.join("new_folder");
         loc.create_folder(&folder).unwrap();
@@ -870,13 +1028,7 @@ mod tests {
     fn test_rename() {
         let tmp = TempDir::new().unwrap();
         let root = tmp.path().to_path_buf();
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let old_path = root.join("old.md");
         let new_path = root.join("new.md");
@@ -891,13 +1043,7 @@ mod tests {
     fn test_move_entry() {
         let tmp = TempDir::new().unwrap();
         let root = tmp.path().to_path_buf();
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         loc.create_folder(&root.join("dest")).unwrap();
         loc.create_file(&root.join("file.md"), b"data").unwrap();
@@ -938,13 +1084,7 @@ mod tests {
     fn test_delete_file() {
         let tmp = TempDir::new().unwrap();
         let root = tmp.path().to_path_buf();
-        let loc = Location {
-            id: Uuid::now_v7(),
-            label: "test".into(),
-            root: root.clone(),
-            managed: true,
-            ignore_rules: IgnoreRules::empty(),
-        };
+        let loc = managed_location(&root);
 
         let path = root.join("delete_me.txt");
         loc.create_file(&path, b"gone soon").unwrap();
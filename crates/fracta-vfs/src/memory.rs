@@ -0,0 +1,460 @@
+//! Deterministic in-memory [`VfsBackend`] for tests.
+//!
+//! Nothing touches the disk, listings are always in path order, and
+//! timestamps come from a logical clock that advances one second per
+//! mutation, so fixtures built on it behave the same on every run.
+//!
+//! Failures can be injected per operation with [`MemoryBackend::fail_next`]:
+//! e.g. a failed fsync makes the next `write_atomic` fail with
+//! `AtomicWriteFailed` and leave the old content in place, as the real
+//! atomic writer does.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::backend::{BackendWatch, FileStat, VfsBackend};
+use crate::entry::EntryKind;
+use crate::error::{VfsError, VfsResult};
+use crate::handle::{map_io_error, RelPath};
+use crate::watcher::FsEvent;
+
+/// Where the logical clock starts (2024-01-01T00:00:00Z).
+const CLOCK_EPOCH: i64 = 1_704_067_200;
+
+/// An operation of [`MemoryBackend`] that a fault can be injected into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendOp {
+    Read,
+    /// The whole of `write_atomic`, failing before anything is written.
+    Write,
+    /// The fsync step of `write_atomic`, after the new content is staged.
+    Sync,
+    CreateDir,
+    List,
+    Stat,
+    Rename,
+    Remove,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    File {
+        content: Vec<u8>,
+        modified: DateTime<Utc>,
+    },
+    Folder {
+        modified: DateTime<Utc>,
+    },
+}
+
+impl Node {
+    fn stat(&self) -> FileStat {
+        match self {
+            Node::File { content, modified } => FileStat {
+                kind: EntryKind::File,
+                size: content.len() as u64,
+                modified: Some(*modified),
+            },
+            Node::Folder { modified } => FileStat {
+                kind: EntryKind::Folder,
+                size: 0,
+                modified: Some(*modified),
+            },
+        }
+    }
+}
+
+type EventQueue = Mutex<Vec<FsEvent>>;
+
+#[derive(Default)]
+struct State {
+    /// Every entry except the root, keyed by relative path.
+    nodes: BTreeMap<PathBuf, Node>,
+    ticks: i64,
+    faults: Vec<(BackendOp, io::ErrorKind)>,
+    watchers: Vec<Weak<EventQueue>>,
+}
+
+impl State {
+    /// Advance the logical clock and return the new time.
+    fn tick(&mut self) -> DateTime<Utc> {
+        self.ticks += 1;
+        DateTime::from_timestamp(CLOCK_EPOCH, 0).unwrap() + Duration::seconds(self.ticks)
+    }
+
+    fn take_fault(&mut self, op: BackendOp) -> Option<io::Error> {
+        let index = self.faults.iter().position(|(o, _)| *o == op)?;
+        Some(self.faults.remove(index).1.into())
+    }
+
+    fn is_dir(&self, rel: &RelPath) -> bool {
+        rel.is_root() || matches!(self.nodes.get(rel.as_path()), Some(Node::Folder { .. }))
+    }
+
+    fn emit(&mut self, event: FsEvent) {
+        self.watchers.retain(|queue| match queue.upgrade() {
+            Some(queue) => {
+                queue.lock().unwrap().push(event.clone());
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+/// Watch handle returned by [`MemoryBackend::watch`].
+struct MemoryWatch(Arc<EventQueue>);
+
+impl BackendWatch for MemoryWatch {
+    fn drain_events(&self) -> Vec<FsEvent> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// A file tree held in memory.
+pub struct MemoryBackend {
+    root: PathBuf,
+    state: Mutex<State>,
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new("/memory")
+    }
+}
+
+impl MemoryBackend {
+    /// An empty tree whose absolute paths are reported under `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Make the next call of `op` fail with an error of `kind`.
+    ///
+    /// Faults queue up: injecting two for the same operation fails its next
+    /// two calls.
+    pub fn fail_next(&self, op: BackendOp, kind: io::ErrorKind) {
+        self.state.lock().unwrap().faults.push((op, kind));
+    }
+
+    /// Write a file, creating missing parent folders (fixture shorthand).
+    pub fn add_file(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> VfsResult<()> {
+        let rel = RelPath::new(path)?;
+        if let Some(parent) = rel.parent() {
+            self.create_dir_all(&parent)?;
+        }
+        self.write_atomic(&rel, content.as_ref())
+    }
+
+    fn absolute(&self, rel: &RelPath) -> PathBuf {
+        self.root.join(rel.as_path())
+    }
+
+    fn fail(&self, state: &mut State, op: BackendOp, rel: &RelPath) -> VfsResult<()> {
+        match state.take_fault(op) {
+            Some(e) => Err(map_io_error(&self.absolute(rel), e)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_parent(&self, state: &State, rel: &RelPath) -> VfsResult<()> {
+        match rel.parent() {
+            Some(parent) if !state.is_dir(&parent) => {
+                Err(VfsError::NotFound(self.absolute(&parent)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl VfsBackend for MemoryBackend {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn read(&self, rel: &RelPath) -> VfsResult<Vec<u8>> {
+        let mut state = self.state.lock().unwrap();
+        self.fail(&mut state, BackendOp::Read, rel)?;
+        match state.nodes.get(rel.as_path()) {
+            Some(Node::File { content, .. }) => Ok(content.clone()),
+            Some(Node::Folder { .. }) => Err(map_io_error(
+                &self.absolute(rel),
+                io::ErrorKind::IsADirectory.into(),
+            )),
+            None => Err(VfsError::NotFound(self.absolute(rel))),
+        }
+    }
+
+    fn write_atomic(&self, rel: &RelPath, content: &[u8]) -> VfsResult<()> {
+        let mut state = self.state.lock().unwrap();
+        self.fail(&mut state, BackendOp::Write, rel)?;
+        self.check_parent(&state, rel)?;
+        if state.is_dir(rel) {
+            return Err(VfsError::AlreadyExists(self.absolute(rel)));
+        }
+        if let Some(e) = state.take_fault(BackendOp::Sync) {
+            return Err(VfsError::AtomicWriteFailed {
+                path: self.absolute(rel),
+                reason: format!("failed to sync: {e}"),
+            });
+        }
+
+        let modified = state.tick();
+        let previous = state.nodes.insert(
+            rel.as_path().to_path_buf(),
+            Node::File {
+                content: content.to_vec(),
+                modified,
+            },
+        );
+        let path = self.absolute(rel);
+        state.emit(match previous {
+            Some(_) => FsEvent::Modified(path),
+            None => FsEvent::Created(path),
+        });
+        Ok(())
+    }
+
+    fn create_dir_all(&self, rel: &RelPath) -> VfsResult<()> {
+        let mut state = self.state.lock().unwrap();
+        self.fail(&mut state, BackendOp::CreateDir, rel)?;
+        let mut current = RelPath::root();
+        for name in rel.as_path().iter() {
+            current = current.join(name)?;
+            match state.nodes.get(current.as_path()) {
+                Some(Node::Folder { .. }) => {}
+                Some(Node::File { .. }) => {
+                    return Err(VfsError::AlreadyExists(self.absolute(&current)));
+                }
+                None => {
+                    let modified = state.tick();
+                    state
+                        .nodes
+                        .insert(current.as_path().to_path_buf(), Node::Folder { modified });
+                    state.emit(FsEvent::Created(self.absolute(&current)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn list(&self, dir: &RelPath) -> VfsResult<Vec<(RelPath, FileStat)>> {
+        let mut state = self.state.lock().unwrap();
+        self.fail(&mut state, BackendOp::List, dir)?;
+        if !state.is_dir(dir) {
+            return Err(VfsError::NotFound(self.absolute(dir)));
+        }
+        Ok(state
+            .nodes
+            .iter()
+            .filter(|(path, _)| path.parent() == Some(dir.as_path()))
+            .map(|(path, node)| (RelPath::new(path).unwrap_or_default(), node.stat()))
+            .collect())
+    }
+
+    fn stat(&self, rel: &RelPath) -> VfsResult<FileStat> {
+        let mut state = self.state.lock().unwrap();
+        self.fail(&mut state, BackendOp::Stat, rel)?;
+        if rel.is_root() {
+            return Ok(FileStat {
+                kind: EntryKind::Folder,
+                size: 0,
+                modified: None,
+            });
+        }
+        state
+            .nodes
+            .get(rel.as_path())
+            .map(Node::stat)
+            .ok_or_else(|| VfsError::NotFound(self.absolute(rel)))
+    }
+
+    fn rename(&self, from: &RelPath, to: &RelPath) -> VfsResult<()> {
+        let mut state = self.state.lock().unwrap();
+        self.fail(&mut state, BackendOp::Rename, from)?;
+        if from.is_root() || !state.nodes.contains_key(from.as_path()) {
+            return Err(VfsError::NotFound(self.absolute(from)));
+        }
+        if to.is_root() || state.nodes.contains_key(to.as_path()) {
+            return Err(VfsError::AlreadyExists(self.absolute(to)));
+        }
+        if to.starts_with(from) {
+            return Err(VfsError::Io {
+                source: io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "cannot move a folder into itself",
+                ),
+            });
+        }
+        self.check_parent(&state, to)?;
+
+        let moved: Vec<PathBuf> = state
+            .nodes
+            .keys()
+            .filter(|path| path.starts_with(from.as_path()))
+            .cloned()
+            .collect();
+        for path in moved {
+            let node = state.nodes.remove(&path).unwrap();
+            let suffix = path.strip_prefix(from.as_path()).unwrap_or(Path::new(""));
+            let dest = to.as_path().join(suffix);
+            state.nodes.insert(dest, node);
+        }
+        state.emit(FsEvent::Renamed {
+            from: self.absolute(from),
+            to: self.absolute(to),
+        });
+        Ok(())
+    }
+
+    fn remove(&self, rel: &RelPath) -> VfsResult<()> {
+        let mut state = self.state.lock().unwrap();
+        self.fail(&mut state, BackendOp::Remove, rel)?;
+        if rel.is_root() {
+            return Err(VfsError::PermissionDenied(self.root.clone()));
+        }
+        if !state.nodes.contains_key(rel.as_path()) {
+            return Err(VfsError::NotFound(self.absolute(rel)));
+        }
+        state
+            .nodes
+            .retain(|path, _| !path.starts_with(rel.as_path()));
+        state.emit(FsEvent::Deleted(self.absolute(rel)));
+        Ok(())
+    }
+
+    fn watch(&self) -> VfsResult<Box<dyn BackendWatch>> {
+        let queue = Arc::new(EventQueue::default());
+        self.state
+            .lock()
+            .unwrap()
+            .watchers
+            .push(Arc::downgrade(&queue));
+        Ok(Box::new(MemoryWatch(queue)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rel(path: &str) -> RelPath {
+        RelPath::new(path).unwrap()
+    }
+
+    #[test]
+    fn test_tree_operations() {
+        let backend = MemoryBackend::default();
+        backend.add_file("notes/a.md", "A").unwrap();
+        backend.add_file("notes/deep/b.md", "B").unwrap();
+        backend.add_file("top.md", "top").unwrap();
+
+        let names: Vec<_> = backend
+            .list(&rel("notes"))
+            .unwrap()
+            .into_iter()
+            .map(|(rel, _)| rel)
+            .collect();
+        assert_eq!(names, vec![rel("notes/a.md"), rel("notes/deep")]);
+
+        backend.rename(&rel("notes"), &rel("archive")).unwrap();
+        assert_eq!(backend.read(&rel("archive/deep/b.md")).unwrap(), b"B");
+        assert!(backend.stat(&rel("notes/a.md")).is_err());
+
+        backend.remove(&rel("archive")).unwrap();
+        let files = backend.walk_files(&RelPath::root()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, rel("top.md"));
+    }
+
+    #[test]
+    fn test_errors_match_filesystem() {
+        let backend = MemoryBackend::default();
+        backend.add_file("a.md", "A").unwrap();
+
+        assert!(matches!(
+            backend.write_atomic(&rel("missing/b.md"), b"B"),
+            Err(VfsError::NotFound(_))
+        ));
+        assert!(matches!(
+            backend.rename(&rel("a.md"), &rel("a.md")),
+            Err(VfsError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            backend.read(&rel("nope.md")),
+            Err(VfsError::NotFound(p)) if p == Path::new("/memory/nope.md")
+        ));
+    }
+
+    #[test]
+    fn test_deterministic_clock() {
+        let run = || {
+            let backend = MemoryBackend::default();
+            backend.add_file("a.md", "A").unwrap();
+            backend.add_file("b.md", "B").unwrap();
+            backend.walk_files(&RelPath::root()).unwrap()
+        };
+        let files = run();
+        assert_eq!(files, run());
+        assert!(files[0].1.modified < files[1].1.modified);
+    }
+
+    #[test]
+    fn test_failed_sync_keeps_old_content() {
+        let backend = MemoryBackend::default();
+        backend.add_file("note.md", "old").unwrap();
+
+        backend.fail_next(BackendOp::Sync, io::ErrorKind::Other);
+        let result = backend.write_atomic(&rel("note.md"), b"new");
+        assert!(matches!(result, Err(VfsError::AtomicWriteFailed { .. })));
+        assert_eq!(backend.read(&rel("note.md")).unwrap(), b"old");
+
+        // The fault is consumed
+        backend.write_atomic(&rel("note.md"), b"new").unwrap();
+        assert_eq!(backend.read(&rel("note.md")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_injected_read_fault() {
+        let backend = MemoryBackend::default();
+        backend.add_file("note.md", "x").unwrap();
+        backend.fail_next(BackendOp::Read, io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            backend.read(&rel("note.md")),
+            Err(VfsError::PermissionDenied(_))
+        ));
+        assert!(backend.read(&rel("note.md")).is_ok());
+    }
+
+    #[test]
+    fn test_watch_events() {
+        let backend = MemoryBackend::default();
+        let watch = backend.watch().unwrap();
+        backend.add_file("a.md", "A").unwrap();
+        backend.write_atomic(&rel("a.md"), b"A2").unwrap();
+        backend.rename(&rel("a.md"), &rel("b.md")).unwrap();
+        backend.remove(&rel("b.md")).unwrap();
+
+        let root = Path::new("/memory");
+        assert_eq!(
+            watch.drain_events(),
+            vec![
+                FsEvent::Created(root.join("a.md")),
+                FsEvent::Modified(root.join("a.md")),
+                FsEvent::Renamed {
+                    from: root.join("a.md"),
+                    to: root.join("b.md"),
+                },
+                FsEvent::Deleted(root.join("b.md")),
+            ]
+        );
+        assert!(watch.drain_events().is_empty());
+    }
+}
@@ -0,0 +1,664 @@
+//! Location registry (Global View).
+//!
+//! Keeps the set of Locations the user has added (SPEC §6.2, ADR-0006),
+//! persists it to a JSON file owned by the application (not inside any
+//! Location), and answers Global View questions: which Location owns a path,
+//! and what the merged tree across all roots looks like.
+//!
+//! Locations are keyed by their stable `LocationSettings.id`. Roots may not be
+//! nested inside each other — every path belongs to at most one Location.
+//!
+//! A managed Location is only opened on load if the `.fracta/` at its root
+//! carries the registered ID in a supported layout. Otherwise it stays
+//! registered but unavailable, with the reason kept for the UI.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::entry::{Entry, EntryKind};
+use crate::error::{VfsError, VfsResult};
+use crate::layout::check_layout;
+use crate::location::{Location, WalkOptions};
+use crate::relocate::location_id_at;
+use crate::scope::Scope;
+use crate::settings::LocationSettings;
+use crate::writer::{atomic_write_string, ensure_dir};
+
+/// Current on-disk format version of the registry file.
+const REGISTRY_VERSION: u32 = 1;
+
+/// The set of Locations aggregated into the Global View.
+#[derive(Debug, Default)]
+pub struct LocationRegistry {
+    /// Where the registry is persisted (None = in-memory only).
+    path: Option<PathBuf>,
+    /// Registered Locations, in the order they were added.
+    locations: Vec<Location>,
+    /// Managed Locations that could not be opened, by ID. Their entry in
+    /// `locations` is an unmanaged placeholder holding the registered ID,
+    /// label and root.
+    unavailable: HashMap<Uuid, VfsError>,
+}
+
+/// On-disk registry file.
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistryFile {
+    version: u32,
+    locations: Vec<RegistryRecord>,
+}
+
+/// One persisted Location.
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistryRecord {
+    id: Uuid,
+    label: String,
+    root: PathBuf,
+    managed: bool,
+}
+
+// ── Construction & persistence ─────────────────────────────────────────
+
+impl LocationRegistry {
+    /// Create an empty in-memory registry (nothing is persisted).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a registry from `path`, or start empty if the file doesn't exist.
+    ///
+    /// Managed Locations that cannot be opened are kept as unavailable
+    /// entries with their registered ID (see [`LocationRegistry::open_error`]):
+    /// a missing root (unmounted disk, revoked permission), a root now
+    /// holding a different Location, or a layout from a newer engine.
+    /// [`LocationRegistry::reopen`] retries once the cause is gone.
+    pub fn open(path: &Path) -> VfsResult<Self> {
+        let mut registry = Self {
+            path: Some(path.to_path_buf()),
+            ..Self::default()
+        };
+
+        if !path.exists() {
+            return Ok(registry);
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        let file: RegistryFile = serde_json::from_str(&content).map_err(|e| VfsError::Io {
+            source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
+        })?;
+
+        for record in file.locations {
+            match Self::restore(&record) {
+                Ok(loc) => registry.locations.push(loc),
+                Err(error) => {
+                    registry.unavailable.insert(record.id, error);
+                    registry.locations.push(Self::placeholder(&record));
+                }
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Write the registry to its backing file (no-op for in-memory registries).
+    pub fn save(&self) -> VfsResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            ensure_dir(parent)?;
+        }
+
+        let file = RegistryFile {
+            version: REGISTRY_VERSION,
+            locations: self
+                .locations
+                .iter()
+                .map(|loc| RegistryRecord {
+                    id: loc.id,
+                    label: loc.label.clone(),
+                    root: loc.root.clone(),
+                    managed: loc.managed || self.unavailable.contains_key(&loc.id),
+                })
+                .collect(),
+        };
+        let content = serde_json::to_string_pretty(&file).map_err(|e| VfsError::Io {
+            source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
+        })?;
+
+        atomic_write_string(path, &content)
+    }
+
+    /// Rebuild a Location from its persisted record.
+    ///
+    /// A managed Location is opened only after its root is checked: the
+    /// layout must be supported and the stored ID must match the record, so
+    /// nothing is migrated or rewritten in a tree that is not ours to open.
+    fn restore(record: &RegistryRecord) -> VfsResult<Location> {
+        if !record.managed {
+            return Ok(Self::placeholder(record));
+        }
+        if !record.root.is_dir() {
+            return Err(VfsError::NotFound(record.root.clone()));
+        }
+        check_layout(&record.root)?;
+        let found = location_id_at(&record.root)?;
+        if found != Some(record.id) {
+            return Err(VfsError::LocationMismatch {
+                path: record.root.clone(),
+                expected: record.id,
+                found,
+            });
+        }
+        let mut loc = Location::open(record.label.clone(), &record.root)?;
+        // The registry is authoritative for the label shown in the UI
+        loc.label = record.label.clone();
+        Ok(loc)
+    }
+
+    /// An unmanaged Location carrying the record's ID, label and root.
+    fn placeholder(record: &RegistryRecord) -> Location {
+        let mut loc = Location::new(record.label.clone(), record.root.clone());
+        loc.id = record.id;
+        loc
+    }
+}
+
+// ── Mutation ───────────────────────────────────────────────────────────
+
+impl LocationRegistry {
+    /// Register a Location and persist the registry.
+    ///
+    /// Fails with `AlreadyExists` if the ID is already registered, and with
+    /// `OverlappingLocation` if the root is nested in (or contains) the root
+    /// of a registered Location.
+    pub fn add(&mut self, location: Location) -> VfsResult<Uuid> {
+        if let Some(existing) = self.get(location.id) {
+            // The same Location back at a new root after the old one vanished
+            if !existing.root.is_dir() {
+                self.relocate(location.id, &location.root)?;
+                return Ok(location.id);
+            }
+            return Err(VfsError::AlreadyExists(location.root.clone()));
+        }
+        self.check_overlap(&location.root, None)?;
+
+        let id = location.id;
+        self.locations.push(location);
+        self.save()?;
+        Ok(id)
+    }
+
+    /// Re-bind a Location to `new_root` after its folder was moved, renamed
+    /// or mounted elsewhere, and persist the registry.
+    ///
+    /// The registered ID must match the one stored at `new_root` (see
+    /// [`Location::relocate`]). The label shown in the UI is kept.
+    pub fn relocate(&mut self, id: Uuid, new_root: &Path) -> VfsResult<()> {
+        self.check_overlap(new_root, Some(id))?;
+        let unavailable = self.unavailable.contains_key(&id);
+        let loc = self
+            .locations
+            .iter_mut()
+            .find(|loc| loc.id == id)
+            .ok_or(VfsError::UnknownLocation(id))?;
+        let mut moved = loc.clone();
+        // An unavailable entry is still a managed Location: check its ID
+        moved.managed |= unavailable;
+        moved.relocate(new_root)?;
+        moved.label = loc.label.clone();
+        *loc = moved;
+        self.unavailable.remove(&id);
+        self.save()
+    }
+
+    /// Retry opening a Location that was unavailable when the registry was
+    /// loaded, e.g. after its disk is mounted again.
+    ///
+    /// Does nothing for a Location that is already open. On failure the
+    /// Location stays unavailable and the error is returned.
+    pub fn reopen(&mut self, id: Uuid) -> VfsResult<()> {
+        let loc = self
+            .locations
+            .iter_mut()
+            .find(|loc| loc.id == id)
+            .ok_or(VfsError::UnknownLocation(id))?;
+        if !self.unavailable.contains_key(&id) {
+            return Ok(());
+        }
+        let record = RegistryRecord {
+            id,
+            label: loc.label.clone(),
+            root: loc.root.clone(),
+            managed: true,
+        };
+        let opened = Self::restore(&record)?;
+        *loc = opened;
+        self.unavailable.remove(&id);
+        Ok(())
+    }
+
+    /// Unregister a Location and persist the registry.
+    ///
+    /// Does not touch the Location on disk — its `.fracta/` directory stays,
+    /// so re-adding it later keeps the same ID and caches.
+    pub fn remove(&mut self, id: Uuid) -> VfsResult<Location> {
+        let index = self
+            .locations
+            .iter()
+            .position(|loc| loc.id == id)
+            .ok_or(VfsError::UnknownLocation(id))?;
+        let removed = self.locations.remove(index);
+        self.unavailable.remove(&id);
+        self.save()?;
+        Ok(removed)
+    }
+
+    /// Change a Location's label.
+    ///
+    /// Persists the registry and, for managed Locations, the label stored in
+    /// `.fracta/config/settings.json`.
+    pub fn relabel(&mut self, id: Uuid, label: impl Into<String>) -> VfsResult<()> {
+        let label = label.into();
+        let loc = self
+            .locations
+            .iter_mut()
+            .find(|loc| loc.id == id)
+            .ok_or(VfsError::UnknownLocation(id))?;
+        loc.label = label.clone();
+
+        if loc.managed && loc.fracta_dir().is_dir() {
+            let mut settings = LocationSettings::load(&loc.root)?;
+            settings.label = Some(label);
+            settings.save(&loc.root)?;
+        }
+
+        self.save()
+    }
+}
+
+// ── Queries ────────────────────────────────────────────────────────────
+
+impl LocationRegistry {
+    /// All registered Locations, in the order they were added.
+    pub fn locations(&self) -> &[Location] {
+        &self.locations
+    }
+
+    /// Look up a Location by ID.
+    pub fn get(&self, id: Uuid) -> Option<&Location> {
+        self.locations.iter().find(|loc| loc.id == id)
+    }
+
+    /// The registered Location that `root` is a moved copy of, if any.
+    ///
+    /// Matches the ID stored in `root`'s `.fracta/` against Locations whose
+    /// registered root is no longer reachable; pass the result to
+    /// [`LocationRegistry::relocate`] to re-bind it.
+    pub fn find_relocated(&self, root: &Path) -> VfsResult<Option<Uuid>> {
+        let Some(id) = location_id_at(root)? else {
+            return Ok(None);
+        };
+        Ok(self
+            .get(id)
+            .filter(|loc| !loc.root.is_dir() && comparable_root(&loc.root) != comparable_root(root))
+            .map(|loc| loc.id))
+    }
+
+    /// Whether a Location was opened and its root is currently reachable.
+    pub fn is_available(&self, id: Uuid) -> bool {
+        !self.unavailable.contains_key(&id)
+            && self.get(id).map(|loc| loc.root.is_dir()).unwrap_or(false)
+    }
+
+    /// Why a registered managed Location could not be opened, if it could
+    /// not.
+    pub fn open_error(&self, id: Uuid) -> Option<&VfsError> {
+        self.unavailable.get(&id)
+    }
+
+    /// Find the Location that owns an absolute path.
+    ///
+    /// Locations that could not be opened own nothing.
+    pub fn location_for(&self, path: &Path) -> Option<&Location> {
+        // Roots never overlap, so at most one Location can match.
+        self.available().find(|loc| loc.contains(path))
+    }
+
+    /// One entry per reachable Location root — the top level of the Global View.
+    pub fn list_roots(&self) -> Vec<Entry> {
+        self.available()
+            .filter_map(|loc| {
+                let metadata = std::fs::metadata(&loc.root).ok()?;
+                metadata.is_dir().then(|| Entry {
+                    path: loc.root.clone(),
+                    kind: EntryKind::Folder,
+                    name: loc.label.clone(),
+                    extension: None,
+                    size: 0,
+                    modified: metadata.modified().ok().map(Into::into),
+                    created: metadata.created().ok().map(Into::into),
+                    scope: if loc.managed {
+                        Scope::Managed
+                    } else {
+                        Scope::Plain
+                    },
+                    symlink: None,
+                    cloud: None,
+                })
+            })
+            .collect()
+    }
+
+    /// List a directory in whichever Location owns it.
+    pub fn list_directory(&self, dir: &Path) -> VfsResult<Vec<Entry>> {
+        self.location_for(dir)
+            .ok_or_else(|| VfsError::OutsideLocation(dir.to_path_buf()))?
+            .list_directory(dir)
+    }
+
+    /// Walk every reachable Location and return the merged entries.
+    ///
+    /// Locations whose root is currently missing are skipped.
+    pub fn walk(&self, options: &WalkOptions) -> VfsResult<Vec<Entry>> {
+        let mut entries = Vec::new();
+        for loc in self.available() {
+            if !loc.root.is_dir() {
+                continue;
+            }
+            entries.extend(loc.walk(&loc.root, options)?);
+        }
+        Ok(entries)
+    }
+}
+
+impl LocationRegistry {
+    /// Registered Locations that were opened (their root may still be
+    /// missing).
+    fn available(&self) -> impl Iterator<Item = &Location> {
+        self.locations
+            .iter()
+            .filter(|loc| !self.unavailable.contains_key(&loc.id))
+    }
+
+    /// Fail with `OverlappingLocation` if `root` is nested in (or contains)
+    /// the root of a registered Location other than `except`.
+    fn check_overlap(&self, root: &Path, except: Option<Uuid>) -> VfsResult<()> {
+        let new_root = comparable_root(root);
+        for existing in self.locations.iter().filter(|loc| Some(loc.id) != except) {
+            let existing_root = comparable_root(&existing.root);
+            if new_root.starts_with(&existing_root) || existing_root.starts_with(&new_root) {
+                return Err(VfsError::OverlappingLocation {
+                    path: root.to_path_buf(),
+                    existing: existing.root.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Root path used for overlap checks (symlinks resolved when possible).
+fn comparable_root(root: &Path) -> PathBuf {
+    root.canonicalize().unwrap_or_else(|_| root.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn managed_location(root: &Path, label: &str) -> Location {
+        let mut loc = Location::new(label, root);
+        loc.init().unwrap();
+        loc
+    }
+
+    #[test]
+    fn test_add_and_find_owner() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        std::fs::write(b.path().join("note.md"), "hi").unwrap();
+
+        let mut registry = LocationRegistry::new();
+        registry.add(managed_location(a.path(), "A")).unwrap();
+        let b_id = registry.add(managed_location(b.path(), "B")).unwrap();
+
+        let owner = registry.location_for(&b.path().join("note.md")).unwrap();
+        assert_eq!(owner.id, b_id);
+        assert!(registry.location_for(Path::new("/nowhere/x.md")).is_none());
+    }
+
+    #[test]
+    fn test_rejects_nested_and_overlapping_roots() {
+        let outer = TempDir::new().unwrap();
+        let inner = outer.path().join("inner");
+        std::fs::create_dir(&inner).unwrap();
+
+        let mut registry = LocationRegistry::new();
+        registry.add(Location::new("outer", outer.path())).unwrap();
+
+        let err = registry.add(Location::new("inner", &inner)).unwrap_err();
+        assert!(matches!(err, VfsError::OverlappingLocation { .. }));
+
+        let mut registry = LocationRegistry::new();
+        registry.add(Location::new("inner", &inner)).unwrap();
+        let err = registry
+            .add(Location::new("outer", outer.path()))
+            .unwrap_err();
+        assert!(matches!(err, VfsError::OverlappingLocation { .. }));
+    }
+
+    #[test]
+    fn test_persist_relabel_and_remove() {
+        let state = TempDir::new().unwrap();
+        let root = TempDir::new().unwrap();
+        let registry_path = state.path().join("locations.json");
+
+        let mut registry = LocationRegistry::open(&registry_path).unwrap();
+        let id = registry.add(managed_location(root.path(), "Docs")).unwrap();
+        registry.relabel(id, "Documents").unwrap();
+
+        // Reload from disk: ID and label survive
+        let reloaded = LocationRegistry::open(&registry_path).unwrap();
+        let loc = reloaded.get(id).unwrap();
+        assert_eq!(loc.label, "Documents");
+        assert!(loc.managed);
+        assert_eq!(
+            LocationSettings::load(root.path())
+                .unwrap()
+                .label
+                .as_deref(),
+            Some("Documents")
+        );
+
+        let mut reloaded = reloaded;
+        reloaded.remove(id).unwrap();
+        assert!(LocationRegistry::open(&registry_path)
+            .unwrap()
+            .locations()
+            .is_empty());
+        // Removing from the registry leaves the Location on disk
+        assert!(root.path().join(".fracta").is_dir());
+    }
+
+    #[test]
+    fn test_missing_root_kept_but_unavailable() {
+        let state = TempDir::new().unwrap();
+        let registry_path = state.path().join("locations.json");
+        let root = TempDir::new().unwrap();
+        let root_path = root.path().to_path_buf();
+
+        let mut registry = LocationRegistry::open(&registry_path).unwrap();
+        let id = registry
+            .add(managed_location(&root_path, "External"))
+            .unwrap();
+        drop(root);
+
+        let reloaded = LocationRegistry::open(&registry_path).unwrap();
+        assert_eq!(reloaded.get(id).unwrap().root, root_path);
+        assert!(!reloaded.is_available(id));
+        assert!(reloaded.walk(&WalkOptions::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mismatched_id_kept_unavailable() {
+        let state = TempDir::new().unwrap();
+        let registry_path = state.path().join("locations.json");
+        let root = TempDir::new().unwrap();
+
+        let mut registry = LocationRegistry::open(&registry_path).unwrap();
+        let id = registry.add(managed_location(root.path(), "Docs")).unwrap();
+
+        // A different Location now lives at the registered root
+        std::fs::remove_dir_all(root.path().join(".fracta")).unwrap();
+        let other = managed_location(root.path(), "Other").id;
+
+        let reloaded = LocationRegistry::open(&registry_path).unwrap();
+        assert!(reloaded.get(other).is_none());
+        let loc = reloaded.get(id).unwrap();
+        assert_eq!(loc.root, root.path());
+        assert!(!loc.managed);
+        assert!(!reloaded.is_available(id));
+        assert!(reloaded.location_for(root.path()).is_none());
+        assert!(matches!(
+            reloaded.open_error(id),
+            Some(VfsError::LocationMismatch { expected, found, .. })
+                if *expected == id && *found == Some(other)
+        ));
+
+        // Saving keeps the registered ID and managed flag
+        reloaded.save().unwrap();
+        let reloaded = LocationRegistry::open(&registry_path).unwrap();
+        assert!(reloaded.get(id).is_some());
+        assert!(reloaded.open_error(id).is_some());
+    }
+
+    #[test]
+    fn test_newer_layout_kept_unavailable() {
+        let state = TempDir::new().unwrap();
+        let registry_path = state.path().join("locations.json");
+        let root = TempDir::new().unwrap();
+
+        let mut registry = LocationRegistry::open(&registry_path).unwrap();
+        let id = registry.add(managed_location(root.path(), "Docs")).unwrap();
+
+        let mut settings = LocationSettings::load(root.path()).unwrap();
+        settings.layout_version = Some(crate::layout::LAYOUT_VERSION + 1);
+        settings.save(root.path()).unwrap();
+        let written = std::fs::read(root.path().join(".fracta/config/settings.json")).unwrap();
+
+        let mut reloaded = LocationRegistry::open(&registry_path).unwrap();
+        assert!(!reloaded.is_available(id));
+        assert!(!reloaded.get(id).unwrap().managed);
+        assert!(matches!(
+            reloaded.open_error(id),
+            Some(VfsError::UnsupportedLayout { .. })
+        ));
+        // Loading the registry left the newer tree alone
+        assert_eq!(
+            std::fs::read(root.path().join(".fracta/config/settings.json")).unwrap(),
+            written
+        );
+        assert!(matches!(
+            reloaded.reopen(id),
+            Err(VfsError::UnsupportedLayout { .. })
+        ));
+
+        settings.layout_version = Some(crate::layout::LAYOUT_VERSION);
+        settings.save(root.path()).unwrap();
+        reloaded.reopen(id).unwrap();
+        assert!(reloaded.is_available(id));
+        assert!(reloaded.get(id).unwrap().managed);
+        assert!(reloaded.open_error(id).is_none());
+    }
+
+    #[test]
+    fn test_merged_walk_and_listing() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        std::fs::write(a.path().join("a.md"), "a").unwrap();
+        std::fs::create_dir(b.path().join("sub")).unwrap();
+        std::fs::write(b.path().join("sub/b.md"), "b").unwrap();
+
+        let mut registry = LocationRegistry::new();
+        registry.add(managed_location(a.path(), "A")).unwrap();
+        registry.add(managed_location(b.path(), "B")).unwrap();
+
+        let roots = registry.list_roots();
+        let labels: Vec<_> = roots.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(labels, vec!["A", "B"]);
+
+        let names: Vec<_> = registry
+            .walk(&WalkOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert!(names.contains(&"a.md".to_string()));
+        assert!(names.contains(&"b.md".to_string()));
+
+        let listing = registry.list_directory(&b.path().join("sub")).unwrap();
+        assert_eq!(listing.len(), 1);
+        assert_eq!(listing[0].name, "b.md");
+    }
+
+    #[test]
+    fn test_moved_root_is_found_and_relocated() {
+        let state = TempDir::new().unwrap();
+        let registry_path = state.path().join("locations.json");
+        let drive = TempDir::new().unwrap();
+        let old_root = drive.path().join("Docs");
+        let new_root = drive.path().join("Archive/Docs");
+        std::fs::create_dir(&old_root).unwrap();
+
+        let mut registry = LocationRegistry::open(&registry_path).unwrap();
+        let id = registry.add(managed_location(&old_root, "Docs")).unwrap();
+        registry.relabel(id, "My Docs").unwrap();
+
+        std::fs::create_dir(drive.path().join("Archive")).unwrap();
+        std::fs::rename(&old_root, &new_root).unwrap();
+
+        let mut registry = LocationRegistry::open(&registry_path).unwrap();
+        assert!(!registry.is_available(id));
+        assert_eq!(registry.find_relocated(&new_root).unwrap(), Some(id));
+        assert_eq!(registry.find_relocated(state.path()).unwrap(), None);
+
+        registry.relocate(id, &new_root).unwrap();
+        let loc = registry.get(id).unwrap();
+        assert_eq!(loc.root, new_root);
+        assert_eq!(loc.label, "My Docs");
+        assert!(loc.managed);
+        assert_eq!(registry.find_relocated(&new_root).unwrap(), None);
+
+        // Persisted
+        let reloaded = LocationRegistry::open(&registry_path).unwrap();
+        assert_eq!(reloaded.get(id).unwrap().root, new_root);
+    }
+
+    #[test]
+    fn test_readding_moved_location_relocates() {
+        let drive = TempDir::new().unwrap();
+        let old_root = drive.path().join("a");
+        let new_root = drive.path().join("b");
+        std::fs::create_dir(&old_root).unwrap();
+
+        let mut registry = LocationRegistry::new();
+        let id = registry.add(managed_location(&old_root, "A")).unwrap();
+
+        // Still at its root: a second registration is refused
+        let again = Location::open("A", &old_root).unwrap();
+        assert!(matches!(
+            registry.add(again),
+            Err(VfsError::AlreadyExists(_))
+        ));
+
+        std::fs::rename(&old_root, &new_root).unwrap();
+        let moved = Location::open("A", &new_root).unwrap();
+        assert_eq!(registry.add(moved).unwrap(), id);
+        assert_eq!(registry.locations().len(), 1);
+        assert_eq!(registry.get(id).unwrap().root, new_root);
+    }
+}
@@ -0,0 +1,122 @@
+//! Relocated Location roots.
+//!
+//! A Location's identity is the `id` in `.fracta/config/settings.json`, not
+//! its absolute path. When the user moves or renames the root folder, or
+//! mounts it at another path on a different machine, the `.fracta/` tree
+//! travels with it, so the ID still matches. [`Location::relocate`] re-binds
+//! a Location to its new root after checking that ID, and
+//! [`crate::LocationRegistry::find_relocated`] spots a registered Location at
+//! a new path.
+//!
+//! Everything stored inside `.fracta/` (UIDs, journal, trash, the index
+//! cache) is keyed by paths relative to the root and stays valid as is.
+
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use crate::error::{VfsError, VfsResult};
+use crate::location::{Location, FRACTA_DIR};
+use crate::settings::LocationSettings;
+
+/// The persisted ID of the managed Location at `root`, if there is one.
+pub fn location_id_at(root: &Path) -> VfsResult<Option<Uuid>> {
+    if !root.join(FRACTA_DIR).is_dir() {
+        return Ok(None);
+    }
+    Ok(LocationSettings::load(root)?.id)
+}
+
+impl Location {
+    /// Re-bind this Location to `new_root`, where its folder now lives.
+    ///
+    /// For a managed Location the `.fracta/` at `new_root` must carry this
+    /// Location's ID, otherwise this fails with `LocationMismatch` and
+    /// nothing changes. Settings and ignore rules are reloaded from the new
+    /// root. Unmanaged Locations have no persisted ID; their root is
+    /// replaced as is.
+    pub fn relocate(&mut self, new_root: impl Into<PathBuf>) -> VfsResult<()> {
+        let new_root = new_root.into();
+        if !new_root.is_dir() {
+            return Err(VfsError::NotFound(new_root));
+        }
+        if !self.managed {
+            let mut moved = Location::new(self.label.clone(), new_root);
+            moved.id = self.id;
+            moved.actor = self.actor;
+            moved.set_symlink_policy(self.symlink_policy())?;
+            *self = moved;
+            return Ok(());
+        }
+
+        let found = location_id_at(&new_root)?;
+        if found != Some(self.id) {
+            return Err(VfsError::LocationMismatch {
+                path: new_root,
+                expected: self.id,
+                found,
+            });
+        }
+        let mut reopened = Location::open(self.label.clone(), new_root)?;
+        reopened.actor = self.actor;
+        *self = reopened;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_relocate_after_root_move() {
+        let tmp = TempDir::new().unwrap();
+        let old_root = tmp.path().join("Notes");
+        fs::create_dir(&old_root).unwrap();
+        let mut loc = Location::new("Notes", &old_root);
+        loc.init().unwrap();
+        loc.create_file(&old_root.join("a.md"), b"a").unwrap();
+        let uid = loc.ensure_uid(&old_root.join("a.md")).unwrap();
+
+        let new_root = tmp.path().join("Renamed");
+        fs::rename(&old_root, &new_root).unwrap();
+        assert_eq!(location_id_at(&new_root).unwrap(), Some(loc.id));
+
+        let id = loc.id;
+        loc.relocate(&new_root).unwrap();
+        assert_eq!(loc.id, id);
+        assert_eq!(loc.root, new_root);
+        // Relative state moved along
+        assert_eq!(loc.path_of_uid(uid).unwrap(), Some(new_root.join("a.md")));
+        assert_eq!(loc.read_file(&new_root.join("a.md")).unwrap(), b"a");
+    }
+
+    #[test]
+    fn test_relocate_rejects_other_location() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        let mut loc = Location::new("A", a.path());
+        loc.init().unwrap();
+        let mut other = Location::new("B", b.path());
+        other.init().unwrap();
+
+        match loc.relocate(b.path()) {
+            Err(VfsError::LocationMismatch {
+                expected, found, ..
+            }) => {
+                assert_eq!(expected, loc.id);
+                assert_eq!(found, Some(other.id));
+            }
+            result => panic!("expected LocationMismatch, got {result:?}"),
+        }
+        assert_eq!(loc.root, a.path());
+
+        let plain = TempDir::new().unwrap();
+        assert!(matches!(
+            loc.relocate(plain.path()),
+            Err(VfsError::LocationMismatch { found: None, .. })
+        ));
+    }
+}
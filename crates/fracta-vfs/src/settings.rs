@@ -2,19 +2,196 @@
 //!
 //! Handles reading and writing `.fracta/config/settings.json`, which stores
 //! Location-level configuration including the persistent Location ID.
+//!
+//! Settings at their default value are not written. Keys this version does
+//! not know (e.g. written by a newer client) are kept in `extra` and written
+//! back unchanged. Values are validated on load and save; a bad value fails
+//! with `VfsError::InvalidSettings` naming the offending key.
 
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use uuid::Uuid;
 
 use crate::error::{VfsError, VfsResult};
 use crate::location::FRACTA_DIR;
+use crate::symlink::SymlinkPolicy;
 use crate::writer::{atomic_write_string, ensure_dir};
 
+/// Default content size limit for indexing (10 MiB).
+pub const DEFAULT_MAX_INDEX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Default watcher debounce window in milliseconds.
+pub const DEFAULT_WATCH_DEBOUNCE_MS: u64 = 500;
+
+/// Default size at which the operation journal is rotated (4 MiB).
+pub const DEFAULT_JOURNAL_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Default number of rotated journal files kept.
+pub const DEFAULT_JOURNAL_KEEP_FILES: u32 = 4;
+
+/// Default number of days trash items are kept.
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+/// Accepted range for `watcher.debounce_ms`.
+const DEBOUNCE_RANGE_MS: std::ops::RangeInclusive<u64> = 10..=60_000;
+
+/// Which files the index reads and parses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexingSettings {
+    /// Extensions (lowercase, without dot) whose content is indexed. Other
+    /// files are recorded with metadata only.
+    pub extensions: Vec<String>,
+
+    /// Files larger than this many bytes are recorded with metadata only.
+    pub max_file_size: u64,
+
+    /// Keys not known to this version, kept for round-tripping.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for IndexingSettings {
+    fn default() -> Self {
+        Self {
+            extensions: vec!["md".to_string(), "markdown".to_string()],
+            max_file_size: DEFAULT_MAX_INDEX_FILE_SIZE,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl IndexingSettings {
+    /// Whether the content of a file at `path` with `size` bytes is indexed.
+    pub fn indexes(&self, path: &Path, size: u64) -> bool {
+        let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+            return false;
+        };
+        size <= self.max_file_size && self.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext))
+    }
+
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Filesystem watcher tuning.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatcherSettings {
+    /// Window in which rapid changes are coalesced into one event.
+    pub debounce_ms: u64,
+
+    /// Keys not known to this version, kept for round-tripping.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for WatcherSettings {
+    fn default() -> Self {
+        Self {
+            debounce_ms: DEFAULT_WATCH_DEBOUNCE_MS,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl WatcherSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Operation journal (see [`crate::journal`]).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalSettings {
+    /// Record every mutation made through `Location` (opt-in).
+    pub enabled: bool,
+
+    /// Rotate the active journal file before it grows past this many bytes.
+    pub max_file_size: u64,
+
+    /// Rotated files kept besides the active one; older ones are deleted.
+    pub keep_files: u32,
+
+    /// Keys not known to this version, kept for round-tripping.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for JournalSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_file_size: DEFAULT_JOURNAL_MAX_FILE_SIZE,
+            keep_files: DEFAULT_JOURNAL_KEEP_FILES,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl JournalSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Trash (see [`crate::trash`]).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashSettings {
+    /// Items trashed more than this many days ago are purged when the
+    /// Location is opened; 0 keeps them until the trash is emptied.
+    pub retention_days: u32,
+
+    /// Keys not known to this version, kept for round-tripping.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl TrashSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Privacy level of content (ENGINEERING §5.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Privacy {
+    Public,
+    #[default]
+    Internal,
+    Sensitive,
+}
+
+impl Privacy {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// Location settings stored in `.fracta/config/settings.json`.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct LocationSettings {
+    /// Version of the `.fracta/` layout (see [`crate::layout`]); absent in
+    /// trees that predate versioning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_version: Option<u32>,
+
     /// Persistent Location ID (survives across sessions).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<Uuid>,
@@ -22,30 +199,84 @@ pub struct LocationSettings {
     /// Location label (user-friendly name).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
+
+    /// Honor `.gitignore` files found inside the Location (opt-in).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub honor_gitignore: bool,
+
+    /// Extra ignore patterns per folder (relative path → gitignore lines),
+    /// applied as if written in that folder but without touching it.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub folder_ignores: BTreeMap<String, Vec<String>>,
+
+    /// How symlinks inside the Location are treated (see [`SymlinkPolicy`]).
+    #[serde(default, skip_serializing_if = "SymlinkPolicy::is_default")]
+    pub symlinks: SymlinkPolicy,
+
+    /// What the index reads.
+    #[serde(default, skip_serializing_if = "IndexingSettings::is_default")]
+    pub indexing: IndexingSettings,
+
+    /// Watcher tuning.
+    #[serde(default, skip_serializing_if = "WatcherSettings::is_default")]
+    pub watcher: WatcherSettings,
+
+    /// Operation journal.
+    #[serde(default, skip_serializing_if = "JournalSettings::is_default")]
+    pub journal: JournalSettings,
+
+    /// Trash retention.
+    #[serde(default, skip_serializing_if = "TrashSettings::is_default")]
+    pub trash: TrashSettings,
+
+    /// Privacy level of content that does not declare its own.
+    #[serde(default, skip_serializing_if = "Privacy::is_default")]
+    pub default_privacy: Privacy,
+
+    /// Allow AI features to process this Location (opt-in).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ai_enabled: bool,
+
+    /// Keys not known to this version, kept for round-tripping.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl LocationSettings {
     /// Load settings from a Location root directory.
     ///
-    /// Returns default settings if the file doesn't exist or is invalid.
+    /// Returns default settings if the file doesn't exist. Fails with
+    /// `InvalidSettings` if it cannot be parsed or a value is out of range.
     pub fn load(root: &Path) -> VfsResult<Self> {
-        let path = root.join(FRACTA_DIR).join("config").join("settings.json");
+        let path = Self::path(root);
 
         if !path.exists() {
             return Ok(Self::default());
         }
 
         let content = std::fs::read_to_string(&path).map_err(|e| VfsError::Io { source: e })?;
+        Self::parse(&content, &path)
+    }
 
-        serde_json::from_str(&content).map_err(|e| VfsError::Io {
-            source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
-        })
+    /// Parse and validate settings read from `path` by other means (e.g.
+    /// through a [`crate::VfsBackend`]).
+    pub fn parse(content: &str, path: &Path) -> VfsResult<Self> {
+        let settings: Self =
+            serde_json::from_str(content).map_err(|e| VfsError::InvalidSettings {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            })?;
+        settings.validate(path)?;
+        Ok(settings)
     }
 
     /// Save settings to a Location root directory.
+    ///
+    /// Fails with `InvalidSettings`, writing nothing, if a value is invalid.
     pub fn save(&self, root: &Path) -> VfsResult<()> {
         let config_dir = root.join(FRACTA_DIR).join("config");
-        let path = config_dir.join("settings.json");
+        let path = Self::path(root);
+        self.validate(&path)?;
 
         // Ensure the config directory exists before writing
         ensure_dir(&config_dir)?;
@@ -57,6 +288,44 @@ impl LocationSettings {
         atomic_write_string(&path, &content)
     }
 
+    /// Check every value, naming the first offending key.
+    pub fn validate(&self, path: &Path) -> VfsResult<()> {
+        let invalid = |reason: String| {
+            Err(VfsError::InvalidSettings {
+                path: path.to_path_buf(),
+                reason,
+            })
+        };
+
+        for ext in &self.indexing.extensions {
+            if ext.is_empty() || ext.contains(['.', '/', '\\']) {
+                return invalid(format!(
+                    "indexing.extensions: {ext:?} must be a bare extension like \"md\""
+                ));
+            }
+        }
+        if self.indexing.max_file_size == 0 {
+            return invalid("indexing.max_file_size must be greater than 0".to_string());
+        }
+        if self.journal.max_file_size == 0 {
+            return invalid("journal.max_file_size must be greater than 0".to_string());
+        }
+        if !DEBOUNCE_RANGE_MS.contains(&self.watcher.debounce_ms) {
+            return invalid(format!(
+                "watcher.debounce_ms must be between {} and {}, got {}",
+                DEBOUNCE_RANGE_MS.start(),
+                DEBOUNCE_RANGE_MS.end(),
+                self.watcher.debounce_ms
+            ));
+        }
+        Ok(())
+    }
+
+    /// Path of the settings file for a Location root.
+    fn path(root: &Path) -> PathBuf {
+        root.join(FRACTA_DIR).join("config").join("settings.json")
+    }
+
     /// Get or create a persistent ID.
     ///
     /// If no ID exists, generates a new one and returns it.
@@ -103,6 +372,82 @@ mod tests {
         assert_eq!(loaded.label, Some("Test Location".to_string()));
     }
 
+    #[test]
+    fn test_defaults_not_written() {
+        let tmp = TempDir::new().unwrap();
+        LocationSettings::default().save(tmp.path()).unwrap();
+        let written = std::fs::read_to_string(LocationSettings::path(tmp.path())).unwrap();
+        assert_eq!(written, "{}");
+    }
+
+    #[test]
+    fn test_typed_settings_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let mut settings = LocationSettings::default();
+        settings.indexing.extensions = vec!["md".into(), "txt".into()];
+        settings.indexing.max_file_size = 1024;
+        settings.watcher.debounce_ms = 200;
+        settings.default_privacy = Privacy::Sensitive;
+        settings.ai_enabled = true;
+        settings.save(tmp.path()).unwrap();
+
+        let loaded = LocationSettings::load(tmp.path()).unwrap();
+        assert_eq!(loaded, settings);
+        assert!(loaded.indexing.indexes(Path::new("a/notes.TXT"), 10));
+        assert!(!loaded.indexing.indexes(Path::new("a/notes.txt"), 2048));
+        assert!(!loaded.indexing.indexes(Path::new("image.png"), 10));
+    }
+
+    #[test]
+    fn test_unknown_keys_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let path = LocationSettings::path(tmp.path());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            r#"{"label":"Old","future":{"x":1},"indexing":{"max_file_size":5,"future_flag":true}}"#,
+        )
+        .unwrap();
+
+        let mut settings = LocationSettings::load(tmp.path()).unwrap();
+        settings.label = Some("New".into());
+        settings.save(tmp.path()).unwrap();
+
+        let json: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["label"], "New");
+        assert_eq!(json["future"]["x"], 1);
+        assert_eq!(json["indexing"]["max_file_size"], 5);
+        assert_eq!(json["indexing"]["future_flag"], true);
+    }
+
+    #[test]
+    fn test_invalid_values_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let path = LocationSettings::path(tmp.path());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        std::fs::write(&path, r#"{"watcher":{"debounce_ms":0}}"#).unwrap();
+        match LocationSettings::load(tmp.path()) {
+            Err(VfsError::InvalidSettings { reason, .. }) => {
+                assert!(reason.contains("watcher.debounce_ms"), "{reason}");
+            }
+            other => panic!("expected InvalidSettings, got {other:?}"),
+        }
+
+        std::fs::write(&path, r#"{"default_privacy":"secret"}"#).unwrap();
+        assert!(matches!(
+            LocationSettings::load(tmp.path()),
+            Err(VfsError::InvalidSettings { .. })
+        ));
+
+        let mut settings = LocationSettings::default();
+        settings.indexing.extensions = vec![".md".into()];
+        assert!(matches!(
+            settings.save(tmp.path()),
+            Err(VfsError::InvalidSettings { .. })
+        ));
+    }
+
     #[test]
     fn test_get_or_create_id_idempotent() {
         let mut settings = LocationSettings::default();
@@ -0,0 +1,127 @@
+//! Symlink policy (ENGINEERING §5.3).
+//!
+//! Each Location decides how symlinks inside it are treated, stored as
+//! `symlinks` in `.fracta/config/settings.json`:
+//!
+//! - `ignore`: symlinks are never followed and are left out of listings
+//!   and walks.
+//! - `follow_within` (default): symlinks whose target stays inside the
+//!   Location are followed; escaping ones are listed with Plain scope but
+//!   cannot be read or written through.
+//! - `follow_anywhere`: every symlink is followed for reading, so walks may
+//!   leave the Location; anything reached outside the root has Plain scope
+//!   and is read-only.
+//!
+//! Walks that follow symlinks enter each folder once: a link to a folder
+//! the walk covers anyway, including one of its own ancestors, is listed but
+//! not entered. A cycle between links, which never resolves, is reported as
+//! skipped.
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VfsResult;
+use crate::handle::{LocationHandle, RelPath};
+
+/// How a Location treats symlinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    /// Never follow symlinks; hide them from listings and walks.
+    Ignore,
+    /// Follow symlinks that resolve inside the Location.
+    #[default]
+    FollowWithin,
+    /// Follow any symlink; targets outside the Location are read-only.
+    FollowAnywhere,
+}
+
+impl SymlinkPolicy {
+    /// Whether a symlink of `kind` is followed under this policy.
+    pub fn follows(self, kind: SymlinkKind) -> bool {
+        match (self, kind) {
+            (_, SymlinkKind::Broken | SymlinkKind::Loop) => false,
+            (SymlinkPolicy::Ignore, _) => false,
+            (SymlinkPolicy::FollowWithin, kind) => kind == SymlinkKind::Internal,
+            (SymlinkPolicy::FollowAnywhere, _) => true,
+        }
+    }
+
+    /// Whether this is the default policy (not written to settings).
+    pub fn is_default(&self) -> bool {
+        *self == SymlinkPolicy::default()
+    }
+}
+
+/// Where a symlink points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkKind {
+    /// Resolves to a path inside the Location.
+    Internal,
+    /// Resolves to a path outside the Location.
+    External,
+    /// The target does not exist.
+    Broken,
+    /// Resolving the target loops back on itself.
+    Loop,
+}
+
+/// A symlink entry: what it points to and how that resolves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symlink {
+    /// Where the target lies relative to the Location.
+    pub kind: SymlinkKind,
+    /// The target as stored in the link (may be relative to the link).
+    pub target: PathBuf,
+}
+
+impl LocationHandle {
+    /// Inspect the symlink at `rel` without following it for access.
+    ///
+    /// Returns the link description and, unless it is broken or loops, the
+    /// fully resolved target.
+    pub(crate) fn inspect_symlink(&self, rel: &RelPath) -> VfsResult<(Symlink, Option<PathBuf>)> {
+        let parent = self.resolve(&rel.parent().unwrap_or_default())?;
+        let link = match rel.file_name() {
+            Some(name) => parent.join(name),
+            None => parent.to_path_buf(),
+        };
+        let target = fs::read_link(&link).unwrap_or_default();
+
+        let (kind, resolved) = match link.canonicalize() {
+            Ok(resolved) if resolved.starts_with(self.canonical_root()) => {
+                (SymlinkKind::Internal, Some(resolved))
+            }
+            Ok(resolved) => (SymlinkKind::External, Some(resolved)),
+            Err(e) if crate::walk::is_symlink_loop(&e) => (SymlinkKind::Loop, None),
+            Err(_) => (SymlinkKind::Broken, None),
+        };
+        Ok((Symlink { kind, target }, resolved))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_follows() {
+        use SymlinkKind::*;
+        assert!(!SymlinkPolicy::Ignore.follows(Internal));
+        assert!(SymlinkPolicy::FollowWithin.follows(Internal));
+        assert!(!SymlinkPolicy::FollowWithin.follows(External));
+        assert!(SymlinkPolicy::FollowAnywhere.follows(External));
+        assert!(!SymlinkPolicy::FollowAnywhere.follows(Broken));
+        assert!(!SymlinkPolicy::FollowAnywhere.follows(Loop));
+    }
+
+    #[test]
+    fn test_policy_serde() {
+        let json = serde_json::to_string(&SymlinkPolicy::FollowAnywhere).unwrap();
+        assert_eq!(json, "\"follow_anywhere\"");
+        assert!(SymlinkPolicy::default().is_default());
+    }
+}
@@ -0,0 +1,653 @@
+//! Per-Location trash (soft delete).
+//!
+//! Deleting inside a managed Location moves the entry into
+//! `.fracta/trash/<id>/` instead of removing it, so a bad click, sync glitch
+//! or AI action can be undone. Each trash item is a directory holding:
+//!
+//! - `info.json` — original relative path, entry kind and deletion time
+//! - `payload` — the deleted file or folder itself (renamed, not copied)
+//!
+//! An item is assembled in a hidden staging directory (`.partial-<id>`) and
+//! renamed into place once its payload is in, so listing and purging never
+//! see a half-moved entry. Purging leaves staging directories alone until
+//! they are older than a grace period; then one left behind by a crash is
+//! published if its payload arrived, and removed otherwise.
+//!
+//! Items older than the `trash.retention_days` setting are purged when the
+//! Location is opened.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::entry::EntryKind;
+use crate::error::{VfsError, VfsResult};
+use crate::handle::{LocationHandle, RelPath};
+use crate::journal::JournalOp;
+use crate::location::{Location, FRACTA_DIR};
+
+/// Trash directory name within `.fracta/`.
+pub const TRASH_DIR: &str = "trash";
+
+/// Metadata file inside each trash item.
+const INFO_FILE: &str = "info.json";
+
+/// The moved entry inside each trash item.
+const PAYLOAD: &str = "payload";
+
+/// Name prefix of an item directory that is still being assembled.
+const STAGING_PREFIX: &str = ".partial-";
+
+/// How long a staging directory may be in use by a concurrent move.
+const STAGING_GRACE: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// A soft-deleted entry that can be restored or purged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    /// Trash item ID (UUIDv7, so IDs sort by deletion time).
+    pub id: Uuid,
+    /// Absolute path the entry had when it was deleted.
+    pub original_path: PathBuf,
+    /// File name of the deleted entry.
+    pub name: String,
+    /// File or folder.
+    pub kind: EntryKind,
+    /// When the entry was moved to the trash.
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// What to do when restoring onto a path that is occupied again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestoreConflict {
+    /// Fail with `AlreadyExists`.
+    #[default]
+    Fail,
+    /// Restore next to the occupant as "name (restored).ext".
+    KeepBoth,
+    /// Move the occupant to the trash, then restore into its place.
+    Replace,
+}
+
+/// Persisted contents of `info.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct TrashInfo {
+    id: Uuid,
+    /// Relative to the Location root.
+    original_path: PathBuf,
+    kind: EntryKind,
+    deleted_at: DateTime<Utc>,
+}
+
+impl Location {
+    /// Path to the trash area (`.fracta/trash/`).
+    pub fn trash_dir(&self) -> PathBuf {
+        self.fracta_dir().join(TRASH_DIR)
+    }
+
+    /// Whether deletes in this Location go to the trash.
+    ///
+    /// True for managed Locations whose `.fracta/` directory exists.
+    pub fn uses_trash(&self) -> bool {
+        self.managed && self.fracta_dir().is_dir()
+    }
+
+    /// Move a file or folder into the trash. Returns the new trash item.
+    pub fn move_to_trash(&self, path: &Path) -> VfsResult<TrashItem> {
+        let rel = self.writable_rel(path)?;
+        if rel.is_root() {
+            return Err(VfsError::PermissionDenied(path.to_path_buf()));
+        }
+        let handle = self.handle()?;
+        let metadata = handle.symlink_metadata(&rel)?;
+
+        let info = TrashInfo {
+            id: Uuid::now_v7(),
+            original_path: rel.as_path().to_path_buf(),
+            kind: if metadata.is_dir() {
+                EntryKind::Folder
+            } else {
+                EntryKind::File
+            },
+            deleted_at: Utc::now(),
+        };
+
+        let trash = trash_rel()?;
+        let staging = trash.join(format!("{STAGING_PREFIX}{}", info.id))?;
+        handle.create_dir_all(&staging)?;
+        handle.create_file(&staging.join(INFO_FILE)?, &encode_info(&info)?)?;
+        let before = self.journal_hash(&rel);
+        handle.rename(&rel, &staging.join(PAYLOAD)?)?;
+        // Publish the complete item in one step
+        let item_rel = trash.join(info.id.to_string())?;
+        handle.rename(&staging, &item_rel)?;
+        let payload = item_rel.join(PAYLOAD)?;
+        self.record_uid_move(&rel, &payload);
+        self.record_op(JournalOp::Trash, &rel, None, before, None);
+
+        Ok(self.trash_item(info))
+    }
+
+    /// List trash items, most recently deleted first.
+    ///
+    /// Incomplete or unreadable items are skipped.
+    pub fn list_trash(&self) -> VfsResult<Vec<TrashItem>> {
+        let mut items: Vec<_> = self
+            .read_trash_infos()?
+            .into_iter()
+            .map(|info| self.trash_item(info))
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+        Ok(items)
+    }
+
+    /// Restore a trash item to its original path. Returns the restored path.
+    ///
+    /// Missing parent folders are recreated. `on_conflict` decides what
+    /// happens if something else, or a name that collides with it, now
+    /// occupies the original path. A recorded path that a normal write could
+    /// not use, such as one inside `.fracta/`, is refused.
+    pub fn restore_from_trash(&self, id: Uuid, on_conflict: RestoreConflict) -> VfsResult<PathBuf> {
+        let handle = self.handle()?;
+        let item_rel = trash_rel()?.join(id.to_string())?;
+        let info = self.read_trash_info(&item_rel)?;
+        let payload = item_rel.join(PAYLOAD)?;
+        if !has_payload(handle, &item_rel)? {
+            return Err(VfsError::NotFound(handle.absolute(&item_rel)));
+        }
+
+        // info.json is only data: restore where a normal write could go
+        let original = handle.absolute(&RelPath::new(&info.original_path)?);
+        let mut target = self.writable_rel(&original)?;
+        if target.is_root() {
+            return Err(VfsError::PermissionDenied(original));
+        }
+        match on_conflict {
+            RestoreConflict::KeepBoth => {
+                // The original path itself when it is free
+                target = self.unique_rel(&target, |n| match n {
+                    1 => " (restored)".to_string(),
+                    n => format!(" (restored {n})"),
+                })?;
+            }
+            RestoreConflict::Fail | RestoreConflict::Replace => {
+                // A case-folding or Unicode twin is never replaced
+                self.check_collision(&target, None)?;
+                if handle.exists(&target) {
+                    if on_conflict == RestoreConflict::Fail {
+                        return Err(VfsError::AlreadyExists(original));
+                    }
+                    self.move_to_trash(&original)?;
+                }
+            }
+        }
+
+        if let Some(parent) = target.parent() {
+            handle.create_dir_all(&parent)?;
+        }
+        handle.rename(&payload, &target)?;
+        self.record_uid_move(&payload, &target);
+        handle.remove_dir_all(&item_rel)?;
+        let after = self.journal_hash(&target);
+        self.record_op(JournalOp::Restore, &target, None, None, after);
+
+        Ok(handle.absolute(&target))
+    }
+
+    /// Permanently delete a single trash item.
+    pub fn purge_trash_item(&self, id: Uuid) -> VfsResult<()> {
+        let handle = self.handle()?;
+        let item_rel = trash_rel()?.join(id.to_string())?;
+        if !handle.exists(&item_rel) {
+            return Err(VfsError::NotFound(handle.absolute(&item_rel)));
+        }
+        handle.remove_dir_all(&item_rel)?;
+        self.record_uid_removal(&item_rel);
+        Ok(())
+    }
+
+    /// Permanently delete everything in the trash. Returns the number of items removed.
+    pub fn empty_trash(&self) -> VfsResult<usize> {
+        self.purge_trash_where(|_| true)
+    }
+
+    /// Permanently delete items trashed more than `retention` ago.
+    ///
+    /// Also cleans up after a crash: incomplete items are removed and stale
+    /// staged ones are published or removed. Returns the number of items
+    /// removed.
+    pub fn purge_expired_trash(&self, retention: chrono::Duration) -> VfsResult<usize> {
+        let cutoff = Utc::now() - retention;
+        self.purge_trash_where(|info| info.is_none_or(|info| info.deleted_at < cutoff))
+    }
+
+    /// Purge items older than `retention_days` (0 keeps everything).
+    ///
+    /// Run when the Location is opened. A failure is logged rather than
+    /// returned, so a damaged trash never keeps a Location from opening.
+    pub(crate) fn purge_trash_on_open(&self, retention_days: u32) {
+        if retention_days == 0 {
+            return;
+        }
+        let retention = chrono::Duration::days(retention_days.into());
+        if let Err(e) = self.purge_expired_trash(retention) {
+            tracing::warn!(root = %self.root.display(), "trash purge failed: {e}");
+        }
+    }
+
+    /// Remove every trash item directory for which `should_purge` returns true.
+    ///
+    /// The predicate receives `None` for items whose info is missing or whose
+    /// payload is gone.
+    fn purge_trash_where(
+        &self,
+        should_purge: impl Fn(Option<&TrashInfo>) -> bool,
+    ) -> VfsResult<usize> {
+        let handle = self.handle()?;
+        let trash = trash_rel()?;
+        if !handle.exists(&trash) {
+            return Ok(0);
+        }
+
+        let mut removed = 0;
+        for child in handle.read_dir(&trash)? {
+            let (item_rel, _) = child?;
+            if is_staging(&item_rel) {
+                removed += self.recover_staged(&item_rel)?;
+                continue;
+            }
+            let complete = has_payload(handle, &item_rel)?;
+            let info = self.read_trash_info(&item_rel).ok().filter(|_| complete);
+            if should_purge(info.as_ref()) {
+                handle.remove_dir_all(&item_rel)?;
+                self.record_uid_removal(&item_rel);
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Finish or drop a staging directory older than [`STAGING_GRACE`].
+    ///
+    /// Returns 1 if it was removed, 0 if it was published or left alone.
+    fn recover_staged(&self, staging: &RelPath) -> VfsResult<usize> {
+        let handle = self.handle()?;
+        let modified = handle.symlink_metadata(staging)?.modified()?;
+        if modified.elapsed().is_ok_and(|age| age < STAGING_GRACE) {
+            return Ok(0);
+        }
+        let id = staging
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_prefix(STAGING_PREFIX))
+            .and_then(|id| Uuid::parse_str(id).ok());
+        if let Some(id) = id {
+            if has_payload(handle, staging)? && self.read_trash_info(staging).is_ok() {
+                let item_rel = trash_rel()?.join(id.to_string())?;
+                handle.rename(staging, &item_rel)?;
+                return Ok(0);
+            }
+        }
+        handle.remove_dir_all(staging)?;
+        Ok(1)
+    }
+
+    /// Read every complete trash item's info.
+    fn read_trash_infos(&self) -> VfsResult<Vec<TrashInfo>> {
+        let handle = self.handle()?;
+        let trash = trash_rel()?;
+        if !handle.exists(&trash) {
+            return Ok(Vec::new());
+        }
+
+        let mut infos = Vec::new();
+        for child in handle.read_dir(&trash)? {
+            let Ok((item_rel, _)) = child else { continue };
+            if is_staging(&item_rel) {
+                continue;
+            }
+            let Ok(info) = self.read_trash_info(&item_rel) else {
+                continue;
+            };
+            if has_payload(handle, &item_rel)? {
+                infos.push(info);
+            }
+        }
+        Ok(infos)
+    }
+
+    fn read_trash_info(&self, item_rel: &RelPath) -> VfsResult<TrashInfo> {
+        let bytes = self.handle()?.read(&item_rel.join(INFO_FILE)?)?;
+        serde_json::from_slice(&bytes).map_err(|e| VfsError::Io {
+            source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
+        })
+    }
+
+    fn trash_item(&self, info: TrashInfo) -> TrashItem {
+        TrashItem {
+            id: info.id,
+            name: info
+                .original_path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            original_path: self.root.join(&info.original_path),
+            kind: info.kind,
+            deleted_at: info.deleted_at,
+        }
+    }
+}
+
+/// `.fracta/trash` as a relative path.
+fn trash_rel() -> VfsResult<RelPath> {
+    RelPath::root().join(FRACTA_DIR)?.join(TRASH_DIR)
+}
+
+/// Whether `item_rel` is a staging directory rather than a trash item.
+fn is_staging(item_rel: &RelPath) -> bool {
+    item_rel
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(STAGING_PREFIX))
+}
+
+/// Whether a trash item (or staging directory) holds its payload. A
+/// trashed symlink counts even if it dangles.
+fn has_payload(handle: &LocationHandle, item_rel: &RelPath) -> VfsResult<bool> {
+    Ok(handle.symlink_metadata(&item_rel.join(PAYLOAD)?).is_ok())
+}
+
+fn encode_info(info: &TrashInfo) -> VfsResult<Vec<u8>> {
+    serde_json::to_vec_pretty(info).map_err(|e| VfsError::Io {
+        source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn managed(tmp: &TempDir) -> Location {
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        loc
+    }
+
+    #[test]
+    fn test_delete_moves_to_trash_and_restores() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        let path = tmp.path().join("note.md");
+        loc.create_file(&path, b"keep me").unwrap();
+
+        loc.delete_file(&path).unwrap();
+        assert!(!path.exists());
+
+        let items = loc.list_trash().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].original_path, path);
+        assert_eq!(items[0].name, "note.md");
+        assert_eq!(items[0].kind, EntryKind::File);
+
+        let restored = loc
+            .restore_from_trash(items[0].id, RestoreConflict::Fail)
+            .unwrap();
+        assert_eq!(restored, path);
+        assert_eq!(loc.read_file_string(&path).unwrap(), "keep me");
+        assert!(loc.list_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_checks_entry_kind() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        let folder = tmp.path().join("projects");
+        let file = tmp.path().join("note.md");
+        std::fs::create_dir(&folder).unwrap();
+        loc.create_file(&file, b"x").unwrap();
+
+        assert!(loc.delete_file(&folder).is_err());
+        assert!(loc.delete_folder(&file).is_err());
+        assert!(folder.is_dir() && file.is_file());
+        assert!(loc.list_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_dangling_symlink_deleted() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        let trashed = tmp.path().join("trashed");
+        let removed = tmp.path().join("removed");
+        std::os::unix::fs::symlink(tmp.path().join("gone.md"), &trashed).unwrap();
+        std::os::unix::fs::symlink(tmp.path().join("gone.md"), &removed).unwrap();
+
+        loc.delete_file(&trashed).unwrap();
+        loc.delete_permanently(&removed).unwrap();
+        assert!(std::fs::symlink_metadata(&trashed).is_err());
+        assert!(std::fs::symlink_metadata(&removed).is_err());
+        assert_eq!(loc.list_trash().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_folder_restore_recreates_parent() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        let folder = tmp.path().join("projects/alpha");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("plan.md"), "plan").unwrap();
+
+        let item = loc.move_to_trash(&folder).unwrap();
+        assert_eq!(item.kind, EntryKind::Folder);
+        std::fs::remove_dir(tmp.path().join("projects")).unwrap();
+
+        loc.restore_from_trash(item.id, RestoreConflict::Fail)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(folder.join("plan.md")).unwrap(),
+            "plan"
+        );
+    }
+
+    #[test]
+    fn test_restore_conflicts() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        let path = tmp.path().join("note.md");
+
+        loc.create_file(&path, b"old").unwrap();
+        let item = loc.move_to_trash(&path).unwrap();
+        loc.create_file(&path, b"new").unwrap();
+
+        let err = loc
+            .restore_from_trash(item.id, RestoreConflict::Fail)
+            .unwrap_err();
+        assert!(matches!(err, VfsError::AlreadyExists(_)));
+
+        let restored = loc
+            .restore_from_trash(item.id, RestoreConflict::KeepBoth)
+            .unwrap();
+        assert_eq!(restored, tmp.path().join("note (restored).md"));
+        assert_eq!(loc.read_file_string(&path).unwrap(), "new");
+
+        // Replace: the occupant goes to the trash instead of being lost
+        let item = loc.move_to_trash(&path).unwrap();
+        loc.create_file(&path, b"newer").unwrap();
+        loc.restore_from_trash(item.id, RestoreConflict::Replace)
+            .unwrap();
+        assert_eq!(loc.read_file_string(&path).unwrap(), "new");
+
+        let trashed = loc.list_trash().unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].original_path, path);
+    }
+
+    #[test]
+    fn test_restore_target_checked() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        let path = tmp.path().join("Note.md");
+        loc.create_file(&path, b"old").unwrap();
+        let item = loc.move_to_trash(&path).unwrap();
+
+        // A case-folding twin blocks the restore unless both are kept
+        let twin = tmp.path().join("note.md");
+        loc.create_file(&twin, b"twin").unwrap();
+        for on_conflict in [RestoreConflict::Fail, RestoreConflict::Replace] {
+            let err = loc.restore_from_trash(item.id, on_conflict).unwrap_err();
+            assert!(matches!(err, VfsError::NameCollision { .. }));
+        }
+        assert_eq!(loc.read_file_string(&twin).unwrap(), "twin");
+        assert_eq!(loc.list_trash().unwrap().len(), 1);
+
+        // A tampered entry cannot restore over engine state
+        let settings = loc.fracta_dir().join("config/settings.json");
+        let before = std::fs::read(&settings).unwrap();
+        let info_path = loc.trash_dir().join(item.id.to_string()).join(INFO_FILE);
+        let mut info: TrashInfo =
+            serde_json::from_slice(&std::fs::read(&info_path).unwrap()).unwrap();
+        info.original_path = PathBuf::from(FRACTA_DIR).join("config/settings.json");
+        std::fs::write(&info_path, encode_info(&info).unwrap()).unwrap();
+
+        for on_conflict in [RestoreConflict::Fail, RestoreConflict::Replace] {
+            let err = loc.restore_from_trash(item.id, on_conflict).unwrap_err();
+            assert!(matches!(err, VfsError::PermissionDenied(_)));
+        }
+        assert_eq!(std::fs::read(&settings).unwrap(), before);
+        assert_eq!(loc.list_trash().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_purge_and_retention() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        for name in ["a.md", "b.md", "c.md"] {
+            let path = tmp.path().join(name);
+            loc.create_file(&path, b"x").unwrap();
+            loc.delete_file(&path).unwrap();
+        }
+
+        let items = loc.list_trash().unwrap();
+        loc.purge_trash_item(items[0].id).unwrap();
+        assert_eq!(loc.list_trash().unwrap().len(), 2);
+
+        // Nothing is older than a day yet
+        assert_eq!(
+            loc.purge_expired_trash(chrono::Duration::days(1)).unwrap(),
+            0
+        );
+        // Zero retention purges everything
+        assert_eq!(
+            loc.purge_expired_trash(chrono::Duration::zero()).unwrap(),
+            2
+        );
+        assert!(loc.list_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_purges_by_retention_setting() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        for name in ["old.md", "new.md"] {
+            let path = tmp.path().join(name);
+            loc.create_file(&path, b"x").unwrap();
+            loc.delete_file(&path).unwrap();
+        }
+        // Backdate the first item past the default retention
+        let old = loc.list_trash().unwrap().pop().unwrap();
+        let info_path = loc.trash_dir().join(old.id.to_string()).join(INFO_FILE);
+        let mut info: TrashInfo =
+            serde_json::from_slice(&std::fs::read(&info_path).unwrap()).unwrap();
+        info.deleted_at -= chrono::Duration::days(31);
+        std::fs::write(&info_path, encode_info(&info).unwrap()).unwrap();
+
+        let mut settings = crate::LocationSettings::load(tmp.path()).unwrap();
+        settings.trash.retention_days = 0;
+        settings.save(tmp.path()).unwrap();
+        let reopened = Location::open("test", tmp.path()).unwrap();
+        assert_eq!(reopened.list_trash().unwrap().len(), 2);
+
+        settings.trash = Default::default();
+        settings.save(tmp.path()).unwrap();
+        let reopened = Location::open("test", tmp.path()).unwrap();
+        let items = reopened.list_trash().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "new.md");
+    }
+
+    #[test]
+    fn test_incomplete_items_skipped_and_purged() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        // Simulate a crash after the item directory was created
+        std::fs::create_dir_all(loc.trash_dir().join(Uuid::now_v7().to_string())).unwrap();
+
+        assert!(loc.list_trash().unwrap().is_empty());
+        assert_eq!(
+            loc.purge_expired_trash(chrono::Duration::days(30)).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_staged_items_wait_for_grace_period() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed(&tmp);
+        let path = tmp.path().join("note.md");
+        loc.create_file(&path, b"x").unwrap();
+
+        // A move caught between writing info.json and publishing the item
+        let id = Uuid::now_v7();
+        let staging = loc.trash_dir().join(format!("{STAGING_PREFIX}{id}"));
+        std::fs::create_dir_all(&staging).unwrap();
+        let info = TrashInfo {
+            id,
+            original_path: "note.md".into(),
+            kind: EntryKind::File,
+            deleted_at: Utc::now(),
+        };
+        std::fs::write(staging.join(INFO_FILE), encode_info(&info).unwrap()).unwrap();
+        let abandoned = loc
+            .trash_dir()
+            .join(format!("{STAGING_PREFIX}{}", Uuid::now_v7()));
+        std::fs::create_dir_all(&abandoned).unwrap();
+
+        assert_eq!(loc.empty_trash().unwrap(), 0);
+        assert!(staging.is_dir() && abandoned.is_dir());
+        assert!(loc.list_trash().unwrap().is_empty());
+
+        std::fs::rename(&path, staging.join(PAYLOAD)).unwrap();
+        let stale = std::time::SystemTime::now() - 2 * STAGING_GRACE;
+        for dir in [&staging, &abandoned] {
+            std::fs::File::open(dir)
+                .unwrap()
+                .set_modified(stale)
+                .unwrap();
+        }
+
+        // Past the grace period: the finished one is published, the other dropped
+        assert_eq!(
+            loc.purge_expired_trash(chrono::Duration::days(30)).unwrap(),
+            1
+        );
+        assert!(!abandoned.exists());
+        let items = loc.list_trash().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, id);
+        loc.restore_from_trash(id, RestoreConflict::Fail).unwrap();
+        assert_eq!(loc.read_file_string(&path).unwrap(), "x");
+    }
+
+    #[test]
+    fn test_unmanaged_delete_is_permanent() {
+        let tmp = TempDir::new().unwrap();
+        let loc = Location::new("plain", tmp.path());
+        let path = tmp.path().join("gone.md");
+        std::fs::write(&path, "x").unwrap();
+
+        loc.delete_file(&path).unwrap();
+        assert!(!path.exists());
+        assert!(!loc.trash_dir().exists());
+    }
+}
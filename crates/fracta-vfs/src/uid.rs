@@ -0,0 +1,594 @@
+//! Lazy UID assignment (ADR-0005, SPEC §8).
+//!
+//! A UID is a UUIDv7 that gives a file or folder a stable identity across
+//! moves and renames. UIDs are only created when something needs one
+//! (metadata, links, proofs), never up front.
+//!
+//! The mapping lives in `.fracta/meta/uids.jsonl` as an append-only log:
+//! each line sets a UID's current relative path, or releases it with
+//! `"path": null`; later lines win. Appends are fsynced one line at a time,
+//! so a crash can at worst leave a torn last line, which loading skips.
+//! The log is compacted (rewritten atomically as one line per live UID)
+//! when it grows well past the number of live entries, or after a torn
+//! line was found.
+//!
+//! Every `Location` open on the same root shares one in-memory map, so two
+//! instances (say, one opened by the shell and the registry's copy) never
+//! append conflicting lines or compact away each other's UIDs. Paths are
+//! looked up by their NFC form (see [`normalize_path`]), so NFD and NFC
+//! spellings of a name share one UID.
+//!
+//! Moves done through `Location` (rename, move, trash, restore) and renames
+//! picked up by the watcher keep the mapping up to date. Copies do not
+//! inherit a UID; a UID that turns up on a second path (e.g. a copied file
+//! that carries it in front matter) is reported by [`Location::claim_uid`].
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::collision::normalize_path;
+use crate::error::{VfsError, VfsResult};
+use crate::handle::{LocationHandle, RelPath};
+use crate::location::{Location, FRACTA_DIR};
+use crate::scope::Scope;
+use crate::writer::atomic_write;
+
+/// UID log file name within `.fracta/meta/`.
+pub const UIDS_FILE: &str = "uids.jsonl";
+
+/// Compact once the log has this many more lines than live entries.
+const COMPACTION_SLACK: usize = 256;
+
+/// One line of `uids.jsonl`.
+#[derive(Debug, Serialize, Deserialize)]
+struct UidRecord {
+    uid: Uuid,
+    /// Relative to the Location root; `None` releases the UID.
+    path: Option<PathBuf>,
+}
+
+/// In-memory view of `uids.jsonl`.
+#[derive(Debug, Default)]
+pub(crate) struct UidMap {
+    by_uid: HashMap<Uuid, PathBuf>,
+    /// Keyed by the NFC form of the path.
+    by_path: HashMap<PathBuf, Uuid>,
+    /// Lines currently in the log file.
+    log_lines: usize,
+    /// The file has a torn line or missed an append; rewrite before appending.
+    needs_compaction: bool,
+}
+
+impl UidMap {
+    /// Load the log, skipping unreadable lines.
+    fn load(handle: &LocationHandle) -> VfsResult<Self> {
+        let mut map = Self::default();
+        let rel = uids_rel()?;
+        if !handle.exists(&rel) {
+            return Ok(map);
+        }
+
+        let content = handle.read(&rel)?;
+        for line in String::from_utf8_lossy(&content).lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            map.log_lines += 1;
+            match serde_json::from_str::<UidRecord>(line) {
+                Ok(record) => map.apply(record.uid, record.path),
+                Err(_) => map.needs_compaction = true,
+            }
+        }
+        if !content.is_empty() && !content.ends_with(b"\n") {
+            map.needs_compaction = true;
+        }
+        Ok(map)
+    }
+
+    /// The UID assigned to `path` (in any normalization form).
+    fn uid_at(&self, path: &Path) -> Option<Uuid> {
+        self.by_path.get(&normalize_path(path)).copied()
+    }
+
+    /// Update the in-memory mapping.
+    fn apply(&mut self, uid: Uuid, path: Option<PathBuf>) {
+        if let Some(old) = self.by_uid.remove(&uid) {
+            self.by_path.remove(&normalize_path(&old));
+        }
+        if let Some(path) = path {
+            if let Some(previous) = self.by_path.insert(normalize_path(&path), uid) {
+                // A path holds one UID; the newer assignment replaces the older
+                self.by_uid.remove(&previous);
+            }
+            self.by_uid.insert(uid, path);
+        }
+    }
+
+    /// Apply a batch of changes and persist them.
+    ///
+    /// The in-memory map is always updated. If the append fails, the next
+    /// write (or [`UidMap::compact`]) rewrites the whole file instead.
+    fn set(
+        &mut self,
+        handle: &LocationHandle,
+        changes: Vec<(Uuid, Option<PathBuf>)>,
+    ) -> VfsResult<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for (uid, path) in changes {
+            lines.push_str(&encode_record(&UidRecord {
+                uid,
+                path: path.clone(),
+            })?);
+            self.apply(uid, path);
+            self.log_lines += 1;
+        }
+
+        if self.needs_compaction || self.log_lines > self.by_uid.len() * 2 + COMPACTION_SLACK {
+            return self.compact(handle);
+        }
+        if let Err(e) = append(handle, lines.as_bytes()) {
+            self.needs_compaction = true;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Rewrite the log atomically as one line per live UID.
+    fn compact(&mut self, handle: &LocationHandle) -> VfsResult<()> {
+        let mut entries: Vec<_> = self.by_uid.iter().collect();
+        // UUIDv7 sorts by assignment time
+        entries.sort();
+        let mut content = String::new();
+        for (uid, path) in &entries {
+            content.push_str(&encode_record(&UidRecord {
+                uid: **uid,
+                path: Some((*path).clone()),
+            })?);
+        }
+
+        let rel = uids_rel()?;
+        if let Some(parent) = rel.parent() {
+            handle.create_dir_all(&parent)?;
+        }
+        atomic_write(&handle.resolve_writable(&rel)?, content.as_bytes())?;
+        self.log_lines = entries.len();
+        self.needs_compaction = false;
+        Ok(())
+    }
+
+    /// UIDs at or below `base`, with their paths.
+    fn below<'a>(&'a self, base: &Path) -> impl Iterator<Item = (Uuid, &'a PathBuf)> + 'a {
+        let base = normalize_path(base);
+        self.by_uid
+            .iter()
+            .filter(move |(_, path)| normalize_path(path).starts_with(&base))
+            .map(|(uid, path)| (*uid, path))
+    }
+
+    /// Changes that move every UID at or below `from` to `to`.
+    fn moves(&self, from: &Path, to: &Path) -> Vec<(Uuid, Option<PathBuf>)> {
+        let depth = from.components().count();
+        self.below(from)
+            .map(|(uid, path)| {
+                let rest: PathBuf = path.components().skip(depth).collect();
+                let moved = if rest.as_os_str().is_empty() {
+                    to.to_path_buf()
+                } else {
+                    to.join(rest)
+                };
+                (uid, Some(moved))
+            })
+            .collect()
+    }
+}
+
+impl Location {
+    /// The UID of a file or folder, if one has been assigned.
+    pub fn uid_of(&self, path: &Path) -> VfsResult<Option<Uuid>> {
+        let rel = self.relativize(path)?;
+        Ok(self.uid_map()?.uid_at(rel.as_path()))
+    }
+
+    /// The current path of a UID, if it is known.
+    pub fn path_of_uid(&self, uid: Uuid) -> VfsResult<Option<PathBuf>> {
+        let handle = self.handle()?;
+        let map = self.uid_map()?;
+        match map.by_uid.get(&uid) {
+            Some(path) => Ok(Some(handle.absolute(&RelPath::new(path)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// The UID of a file or folder, assigning a new one on first use.
+    ///
+    /// Only Managed paths of a managed Location get UIDs.
+    pub fn ensure_uid(&self, path: &Path) -> VfsResult<Uuid> {
+        let rel = self.uid_rel(path)?;
+        let handle = self.handle()?;
+        let mut map = self.uid_map()?;
+        if let Some(uid) = map.uid_at(rel.as_path()) {
+            return Ok(uid);
+        }
+        let uid = Uuid::now_v7();
+        map.set(handle, vec![(uid, Some(rel.as_path().to_path_buf()))])?;
+        Ok(uid)
+    }
+
+    /// Record that `path` carries `uid` (e.g. found in its front matter).
+    ///
+    /// Succeeds if the UID is unknown, already points at `path`, or points at
+    /// a path that no longer exists (the file was moved outside Fracta).
+    /// Fails with `UidConflict` if another existing entry holds the UID —
+    /// typically a copy of a file that embeds its UID.
+    pub fn claim_uid(&self, path: &Path, uid: Uuid) -> VfsResult<()> {
+        let rel = self.uid_rel(path)?;
+        let handle = self.handle()?;
+        let mut map = self.uid_map()?;
+        if let Some(existing) = map.by_uid.get(&uid) {
+            if normalize_path(existing) == normalize_path(rel.as_path()) {
+                return Ok(());
+            }
+            let existing = RelPath::new(existing)?;
+            if handle.exists(&existing) {
+                return Err(VfsError::UidConflict {
+                    uid,
+                    existing: handle.absolute(&existing),
+                    claimed_by: path.to_path_buf(),
+                });
+            }
+        }
+        map.set(handle, vec![(uid, Some(rel.as_path().to_path_buf()))])
+    }
+
+    /// Rewrite `uids.jsonl` as one line per live UID.
+    pub fn compact_uids(&self) -> VfsResult<()> {
+        let handle = self.handle()?;
+        self.uid_map()?.compact(handle)
+    }
+
+    /// Move the UIDs at or below `from` to `to` after an entry was moved.
+    ///
+    /// Never fails the move itself: a persistence error is retried by the
+    /// next UID write.
+    pub(crate) fn record_uid_move(&self, from: &RelPath, to: &RelPath) {
+        let Ok(handle) = self.handle() else {
+            return;
+        };
+        let Ok(mut map) = self.uid_map() else {
+            return;
+        };
+        let changes = map.moves(from.as_path(), to.as_path());
+        let _ = map.set(handle, changes);
+    }
+
+    /// [`Location::record_uid_move`] for absolute paths, e.g. from the watcher.
+    pub(crate) fn record_uid_rename(&self, from: &Path, to: &Path) {
+        if let (Ok(from), Ok(to)) = (self.relativize(from), self.relativize(to)) {
+            self.record_uid_move(&from, &to);
+        }
+    }
+
+    /// Release the UIDs at or below `rel` after it was permanently deleted.
+    pub(crate) fn record_uid_removal(&self, rel: &RelPath) {
+        let Ok(handle) = self.handle() else {
+            return;
+        };
+        let Ok(mut map) = self.uid_map() else {
+            return;
+        };
+        let changes = map
+            .below(rel.as_path())
+            .map(|(uid, _)| (uid, None))
+            .collect();
+        let _ = map.set(handle, changes);
+    }
+
+    /// Resolve a path that may receive a UID: existing and Managed.
+    fn uid_rel(&self, path: &Path) -> VfsResult<RelPath> {
+        if !self.managed {
+            return Err(VfsError::NotManaged(self.root.clone()));
+        }
+        let rel = self.writable_rel(path)?;
+        let metadata = self.handle()?.metadata(&rel)?;
+        if self.scope_of_rel(&rel, metadata.is_dir()) == Scope::Ignored {
+            return Err(VfsError::IgnoredScope(path.to_path_buf()));
+        }
+        Ok(rel)
+    }
+
+    /// The UID map shared by every Location on this root, loaded on first use.
+    fn uid_map(&self) -> VfsResult<MutexGuard<'_, UidMap>> {
+        let mutex = match self.uids.get() {
+            Some(mutex) => mutex,
+            None => {
+                let map = shared_uid_map(self.handle()?)?;
+                self.uids.get_or_init(|| map)
+            }
+        };
+        Ok(mutex.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// The process-wide UID map for the root of `handle`, keyed by its canonical
+/// path. Kept only while some Location holds it.
+fn shared_uid_map(handle: &LocationHandle) -> VfsResult<Arc<Mutex<UidMap>>> {
+    static MAPS: OnceLock<Mutex<HashMap<PathBuf, Weak<Mutex<UidMap>>>>> = OnceLock::new();
+    let mut maps = MAPS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let root = handle.canonical_root();
+    if let Some(map) = maps.get(&root).and_then(Weak::upgrade) {
+        return Ok(map);
+    }
+    let map = Arc::new(Mutex::new(UidMap::load(handle)?));
+    maps.retain(|_, map| map.strong_count() > 0);
+    maps.insert(root, Arc::downgrade(&map));
+    Ok(map)
+}
+
+/// `.fracta/meta/uids.jsonl` as a relative path.
+fn uids_rel() -> VfsResult<RelPath> {
+    RelPath::new(Path::new(FRACTA_DIR).join("meta").join(UIDS_FILE))
+}
+
+fn encode_record(record: &UidRecord) -> VfsResult<String> {
+    let mut line = serde_json::to_string(record).map_err(|e| VfsError::Io {
+        source: std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
+    })?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Append complete lines to the log and sync them to disk.
+fn append(handle: &LocationHandle, lines: &[u8]) -> VfsResult<()> {
+    let rel = uids_rel()?;
+    if let Some(parent) = rel.parent() {
+        handle.create_dir_all(&parent)?;
+    }
+    let path = handle.resolve_writable(&rel)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(lines)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn managed_location(tmp: &TempDir) -> Location {
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        loc
+    }
+
+    fn log_path(tmp: &TempDir) -> PathBuf {
+        tmp.path().join(".fracta/meta").join(UIDS_FILE)
+    }
+
+    #[test]
+    fn test_uid_assigned_lazily_and_persisted() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let note = tmp.path().join("note.md");
+        fs::write(&note, "x").unwrap();
+
+        assert_eq!(loc.uid_of(&note).unwrap(), None);
+        assert!(!log_path(&tmp).exists());
+
+        let uid = loc.ensure_uid(&note).unwrap();
+        assert_eq!(loc.ensure_uid(&note).unwrap(), uid);
+        assert_eq!(loc.path_of_uid(uid).unwrap(), Some(note.clone()));
+
+        let reopened = Location::open("test", tmp.path()).unwrap();
+        assert_eq!(reopened.uid_of(&note).unwrap(), Some(uid));
+    }
+
+    #[test]
+    fn test_uid_follows_rename_and_move() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        fs::create_dir_all(tmp.path().join("inbox")).unwrap();
+        fs::create_dir_all(tmp.path().join("archive")).unwrap();
+        let note = tmp.path().join("inbox/note.md");
+        fs::write(&note, "x").unwrap();
+        let note_uid = loc.ensure_uid(&note).unwrap();
+        let folder_uid = loc.ensure_uid(&tmp.path().join("inbox")).unwrap();
+
+        let renamed = tmp.path().join("inbox/renamed.md");
+        loc.rename(&note, &renamed).unwrap();
+        assert_eq!(loc.path_of_uid(note_uid).unwrap(), Some(renamed));
+
+        // Moving a folder carries its descendants along
+        let moved = loc
+            .move_entry(&tmp.path().join("inbox"), &tmp.path().join("archive"))
+            .unwrap();
+        assert_eq!(loc.path_of_uid(folder_uid).unwrap(), Some(moved.clone()));
+        assert_eq!(
+            loc.path_of_uid(note_uid).unwrap(),
+            Some(moved.join("renamed.md"))
+        );
+
+        let reopened = Location::open("test", tmp.path()).unwrap();
+        assert_eq!(
+            reopened.path_of_uid(note_uid).unwrap(),
+            Some(moved.join("renamed.md"))
+        );
+    }
+
+    #[test]
+    fn test_uid_survives_trash_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let note = tmp.path().join("note.md");
+        fs::write(&note, "x").unwrap();
+        let uid = loc.ensure_uid(&note).unwrap();
+
+        let item = loc.move_to_trash(&note).unwrap();
+        assert_eq!(loc.uid_of(&note).unwrap(), None);
+        loc.restore_from_trash(item.id, Default::default()).unwrap();
+        assert_eq!(loc.uid_of(&note).unwrap(), Some(uid));
+
+        loc.delete_permanently(&note).unwrap();
+        assert_eq!(loc.path_of_uid(uid).unwrap(), None);
+    }
+
+    #[test]
+    fn test_copy_does_not_inherit_uid_and_claims_conflict() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let note = tmp.path().join("note.md");
+        fs::write(&note, "x").unwrap();
+        let uid = loc.ensure_uid(&note).unwrap();
+
+        let copy = loc.duplicate(&note).unwrap();
+        assert_eq!(loc.uid_of(&copy).unwrap(), None);
+
+        let result = loc.claim_uid(&copy, uid);
+        assert!(matches!(
+            result,
+            Err(VfsError::UidConflict { existing, .. }) if existing == note
+        ));
+
+        // Once the original is gone, the claim is a move
+        fs::remove_file(&note).unwrap();
+        loc.claim_uid(&copy, uid).unwrap();
+        assert_eq!(loc.path_of_uid(uid).unwrap(), Some(copy));
+    }
+
+    #[test]
+    fn test_torn_line_skipped_and_compacted() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let a = tmp.path().join("a.md");
+        let b = tmp.path().join("b.md");
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+        let uid_a = loc.ensure_uid(&a).unwrap();
+        drop(loc);
+
+        // Simulate a crash mid-append
+        let mut log = fs::OpenOptions::new()
+            .append(true)
+            .open(log_path(&tmp))
+            .unwrap();
+        log.write_all(b"{\"uid\":\"0190").unwrap();
+        drop(log);
+
+        let reopened = Location::open("test", tmp.path()).unwrap();
+        assert_eq!(reopened.uid_of(&a).unwrap(), Some(uid_a));
+        let uid_b = reopened.ensure_uid(&b).unwrap();
+
+        let content = fs::read_to_string(log_path(&tmp)).unwrap();
+        assert_eq!(content.lines().count(), 2);
+        assert!(content
+            .lines()
+            .all(|l| serde_json::from_str::<UidRecord>(l).is_ok()));
+        let reopened = Location::open("test", tmp.path()).unwrap();
+        assert_eq!(reopened.uid_of(&b).unwrap(), Some(uid_b));
+    }
+
+    #[test]
+    fn test_compaction_drops_superseded_lines() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let a = tmp.path().join("a.md");
+        let b = tmp.path().join("b.md");
+        fs::write(&a, "a").unwrap();
+        let uid = loc.ensure_uid(&a).unwrap();
+        loc.rename(&a, &b).unwrap();
+        loc.rename(&b, &a).unwrap();
+        assert_eq!(
+            fs::read_to_string(log_path(&tmp)).unwrap().lines().count(),
+            3
+        );
+
+        loc.compact_uids().unwrap();
+        assert_eq!(
+            fs::read_to_string(log_path(&tmp)).unwrap().lines().count(),
+            1
+        );
+        assert_eq!(loc.path_of_uid(uid).unwrap(), Some(a));
+    }
+
+    #[test]
+    fn test_uids_require_managed_scope() {
+        let tmp = TempDir::new().unwrap();
+        let plain = Location::new("plain", tmp.path());
+        fs::write(tmp.path().join("a.md"), "a").unwrap();
+        assert!(matches!(
+            plain.ensure_uid(&tmp.path().join("a.md")),
+            Err(VfsError::NotManaged(_))
+        ));
+
+        let loc = managed_location(&tmp);
+        fs::create_dir_all(tmp.path().join("node_modules")).unwrap();
+        assert!(matches!(
+            loc.ensure_uid(&tmp.path().join("node_modules")),
+            Err(VfsError::IgnoredScope(_))
+        ));
+    }
+
+    #[test]
+    fn test_locations_on_one_root_share_uids() {
+        let tmp = TempDir::new().unwrap();
+        let first = managed_location(&tmp);
+        let second = Location::open("test", tmp.path()).unwrap();
+        let a = tmp.path().join("a.md");
+        let b = tmp.path().join("b.md");
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+
+        let uid_a = first.ensure_uid(&a).unwrap();
+        let uid_b = second.ensure_uid(&b).unwrap();
+        assert_eq!(second.uid_of(&a).unwrap(), Some(uid_a));
+
+        // Compacting through one keeps what the other assigned
+        first.compact_uids().unwrap();
+        drop((first, second));
+        let reopened = Location::open("test", tmp.path()).unwrap();
+        assert_eq!(reopened.uid_of(&a).unwrap(), Some(uid_a));
+        assert_eq!(reopened.uid_of(&b).unwrap(), Some(uid_b));
+    }
+
+    #[test]
+    fn test_uid_lookup_ignores_normalization() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let nfc = tmp.path().join("caf\u{e9}");
+        let nfd = tmp.path().join("cafe\u{301}");
+        fs::create_dir(&nfc).unwrap();
+        fs::write(nfc.join("note.md"), "x").unwrap();
+        let folder_uid = loc.ensure_uid(&nfc).unwrap();
+        let note_uid = loc.ensure_uid(&nfc.join("note.md")).unwrap();
+
+        // Only the looked-up spelling differs
+        let map = loc.uid_map().unwrap();
+        assert_eq!(map.uid_at(&PathBuf::from("cafe\u{301}")), Some(folder_uid));
+        assert_eq!(
+            map.uid_at(&PathBuf::from("cafe\u{301}/note.md")),
+            Some(note_uid)
+        );
+        drop(map);
+
+        // Moves match the stored path whatever form the caller used
+        fs::rename(&nfc, tmp.path().join("moved")).unwrap();
+        loc.record_uid_rename(&nfd, &tmp.path().join("moved"));
+        assert_eq!(
+            loc.path_of_uid(note_uid).unwrap(),
+            Some(tmp.path().join("moved/note.md"))
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Optimistic concurrency for file writes.
+//!
+//! Editors read a file together with its [`FileVersion`] and hand that
+//! version back when saving. If a cloud-sync client or another app changed
+//! the file in between, [`Location::write_file_if`] refuses with
+//! `VfsError::Conflict` instead of silently discarding the other edit.
+//!
+//! Two kinds of version are supported: the cheap stat version (modification
+//! time + size) and a BLAKE3 content hash, which also catches same-size
+//! rewrites that keep or restore the old mtime (some sync clients do, and
+//! filesystem timestamps can be coarser than back-to-back saves) at the
+//! cost of re-reading the file before the write.
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{VfsError, VfsResult};
+use crate::journal::JournalOp;
+use crate::location::Location;
+
+/// The version of a file's content, as observed at some point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileVersion {
+    /// Modification time and size in bytes.
+    Stat {
+        modified: Option<DateTime<Utc>>,
+        size: u64,
+    },
+    /// Hex-encoded BLAKE3 hash of the content.
+    Hash(String),
+}
+
+impl FileVersion {
+    /// Content-hash version of `content`.
+    pub fn of_content(content: &[u8]) -> Self {
+        FileVersion::Hash(blake3::hash(content).to_hex().to_string())
+    }
+
+    /// Stat version from file metadata.
+    pub fn of_metadata(metadata: &fs::Metadata) -> Self {
+        FileVersion::Stat {
+            modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            size: metadata.len(),
+        }
+    }
+
+    /// Whether this is a content-hash version.
+    pub fn is_hash(&self) -> bool {
+        matches!(self, FileVersion::Hash(_))
+    }
+}
+
+impl Location {
+    /// The current stat version of a file.
+    pub fn file_version(&self, path: &Path) -> VfsResult<FileVersion> {
+        let rel = self.relativize(path)?;
+        Ok(FileVersion::of_metadata(&self.handle()?.metadata(&rel)?))
+    }
+
+    /// Read a file together with its stat version.
+    ///
+    /// The version is taken before reading, so a change racing with the read
+    /// shows up as a conflict on the next [`Location::write_file_if`] rather
+    /// than going unnoticed. Use [`FileVersion::of_content`] on the returned
+    /// bytes for a hash version instead.
+    pub fn read_file_versioned(&self, path: &Path) -> VfsResult<(Vec<u8>, FileVersion)> {
+        let version = self.file_version(path)?;
+        let content = self.read_file(path)?;
+        Ok((content, version))
+    }
+
+    /// Overwrite a file only if it is still at version `expected`.
+    ///
+    /// Fails with `VfsError::Conflict` (carrying both versions) if the file
+    /// changed or disappeared since `expected` was taken. On success, returns
+    /// the new version, of the same kind as `expected`.
+    pub fn write_file_if(
+        &self,
+        path: &Path,
+        content: &[u8],
+        expected: &FileVersion,
+    ) -> VfsResult<FileVersion> {
+        let rel = self.writable_rel(path)?;
+        let handle = self.handle()?;
+
+        let current = || -> VfsResult<Option<FileVersion>> {
+            if !handle.exists(&rel) {
+                return Ok(None);
+            }
+            if expected.is_hash() {
+                Ok(Some(FileVersion::of_content(&handle.read(&rel)?)))
+            } else {
+                Ok(Some(FileVersion::of_metadata(&handle.metadata(&rel)?)))
+            }
+        };
+        let conflict = |actual: Option<FileVersion>| VfsError::Conflict {
+            path: handle.absolute(&rel),
+            expected: expected.clone(),
+            actual,
+        };
+
+        // Check up front to skip the write entirely in the common conflict case
+        let actual = current()?;
+        if actual.as_ref() != Some(expected) {
+            return Err(conflict(actual));
+        }
+        let before = self.journal_hash(&rel);
+        handle.write_file_if(&rel, content, || {
+            let actual = current()?;
+            if actual.as_ref() == Some(expected) {
+                Ok(())
+            } else {
+                Err(conflict(actual))
+            }
+        })?;
+        let after = self.journal_content_hash(content);
+        self.record_op(JournalOp::Write, &rel, None, before, after);
+
+        if expected.is_hash() {
+            Ok(FileVersion::of_content(content))
+        } else {
+            Ok(FileVersion::of_metadata(&handle.metadata(&rel)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    fn set_mtime(path: &Path, time: SystemTime) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_write_if_unchanged_succeeds() {
+        let tmp = TempDir::new().unwrap();
+        let loc = Location::new("test", tmp.path());
+        let note = tmp.path().join("note.md");
+        fs::write(&note, "v1").unwrap();
+
+        let (content, version) = loc.read_file_versioned(&note).unwrap();
+        assert_eq!(content, b"v1");
+        let next = loc.write_file_if(&note, b"v2", &version).unwrap();
+        assert_eq!(fs::read_to_string(&note).unwrap(), "v2");
+        assert_eq!(next, loc.file_version(&note).unwrap());
+
+        // The returned version chains into the next save
+        loc.write_file_if(&note, b"v3", &next).unwrap();
+        assert_eq!(fs::read_to_string(&note).unwrap(), "v3");
+    }
+
+    #[test]
+    fn test_external_change_conflicts() {
+        let tmp = TempDir::new().unwrap();
+        let loc = Location::new("test", tmp.path());
+        let note = tmp.path().join("note.md");
+        fs::write(&note, "ours").unwrap();
+        let (_, version) = loc.read_file_versioned(&note).unwrap();
+
+        // Another editor saves a different length
+        fs::write(&note, "theirs!").unwrap();
+
+        match loc.write_file_if(&note, b"mine", &version) {
+            Err(VfsError::Conflict {
+                path,
+                expected,
+                actual,
+            }) => {
+                assert_eq!(path, note);
+                assert_eq!(expected, version);
+                assert_eq!(actual, Some(loc.file_version(&note).unwrap()));
+            }
+            other => panic!("expected conflict, got {other:?}"),
+        }
+        assert_eq!(fs::read_to_string(&note).unwrap(), "theirs!");
+    }
+
+    #[test]
+    fn test_hash_version_catches_restored_mtime() {
+        let tmp = TempDir::new().unwrap();
+        let loc = Location::new("test", tmp.path());
+        let note = tmp.path().join("note.md");
+        fs::write(&note, "aaaa").unwrap();
+        let mtime = SystemTime::now() - Duration::from_secs(60);
+        set_mtime(&note, mtime);
+
+        let (content, _) = loc.read_file_versioned(&note).unwrap();
+        let stat = loc.file_version(&note).unwrap();
+        let hash = FileVersion::of_content(&content);
+
+        // Same size, mtime put back: invisible to the stat version
+        fs::write(&note, "bbbb").unwrap();
+        set_mtime(&note, mtime);
+        assert_eq!(loc.file_version(&note).unwrap(), stat);
+
+        let result = loc.write_file_if(&note, b"cccc", &hash);
+        assert!(matches!(result, Err(VfsError::Conflict { .. })));
+        assert_eq!(fs::read_to_string(&note).unwrap(), "bbbb");
+    }
+
+    #[test]
+    fn test_deleted_file_conflicts() {
+        let tmp = TempDir::new().unwrap();
+        let loc = Location::new("test", tmp.path());
+        let note = tmp.path().join("note.md");
+        fs::write(&note, "x").unwrap();
+        let (_, version) = loc.read_file_versioned(&note).unwrap();
+        fs::remove_file(&note).unwrap();
+
+        let result = loc.write_file_if(&note, b"y", &version);
+        assert!(matches!(
+            result,
+            Err(VfsError::Conflict { actual: None, .. })
+        ));
+        assert!(!note.exists());
+    }
+}
@@ -0,0 +1,766 @@
+//! Streaming, parallel directory walker.
+//!
+//! [`Location::walk_stream`] lists directories on a small pool of worker
+//! threads and hands entries out lazily through a bounded channel, so
+//! memory stays flat on large Locations and the consumer (e.g. the indexer)
+//! can start before the walk is done.
+//!
+//! Each directory job carries the scope of its folder. A child of an
+//! Ignored folder is Ignored; a child of a Managed folder only needs the
+//! rules for its own name checked. Nothing is canonicalized except
+//! symlinks, which are handled per the Location's
+//! [`SymlinkPolicy`](crate::symlink::SymlinkPolicy). Each folder is walked
+//! once: a link into the walked folder is listed but not entered, since its
+//! target is walked under its own path, and a folder outside it is entered
+//! through the first link that reaches it. This also cuts symlink loops.
+//!
+//! Entries that can't be read are recorded in a [`WalkReport`], with the
+//! reason, instead of being dropped silently. A parent folder is always yielded
+//! before its children; otherwise the order is unspecified.
+
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use crate::entry::{Entry, EntryKind};
+use crate::error::{VfsError, VfsResult};
+use crate::handle::{LocationHandle, RelPath};
+use crate::location::{Location, WalkOptions, FRACTA_DIR};
+use crate::scope::Scope;
+use crate::symlink::SymlinkKind;
+
+/// Upper bound on walker threads; directory listing is I/O bound.
+const MAX_WALK_THREADS: usize = 8;
+
+/// Entries buffered between the workers and the consumer.
+const WALK_CHANNEL_CAPACITY: usize = 1024;
+
+/// Why the walk skipped an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkipReason {
+    /// The entry or directory is not accessible.
+    PermissionDenied,
+    /// The entry disappeared between listing and reading it.
+    Vanished,
+    /// The entry's metadata could not be read.
+    MetadataError,
+    /// A symlink points (directly or indirectly) at itself.
+    SymlinkLoop,
+    /// The directory could not be listed (or a file read) for another reason.
+    Unreadable,
+}
+
+impl SkipReason {
+    /// Classify an error from listing a directory.
+    pub(crate) fn of_vfs_error(e: &VfsError) -> Self {
+        match e {
+            VfsError::PermissionDenied(_) => SkipReason::PermissionDenied,
+            VfsError::NotFound(_) => SkipReason::Vanished,
+            VfsError::Io { source } if is_symlink_loop(source) => SkipReason::SymlinkLoop,
+            _ => SkipReason::Unreadable,
+        }
+    }
+
+    /// Classify an error from reading an entry, `fallback` if unrecognised.
+    fn of_io_error(e: &io::Error, fallback: Self) -> Self {
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => SkipReason::PermissionDenied,
+            io::ErrorKind::NotFound => SkipReason::Vanished,
+            _ if is_symlink_loop(e) => SkipReason::SymlinkLoop,
+            _ => fallback,
+        }
+    }
+}
+
+/// `ELOOP`: too many levels of symbolic links.
+pub(crate) fn is_symlink_loop(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    let eloop = Some(libc::ELOOP);
+    #[cfg(not(unix))]
+    let eloop = None;
+    eloop.is_some() && e.raw_os_error() == eloop
+}
+
+/// An entry the walk could not read.
+#[derive(Debug, Clone)]
+pub struct SkippedEntry {
+    /// Absolute path of the entry (or unreadable directory).
+    pub path: PathBuf,
+    /// Why it was skipped.
+    pub reason: SkipReason,
+    /// The underlying error message.
+    pub message: String,
+}
+
+impl SkippedEntry {
+    /// The error an operation that needed this entry fails with.
+    pub(crate) fn into_error(self) -> VfsError {
+        match self.reason {
+            SkipReason::PermissionDenied => VfsError::PermissionDenied(self.path),
+            SkipReason::Vanished => VfsError::NotFound(self.path),
+            SkipReason::MetadataError | SkipReason::SymlinkLoop | SkipReason::Unreadable => {
+                VfsError::Io {
+                    source: io::Error::other(format!("{}: {}", self.path.display(), self.message)),
+                }
+            }
+        }
+    }
+}
+
+/// What a walk left out, alongside the entries it yielded.
+#[derive(Debug, Clone, Default)]
+pub struct WalkReport {
+    /// Every skipped entry, in the order encountered.
+    pub skipped: Vec<SkippedEntry>,
+    /// The walk was cancelled before it finished.
+    pub cancelled: bool,
+}
+
+impl WalkReport {
+    /// Number of entries skipped for `reason`.
+    pub fn count(&self, reason: SkipReason) -> usize {
+        self.skipped.iter().filter(|s| s.reason == reason).count()
+    }
+
+    /// Whether nothing was skipped and the walk ran to completion.
+    pub fn is_complete(&self) -> bool {
+        self.skipped.is_empty() && !self.cancelled
+    }
+}
+
+/// A directory waiting to be listed.
+struct DirJob {
+    rel: RelPath,
+    depth: usize,
+    scope: Scope,
+    /// Where the directory really is.
+    canonical: PathBuf,
+    /// Reached through a followed symlink.
+    linked: bool,
+}
+
+#[derive(Default)]
+struct Queue {
+    jobs: VecDeque<DirJob>,
+    /// Jobs currently being listed by a worker.
+    active: usize,
+}
+
+/// State shared between the workers, the stream and cancel handles.
+struct Shared {
+    location: Location,
+    options: WalkOptions,
+    queue: Mutex<Queue>,
+    ready: Condvar,
+    cancelled: AtomicBool,
+    skipped: Mutex<Vec<SkippedEntry>>,
+    /// Canonical path of the walked folder.
+    root: PathBuf,
+    /// Folders outside `root` entered so far.
+    visited: Mutex<HashSet<PathBuf>>,
+}
+
+impl Shared {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        // Wake workers waiting for jobs so they can exit
+        let _guard = self.queue.lock().unwrap();
+        self.ready.notify_all();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn skip(&self, path: PathBuf, reason: SkipReason, error: impl ToString) {
+        self.skipped.lock().unwrap().push(SkippedEntry {
+            path,
+            reason,
+            message: error.to_string(),
+        });
+    }
+
+    /// Claim a folder reached through a symlink, so it is walked only once.
+    ///
+    /// Refused inside `root`, which is walked under its own paths, and for a
+    /// folder claimed before. A symlink `target` is also refused inside a
+    /// claimed folder, which includes every link back to an ancestor.
+    fn claim(&self, canonical: &Path, target: bool) -> bool {
+        if canonical.starts_with(&self.root) {
+            return false;
+        }
+        let mut visited = self.visited.lock().unwrap();
+        if target && canonical.ancestors().any(|dir| visited.contains(dir)) {
+            return false;
+        }
+        visited.insert(canonical.to_path_buf())
+    }
+
+    /// Block until a job is available; `None` once the walk is finished.
+    fn next_job(&self) -> Option<DirJob> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if self.is_cancelled() {
+                return None;
+            }
+            if let Some(job) = queue.jobs.pop_front() {
+                queue.active += 1;
+                return Some(job);
+            }
+            if queue.active == 0 {
+                // Nothing queued and nobody left to queue more
+                self.ready.notify_all();
+                return None;
+            }
+            queue = self.ready.wait(queue).unwrap();
+        }
+    }
+
+    fn push_job(&self, job: DirJob) {
+        self.queue.lock().unwrap().jobs.push_back(job);
+        self.ready.notify_one();
+    }
+
+    fn finish_job(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.active -= 1;
+        if queue.active == 0 && queue.jobs.is_empty() {
+            self.ready.notify_all();
+        }
+    }
+
+    fn run_worker(&self, tx: SyncSender<Entry>) {
+        let Ok(handle) = self.location.handle() else {
+            return;
+        };
+        while let Some(job) = self.next_job() {
+            let sent = self.list(handle, &job, &tx);
+            self.finish_job();
+            if !sent {
+                // The consumer is gone
+                self.cancel();
+            }
+        }
+    }
+
+    /// List one directory, queueing subfolders. Returns false if the
+    /// consumer hung up.
+    fn list(&self, handle: &LocationHandle, job: &DirJob, tx: &SyncSender<Entry>) -> bool {
+        if self.options.max_depth.is_some_and(|max| job.depth >= max) {
+            return true;
+        }
+
+        let read_dir = match handle.read_dir(&job.rel) {
+            Ok(rd) => rd,
+            Err(e) => {
+                self.skip(handle.absolute(&job.rel), SkipReason::of_vfs_error(&e), e);
+                return true;
+            }
+        };
+
+        for child in read_dir {
+            if self.is_cancelled() {
+                return true;
+            }
+            let (rel, dir_entry) = match child {
+                Ok(child) => child,
+                Err(e) => {
+                    let reason = SkipReason::of_io_error(&e, SkipReason::Unreadable);
+                    self.skip(handle.absolute(&job.rel), reason, e);
+                    continue;
+                }
+            };
+
+            // Always skip .fracta directory
+            if dir_entry.file_name() == FRACTA_DIR {
+                continue;
+            }
+
+            let child = match self
+                .location
+                .read_child(handle, &rel, &dir_entry, Some(job.scope))
+            {
+                Ok(Some(child)) => child,
+                // Hidden by the symlink policy
+                Ok(None) => continue,
+                Err(e) => {
+                    let reason = SkipReason::of_io_error(&e, SkipReason::MetadataError);
+                    self.skip(handle.absolute(&rel), reason, e);
+                    continue;
+                }
+            };
+            let entry = child.entry;
+            if entry.symlink.as_ref().map(|s| s.kind) == Some(SymlinkKind::Loop) {
+                self.skip(entry.path, SkipReason::SymlinkLoop, "symlink loop");
+                continue;
+            }
+
+            // Skip ignored entries unless explicitly requested
+            if entry.scope == Scope::Ignored && !self.options.include_ignored {
+                continue;
+            }
+
+            // Queue subfolders only after sending, so parents come first
+            let subfolder = if entry.symlink.is_none() && entry.kind == EntryKind::Folder {
+                let canonical = job.canonical.join(dir_entry.file_name());
+                // Below a link, a folder may be reachable another way too
+                (!job.linked || self.claim(&canonical, false)).then(|| DirJob {
+                    canonical,
+                    linked: job.linked,
+                    rel,
+                    depth: job.depth + 1,
+                    scope: entry.scope,
+                })
+            } else if let Some(target) = child.follow {
+                // Otherwise listed, but not entered
+                self.claim(&target, true).then(|| DirJob {
+                    canonical: target,
+                    linked: true,
+                    rel,
+                    depth: job.depth + 1,
+                    scope: entry.scope,
+                })
+            } else {
+                None
+            };
+            if tx.send(entry).is_err() {
+                return false;
+            }
+            if let Some(subfolder) = subfolder {
+                self.push_job(subfolder);
+            }
+        }
+        true
+    }
+}
+
+/// A lazily produced walk over a Location subtree.
+///
+/// Iterating yields entries as the workers find them. Dropping the stream
+/// (or calling [`WalkCancel::cancel`]) stops the workers.
+pub struct WalkStream {
+    shared: Arc<Shared>,
+    receiver: Option<Receiver<Entry>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// Cancels a running [`WalkStream`] from any thread.
+#[derive(Clone)]
+pub struct WalkCancel(Arc<Shared>);
+
+impl WalkCancel {
+    /// Stop the walk; the stream ends after the entries already buffered.
+    pub fn cancel(&self) {
+        self.0.cancel();
+    }
+}
+
+impl WalkStream {
+    /// A handle that can cancel this walk.
+    pub fn cancel_handle(&self) -> WalkCancel {
+        WalkCancel(Arc::clone(&self.shared))
+    }
+
+    /// Whether the walk was cancelled before finishing.
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.is_cancelled()
+    }
+
+    /// What the walk skipped so far. Complete once iteration has ended.
+    pub fn report(&self) -> WalkReport {
+        WalkReport {
+            skipped: self.shared.skipped.lock().unwrap().clone(),
+            cancelled: self.is_cancelled(),
+        }
+    }
+}
+
+impl Iterator for WalkStream {
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        self.receiver.as_ref()?.recv().ok()
+    }
+}
+
+impl Drop for WalkStream {
+    fn drop(&mut self) {
+        self.shared.cancel();
+        // Unblock workers stuck on a full channel before joining them
+        self.receiver = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Location {
+    /// Walk the directory tree under `dir` lazily and in parallel.
+    ///
+    /// Fails up front if `dir` is outside the Location or not a folder;
+    /// problems further down are reported by [`WalkStream::report`].
+    pub fn walk_stream(&self, dir: &Path, options: &WalkOptions) -> VfsResult<WalkStream> {
+        let rel = self.relativize(dir)?;
+        let handle = self.handle()?;
+        if !handle.metadata(&rel)?.is_dir() {
+            return Err(VfsError::NotFound(dir.to_path_buf()));
+        }
+        let root = handle.resolve(&rel)?.canonicalize()?;
+
+        let shared = Arc::new(Shared {
+            location: self.clone(),
+            options: options.clone(),
+            queue: Mutex::new(Queue::default()),
+            ready: Condvar::new(),
+            cancelled: AtomicBool::new(false),
+            skipped: Mutex::new(Vec::new()),
+            root: root.clone(),
+            visited: Mutex::new(HashSet::new()),
+        });
+        shared.push_job(DirJob {
+            scope: self.scope_of_rel(&rel, true),
+            canonical: root,
+            linked: false,
+            rel,
+            depth: 0,
+        });
+
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(MAX_WALK_THREADS);
+        let (tx, rx) = mpsc::sync_channel(WALK_CHANNEL_CAPACITY);
+        let mut workers = Vec::with_capacity(threads);
+        for i in 0..threads {
+            let shared = Arc::clone(&shared);
+            let tx = tx.clone();
+            let worker = std::thread::Builder::new()
+                .name(format!("fracta-walk-{i}"))
+                .spawn(move || shared.run_worker(tx))?;
+            workers.push(worker);
+        }
+
+        Ok(WalkStream {
+            shared,
+            receiver: Some(rx),
+            workers,
+        })
+    }
+
+    /// Walk the directory tree under `dir`, collecting the entries together
+    /// with a report of what was skipped.
+    pub fn walk_with_report(
+        &self,
+        dir: &Path,
+        options: &WalkOptions,
+    ) -> VfsResult<(Vec<Entry>, WalkReport)> {
+        let mut stream = self.walk_stream(dir, options)?;
+        let entries = stream.by_ref().collect();
+        Ok((entries, stream.report()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn managed_location(tmp: &TempDir) -> Location {
+        let mut loc = Location::new("test", tmp.path());
+        loc.init().unwrap();
+        loc
+    }
+
+    #[test]
+    fn test_stream_matches_scope_of() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        for dir in ["a/b/c", "a/node_modules/pkg", "d/build"] {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+        }
+        fs::write(tmp.path().join("a/b/c/note.md"), "x").unwrap();
+        fs::write(tmp.path().join("a/node_modules/pkg/index.js"), "x").unwrap();
+
+        let options = WalkOptions {
+            include_ignored: true,
+            max_depth: None,
+        };
+        let entries: Vec<_> = loc.walk_stream(tmp.path(), &options).unwrap().collect();
+        assert_eq!(entries.len(), 9);
+        for entry in &entries {
+            assert_eq!(Some(entry.scope), loc.scope_of(&entry.path), "{entry:?}");
+        }
+    }
+
+    #[test]
+    fn test_parents_before_children() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        for i in 0..20 {
+            fs::create_dir_all(tmp.path().join(format!("d{i}/sub"))).unwrap();
+            fs::write(tmp.path().join(format!("d{i}/sub/f.md")), "x").unwrap();
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for entry in loc
+            .walk_stream(tmp.path(), &WalkOptions::default())
+            .unwrap()
+        {
+            let parent = entry.path.parent().unwrap();
+            assert!(parent == tmp.path() || seen.contains(parent), "{entry:?}");
+            seen.insert(entry.path);
+        }
+        assert_eq!(seen.len(), 60);
+    }
+
+    #[test]
+    fn test_cancel_stops_walk() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        for i in 0..50 {
+            fs::create_dir_all(tmp.path().join(format!("d{i}"))).unwrap();
+            for j in 0..50 {
+                fs::write(tmp.path().join(format!("d{i}/{j}.md")), "x").unwrap();
+            }
+        }
+
+        let mut stream = loc
+            .walk_stream(tmp.path(), &WalkOptions::default())
+            .unwrap();
+        stream.next().unwrap();
+        stream.cancel_handle().cancel();
+        assert!(stream.is_cancelled());
+        // Only what was already buffered can still come out
+        assert!(stream.count() < 2550);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loops_are_cut() {
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        fs::create_dir_all(tmp.path().join("a/b")).unwrap();
+        fs::write(tmp.path().join("a/b/note.md"), "x").unwrap();
+        // Back to an ancestor, and a cycle between two links
+        symlink(tmp.path().join("a"), tmp.path().join("a/b/up")).unwrap();
+        fs::create_dir(tmp.path().join("c")).unwrap();
+        symlink(tmp.path().join("c"), tmp.path().join("a/to_c")).unwrap();
+        symlink(tmp.path().join("a"), tmp.path().join("c/to_a")).unwrap();
+        // Links that never resolve
+        symlink(tmp.path().join("a/y"), tmp.path().join("a/x")).unwrap();
+        symlink(tmp.path().join("a/x"), tmp.path().join("a/y")).unwrap();
+
+        // Walked from `a`, so that `c` is only reached through a link
+        let (entries, report) = loc
+            .walk_with_report(&tmp.path().join("a"), &WalkOptions::default())
+            .unwrap();
+        let up = entries
+            .iter()
+            .find(|e| e.path == tmp.path().join("a/b/up"))
+            .unwrap();
+        assert_eq!(up.kind, EntryKind::Folder);
+        assert_eq!(up.symlink.as_ref().unwrap().kind, SymlinkKind::Internal);
+        let relative = |path: &Path| path.strip_prefix(tmp.path()).unwrap().to_path_buf();
+        let mut found: Vec<_> = entries.iter().map(|e| relative(&e.path)).collect();
+        found.sort();
+        let expected: Vec<PathBuf> = ["a/b", "a/b/note.md", "a/b/up", "a/to_c", "a/to_c/to_a"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(found, expected);
+
+        // A link back to a folder already on the path is listed but not
+        // entered; a link that never resolves is skipped instead of listed
+        assert!(report
+            .skipped
+            .iter()
+            .all(|s| s.reason == SkipReason::SymlinkLoop));
+        let mut loops: Vec<_> = report.skipped.iter().map(|s| relative(&s.path)).collect();
+        loops.sort();
+        assert_eq!(loops, vec![PathBuf::from("a/x"), PathBuf::from("a/y")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policy_in_walk() {
+        use crate::symlink::SymlinkPolicy;
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("ext.md"), "x").unwrap();
+        fs::create_dir(tmp.path().join("real")).unwrap();
+        fs::write(tmp.path().join("real/in.md"), "x").unwrap();
+        symlink(tmp.path().join("real"), tmp.path().join("alias")).unwrap();
+        symlink(outside.path(), tmp.path().join("ext")).unwrap();
+        let mut loc = managed_location(&tmp);
+
+        let names = |loc: &Location| {
+            let mut names: Vec<_> = loc
+                .walk(tmp.path(), &WalkOptions::default())
+                .unwrap()
+                .into_iter()
+                .map(|e| {
+                    let rel = e.path.strip_prefix(tmp.path()).unwrap().to_path_buf();
+                    (rel.display().to_string(), e.scope)
+                })
+                .collect();
+            names.sort_by(|a, b| a.0.cmp(&b.0));
+            names
+        };
+
+        // Default: list inner and escaping links (the latter as Plain); the
+        // inner target is walked under its own path
+        assert_eq!(
+            names(&loc),
+            vec![
+                ("alias".to_string(), Scope::Managed),
+                ("ext".to_string(), Scope::Plain),
+                ("real".to_string(), Scope::Managed),
+                ("real/in.md".to_string(), Scope::Managed),
+            ]
+        );
+
+        loc.set_symlink_policy(SymlinkPolicy::FollowAnywhere)
+            .unwrap();
+        assert!(names(&loc).contains(&("ext/ext.md".to_string(), Scope::Plain)));
+        let reopened = Location::open("test", tmp.path()).unwrap();
+        assert_eq!(reopened.symlink_policy(), SymlinkPolicy::FollowAnywhere);
+
+        loc.set_symlink_policy(SymlinkPolicy::Ignore).unwrap();
+        assert_eq!(
+            names(&loc),
+            vec![
+                ("real".to_string(), Scope::Managed),
+                ("real/in.md".to_string(), Scope::Managed),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_linked_folders_walked_once() {
+        use crate::symlink::SymlinkPolicy;
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("a")).unwrap();
+        fs::create_dir_all(tmp.path().join("b/sub")).unwrap();
+        fs::write(tmp.path().join("b/sub/note.md"), "x").unwrap();
+        symlink("../b", tmp.path().join("a/link")).unwrap();
+        symlink("b/sub", tmp.path().join("deep")).unwrap();
+        fs::write(outside.path().join("ext.md"), "x").unwrap();
+        symlink(outside.path(), tmp.path().join("one")).unwrap();
+        symlink(outside.path(), tmp.path().join("two")).unwrap();
+        let mut loc = managed_location(&tmp);
+        loc.set_symlink_policy(SymlinkPolicy::FollowAnywhere)
+            .unwrap();
+
+        let notes = |dir: &Path| {
+            let mut notes: Vec<_> = loc
+                .walk(dir, &WalkOptions::default())
+                .unwrap()
+                .into_iter()
+                .filter(|e| e.kind == EntryKind::File)
+                .map(|e| e.path.strip_prefix(tmp.path()).unwrap().to_path_buf())
+                .collect();
+            notes.sort();
+            notes
+        };
+
+        // Each note once, under its own path or through the first link to it
+        let all = notes(tmp.path());
+        assert_eq!(all.len(), 2, "{all:?}");
+        assert_eq!(all[0], PathBuf::from("b/sub/note.md"));
+        assert!(all[1] == Path::new("one/ext.md") || all[1] == Path::new("two/ext.md"));
+        // From `a`, `b` is only reachable through the link
+        assert_eq!(
+            notes(&tmp.path().join("a")),
+            vec![PathBuf::from("a/link/sub/note.md")]
+        );
+    }
+
+    #[test]
+    fn test_skip_reasons() {
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        let gone = io::Error::from(io::ErrorKind::NotFound);
+        let other = io::Error::other("bad sector");
+        assert_eq!(
+            SkipReason::of_io_error(&denied, SkipReason::MetadataError),
+            SkipReason::PermissionDenied
+        );
+        assert_eq!(
+            SkipReason::of_io_error(&gone, SkipReason::MetadataError),
+            SkipReason::Vanished
+        );
+        assert_eq!(
+            SkipReason::of_io_error(&other, SkipReason::MetadataError),
+            SkipReason::MetadataError
+        );
+        assert_eq!(
+            SkipReason::of_vfs_error(&VfsError::Io { source: other }),
+            SkipReason::Unreadable
+        );
+
+        #[cfg(unix)]
+        {
+            let looped = io::Error::from_raw_os_error(libc::ELOOP);
+            assert_eq!(
+                SkipReason::of_vfs_error(&VfsError::Io { source: looped }),
+                SkipReason::SymlinkLoop
+            );
+        }
+    }
+
+    #[test]
+    fn test_clean_walk_report_is_complete() {
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        fs::create_dir(tmp.path().join("a")).unwrap();
+        fs::write(tmp.path().join("a/note.md"), "x").unwrap();
+
+        let (entries, report) = loc
+            .walk_with_report(tmp.path(), &WalkOptions::default())
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(report.is_complete());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_dir_is_reported() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = TempDir::new().unwrap();
+        let loc = managed_location(&tmp);
+        let locked = tmp.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("secret.md"), "x").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read_dir(&locked).is_ok() {
+            // Running as root: permissions aren't enforced
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            return;
+        }
+
+        let mut stream = loc
+            .walk_stream(tmp.path(), &WalkOptions::default())
+            .unwrap();
+        let names: Vec<_> = stream.by_ref().map(|e| e.name).collect();
+        assert_eq!(names, vec!["locked"]);
+        let report = stream.report();
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].path, locked);
+        assert_eq!(report.count(SkipReason::PermissionDenied), 1);
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
@@ -3,21 +3,43 @@
 //! Watches a Location for file changes and emits events that other subsystems
 //! (Index, Pipelines) can react to.
 //!
-//! Uses `notify-debouncer-mini` for cross-platform watching with debouncing
-//! (coalesces rapid changes into single events). Events accumulate in a
-//! thread-safe queue; consumers call `drain_events()` to retrieve them.
-
+//! Uses `notify-debouncer-full` for cross-platform watching with debouncing.
+//! Unlike a "something changed here" debouncer it keeps notify's event kinds
+//! and pairs the two halves of a rename (by inotify cookie on Linux, by file
+//! ID on macOS/Windows), so moves surface as a single `Renamed` event instead
+//! of delete + create.
+//!
+//! Events can be consumed two ways:
+//! - Poll: events accumulate in a bounded, thread-safe queue; call
+//!   `drain_watch_events()`. When the queue is full the oldest events are
+//!   dropped and the next drain starts with [`WatchEvent::RescanNeeded`].
+//! - Push: `subscribe()` returns an [`EventSubscription`], an async stream
+//!   backed by a bounded broadcast channel. Any number of subscribers can
+//!   listen; one that falls behind receives [`WatchEvent::RescanNeeded`]
+//!   instead of blocking the watcher or the other subscribers.
+
+use std::collections::{BTreeSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use notify::event::{EventKind, ModifyKind, RenameMode};
 use notify::{RecommendedWatcher, RecursiveMode};
-use notify_debouncer_mini::{new_debouncer, DebouncedEventKind, Debouncer};
+use notify_debouncer_full::{
+    new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache,
+};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
 
 use crate::error::{VfsError, VfsResult};
+use crate::ignore::GITIGNORE_FILE;
+use crate::location::{Location, FRACTA_DIR};
+use crate::scope::Scope;
+use crate::settings::{LocationSettings, DEFAULT_WATCH_DEBOUNCE_MS};
 
 /// Events emitted by the filesystem watcher.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FsEvent {
     /// A file or folder was created.
     Created(PathBuf),
@@ -29,51 +51,110 @@ pub enum FsEvent {
     Renamed { from: PathBuf, to: PathBuf },
 }
 
+/// A watcher event tagged with the Scope of the path it affects.
+///
+/// For `Renamed`, the scope is that of the new path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopedEvent {
+    pub event: FsEvent,
+    pub scope: Scope,
+}
+
+/// Default number of events a subscriber (or the poll queue) may fall
+/// behind before it receives [`WatchEvent::RescanNeeded`].
+pub const DEFAULT_SUBSCRIPTION_CAPACITY: usize = 1024;
+
+/// An item delivered to an [`EventSubscription`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A filesystem change.
+    Change(ScopedEvent),
+    /// The subscriber fell behind and `missed` events were dropped.
+    /// Its view of the Location is stale; rescan (e.g. `update_incremental`).
+    RescanNeeded { missed: u64 },
+}
+
+/// Options for [`LocationWatcher::for_location`].
+#[derive(Debug, Clone, Default)]
+pub struct WatchOptions {
+    /// Emit events for Ignored paths too. When false (the default) they are
+    /// suppressed, and moves across the Ignored boundary are reported as the
+    /// delete or create they look like from the Managed side.
+    pub include_ignored: bool,
+
+    /// How far a subscriber, or the undrained poll queue, may lag before it
+    /// is told to rescan. `None` uses [`DEFAULT_SUBSCRIPTION_CAPACITY`].
+    pub subscription_capacity: Option<usize>,
+}
+
 /// Filesystem watcher for a Location root.
 ///
-/// Accumulates debounced events in a queue. Call `drain_events()` to
-/// consume them. Thread-safe — the watcher runs on a background thread.
+/// Accumulates debounced events in a bounded queue. Call
+/// `drain_watch_events()` to consume them. Thread-safe — the watcher runs on
+/// a background thread.
 pub struct LocationWatcher {
-    _debouncer: Debouncer<RecommendedWatcher>,
-    events: Arc<Mutex<Vec<FsEvent>>>,
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+    events: Arc<Mutex<PollQueue>>,
+    broadcast: broadcast::Sender<ScopedEvent>,
     root: PathBuf,
 }
 
 impl LocationWatcher {
-    /// Start watching a directory tree.
+    /// Start watching a bare directory tree, without scope information.
     ///
-    /// Events are debounced with a 500ms window to coalesce rapid changes.
+    /// Equivalent to watching an unmanaged Location: every event is Plain.
     pub fn start(root: &Path) -> VfsResult<Self> {
-        let events: Arc<Mutex<Vec<FsEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        Self::for_location(&Location::new("", root), WatchOptions::default())
+    }
+
+    /// Start watching a Location, filtering and tagging events by Scope.
+    ///
+    /// The watcher keeps its own copy of the Location's ignore rules and
+    /// reloads them whenever a rule source (`.fracta/config/ignore`,
+    /// settings, or a `.gitignore`) changes.
+    /// Events are debounced to coalesce rapid changes, over the window set in
+    /// the Location's `watcher.debounce_ms` setting (500ms by default).
+    pub fn for_location(location: &Location, options: WatchOptions) -> VfsResult<Self> {
+        let debounce_ms = if location.managed {
+            LocationSettings::load(&location.root)?.watcher.debounce_ms
+        } else {
+            DEFAULT_WATCH_DEBOUNCE_MS
+        };
+        let capacity = options
+            .subscription_capacity
+            .unwrap_or(DEFAULT_SUBSCRIPTION_CAPACITY)
+            .max(1);
+        let events = Arc::new(Mutex::new(PollQueue::new(capacity)));
         let events_clone = events.clone();
-        let root_buf = root.to_path_buf();
+        let root_buf = location.root.clone();
+        let mut location = location.clone();
+        let (broadcast, _) = broadcast::channel(capacity);
+        let broadcast_clone = broadcast.clone();
 
         let mut debouncer = new_debouncer(
-            Duration::from_millis(500),
-            move |result: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| {
+            Duration::from_millis(debounce_ms),
+            None,
+            move |result: DebounceEventResult| {
                 match result {
                     Ok(debounced_events) => {
+                        // Apply rule changes before scoping the batch they arrived in
+                        if location.managed {
+                            reload_rules(&mut location, &debounced_events);
+                        }
+
                         let mut queue = events_clone.lock().unwrap();
                         for event in debounced_events {
-                            // Skip .fracta/ internal changes
-                            if event.path.components().any(|c| c.as_os_str() == ".fracta") {
-                                continue;
-                            }
-
-                            let fs_event = match event.kind {
-                                DebouncedEventKind::Any => {
-                                    // notify-debouncer-mini coalesces all change types into Any
-                                    if event.path.exists() {
-                                        FsEvent::Modified(event.path)
-                                    } else {
-                                        FsEvent::Deleted(event.path)
-                                    }
+                            for fs_event in classify(&event.kind, &event.paths) {
+                                // Keep UIDs attached to entries moved outside Fracta
+                                if let FsEvent::Renamed { from, to } = &fs_event {
+                                    location.record_uid_rename(from, to);
                                 }
-                                DebouncedEventKind::AnyContinuous | _ => {
-                                    FsEvent::Modified(event.path)
+                                if let Some(scoped) = scope_event(&location, fs_event, &options) {
+                                    // Err only means there are no subscribers right now
+                                    let _ = broadcast_clone.send(scoped.clone());
+                                    queue.push(scoped);
                                 }
-                            };
-                            queue.push(fs_event);
+                            }
                         }
                     }
                     Err(_) => {
@@ -85,27 +166,64 @@ impl LocationWatcher {
         .map_err(|e| VfsError::WatcherError(e.to_string()))?;
 
         debouncer
-            .watcher()
-            .watch(root, RecursiveMode::Recursive)
+            .watch(&root_buf, RecursiveMode::Recursive)
             .map_err(|e| VfsError::WatcherError(e.to_string()))?;
 
         Ok(LocationWatcher {
             _debouncer: debouncer,
             events,
+            broadcast,
             root: root_buf,
         })
     }
 
+    /// Subscribe to events as they are emitted.
+    ///
+    /// The subscription only sees events from this point on. It ends when
+    /// the watcher is dropped.
+    pub fn subscribe(&self) -> EventSubscription {
+        EventSubscription {
+            rx: self.broadcast.subscribe(),
+        }
+    }
+
     /// Drain all accumulated events, returning them and clearing the queue.
+    ///
+    /// If the queue overflowed since the last drain, the first item is
+    /// [`WatchEvent::RescanNeeded`] with the number of dropped events.
+    pub fn drain_watch_events(&self) -> Vec<WatchEvent> {
+        self.events.lock().unwrap().drain()
+    }
+
+    /// Drain accumulated events without their Scope.
+    ///
+    /// Discards any overflow notice; use
+    /// [`drain_watch_events`](Self::drain_watch_events) to learn when to rescan.
     pub fn drain_events(&self) -> Vec<FsEvent> {
-        let mut queue = self.events.lock().unwrap();
-        std::mem::take(&mut *queue)
+        self.drain_scoped_events()
+            .into_iter()
+            .map(|e| e.event)
+            .collect()
+    }
+
+    /// Drain accumulated events together with their Scope.
+    ///
+    /// Discards any overflow notice; use
+    /// [`drain_watch_events`](Self::drain_watch_events) to learn when to rescan.
+    pub fn drain_scoped_events(&self) -> Vec<ScopedEvent> {
+        self.drain_watch_events()
+            .into_iter()
+            .filter_map(|e| match e {
+                WatchEvent::Change(event) => Some(event),
+                WatchEvent::RescanNeeded { .. } => None,
+            })
+            .collect()
     }
 
     /// Check if there are pending events without consuming them.
     pub fn has_pending_events(&self) -> bool {
         let queue = self.events.lock().unwrap();
-        !queue.is_empty()
+        !queue.events.is_empty() || queue.missed > 0
     }
 
     /// Get the root path being watched.
@@ -114,17 +232,241 @@ impl LocationWatcher {
     }
 }
 
+/// The poll queue: keeps the newest `capacity` events and counts the rest,
+/// so a watcher that is never drained holds bounded memory.
+struct PollQueue {
+    events: VecDeque<ScopedEvent>,
+    missed: u64,
+    capacity: usize,
+}
+
+impl PollQueue {
+    fn new(capacity: usize) -> Self {
+        PollQueue {
+            events: VecDeque::new(),
+            missed: 0,
+            capacity,
+        }
+    }
+
+    fn push(&mut self, event: ScopedEvent) {
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+            self.missed += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    fn drain(&mut self) -> Vec<WatchEvent> {
+        let rescan = (self.missed > 0).then_some(WatchEvent::RescanNeeded {
+            missed: self.missed,
+        });
+        self.missed = 0;
+        rescan
+            .into_iter()
+            .chain(self.events.drain(..).map(WatchEvent::Change))
+            .collect()
+    }
+}
+
+/// A push subscription to a [`LocationWatcher`].
+///
+/// Use [`recv`](Self::recv) from async code, [`blocking_recv`](Self::blocking_recv)
+/// from a dedicated thread, or [`into_stream`](Self::into_stream) for a `Stream`.
+pub struct EventSubscription {
+    rx: broadcast::Receiver<ScopedEvent>,
+}
+
+impl EventSubscription {
+    /// Wait for the next event. Returns `None` once the watcher is gone.
+    pub async fn recv(&mut self) -> Option<WatchEvent> {
+        watch_event(self.rx.recv().await)
+    }
+
+    /// Blocking variant of [`recv`](Self::recv). Must not be called from
+    /// inside an async runtime.
+    pub fn blocking_recv(&mut self) -> Option<WatchEvent> {
+        watch_event(self.rx.blocking_recv())
+    }
+
+    /// Convert into a `Stream` of events.
+    pub fn into_stream(self) -> impl Stream<Item = WatchEvent> + Send + Unpin {
+        BroadcastStream::new(self.rx).map(|item| match item {
+            Ok(event) => WatchEvent::Change(event),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => WatchEvent::RescanNeeded { missed },
+        })
+    }
+}
+
+fn watch_event(item: Result<ScopedEvent, broadcast::error::RecvError>) -> Option<WatchEvent> {
+    match item {
+        Ok(event) => Some(WatchEvent::Change(event)),
+        Err(broadcast::error::RecvError::Lagged(missed)) => {
+            Some(WatchEvent::RescanNeeded { missed })
+        }
+        Err(broadcast::error::RecvError::Closed) => None,
+    }
+}
+
+/// Map one debounced notify event to VFS events.
+///
+/// Changes inside `.fracta/` are dropped. A rename across the `.fracta/`
+/// boundary (e.g. moving to or restoring from the trash) is reported as the
+/// delete or create it looks like from the user's side. Kinds that don't say
+/// what happened (macOS reports renames as `Name(Any)` per path) fall back
+/// to checking whether the path still exists.
+fn classify(kind: &EventKind, paths: &[PathBuf]) -> Vec<FsEvent> {
+    let visible = |p: &PathBuf| !is_internal(p);
+
+    match kind {
+        EventKind::Access(_) => Vec::new(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+            let (from, to) = (&paths[0], &paths[1]);
+            match (visible(from), visible(to)) {
+                (true, true) => vec![FsEvent::Renamed {
+                    from: from.clone(),
+                    to: to.clone(),
+                }],
+                (true, false) => vec![FsEvent::Deleted(from.clone())],
+                (false, true) => vec![FsEvent::Created(to.clone())],
+                (false, false) => Vec::new(),
+            }
+        }
+        _ => paths
+            .iter()
+            .filter(|p| visible(p))
+            .map(|p| match kind {
+                EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                    FsEvent::Created(p.clone())
+                }
+                EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                    FsEvent::Deleted(p.clone())
+                }
+                EventKind::Modify(ModifyKind::Name(_)) => {
+                    if p.exists() {
+                        FsEvent::Created(p.clone())
+                    } else {
+                        FsEvent::Deleted(p.clone())
+                    }
+                }
+                _ => {
+                    if p.exists() {
+                        FsEvent::Modified(p.clone())
+                    } else {
+                        FsEvent::Deleted(p.clone())
+                    }
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Tag an event with its Scope, dropping or rewriting it per `options`.
+fn scope_event(location: &Location, event: FsEvent, options: &WatchOptions) -> Option<ScopedEvent> {
+    let tagged = |event: FsEvent, scope: Scope| {
+        (options.include_ignored || scope != Scope::Ignored).then_some(ScopedEvent { event, scope })
+    };
+
+    match event {
+        FsEvent::Renamed { from, to } if !options.include_ignored => {
+            match (scope_of_path(location, &from), scope_of_path(location, &to)) {
+                (Scope::Ignored, Scope::Ignored) => None,
+                (from_scope, Scope::Ignored) => tagged(FsEvent::Deleted(from), from_scope),
+                (Scope::Ignored, to_scope) => tagged(FsEvent::Created(to), to_scope),
+                (_, to_scope) => tagged(FsEvent::Renamed { from, to }, to_scope),
+            }
+        }
+        FsEvent::Renamed { from, to } => {
+            let scope = scope_of_path(location, &to);
+            tagged(FsEvent::Renamed { from, to }, scope)
+        }
+        FsEvent::Created(ref p) | FsEvent::Modified(ref p) | FsEvent::Deleted(ref p) => {
+            let scope = scope_of_path(location, p);
+            tagged(event, scope)
+        }
+    }
+}
+
+/// Scope of an event path.
+///
+/// A path that is gone may have been a folder, so it is Ignored if a rule
+/// ignores it either as a file or as a folder (`node_modules/`).
+fn scope_of_path(location: &Location, path: &Path) -> Scope {
+    match location.relativize(path) {
+        Ok(rel) if path.symlink_metadata().is_err() => match location.scope_of_rel(&rel, true) {
+            Scope::Ignored => Scope::Ignored,
+            _ => location.scope_of_rel(&rel, false),
+        },
+        Ok(rel) => location.scope_of_rel(&rel, path.is_dir()),
+        Err(_) if location.managed => Scope::Managed,
+        Err(_) => Scope::Plain,
+    }
+}
+
+/// Reload the ignore rules a batch of events touched: all of them if
+/// `.fracta/config/ignore` or the settings file (which holds overrides)
+/// changed, otherwise just the `.gitignore` files that did.
+fn reload_rules(location: &mut Location, events: &[DebouncedEvent]) {
+    let paths = || events.iter().flat_map(|e| e.paths.iter());
+    if paths().any(|p| is_config_file(p)) {
+        let _ = location.reload_ignore_rules();
+    }
+    let gitignores: BTreeSet<&PathBuf> = paths()
+        .filter(|p| p.file_name().is_some_and(|n| n == GITIGNORE_FILE) && !is_internal(p))
+        .collect();
+    for path in gitignores {
+        let _ = location.reload_gitignore(path);
+    }
+}
+
+/// Whether a path is `.fracta/config/ignore` or the settings file.
+fn is_config_file(path: &Path) -> bool {
+    let config = Path::new(FRACTA_DIR).join("config");
+    path.ends_with(config.join("ignore")) || path.ends_with(config.join("settings.json"))
+}
+
+/// Whether a path lies inside a `.fracta/` directory.
+fn is_internal(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == FRACTA_DIR)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
     use std::thread;
+    use std::time::Instant;
+
+    /// How long a test waits for an expected event.
+    const EVENT_DEADLINE: Duration = Duration::from_secs(10);
 
     /// Canonicalize path to handle macOS /var -> /private/var symlink
     fn canon(p: &Path) -> PathBuf {
         p.canonicalize().unwrap_or_else(|_| p.to_path_buf())
     }
 
+    /// Poll `drain` until an event matching `wanted` arrives, failing after
+    /// [`EVENT_DEADLINE`]. Returns everything drained.
+    fn drain_until<T: std::fmt::Debug>(
+        mut drain: impl FnMut() -> Vec<T>,
+        wanted: impl Fn(&T) -> bool,
+    ) -> Vec<T> {
+        let deadline = Instant::now() + EVENT_DEADLINE;
+        let mut events = Vec::new();
+        loop {
+            events.extend(drain());
+            if events.iter().any(&wanted) {
+                return events;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "Timed out waiting for event, got {:?}",
+                events
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
     #[test]
     fn test_watcher_detects_file_change() {
         let tmp = tempfile::TempDir::new().unwrap();
@@ -139,20 +481,10 @@ mod tests {
         // Modify the file
         fs::write(&file_path, "modified").unwrap();
 
-        // Wait for debounce window
-        thread::sleep(Duration::from_millis(800));
-
-        let events = watcher.drain_events();
-        assert!(!events.is_empty(), "Expected at least one event");
-
         // Should be a Modified event
-        let has_modify = events
-            .iter()
-            .any(|e| matches!(e, FsEvent::Modified(p) if p == &file_path));
-        assert!(
-            has_modify,
-            "Expected Modified event for {:?}, got {:?}",
-            file_path, events
+        drain_until(
+            || watcher.drain_events(),
+            |e| matches!(e, FsEvent::Modified(p) if p == &file_path),
         );
     }
 
@@ -169,19 +501,312 @@ mod tests {
         // Delete the file
         fs::remove_file(&file_path).unwrap();
 
-        // Wait for debounce window
-        thread::sleep(Duration::from_millis(800));
+        drain_until(
+            || watcher.drain_events(),
+            |e| matches!(e, FsEvent::Deleted(p) if p == &file_path),
+        );
+    }
 
-        let events = watcher.drain_events();
-        assert!(!events.is_empty(), "Expected at least one event");
+    #[test]
+    fn test_watcher_detects_file_create() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
 
-        let has_delete = events
-            .iter()
-            .any(|e| matches!(e, FsEvent::Deleted(p) if p == &file_path));
+        let watcher = LocationWatcher::start(&root).unwrap();
+
+        let file_path = root.join("new.md");
+        fs::write(&file_path, "hello").unwrap();
+
+        let created = FsEvent::Created(file_path);
+        drain_until(|| watcher.drain_events(), |e| e == &created);
+    }
+
+    #[test]
+    fn test_watcher_detects_move_across_subfolders() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+
+        fs::create_dir_all(root.join("inbox")).unwrap();
+        fs::create_dir_all(root.join("projects/alpha")).unwrap();
+        let from = root.join("inbox/note.md");
+        let to = root.join("projects/alpha/note.md");
+        fs::write(&from, "content").unwrap();
+
+        let watcher = LocationWatcher::start(&root).unwrap();
+
+        fs::rename(&from, &to).unwrap();
+
+        let renamed = FsEvent::Renamed {
+            from: from.clone(),
+            to: to.clone(),
+        };
+        let events = drain_until(|| watcher.drain_events(), |e| e == &renamed);
+        assert!(
+            !events.contains(&FsEvent::Deleted(from)),
+            "A move must not also be reported as a delete: {:?}",
+            events
+        );
+    }
+
+    fn managed_location(root: &Path) -> Location {
+        let mut loc = Location::new("test", root);
+        loc.init().unwrap();
+        loc
+    }
+
+    #[test]
+    fn test_watcher_suppresses_ignored_paths() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+        let loc = managed_location(&root);
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+
+        let watcher = LocationWatcher::for_location(&loc, WatchOptions::default()).unwrap();
+
+        fs::write(root.join("node_modules/pkg/index.js"), "x").unwrap();
+        fs::write(root.join("note.md"), "hello").unwrap();
+
+        let created = FsEvent::Created(root.join("note.md"));
+        let events = drain_until(|| watcher.drain_scoped_events(), |e| e.event == created);
         assert!(
-            has_delete,
-            "Expected Deleted event for {:?}, got {:?}",
-            file_path, events
+            events.iter().all(|e| e.scope == Scope::Managed),
+            "Ignored events leaked: {:?}",
+            events
+        );
+    }
+
+    #[test]
+    fn test_watcher_suppresses_deleted_ignored_folder() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+        let loc = managed_location(&root);
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "x").unwrap();
+
+        let watcher = LocationWatcher::for_location(&loc, WatchOptions::default()).unwrap();
+
+        // Once gone, `node_modules` no longer looks like a folder on disk
+        fs::remove_dir_all(root.join("node_modules")).unwrap();
+        fs::write(root.join("note.md"), "hello").unwrap();
+
+        let created = FsEvent::Created(root.join("note.md"));
+        let events = drain_until(|| watcher.drain_events(), |e| e == &created);
+        assert!(
+            events.iter().all(|e| e == &created),
+            "Ignored events leaked: {:?}",
+            events
+        );
+    }
+
+    #[test]
+    fn test_watcher_tags_ignored_paths_when_requested() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+        let loc = managed_location(&root);
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+
+        let options = WatchOptions {
+            include_ignored: true,
+            ..Default::default()
+        };
+        let watcher = LocationWatcher::for_location(&loc, options).unwrap();
+
+        let ignored = root.join("node_modules/index.js");
+        fs::write(&ignored, "x").unwrap();
+
+        let tagged = ScopedEvent {
+            event: FsEvent::Created(ignored),
+            scope: Scope::Ignored,
+        };
+        drain_until(|| watcher.drain_scoped_events(), |e| e == &tagged);
+    }
+
+    #[test]
+    fn test_watcher_move_into_ignored_is_delete() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+        let loc = managed_location(&root);
+        fs::create_dir_all(root.join("build")).unwrap();
+        let from = root.join("report.md");
+        fs::write(&from, "x").unwrap();
+
+        let watcher = LocationWatcher::for_location(&loc, WatchOptions::default()).unwrap();
+
+        fs::rename(&from, root.join("build/report.md")).unwrap();
+
+        let deleted = FsEvent::Deleted(from);
+        let events = drain_until(|| watcher.drain_events(), |e| e == &deleted);
+        assert_eq!(events, vec![deleted]);
+    }
+
+    #[test]
+    fn test_watcher_reloads_ignore_rules() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+        let loc = managed_location(&root);
+        fs::create_dir_all(root.join("drafts")).unwrap();
+
+        let watcher = LocationWatcher::for_location(&loc, WatchOptions::default()).unwrap();
+
+        crate::writer::atomic_write_string(&root.join(".fracta/config/ignore"), "drafts/\n")
+            .unwrap();
+        // Rules are reloaded while handling the batch with the change
+        let first = FsEvent::Created(root.join("first.md"));
+        fs::write(root.join("first.md"), "x").unwrap();
+        drain_until(|| watcher.drain_events(), |e| e == &first);
+
+        fs::write(root.join("drafts/wip.md"), "x").unwrap();
+        // Events arrive in order: once the marker is in, so is the draft
+        let marker = FsEvent::Created(root.join("marker.md"));
+        fs::write(root.join("marker.md"), "x").unwrap();
+
+        let events = drain_until(|| watcher.drain_events(), |e| e == &marker);
+        assert_eq!(events, vec![marker], "Expected only the marker");
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_pushed_events() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+
+        let watcher = LocationWatcher::start(&root).unwrap();
+        let mut first = watcher.subscribe();
+        let mut second = watcher.subscribe().into_stream();
+
+        let file_path = root.join("pushed.md");
+        fs::write(&file_path, "hello").unwrap();
+
+        let expected = WatchEvent::Change(ScopedEvent {
+            event: FsEvent::Created(file_path),
+            scope: Scope::Plain,
+        });
+        let timeout = Duration::from_secs(5);
+        let got = tokio::time::timeout(timeout, first.recv()).await.unwrap();
+        assert_eq!(got, Some(expected.clone()));
+        let got = tokio::time::timeout(timeout, second.next()).await.unwrap();
+        assert_eq!(got, Some(expected));
+    }
+
+    #[test]
+    fn test_lagging_subscriber_gets_rescan_needed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+        let options = WatchOptions {
+            subscription_capacity: Some(2),
+            ..Default::default()
+        };
+        let watcher = LocationWatcher::for_location(&Location::new("", &root), options).unwrap();
+        let mut subscription = watcher.subscribe();
+
+        for i in 0..5 {
+            fs::write(root.join(format!("{i}.md")), "x").unwrap();
+        }
+        // Events arrive in order: once the last file is in, all were sent
+        let last = root.join("4.md");
+        drain_until(
+            || watcher.drain_scoped_events(),
+            |e| matches!(&e.event, FsEvent::Created(p) | FsEvent::Modified(p) if p == &last),
+        );
+
+        assert!(matches!(
+            subscription.blocking_recv(),
+            Some(WatchEvent::RescanNeeded { missed }) if missed > 0
+        ));
+        // The most recent events are still delivered after the signal
+        assert!(matches!(
+            subscription.blocking_recv(),
+            Some(WatchEvent::Change(_))
+        ));
+
+        drop(watcher);
+        while let Some(WatchEvent::Change(_)) = subscription.blocking_recv() {}
+    }
+
+    #[test]
+    fn test_poll_queue_replaces_overflow_with_rescan_needed() {
+        let event = |name: &str| ScopedEvent {
+            event: FsEvent::Created(PathBuf::from(name)),
+            scope: Scope::Plain,
+        };
+        let mut queue = PollQueue::new(2);
+        for name in ["a", "b", "c", "d", "e"] {
+            queue.push(event(name));
+        }
+
+        assert_eq!(
+            queue.drain(),
+            vec![
+                WatchEvent::RescanNeeded { missed: 3 },
+                WatchEvent::Change(event("d")),
+                WatchEvent::Change(event("e")),
+            ]
+        );
+        assert!(queue.drain().is_empty());
+    }
+
+    #[test]
+    fn test_watcher_rename_moves_uid() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = canon(tmp.path());
+        let loc = managed_location(&root);
+        fs::create_dir_all(root.join("a")).unwrap();
+        let from = root.join("a/note.md");
+        fs::write(&from, "x").unwrap();
+        let uid = loc.ensure_uid(&from).unwrap();
+
+        let _watcher = LocationWatcher::for_location(&loc, WatchOptions::default()).unwrap();
+
+        let to = root.join("note.md");
+        fs::rename(&from, &to).unwrap();
+
+        let deadline = Instant::now() + EVENT_DEADLINE;
+        while loc.path_of_uid(uid).unwrap().as_ref() != Some(&to) {
+            assert!(Instant::now() < deadline, "UID did not follow the rename");
+            thread::sleep(Duration::from_millis(50));
+        }
+        let reopened = Location::open("test", &root).unwrap();
+        assert_eq!(reopened.path_of_uid(uid).unwrap(), Some(to));
+    }
+
+    #[test]
+    fn test_classify_rename_pairs() {
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        let from = PathBuf::from("/loc/a/note.md");
+        let to = PathBuf::from("/loc/b/note.md");
+
+        assert_eq!(
+            classify(&rename, &[from.clone(), to.clone()]),
+            vec![FsEvent::Renamed {
+                from: from.clone(),
+                to: to.clone()
+            }]
+        );
+
+        // Unpaired halves: moved out of / into the watched tree
+        assert_eq!(
+            classify(
+                &EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+                std::slice::from_ref(&from)
+            ),
+            vec![FsEvent::Deleted(from.clone())]
+        );
+        assert_eq!(
+            classify(
+                &EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+                std::slice::from_ref(&to)
+            ),
+            vec![FsEvent::Created(to.clone())]
+        );
+
+        // Moves into and out of .fracta/ (trash) look like delete / create
+        let trashed = PathBuf::from("/loc/.fracta/trash/1/payload");
+        assert_eq!(
+            classify(&rename, &[from.clone(), trashed.clone()]),
+            vec![FsEvent::Deleted(from.clone())]
+        );
+        assert_eq!(
+            classify(&rename, &[trashed, to.clone()]),
+            vec![FsEvent::Created(to)]
         );
     }
 
@@ -196,13 +821,15 @@ mod tests {
 
         let watcher = LocationWatcher::start(root).unwrap();
 
-        // Write inside .fracta/
+        // Write inside .fracta/, then outside as a marker
         fs::write(fracta_dir.join("cache.db"), "data").unwrap();
+        let marker = root.join("marker.md");
+        fs::write(&marker, "x").unwrap();
 
-        // Wait for debounce window
-        thread::sleep(Duration::from_millis(800));
-
-        let events = watcher.drain_events();
+        let events = drain_until(
+            || watcher.drain_events(),
+            |e| matches!(e, FsEvent::Created(p) if canon(p) == canon(&marker)),
+        );
         // Events from .fracta/ should be filtered out
         let has_fracta = events.iter().any(|e| {
             let path = match e {
@@ -222,10 +849,8 @@ mod tests {
         let watcher = LocationWatcher::start(root).unwrap();
 
         fs::write(root.join("a.md"), "content").unwrap();
-        thread::sleep(Duration::from_millis(800));
 
-        let events1 = watcher.drain_events();
-        assert!(!events1.is_empty());
+        drain_until(|| watcher.drain_events(), |_| true);
 
         // Second drain should be empty (no new events)
         let events2 = watcher.drain_events();
@@ -7,7 +7,7 @@
 //! "All writes MUST use atomic write patterns (write temp → fsync if needed → atomic rename)."
 
 use std::fs;
-use std::io::Write;
+use std::io::{self, Read, Write};
 use std::path::Path;
 
 use crate::error::{VfsError, VfsResult};
@@ -20,6 +20,81 @@ use crate::error::{VfsError, VfsResult};
 ///
 /// If the process crashes at any point, the original file is untouched.
 pub fn atomic_write(path: &Path, data: &[u8]) -> VfsResult<()> {
+    let (temp, ()) = write_synced_temp(path, |file| file.write_all(data))?;
+
+    // Atomic rename (this is the commit point)
+    temp.persist(path)
+        .map_err(|e| VfsError::AtomicWriteFailed {
+            path: path.to_path_buf(),
+            reason: format!("failed to rename: {e}"),
+        })?;
+
+    Ok(())
+}
+
+/// Write data to a file atomically if `precondition` still holds.
+///
+/// The content is written and synced first, then `precondition` runs right
+/// before the commit rename. That keeps the window in which a concurrent
+/// writer can slip in as small as possible; it does not close it entirely.
+pub fn atomic_write_if(
+    path: &Path,
+    data: &[u8],
+    precondition: impl FnOnce() -> VfsResult<()>,
+) -> VfsResult<()> {
+    let (temp, ()) = write_synced_temp(path, |file| file.write_all(data))?;
+    // Dropping `temp` on failure removes it
+    precondition()?;
+
+    temp.persist(path)
+        .map_err(|e| VfsError::AtomicWriteFailed {
+            path: path.to_path_buf(),
+            reason: format!("failed to rename: {e}"),
+        })?;
+
+    Ok(())
+}
+
+/// Create a new file atomically, failing if `path` already exists.
+///
+/// Same guarantees as [`atomic_write`], but the commit step refuses to
+/// replace an existing file, so a concurrent creator is never clobbered.
+pub fn atomic_write_new(path: &Path, data: &[u8]) -> VfsResult<()> {
+    let (temp, ()) = write_synced_temp(path, |file| file.write_all(data))?;
+    persist_new(temp, path)
+}
+
+/// Stream `reader` into a new file atomically, failing if `path` already exists.
+///
+/// Same guarantees as [`atomic_write_new`], without buffering the whole
+/// content in memory. Returns the number of bytes written.
+pub fn atomic_copy_new(reader: &mut impl Read, path: &Path) -> VfsResult<u64> {
+    let (temp, bytes) = write_synced_temp(path, |file| io::copy(reader, file))?;
+    persist_new(temp, path)?;
+    Ok(bytes)
+}
+
+/// Commit a synced temp file to `path` without replacing an existing file.
+fn persist_new(temp: tempfile::NamedTempFile, path: &Path) -> VfsResult<()> {
+    temp.persist_noclobber(path).map_err(|e| {
+        if e.error.kind() == io::ErrorKind::AlreadyExists {
+            VfsError::AlreadyExists(path.to_path_buf())
+        } else {
+            VfsError::AtomicWriteFailed {
+                path: path.to_path_buf(),
+                reason: format!("failed to rename: {}", e.error),
+            }
+        }
+    })?;
+
+    Ok(())
+}
+
+/// Fill a temp file next to `path` via `fill` and sync it, ready to be persisted.
+fn write_synced_temp<T>(
+    path: &Path,
+    fill: impl FnOnce(&mut tempfile::NamedTempFile) -> io::Result<T>,
+) -> VfsResult<(tempfile::NamedTempFile, T)> {
     let parent = path.parent().ok_or_else(|| VfsError::AtomicWriteFailed {
         path: path.to_path_buf(),
         reason: "path has no parent directory".into(),
@@ -33,11 +108,10 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> VfsResult<()> {
         })?;
 
     // Write all data
-    temp.write_all(data)
-        .map_err(|e| VfsError::AtomicWriteFailed {
-            path: path.to_path_buf(),
-            reason: format!("failed to write data: {e}"),
-        })?;
+    let written = fill(&mut temp).map_err(|e| VfsError::AtomicWriteFailed {
+        path: path.to_path_buf(),
+        reason: format!("failed to write data: {e}"),
+    })?;
 
     // Flush to OS
     temp.flush().map_err(|e| VfsError::AtomicWriteFailed {
@@ -53,14 +127,7 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> VfsResult<()> {
             reason: format!("failed to sync: {e}"),
         })?;
 
-    // Atomic rename (this is the commit point)
-    temp.persist(path)
-        .map_err(|e| VfsError::AtomicWriteFailed {
-            path: path.to_path_buf(),
-            reason: format!("failed to rename: {e}"),
-        })?;
-
-    Ok(())
+    Ok((temp, written))
 }
 
 /// Write a UTF-8 string to a file atomically.
@@ -101,4 +168,15 @@ mod tests {
         let content = fs::read_to_string(&file).unwrap();
         assert_eq!(content, "version 2");
     }
+
+    #[test]
+    fn test_atomic_write_new_refuses_existing() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("test.md");
+
+        atomic_write_new(&file, b"first").unwrap();
+        let err = atomic_write_new(&file, b"second").unwrap_err();
+        assert!(matches!(err, VfsError::AlreadyExists(_)));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "first");
+    }
 }